@@ -0,0 +1,337 @@
+//! Module for exporting placemarks to [Arrow](https://arrow.apache.org/) record batches
+//!
+//! Each placemark becomes a row with `name` and `description` columns, a string column for each
+//! `ExtendedData` property found in the batch, and a `geometry` column of WKB tagged with the
+//! [GeoArrow](https://geoarrow.org/) `geoarrow.wkb` extension type, which DataFusion, GeoParquet,
+//! and other GeoArrow readers understand. KML coordinates are always longitude and latitude, so the
+//! column's CRS is `OGC:CRS84`.
+//!
+//! Geometries are written as 3D if all of their coordinates have an altitude, `Track` and
+//! `LinearRing` are written as line strings, and `MultiTrack` as a multi line string. Models, and
+//! placemarks without a geometry, have a null geometry.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::Kml;
+//!
+//! let kml: Kml = r#"
+//! <Document>
+//!   <Placemark>
+//!     <name>Trailhead</name>
+//!     <ExtendedData><Data name="length"><value>5</value></Data></ExtendedData>
+//!     <Point><coordinates>1,1</coordinates></Point>
+//!   </Placemark>
+//! </Document>"#
+//!     .parse()
+//!     .unwrap();
+//! let batch = kml::arrow::to_record_batch(kml.features()).unwrap();
+//! assert_eq!(batch.num_rows(), 1);
+//! assert!(batch.column_by_name("length").is_some());
+//! ```
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_array::builder::{BinaryBuilder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+
+use crate::errors::Error;
+use crate::reader::KmlReader;
+use crate::types::{Coord, CoordType, Feature, Geometry, Kml, Placemark};
+
+/// Converts features, such as those from [`Kml::features`](../enum.Kml.html#method.features), into
+/// a record batch
+pub fn to_record_batch<'a, T, I>(features: I) -> Result<RecordBatch, Error>
+where
+    T: CoordType + 'a,
+    I: IntoIterator<Item = Feature<'a, T>>,
+{
+    let features: Vec<Feature<'a, T>> = features.into_iter().collect();
+    let properties: BTreeSet<&str> = features
+        .iter()
+        .flat_map(|f| f.properties.keys().map(String::as_str))
+        .collect();
+
+    let mut fields = vec![
+        Field::new("name", DataType::Utf8, true),
+        Field::new("description", DataType::Utf8, true),
+    ];
+    let mut columns: Vec<ArrayRef> = vec![
+        string_column(features.iter().map(|f| f.name)),
+        string_column(features.iter().map(|f| f.description)),
+    ];
+    for property in properties {
+        fields.push(Field::new(property, DataType::Utf8, true));
+        columns.push(string_column(
+            features
+                .iter()
+                .map(|f| f.properties.get(property).map(String::as_str)),
+        ));
+    }
+
+    let mut geometries = BinaryBuilder::new();
+    let mut wkb = Vec::new();
+    for feature in &features {
+        wkb.clear();
+        match feature.geometry {
+            Some(geometry) if write_wkb(geometry, geometry.has_z(), &mut wkb) => {
+                geometries.append_value(&wkb)
+            }
+            _ => geometries.append_null(),
+        }
+    }
+    fields.push(
+        Field::new("geometry", DataType::Binary, true).with_metadata(HashMap::from([
+            (
+                "ARROW:extension:name".to_string(),
+                "geoarrow.wkb".to_string(),
+            ),
+            (
+                "ARROW:extension:metadata".to_string(),
+                r#"{"crs":"OGC:CRS84"}"#.to_string(),
+            ),
+        ])),
+    );
+    columns.push(Arc::new(geometries.finish()));
+
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+fn string_column<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    let mut builder = StringBuilder::new();
+    for value in values {
+        builder.append_option(value);
+    }
+    Arc::new(builder.finish())
+}
+
+impl<B: BufRead, T> KmlReader<B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    /// Returns an iterator over record batches of up to `batch_size` placemarks as they're read,
+    /// without building the tree, see [`to_record_batch`](../arrow/fn.to_record_batch.html)
+    ///
+    /// Property columns are derived from each batch, so batches can have different schemas.
+    pub fn record_batches(&mut self, batch_size: usize) -> RecordBatches<'_, B, T> {
+        RecordBatches {
+            reader: self,
+            batch_size: batch_size.max(1),
+            done: false,
+        }
+    }
+}
+
+/// Iterator over record batches read by a [`KmlReader`](../reader/struct.KmlReader.html), created
+/// by [`KmlReader::record_batches`](../reader/struct.KmlReader.html#method.record_batches)
+pub struct RecordBatches<'a, B: BufRead, T: CoordType + FromStr + Default = f64> {
+    reader: &'a mut KmlReader<B, T>,
+    batch_size: usize,
+    done: bool,
+}
+
+impl<B: BufRead, T> Iterator for RecordBatches<'_, B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    type Item = Result<RecordBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut placemarks: Vec<Placemark<T>> = Vec::with_capacity(self.batch_size);
+        for element in self.reader.features() {
+            match element {
+                Ok(Kml::Placemark(p)) => placemarks.push(p),
+                Ok(_) => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+            if placemarks.len() == self.batch_size {
+                break;
+            }
+        }
+        if placemarks.len() < self.batch_size {
+            self.done = true;
+            if placemarks.is_empty() {
+                return None;
+            }
+        }
+        Some(to_record_batch(placemarks.iter().map(Feature::from)))
+    }
+}
+
+const WKB_POINT: u32 = 1;
+const WKB_LINE_STRING: u32 = 2;
+const WKB_POLYGON: u32 = 3;
+const WKB_MULTI_LINE_STRING: u32 = 5;
+const WKB_GEOMETRY_COLLECTION: u32 = 7;
+/// Added to geometry types with a `z` coordinate in ISO WKB
+const WKB_Z: u32 = 1000;
+
+/// Writes the geometry as little endian ISO WKB, returning `false` if it can't be represented
+fn write_wkb<T: CoordType>(geometry: &Geometry<T>, z: bool, buf: &mut Vec<u8>) -> bool {
+    match geometry {
+        Geometry::Point(p) => {
+            write_header(buf, WKB_POINT, z);
+            write_coord(buf, &p.coord, z);
+        }
+        Geometry::LineString(l) => write_line_string(buf, &l.coords, z),
+        Geometry::LinearRing(l) => write_line_string(buf, &l.coords, z),
+        Geometry::Track(t) => write_line_string(buf, &t.coords, z),
+        Geometry::Polygon(p) => {
+            write_header(buf, WKB_POLYGON, z);
+            write_u32(buf, 1 + p.inner.len());
+            for ring in std::iter::once(&p.outer).chain(&p.inner) {
+                write_coords(buf, &ring.coords, z);
+            }
+        }
+        Geometry::MultiTrack(t) => {
+            write_header(buf, WKB_MULTI_LINE_STRING, z);
+            write_u32(buf, t.tracks.len());
+            for track in &t.tracks {
+                write_line_string(buf, &track.coords, z);
+            }
+        }
+        Geometry::MultiGeometry(g) => {
+            write_header(buf, WKB_GEOMETRY_COLLECTION, z);
+            let count = buf.len();
+            write_u32(buf, 0);
+            let mut written = 0;
+            for geometry in &g.geometries {
+                written += usize::from(write_wkb(geometry, z, buf));
+            }
+            buf[count..count + 4].copy_from_slice(&(written as u32).to_le_bytes());
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn write_header(buf: &mut Vec<u8>, geometry_type: u32, z: bool) {
+    buf.push(1);
+    let geometry_type = if z {
+        geometry_type + WKB_Z
+    } else {
+        geometry_type
+    };
+    buf.extend_from_slice(&geometry_type.to_le_bytes());
+}
+
+fn write_line_string<T: CoordType>(buf: &mut Vec<u8>, coords: &[Coord<T>], z: bool) {
+    write_header(buf, WKB_LINE_STRING, z);
+    write_coords(buf, coords, z);
+}
+
+fn write_coords<T: CoordType>(buf: &mut Vec<u8>, coords: &[Coord<T>], z: bool) {
+    write_u32(buf, coords.len());
+    for coord in coords {
+        write_coord(buf, coord, z);
+    }
+}
+
+fn write_coord<T: CoordType>(buf: &mut Vec<u8>, coord: &Coord<T>, z: bool) {
+    let mut write_f64 = |v: T| buf.extend_from_slice(&v.to_f64().unwrap_or(f64::NAN).to_le_bytes());
+    write_f64(coord.x);
+    write_f64(coord.y);
+    if z {
+        write_f64(coord.z.unwrap_or_else(T::zero));
+    }
+}
+
+fn write_u32(buf: &mut Vec<u8>, value: usize) {
+    buf.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, BinaryArray, StringArray};
+
+    #[test]
+    fn test_to_record_batch() {
+        let kml: Kml = r#"<Document>
+            <Placemark>
+              <name>a</name>
+              <ExtendedData><Data name="kind"><value>trail</value></Data></ExtendedData>
+              <Point><coordinates>1,2,3</coordinates></Point>
+            </Placemark>
+            <Placemark>
+              <ExtendedData><Data name="length"><value>5</value></Data></ExtendedData>
+              <LineString><coordinates>1,2 3,4</coordinates></LineString>
+            </Placemark>
+            <Placemark><name>c</name></Placemark>
+          </Document>"#
+            .parse()
+            .unwrap();
+        let batch = to_record_batch(kml.features()).unwrap();
+
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["name", "description", "kind", "length", "geometry"]
+        );
+        assert_eq!(
+            schema.field(4).metadata()["ARROW:extension:name"],
+            "geoarrow.wkb"
+        );
+
+        let column = |name| batch.column_by_name(name).unwrap();
+        let strings = |name| {
+            let column = column(name);
+            let array = column.as_any().downcast_ref::<StringArray>().unwrap();
+            array
+                .iter()
+                .map(|v| v.map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            strings("name"),
+            vec![Some("a".to_string()), None, Some("c".to_string())]
+        );
+        assert_eq!(strings("kind"), vec![Some("trail".to_string()), None, None]);
+
+        let geometry = column("geometry");
+        let geometry = geometry.as_any().downcast_ref::<BinaryArray>().unwrap();
+        let mut point = vec![1];
+        point.extend_from_slice(&1001u32.to_le_bytes());
+        for v in [1f64, 2., 3.] {
+            point.extend_from_slice(&v.to_le_bytes());
+        }
+        assert_eq!(geometry.value(0), point.as_slice());
+        let mut line_string = vec![1];
+        line_string.extend_from_slice(&2u32.to_le_bytes());
+        line_string.extend_from_slice(&2u32.to_le_bytes());
+        for v in [1f64, 2., 3., 4.] {
+            line_string.extend_from_slice(&v.to_le_bytes());
+        }
+        assert_eq!(geometry.value(1), line_string.as_slice());
+        assert!(geometry.is_null(2));
+    }
+
+    #[test]
+    fn test_record_batches() {
+        let kml_str = r#"<Document>
+            <Placemark><name>a</name></Placemark>
+            <Style id="s"/>
+            <Placemark><name>b</name></Placemark>
+            <Folder><Placemark><name>c</name></Placemark></Folder>
+          </Document>"#;
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        let rows = reader
+            .record_batches(2)
+            .map(|b| b.unwrap().num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![2, 1]);
+    }
+}
@@ -10,11 +10,17 @@
 //! let geo_coord = geo_types::Coord::from(kml_coord);
 //! let kml_coord: Coord = Coord::from(geo_coord);
 //! ```
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::io::BufRead;
+use std::str::FromStr;
 
 use crate::errors::Error;
+use crate::reader::{Features, KmlReader};
 use crate::types::{
-    Coord, CoordType, Geometry, Kml, LineString, LinearRing, MultiGeometry, Point, Polygon,
+    Coord, CoordType, Document, Feature, Folder, Geometry, Kml, KmlDocument, KmlVersion,
+    LineString, LinearRing, MultiGeometry, MultiTrack, Orientation, Placemark, Point, Polygon,
+    Track,
 };
 
 #[allow(deprecated)]
@@ -57,6 +63,17 @@ where
     T: CoordType,
 {
     fn from(val: Point<T>) -> geo_types::Point<T> {
+        geo_types::Point::from(&val)
+    }
+}
+
+#[allow(deprecated)]
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<&Point<T>> for geo_types::Point<T>
+where
+    T: CoordType,
+{
+    fn from(val: &Point<T>) -> geo_types::Point<T> {
         geo_types::Point::from(geo_types::Coordinate::from(val.coord))
     }
 }
@@ -93,9 +110,21 @@ where
     T: CoordType,
 {
     fn from(val: LineString<T>) -> geo_types::LineString<T> {
+        geo_types::LineString::from(&val)
+    }
+}
+
+#[allow(deprecated)]
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<&LineString<T>> for geo_types::LineString<T>
+where
+    T: CoordType,
+{
+    fn from(val: &LineString<T>) -> geo_types::LineString<T> {
         geo_types::LineString(
             val.coords
-                .into_iter()
+                .iter()
+                .copied()
                 .map(geo_types::Coordinate::from)
                 .collect(),
         )
@@ -125,15 +154,121 @@ where
     T: CoordType,
 {
     fn from(val: LinearRing<T>) -> geo_types::LineString<T> {
+        geo_types::LineString::from(&val)
+    }
+}
+
+#[allow(deprecated)]
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<&LinearRing<T>> for geo_types::LineString<T>
+where
+    T: CoordType,
+{
+    fn from(val: &LinearRing<T>) -> geo_types::LineString<T> {
         geo_types::LineString(
             val.coords
-                .into_iter()
+                .iter()
+                .copied()
                 .map(geo_types::Coordinate::from)
                 .collect(),
         )
     }
 }
 
+#[allow(deprecated)]
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<Track<T>> for geo_types::LineString<T>
+where
+    T: CoordType,
+{
+    fn from(val: Track<T>) -> geo_types::LineString<T> {
+        geo_types::LineString::from(&val)
+    }
+}
+
+#[allow(deprecated)]
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<&Track<T>> for geo_types::LineString<T>
+where
+    T: CoordType,
+{
+    fn from(val: &Track<T>) -> geo_types::LineString<T> {
+        geo_types::LineString(
+            val.coords
+                .iter()
+                .copied()
+                .map(geo_types::Coordinate::from)
+                .collect(),
+        )
+    }
+}
+
+/// `Track` converted to a `geo-types` line string, with the time and angles of each point
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+pub struct GeoTrack<T: CoordType = f64> {
+    pub line_string: geo_types::LineString<T>,
+    /// Seconds since the Unix epoch of each point, see
+    /// [`Track::timestamps`](../types/struct.Track.html#method.timestamps)
+    pub timestamps: Vec<Option<f64>>,
+    pub angles: Vec<Option<Orientation<T>>>,
+}
+
+/// Keeps the timestamps and angles of the track's points, which are `None` where the track doesn't
+/// have one
+///
+/// # Example
+///
+/// ```
+/// use kml::conversion::GeoTrack;
+/// use kml::types::{Coord, Track};
+///
+/// let track: Track = Track {
+///     whens: vec!["2010-05-28T02:02:09Z".to_string(), "2010-05-28T02:02:39Z".to_string()],
+///     coords: vec![Coord::new(0., 0., None), Coord::new(0., 0.01, None)],
+///     ..Default::default()
+/// };
+/// let track = GeoTrack::from(track);
+/// let elapsed = track.timestamps[1].unwrap() - track.timestamps[0].unwrap();
+/// assert_eq!(elapsed, 30.);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<Track<T>> for GeoTrack<T>
+where
+    T: CoordType,
+{
+    fn from(val: Track<T>) -> GeoTrack<T> {
+        let timestamps = val.timestamps();
+        let mut angles = val.angles.iter().cloned().map(Some).collect::<Vec<_>>();
+        angles.resize(val.coords.len(), None);
+        GeoTrack {
+            line_string: geo_types::LineString::from(val),
+            timestamps,
+            angles,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<MultiTrack<T>> for geo_types::MultiLineString<T>
+where
+    T: CoordType,
+{
+    fn from(val: MultiTrack<T>) -> geo_types::MultiLineString<T> {
+        geo_types::MultiLineString::from(&val)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<&MultiTrack<T>> for geo_types::MultiLineString<T>
+where
+    T: CoordType,
+{
+    fn from(val: &MultiTrack<T>) -> geo_types::MultiLineString<T> {
+        geo_types::MultiLineString(val.tracks.iter().map(geo_types::LineString::from).collect())
+    }
+}
+
 #[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
 impl<T> From<geo_types::Polygon<T>> for Polygon<T>
 where
@@ -177,10 +312,20 @@ where
     T: CoordType,
 {
     fn from(val: Polygon<T>) -> geo_types::Polygon<T> {
+        geo_types::Polygon::from(&val)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<&Polygon<T>> for geo_types::Polygon<T>
+where
+    T: CoordType,
+{
+    fn from(val: &Polygon<T>) -> geo_types::Polygon<T> {
         geo_types::Polygon::new(
-            geo_types::LineString::from(val.outer),
+            geo_types::LineString::from(&val.outer),
             val.inner
-                .into_iter()
+                .iter()
                 .map(geo_types::LineString::from)
                 .collect::<Vec<geo_types::LineString<T>>>(),
         )
@@ -251,15 +396,108 @@ where
     type Error = Error;
 
     fn try_from(val: MultiGeometry<T>) -> Result<geo_types::GeometryCollection<T>, Self::Error> {
+        geo_types::GeometryCollection::try_from(&val)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> TryFrom<&MultiGeometry<T>> for geo_types::GeometryCollection<T>
+where
+    T: CoordType,
+{
+    type Error = Error;
+
+    fn try_from(val: &MultiGeometry<T>) -> Result<geo_types::GeometryCollection<T>, Self::Error> {
         Ok(geo_types::GeometryCollection(
             val.geometries
-                .into_iter()
+                .iter()
                 .map(geo_types::Geometry::try_from)
                 .collect::<Result<Vec<geo_types::Geometry<T>>, _>>()?,
         ))
     }
 }
 
+/// Converts to a `MultiPoint`, `MultiLineString`, or `MultiPolygon` when all of the geometries
+/// are points, line strings (including linear rings and tracks), or polygons, and a
+/// `GeometryCollection` otherwise
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> TryFrom<MultiGeometry<T>> for geo_types::Geometry<T>
+where
+    T: CoordType,
+{
+    type Error = Error;
+
+    fn try_from(val: MultiGeometry<T>) -> Result<geo_types::Geometry<T>, Self::Error> {
+        geo_types::Geometry::try_from(&val)
+    }
+}
+
+/// Converts to a `MultiPoint`, `MultiLineString`, or `MultiPolygon` when all of the geometries
+/// are points, line strings (including linear rings and tracks), or polygons, and a
+/// `GeometryCollection` otherwise
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> TryFrom<&MultiGeometry<T>> for geo_types::Geometry<T>
+where
+    T: CoordType,
+{
+    type Error = Error;
+
+    fn try_from(val: &MultiGeometry<T>) -> Result<geo_types::Geometry<T>, Self::Error> {
+        let geometries = &val.geometries;
+        if geometries.is_empty() {
+            return Ok(geo_types::Geometry::GeometryCollection(
+                geo_types::GeometryCollection::try_from(val)?,
+            ));
+        }
+        if geometries.iter().all(|g| matches!(g, Geometry::Point(_))) {
+            return Ok(geo_types::Geometry::MultiPoint(
+                geometries
+                    .iter()
+                    .filter_map(|g| match g {
+                        Geometry::Point(p) => Some(geo_types::Point::from(p)),
+                        _ => None,
+                    })
+                    .collect(),
+            ));
+        }
+        let is_line_string = |g: &Geometry<T>| {
+            matches!(
+                g,
+                Geometry::LineString(_) | Geometry::LinearRing(_) | Geometry::Track(_)
+            )
+        };
+        if geometries.iter().all(is_line_string) {
+            return Ok(geo_types::Geometry::MultiLineString(
+                geo_types::MultiLineString(
+                    geometries
+                        .iter()
+                        .filter_map(|g| match g {
+                            Geometry::LineString(l) => Some(geo_types::LineString::from(l)),
+                            Geometry::LinearRing(l) => Some(geo_types::LineString::from(l)),
+                            Geometry::Track(t) => Some(geo_types::LineString::from(t)),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+            ));
+        }
+        if geometries.iter().all(|g| matches!(g, Geometry::Polygon(_))) {
+            return Ok(geo_types::Geometry::MultiPolygon(geo_types::MultiPolygon(
+                geometries
+                    .iter()
+                    .filter_map(|g| match g {
+                        Geometry::Polygon(p) => Some(geo_types::Polygon::from(p)),
+                        _ => None,
+                    })
+                    .collect(),
+            )));
+        }
+        Ok(geo_types::Geometry::GeometryCollection(
+            geo_types::GeometryCollection::try_from(val)?,
+        ))
+    }
+}
+
 #[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
 impl<T> From<geo_types::Geometry<T>> for Geometry<T>
 where
@@ -293,6 +531,18 @@ where
     type Error = Error;
 
     fn try_from(val: Geometry<T>) -> Result<geo_types::Geometry<T>, Self::Error> {
+        geo_types::Geometry::try_from(&val)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> TryFrom<&Geometry<T>> for geo_types::Geometry<T>
+where
+    T: CoordType,
+{
+    type Error = Error;
+
+    fn try_from(val: &Geometry<T>) -> Result<geo_types::Geometry<T>, Self::Error> {
         match val {
             Geometry::Point(p) => Ok(geo_types::Geometry::Point(geo_types::Point::from(p))),
             Geometry::LineString(l) => Ok(geo_types::Geometry::LineString(
@@ -302,8 +552,12 @@ where
                 geo_types::LineString::from(l),
             )),
             Geometry::Polygon(p) => Ok(geo_types::Geometry::Polygon(geo_types::Polygon::from(p))),
-            Geometry::MultiGeometry(g) => Ok(geo_types::Geometry::GeometryCollection(
-                geo_types::GeometryCollection::try_from(g)?,
+            Geometry::MultiGeometry(g) => geo_types::Geometry::try_from(g),
+            Geometry::Track(t) => Ok(geo_types::Geometry::LineString(
+                geo_types::LineString::from(t),
+            )),
+            Geometry::MultiTrack(t) => Ok(geo_types::Geometry::MultiLineString(
+                geo_types::MultiLineString::from(t),
             )),
             _ => Err(Error::InvalidGeometry("Can't convert geometry".to_string())),
         }
@@ -318,51 +572,52 @@ where
     type Error = Error;
 
     fn try_from(k: Kml<T>) -> Result<Vec<geo_types::Geometry<T>>, Self::Error> {
-        match k {
-            Kml::KmlDocument(d) => Ok(d
-                .elements
-                .into_iter()
+        Vec::<geo_types::Geometry<T>>::try_from(&k)
+    }
+}
+
+/// Collects the geometries in the tree, converting from references so the tree can still be used
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> TryFrom<&Kml<T>> for Vec<geo_types::Geometry<T>>
+where
+    T: CoordType,
+{
+    type Error = Error;
+
+    fn try_from(k: &Kml<T>) -> Result<Vec<geo_types::Geometry<T>>, Self::Error> {
+        let from_elements = |elements: &[Kml<T>]| {
+            elements
+                .iter()
                 .flat_map(Vec::<geo_types::Geometry<T>>::try_from)
                 .flatten()
-                .collect::<Vec<geo_types::Geometry<T>>>()),
-            Kml::Point(p) => Ok(vec![
-                geo_types::Geometry::Point(geo_types::Point::from(p));
-                1
-            ]),
-            Kml::LineString(l) => Ok(vec![
-                geo_types::Geometry::LineString(
-                    geo_types::LineString::from(l),
-                );
-                1
-            ]),
-            Kml::LinearRing(l) => Ok(vec![
-                geo_types::Geometry::LineString(
-                    geo_types::LineString::from(l),
-                );
-                1
-            ]),
-            Kml::Polygon(p) => Ok(vec![
-                geo_types::Geometry::Polygon(geo_types::Polygon::from(
-                    p
-                ));
-                1
-            ]),
+                .collect()
+        };
+        match k {
+            Kml::KmlDocument(d) => Ok(from_elements(&d.elements)),
+            Kml::Point(p) => Ok(vec![geo_types::Geometry::Point(geo_types::Point::from(p))]),
+            Kml::LineString(l) => Ok(vec![geo_types::Geometry::LineString(
+                geo_types::LineString::from(l),
+            )]),
+            Kml::LinearRing(l) => Ok(vec![geo_types::Geometry::LineString(
+                geo_types::LineString::from(l),
+            )]),
+            Kml::Polygon(p) => Ok(vec![geo_types::Geometry::Polygon(
+                geo_types::Polygon::from(p),
+            )]),
             Kml::MultiGeometry(g) => Ok(geo_types::GeometryCollection::try_from(g)?.0),
-            Kml::Placemark(p) => Ok(if let Some(g) = p.geometry {
-                vec![geo_types::Geometry::try_from(g)?; 1]
+            Kml::Track(t) => Ok(vec![geo_types::Geometry::LineString(
+                geo_types::LineString::from(t),
+            )]),
+            Kml::MultiTrack(t) => Ok(vec![geo_types::Geometry::MultiLineString(
+                geo_types::MultiLineString::from(t),
+            )]),
+            Kml::Placemark(p) => Ok(if let Some(g) = &p.geometry {
+                vec![geo_types::Geometry::try_from(g)?]
             } else {
                 vec![]
             }),
-            Kml::Document { elements, .. } => Ok(elements
-                .into_iter()
-                .flat_map(Vec::<geo_types::Geometry<T>>::try_from)
-                .flatten()
-                .collect()),
-            Kml::Folder { elements, .. } => Ok(elements
-                .into_iter()
-                .flat_map(Vec::<geo_types::Geometry<T>>::try_from)
-                .flatten()
-                .collect()),
+            Kml::Document(Document { elements, .. }) => Ok(from_elements(elements)),
+            Kml::Folder(Folder { elements, .. }) => Ok(from_elements(elements)),
             _ => Ok(vec![]),
         }
     }
@@ -376,12 +631,244 @@ where
     type Error = Error;
 
     fn try_from(k: Kml<T>) -> Result<geo_types::GeometryCollection<T>, Self::Error> {
+        geo_types::GeometryCollection::try_from(&k)
+    }
+}
+
+/// Builds the collection from references, leaving the tree to the caller
+///
+/// # Example
+///
+/// ```
+/// use geo_types::GeometryCollection;
+/// use kml::Kml;
+///
+/// let k: Kml = "<Folder><Point><coordinates>1,1</coordinates></Point></Folder>"
+///     .parse()
+///     .unwrap();
+/// let collection = GeometryCollection::try_from(&k).unwrap();
+/// assert_eq!(collection.len(), 1);
+/// assert!(matches!(k, Kml::Folder(_)));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> TryFrom<&Kml<T>> for geo_types::GeometryCollection<T>
+where
+    T: CoordType,
+{
+    type Error = Error;
+
+    fn try_from(k: &Kml<T>) -> Result<geo_types::GeometryCollection<T>, Self::Error> {
         Ok(geo_types::GeometryCollection(
             Vec::<geo_types::Geometry<T>>::try_from(k)?,
         ))
     }
 }
 
+/// Wraps the geometry in a `Placemark`, since bare geometries aren't valid KML features
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<geo_types::Geometry<T>> for Kml<T>
+where
+    T: CoordType + Default,
+{
+    fn from(val: geo_types::Geometry<T>) -> Kml<T> {
+        Kml::Placemark(Placemark {
+            geometry: Some(Geometry::from(val)),
+            ..Default::default()
+        })
+    }
+}
+
+/// Wraps the collection in a `Placemark` containing a `MultiGeometry`
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> From<geo_types::GeometryCollection<T>> for Kml<T>
+where
+    T: CoordType + Default,
+{
+    fn from(val: geo_types::GeometryCollection<T>) -> Kml<T> {
+        Kml::from(geo_types::Geometry::GeometryCollection(val))
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> KmlDocument<T>
+where
+    T: CoordType + Default,
+{
+    /// Builds a KML 2.2 document with a `Placemark` for each geometry, named when a name is given
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::KmlDocument;
+    ///
+    /// let doc: KmlDocument = KmlDocument::from_geometries(vec![
+    ///     (Some("Origin".to_string()), geo_types::Point::new(0., 0.)),
+    ///     (None, geo_types::Point::new(1., 1.)),
+    /// ]);
+    /// let kml = kml::Kml::KmlDocument(doc).to_string();
+    /// assert!(kml.contains("<name>Origin</name>"));
+    /// ```
+    pub fn from_geometries<I, G>(geometries: I) -> KmlDocument<T>
+    where
+        I: IntoIterator<Item = (Option<String>, G)>,
+        G: Into<geo_types::Geometry<T>>,
+    {
+        let placemarks = geometries
+            .into_iter()
+            .map(|(name, geometry)| {
+                Kml::Placemark(Placemark {
+                    name,
+                    geometry: Some(Geometry::from(geometry.into())),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        KmlDocument {
+            version: KmlVersion::V22,
+            elements: vec![Kml::Document(Document {
+                elements: placemarks,
+                ..Default::default()
+            })],
+            ..Default::default()
+        }
+    }
+}
+
+/// Collects unnamed geometries into a document, see
+/// [`KmlDocument::from_geometries`](../struct.KmlDocument.html#method.from_geometries)
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T, G> FromIterator<G> for KmlDocument<T>
+where
+    T: CoordType + Default,
+    G: Into<geo_types::Geometry<T>>,
+{
+    fn from_iter<I: IntoIterator<Item = G>>(iter: I) -> KmlDocument<T> {
+        KmlDocument::from_geometries(iter.into_iter().map(|g| (None, g)))
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<T> Feature<'_, T>
+where
+    T: CoordType,
+{
+    /// Converts the feature's geometry to a `geo-types` geometry, if it has one
+    pub fn geo_geometry(&self) -> Result<Option<geo_types::Geometry<T>>, Error> {
+        self.geometry.map(geo_types::Geometry::try_from).transpose()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+impl<B: BufRead, T> KmlReader<B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    /// Returns an iterator converting geometries to `geo-types` as they're read, without building
+    /// the tree, see [`KmlReader::features`](../reader/struct.KmlReader.html#method.features)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::KmlReader;
+    ///
+    /// let kml_str = r#"
+    /// <Document>
+    ///   <Placemark><Point><coordinates>1,1</coordinates></Point></Placemark>
+    ///   <Placemark><LineString><coordinates>1,1 2,2</coordinates></LineString></Placemark>
+    /// </Document>"#;
+    /// let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+    /// let geometries = reader.geometries().collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(geometries.len(), 2);
+    /// ```
+    pub fn geometries(&mut self) -> Geometries<'_, B, T> {
+        Geometries {
+            features: self.elements(),
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    /// Returns an iterator over placemark geometries converted to `geo-types` as they're read,
+    /// paired with their `ExtendedData` values from
+    /// [`Placemark::properties`](../types/struct.Placemark.html#method.properties)
+    ///
+    /// Placemarks without a geometry are skipped, while geometries outside of a placemark have no
+    /// properties.
+    pub fn geo_features(&mut self) -> GeoFeatures<'_, B, T> {
+        GeoFeatures {
+            features: self.elements(),
+            pending: Vec::new().into_iter(),
+        }
+    }
+}
+
+/// Iterator over `geo-types` geometries read by a [`KmlReader`](../reader/struct.KmlReader.html),
+/// created by [`KmlReader::geometries`](../reader/struct.KmlReader.html#method.geometries)
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+pub struct Geometries<'a, B: BufRead, T: CoordType + FromStr + Default = f64> {
+    features: Features<'a, B, T>,
+    pending: std::vec::IntoIter<geo_types::Geometry<T>>,
+}
+
+impl<B: BufRead, T> Iterator for Geometries<'_, B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    type Item = Result<geo_types::Geometry<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(geometry) = self.pending.next() {
+                return Some(Ok(geometry));
+            }
+            match self.features.next()?.and_then(Vec::try_from) {
+                Ok(geometries) => self.pending = geometries.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Iterator over `geo-types` geometries and their properties read by a
+/// [`KmlReader`](../reader/struct.KmlReader.html), created by
+/// [`KmlReader::geo_features`](../reader/struct.KmlReader.html#method.geo_features)
+#[cfg_attr(docsrs, doc(cfg(feature = "geo-types")))]
+pub struct GeoFeatures<'a, B: BufRead, T: CoordType + FromStr + Default = f64> {
+    features: Features<'a, B, T>,
+    pending: std::vec::IntoIter<geo_types::Geometry<T>>,
+}
+
+impl<B: BufRead, T> Iterator for GeoFeatures<'_, B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    type Item = Result<(geo_types::Geometry<T>, HashMap<String, String>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(geometry) = self.pending.next() {
+                return Some(Ok((geometry, HashMap::new())));
+            }
+            let result = match self.features.next()? {
+                Ok(Kml::Placemark(p)) => {
+                    let properties = p.properties();
+                    match p.geometry.map(geo_types::Geometry::try_from) {
+                        Some(geometry) => geometry.map(|g| (g, properties)),
+                        None => continue,
+                    }
+                }
+                k => match k.and_then(Vec::try_from) {
+                    Ok(geometries) => {
+                        self.pending = geometries.into_iter();
+                        continue;
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            return Some(result);
+        }
+    }
+}
+
 /// A shortcut for producing `geo-types` [GeometryCollection](../geo_types/struct.GeometryCollection.html)
 /// from valid KML input.
 ///
@@ -421,7 +908,6 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::KmlDocument;
     use std::collections::HashMap;
 
     #[test]
@@ -429,8 +915,7 @@ mod tests {
         let k = KmlDocument {
             elements: vec![
                 Kml::Point(Point::from(Coord::from((1., 1.)))),
-                Kml::Folder {
-                    attrs: HashMap::new(),
+                Kml::Folder(Folder {
                     elements: vec![
                         Kml::LineString(LineString::from(vec![
                             Coord::from((1., 1.)),
@@ -438,7 +923,8 @@ mod tests {
                         ])),
                         Kml::Point(Point::from(Coord::from((3., 3.)))),
                     ],
-                },
+                    ..Default::default()
+                }),
             ],
             ..Default::default()
         };
@@ -448,9 +934,186 @@ mod tests {
             geo_types::Geometry::LineString(geo_types::LineString::from(vec![(1., 1.), (2., 2.)])),
             geo_types::Geometry::Point(geo_types::Point::from((3., 3.))),
         ]);
+        let k = Kml::KmlDocument(k);
+        assert_eq!(geo_types::GeometryCollection::try_from(&k).unwrap(), gc);
+        assert_eq!(geo_types::GeometryCollection::try_from(k).unwrap(), gc);
+    }
+
+    #[test]
+    fn test_from_geometries() {
+        let doc: KmlDocument = KmlDocument::from_geometries(vec![
+            (Some("a".to_string()), geo_types::Point::new(1., 1.)),
+            (None, geo_types::Point::new(2., 2.)),
+        ]);
+        assert_eq!(doc.version, KmlVersion::V22);
+        let placemarks = match &doc.elements[..] {
+            [Kml::Document(Document { elements, .. })] => elements,
+            _ => panic!("expected a single Document"),
+        };
+        assert_eq!(
+            placemarks,
+            &vec![
+                Kml::Placemark(Placemark {
+                    name: Some("a".to_string()),
+                    geometry: Some(Geometry::Point(Point::new(1., 1., None))),
+                    ..Default::default()
+                }),
+                Kml::Placemark(Placemark {
+                    geometry: Some(Geometry::Point(Point::new(2., 2., None))),
+                    ..Default::default()
+                }),
+            ]
+        );
+
+        let collected: KmlDocument = vec![geo_types::Point::new(1., 1.)].into_iter().collect();
+        assert_eq!(
+            Vec::<geo_types::Geometry>::try_from(Kml::KmlDocument(collected)).unwrap(),
+            vec![geo_types::Geometry::Point(geo_types::Point::new(1., 1.))]
+        );
+    }
+
+    #[test]
+    fn test_kml_from_geometry_collection() {
+        let gc = geo_types::GeometryCollection(vec![geo_types::Geometry::Point(
+            geo_types::Point::new(1., 1.),
+        )]);
+        assert_eq!(
+            Kml::from(gc),
+            Kml::Placemark(Placemark {
+                geometry: Some(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                    Geometry::Point(Point::new(1., 1., None))
+                ]))),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_feature_geo_geometry() {
+        let kml: Kml = "<Placemark><Point><coordinates>1,2</coordinates></Point></Placemark>"
+            .parse()
+            .unwrap();
+        let feature = kml.features().next().unwrap();
+        assert_eq!(
+            feature.geo_geometry().unwrap(),
+            Some(geo_types::Geometry::Point(geo_types::Point::new(1., 2.)))
+        );
+    }
+
+    #[test]
+    fn test_reader_geometries() {
+        let kml_str = r#"<Document>
+            <Placemark>
+              <ExtendedData><Data name="a"><value>1</value></Data></ExtendedData>
+              <Point><coordinates>1,1</coordinates></Point>
+            </Placemark>
+            <Placemark><name>empty</name></Placemark>
+            <Folder>
+              <MultiGeometry>
+                <Point><coordinates>2,2</coordinates></Point>
+                <Point><coordinates>3,3</coordinates></Point>
+              </MultiGeometry>
+            </Folder>
+          </Document>"#;
+        let point = |x| geo_types::Geometry::Point(geo_types::Point::new(x, x));
+
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        let geometries = reader.geometries().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(geometries, vec![point(1.), point(2.), point(3.)]);
+
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        let features = reader
+            .geo_features()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            features,
+            vec![
+                (
+                    point(1.),
+                    HashMap::from([("a".to_string(), "1".to_string())])
+                ),
+                (point(2.), HashMap::new()),
+                (point(3.), HashMap::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_geometry_to_multi() {
+        let point = |x| Geometry::Point(Point::new(x, x, None));
+        let line_string = |x| {
+            Geometry::LineString(LineString::from(vec![
+                Coord::new(x, x, None),
+                Coord::new(x + 1., x, None),
+            ]))
+        };
+
         assert_eq!(
-            geo_types::GeometryCollection::try_from(Kml::KmlDocument(k)).unwrap(),
-            gc
+            geo_types::Geometry::try_from(MultiGeometry::new(vec![point(1.), point(2.)])).unwrap(),
+            geo_types::Geometry::MultiPoint(geo_types::MultiPoint::from(vec![(1., 1.), (2., 2.)]))
+        );
+        assert_eq!(
+            geo_types::Geometry::try_from(MultiGeometry::new(vec![
+                line_string(1.),
+                Geometry::Track(Track::from(vec![Coord::new(3., 3., None)])),
+            ]))
+            .unwrap(),
+            geo_types::Geometry::MultiLineString(geo_types::MultiLineString(vec![
+                geo_types::LineString::from(vec![(1., 1.), (2., 1.)]),
+                geo_types::LineString::from(vec![(3., 3.)]),
+            ]))
+        );
+        let polygon = geo_types::Polygon::new(
+            geo_types::LineString::from(vec![(0., 0.), (1., 0.), (0., 0.)]),
+            vec![],
+        );
+        assert_eq!(
+            geo_types::Geometry::try_from(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                Geometry::Polygon(Polygon::from(polygon.clone())),
+            ])))
+            .unwrap(),
+            geo_types::Geometry::MultiPolygon(geo_types::MultiPolygon(vec![polygon]))
+        );
+        assert_eq!(
+            geo_types::Geometry::try_from(MultiGeometry::new(vec![point(1.), line_string(1.)]))
+                .unwrap(),
+            geo_types::Geometry::GeometryCollection(geo_types::GeometryCollection(vec![
+                geo_types::Geometry::Point(geo_types::Point::new(1., 1.)),
+                geo_types::Geometry::LineString(geo_types::LineString::from(vec![
+                    (1., 1.),
+                    (2., 1.)
+                ])),
+            ]))
+        );
+        assert_eq!(
+            geo_types::Geometry::try_from(MultiGeometry::<f64>::new(vec![])).unwrap(),
+            geo_types::Geometry::GeometryCollection(geo_types::GeometryCollection(vec![]))
+        );
+    }
+
+    #[test]
+    fn test_geo_track() {
+        let angles = Orientation {
+            roll: 0.,
+            tilt: 1.,
+            heading: 2.,
+            attrs: HashMap::new(),
+            namespace: None,
+        };
+        let track = Track {
+            whens: vec!["1970-01-01T00:00:10Z".to_string()],
+            coords: vec![Coord::new(1., 1., None), Coord::new(2., 2., None)],
+            angles: vec![angles.clone()],
+            ..Default::default()
+        };
+        assert_eq!(
+            GeoTrack::from(track),
+            GeoTrack {
+                line_string: geo_types::LineString::from(vec![(1., 1.), (2., 2.)]),
+                timestamps: vec![Some(10.), None],
+                angles: vec![Some(angles), None],
+            }
         );
     }
 }
@@ -0,0 +1,254 @@
+//! Module for writing placemarks as CSV or TSV tables
+//!
+//! Each placemark becomes a row with `name`, `description`, `lon`, `lat`, and `alt` columns, a
+//! `wkt` column with its geometry, and a column for each `ExtendedData` property found in any of
+//! the placemarks. The coordinate columns are only filled for points, and altitude only when the
+//! point has one. Fields containing the delimiter, quotes, or line breaks are quoted.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::Kml;
+//!
+//! let kml: Kml = r#"
+//! <Document>
+//!   <Placemark>
+//!     <name>Trailhead</name>
+//!     <ExtendedData><Data name="length"><value>5</value></Data></ExtendedData>
+//!     <Point><coordinates>1,2</coordinates></Point>
+//!   </Placemark>
+//! </Document>"#
+//!     .parse()
+//!     .unwrap();
+//! let mut csv = Vec::new();
+//! kml::csv::write_csv(&mut csv, kml.features(), b',').unwrap();
+//! assert_eq!(
+//!     String::from_utf8(csv).unwrap(),
+//!     "name,description,lon,lat,alt,wkt,length\nTrailhead,,1,2,,POINT (1 2),5\n"
+//! );
+//! ```
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::errors::Error;
+use crate::types::{Coord, CoordType, Feature, Geometry};
+
+/// Writes features, such as those from [`Kml::features`](../enum.Kml.html#method.features), as a
+/// table separated by `delimiter`, which is usually `b','` for CSV or `b'\t'` for TSV
+pub fn write_csv<'a, W, T, I>(mut writer: W, features: I, delimiter: u8) -> Result<(), Error>
+where
+    W: Write,
+    T: CoordType + 'a,
+    I: IntoIterator<Item = Feature<'a, T>>,
+{
+    let features: Vec<Feature<'a, T>> = features.into_iter().collect();
+    let properties: BTreeSet<&str> = features
+        .iter()
+        .flat_map(|f| f.properties.keys().map(String::as_str))
+        .collect();
+
+    let header = ["name", "description", "lon", "lat", "alt", "wkt"];
+    write_row(
+        &mut writer,
+        header.into_iter().chain(properties.iter().copied()),
+        delimiter,
+    )?;
+
+    for feature in &features {
+        let point = match feature.geometry {
+            Some(Geometry::Point(p)) => Some(&p.coord),
+            _ => None,
+        };
+        let number = |v: Option<T>| v.map(format_number).unwrap_or_default();
+        let fields = [
+            feature.name.unwrap_or_default().to_string(),
+            feature.description.unwrap_or_default().to_string(),
+            number(point.map(|c| c.x)),
+            number(point.map(|c| c.y)),
+            number(point.and_then(|c| c.z)),
+            feature.geometry.map(to_wkt).unwrap_or_default(),
+        ];
+        let values = properties
+            .iter()
+            .map(|p| feature.properties.get(*p).map_or("", String::as_str));
+        write_row(
+            &mut writer,
+            fields.iter().map(String::as_str).chain(values),
+            delimiter,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_row<'a, W: Write>(
+    writer: &mut W,
+    fields: impl Iterator<Item = &'a str>,
+    delimiter: u8,
+) -> Result<(), Error> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            writer.write_all(&[delimiter])?;
+        }
+        let needs_quotes = field
+            .bytes()
+            .any(|b| b == delimiter || matches!(b, b'"' | b'\n' | b'\r'));
+        if needs_quotes {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn format_number<T: CoordType>(value: T) -> String {
+    value.to_f64().unwrap_or(f64::NAN).to_string()
+}
+
+/// Formats the geometry as WKT, with `Track` and `LinearRing` as line strings, `MultiTrack` as a
+/// multi line string, and models as empty
+pub fn to_wkt<T: CoordType>(geometry: &Geometry<T>) -> String {
+    let mut wkt = String::new();
+    write_wkt(&mut wkt, geometry, geometry.has_z());
+    wkt
+}
+
+fn write_wkt<T: CoordType>(wkt: &mut String, geometry: &Geometry<T>, z: bool) {
+    let tag = |wkt: &mut String, name: &str| {
+        wkt.push_str(name);
+        wkt.push_str(if z { " Z " } else { " " });
+    };
+    match geometry {
+        Geometry::Point(p) => {
+            tag(wkt, "POINT");
+            wkt.push('(');
+            write_coord(wkt, &p.coord, z);
+            wkt.push(')');
+        }
+        Geometry::LineString(l) => {
+            tag(wkt, "LINESTRING");
+            write_coords(wkt, &l.coords, z);
+        }
+        Geometry::LinearRing(l) => {
+            tag(wkt, "LINESTRING");
+            write_coords(wkt, &l.coords, z);
+        }
+        Geometry::Track(t) => {
+            tag(wkt, "LINESTRING");
+            write_coords(wkt, &t.coords, z);
+        }
+        Geometry::Polygon(p) => {
+            tag(wkt, "POLYGON");
+            let rings = std::iter::once(&p.outer).chain(&p.inner);
+            write_list(wkt, rings, |wkt, r| write_coords(wkt, &r.coords, z));
+        }
+        Geometry::MultiTrack(t) => {
+            tag(wkt, "MULTILINESTRING");
+            write_list(wkt, t.tracks.iter(), |wkt, t| {
+                write_coords(wkt, &t.coords, z)
+            });
+        }
+        Geometry::MultiGeometry(g) => {
+            tag(wkt, "GEOMETRYCOLLECTION");
+            let geometries = g
+                .geometries
+                .iter()
+                .filter(|g| !matches!(g, Geometry::Element(_)));
+            write_list(wkt, geometries, |wkt, g| write_wkt(wkt, g, z));
+        }
+        Geometry::Element(_) => {}
+    }
+}
+
+fn write_list<I: Iterator>(
+    wkt: &mut String,
+    items: I,
+    mut write_item: impl FnMut(&mut String, I::Item),
+) {
+    let mut empty = true;
+    for item in items {
+        wkt.push_str(if empty { "(" } else { ", " });
+        write_item(wkt, item);
+        empty = false;
+    }
+    wkt.push_str(if empty { "EMPTY" } else { ")" });
+}
+
+fn write_coords<T: CoordType>(wkt: &mut String, coords: &[Coord<T>], z: bool) {
+    write_list(wkt, coords.iter(), |wkt, c| write_coord(wkt, c, z));
+}
+
+fn write_coord<T: CoordType>(wkt: &mut String, coord: &Coord<T>, z: bool) {
+    let _ = write!(wkt, "{} {}", format_number(coord.x), format_number(coord.y));
+    if z {
+        let _ = write!(wkt, " {}", format_number(coord.z.unwrap_or_else(T::zero)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LinearRing, MultiGeometry, Point, Polygon};
+    use crate::Kml;
+
+    #[test]
+    fn test_to_wkt() {
+        let ring = LinearRing::from(vec![
+            Coord::new(0., 0., None),
+            Coord::new(1., 0., None),
+            Coord::new(0., 0., None),
+        ]);
+        let polygon = Geometry::Polygon(Polygon::new(ring, vec![]));
+        assert_eq!(to_wkt(&polygon), "POLYGON ((0 0, 1 0, 0 0))");
+        assert_eq!(
+            to_wkt(&Geometry::Point(Point::new(1.5, 2., Some(3.)))),
+            "POINT Z (1.5 2 3)"
+        );
+        assert_eq!(
+            to_wkt(&Geometry::MultiGeometry(MultiGeometry::new(vec![
+                Geometry::Point(Point::new(1., 2., None)),
+                polygon,
+            ]))),
+            "GEOMETRYCOLLECTION (POINT (1 2), POLYGON ((0 0, 1 0, 0 0)))"
+        );
+        assert_eq!(
+            to_wkt::<f64>(&Geometry::MultiGeometry(MultiGeometry::new(vec![]))),
+            "GEOMETRYCOLLECTION EMPTY"
+        );
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let kml: Kml = r#"<Document>
+            <Placemark>
+              <name>a, b</name>
+              <description>"quoted"</description>
+              <ExtendedData><Data name="kind"><value>trail</value></Data></ExtendedData>
+              <Point><coordinates>1,2,3</coordinates></Point>
+            </Placemark>
+            <Placemark>
+              <ExtendedData><Data name="length"><value>5</value></Data></ExtendedData>
+              <LineString><coordinates>1,2 3,4</coordinates></LineString>
+            </Placemark>
+          </Document>"#
+            .parse()
+            .unwrap();
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, kml.features(), b',').unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "name,description,lon,lat,alt,wkt,kind,length\n\
+             \"a, b\",\"\"\"quoted\"\"\",1,2,3,POINT Z (1 2 3),trail,\n\
+             ,,,,,\"LINESTRING (1 2, 3 4)\",,5\n"
+        );
+
+        let mut tsv = Vec::new();
+        write_csv(&mut tsv, kml.features(), b'\t').unwrap();
+        assert!(String::from_utf8(tsv)
+            .unwrap()
+            .ends_with("\t\t\t\t\tLINESTRING (1 2, 3 4)\t\t5\n"));
+    }
+}
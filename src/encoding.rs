@@ -0,0 +1,56 @@
+//! Detection of non-UTF-8 input so it can be transcoded before parsing
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Returns the encoding of the start of an XML document if it isn't UTF-8, based on its byte
+/// order mark or the `encoding` of its XML declaration
+pub(crate) fn detect_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return Some(encoding).filter(|e| *e != UTF_8);
+    }
+    // UTF-16 without a byte order mark starting with "<?"
+    if bytes.starts_with(&[b'<', 0, b'?', 0]) {
+        return Some(UTF_16LE);
+    }
+    if bytes.starts_with(&[0, b'<', 0, b'?']) {
+        return Some(UTF_16BE);
+    }
+    declared_encoding(bytes).filter(|e| *e != UTF_8)
+}
+
+/// Reads the `encoding` attribute of an XML declaration in an ASCII-compatible encoding
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    if !bytes.starts_with(b"<?xml") {
+        return None;
+    }
+    let decl_end = bytes.windows(2).position(|w| w == b"?>")?;
+    let decl = &bytes[..decl_end];
+    let attr_start = decl.windows(8).position(|w| w == b"encoding")? + 8;
+    let value = decl[attr_start..].trim_ascii_start().strip_prefix(b"=")?;
+    let value = value.trim_ascii_start();
+    let quote = *value.first().filter(|q| **q == b'"' || **q == b'\'')?;
+    let value = &value[1..];
+    let value_end = value.iter().position(|c| *c == quote)?;
+    Encoding::for_label(&value[..value_end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::WINDOWS_1252;
+
+    #[test]
+    fn test_detect_encoding() {
+        assert_eq!(detect_encoding(b"<kml></kml>"), None);
+        assert_eq!(
+            detect_encoding(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><kml></kml>"),
+            None
+        );
+        assert_eq!(
+            detect_encoding(b"<?xml version='1.0' encoding = 'ISO-8859-1'?><kml></kml>"),
+            Some(WINDOWS_1252)
+        );
+        assert_eq!(detect_encoding(b"\xFF\xFE<\0k\0"), Some(UTF_16LE));
+        assert_eq!(detect_encoding(b"\0<\0?\0x\0m\0l"), Some(UTF_16BE));
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBF<kml></kml>"), None);
+    }
+}
@@ -1,8 +1,46 @@
 //! Module for all KML-related errors
+use std::fmt;
+
 use thiserror::Error;
 
+/// Location in the input being read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset from the start of the input
+    pub offset: u64,
+    /// Line number, starting at 1
+    pub line: u64,
+    /// Column in characters, starting at 1
+    pub column: u64,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (byte {})",
+            self.line, self.column, self.offset
+        )
+    }
+}
+
+/// Problem recovered from while reading with
+/// [`KmlReaderConfig::lenient`](reader/struct.KmlReaderConfig.html#structfield.lenient) enabled
+#[derive(Debug)]
+pub struct Warning {
+    pub error: Error,
+    pub position: Position,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.error, self.position)
+    }
+}
+
 /// Errors for KML reading and writing
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Invalid input supplied for XML")]
     InvalidInput(String),
@@ -37,6 +75,55 @@ pub enum Error {
     #[cfg(feature = "zip")]
     #[error("ZIP error: {0}")]
     ZipError(#[from] zip::result::ZipError),
+    #[cfg(feature = "zip")]
+    #[error("KMZ size limit exceeded: {0}")]
+    KmzLimitExceeded(String),
+    #[cfg(feature = "http")]
+    #[error("HTTP error: {0}")]
+    HttpError(Box<ureq::Error>),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    ArrowError(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "shapefile")]
+    #[error("Invalid shapefile: {0}")]
+    InvalidShapefile(String),
+    #[cfg(feature = "shapefile")]
+    #[error("Shapefile error: {0}")]
+    ShapefileError(#[from] shapefile::Error),
+    #[cfg(feature = "shapefile")]
+    #[error("dBase error: {0}")]
+    DbaseError(#[from] shapefile::dbase::Error),
     #[error("Invalid units: {0}")]
     InvalidUnits(String),
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+    #[error("Unexpected element: {0}")]
+    UnexpectedElement(String),
+    #[error("Maximum element depth of {0} exceeded")]
+    MaxDepthExceeded(usize),
+    #[error("{source} at {position}{}", element.as_ref().map(|e| format!(" in {}", e)).unwrap_or_default())]
+    Located {
+        source: Box<Error>,
+        position: Position,
+        element: Option<String>,
+    },
+}
+
+impl Error {
+    /// Returns the position the reader had reached in the input when the error was encountered,
+    /// if known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Error::Located { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error without any attached location
+    pub fn without_location(&self) -> &Error {
+        match self {
+            Error::Located { source, .. } => source.without_location(),
+            err => err,
+        }
+    }
 }
@@ -0,0 +1,612 @@
+//! Module for converting [GeoJSON](https://geojson.org/) feature collections into KML documents
+//!
+//! Each feature becomes a `Placemark` with its properties as `ExtendedData`, apart from the
+//! properties configured in [`GeoJsonConfig`](struct.GeoJsonConfig.html) as the name,
+//! description, style, and folder. Styles are shared between placemarks with the same style.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::geojson::{from_feature_collection, GeoJsonConfig};
+//! use kml::Kml;
+//!
+//! let geojson: geojson::FeatureCollection = r##"{
+//!   "type": "FeatureCollection",
+//!   "features": [{
+//!     "type": "Feature",
+//!     "properties": {
+//!       "name": "Trailhead",
+//!       "folder": "Trails/North",
+//!       "style": {"marker-color": "#ff0000"},
+//!       "length": 5
+//!     },
+//!     "geometry": {"type": "Point", "coordinates": [1, 2]}
+//!   }]
+//! }"##
+//!     .parse()
+//!     .unwrap();
+//! let doc = from_feature_collection::<f64>(&geojson, &GeoJsonConfig::default()).unwrap();
+//! let kml = Kml::KmlDocument(doc).to_string();
+//! assert!(kml.contains("<Folder><name>Trails</name><Folder><name>North</name><Placemark>"));
+//! assert!(kml.contains("<Data name=\"length\"><value>5</value></Data>"));
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use geojson::{Feature, FeatureCollection, JsonValue, Position};
+
+use crate::errors::Error;
+use crate::types::{
+    Coord, CoordType, Document, Element, Folder, Geometry, IconStyle, KmlVersion, LineString,
+    LineStyle, LinearRing, MultiGeometry, Node, Placemark, Point, PolyStyle, Polygon, Style,
+};
+use crate::{Kml, KmlDocument};
+
+/// Configuration for [`from_feature_collection`](fn.from_feature_collection.html)
+///
+/// # Example
+///
+/// ```
+/// use kml::geojson::GeoJsonConfig;
+/// use kml::types::{LineStyle, Style};
+///
+/// let config = GeoJsonConfig::default()
+///     .folder_property(None)
+///     .style(|feature| {
+///         let highway = feature.property("highway")?.as_str()? == "motorway";
+///         highway.then(|| Style {
+///             line: Some(LineStyle {
+///                 width: 4.,
+///                 ..Default::default()
+///             }),
+///             ..Default::default()
+///         })
+///     });
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GeoJsonConfig {
+    /// Property used as the placemark's `name`, `"name"` by default
+    pub name_property: Option<String>,
+    /// Property used as the placemark's `description`, `"description"` by default
+    pub description_property: Option<String>,
+    /// Property used as the placemark's style, `"style"` by default. Objects are read as
+    /// [simplestyle](https://github.com/mapbox/simplestyle-spec) properties like `stroke` and
+    /// `fill-opacity`, and strings as the id of a style to use as the `styleUrl`
+    pub style_property: Option<String>,
+    /// Property with the path of folders to put the placemark in, separated by `/`, `"folder"` by
+    /// default
+    pub folder_property: Option<String>,
+    /// Function returning the style of a feature, taking precedence over
+    /// [`style_property`](#structfield.style_property), set with [`style`](#method.style)
+    pub style: Option<FeatureStyle>,
+}
+
+impl Default for GeoJsonConfig {
+    fn default() -> Self {
+        GeoJsonConfig {
+            name_property: Some("name".to_string()),
+            description_property: Some("description".to_string()),
+            style_property: Some("style".to_string()),
+            folder_property: Some("folder".to_string()),
+            style: None,
+        }
+    }
+}
+
+impl GeoJsonConfig {
+    /// Sets [`name_property`](#structfield.name_property)
+    pub fn name_property(mut self, name_property: Option<String>) -> Self {
+        self.name_property = name_property;
+        self
+    }
+
+    /// Sets [`description_property`](#structfield.description_property)
+    pub fn description_property(mut self, description_property: Option<String>) -> Self {
+        self.description_property = description_property;
+        self
+    }
+
+    /// Sets [`style_property`](#structfield.style_property)
+    pub fn style_property(mut self, style_property: Option<String>) -> Self {
+        self.style_property = style_property;
+        self
+    }
+
+    /// Sets [`folder_property`](#structfield.folder_property)
+    pub fn folder_property(mut self, folder_property: Option<String>) -> Self {
+        self.folder_property = folder_property;
+        self
+    }
+
+    /// Sets the function returning the style of a feature, or `None` to fall back to
+    /// [`style_property`](#structfield.style_property)
+    pub fn style(
+        mut self,
+        style: impl Fn(&Feature) -> Option<Style> + Send + Sync + 'static,
+    ) -> Self {
+        self.style = Some(FeatureStyle(Arc::new(style)));
+        self
+    }
+
+    /// Whether the property is converted to something other than `ExtendedData`
+    fn is_special(&self, property: &str) -> bool {
+        [
+            &self.name_property,
+            &self.description_property,
+            &self.style_property,
+            &self.folder_property,
+        ]
+        .into_iter()
+        .any(|p| p.as_deref() == Some(property))
+    }
+}
+
+/// Function returning the style of a feature, set with
+/// [`GeoJsonConfig::style`](struct.GeoJsonConfig.html#method.style)
+#[derive(Clone)]
+pub struct FeatureStyle(FeatureStyleFn);
+
+type FeatureStyleFn = Arc<dyn Fn(&Feature) -> Option<Style> + Send + Sync>;
+
+impl fmt::Debug for FeatureStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FeatureStyle").finish_non_exhaustive()
+    }
+}
+
+/// Converts a feature collection into a KML 2.2 document, with shared styles followed by the
+/// placemarks and folders in the order they first appear
+pub fn from_feature_collection<T>(
+    collection: &FeatureCollection,
+    config: &GeoJsonConfig,
+) -> Result<KmlDocument<T>, Error>
+where
+    T: CoordType + Default,
+{
+    let mut styles: Vec<Style> = Vec::new();
+    let mut root: Vec<Kml<T>> = Vec::new();
+    for feature in &collection.features {
+        let mut placemark = to_placemark(feature, config)?;
+        if let Some(style) = feature_style(feature, config, &mut placemark) {
+            let index = match styles.iter().position(|s| *s == style) {
+                Some(index) => index,
+                None => {
+                    styles.push(style);
+                    styles.len() - 1
+                }
+            };
+            placemark.style_url = Some(format!("#style-{}", index + 1));
+        }
+
+        let folders = config
+            .folder_property
+            .as_deref()
+            .and_then(|p| feature.property(p))
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .split('/')
+            .map(str::trim)
+            .filter(|f| !f.is_empty());
+        let mut elements = &mut root;
+        for folder in folders {
+            elements = folder_elements(elements, folder);
+        }
+        elements.push(Kml::Placemark(placemark));
+    }
+
+    let mut elements: Vec<Kml<T>> = styles
+        .into_iter()
+        .enumerate()
+        .map(|(i, style)| {
+            Kml::Style(Style {
+                id: Some(format!("style-{}", i + 1)),
+                ..style
+            })
+        })
+        .collect();
+    elements.extend(root);
+    Ok(KmlDocument {
+        version: KmlVersion::V22,
+        elements: vec![Kml::Document(Document {
+            elements,
+            ..Default::default()
+        })],
+        ..Default::default()
+    })
+}
+
+/// Returns the elements of the folder named `name` in `elements`, adding it if it's missing
+fn folder_elements<'a, T: CoordType>(
+    elements: &'a mut Vec<Kml<T>>,
+    name: &str,
+) -> &'a mut Vec<Kml<T>> {
+    let is_folder = |e: &Kml<T>| matches!(e, Kml::Folder(f) if f.name.as_deref() == Some(name));
+    let index = match elements.iter().position(is_folder) {
+        Some(index) => index,
+        None => {
+            elements.push(Kml::Folder(Folder {
+                name: Some(name.to_string()),
+                ..Default::default()
+            }));
+            elements.len() - 1
+        }
+    };
+    match &mut elements[index] {
+        Kml::Folder(Folder { elements, .. }) => elements,
+        _ => unreachable!(),
+    }
+}
+
+fn to_placemark<T>(feature: &Feature, config: &GeoJsonConfig) -> Result<Placemark<T>, Error>
+where
+    T: CoordType + Default,
+{
+    let text = |property: &Option<String>| {
+        property
+            .as_deref()
+            .and_then(|p| feature.property(p))
+            .and_then(json_text)
+    };
+    let mut attrs = HashMap::new();
+    match &feature.id {
+        Some(geojson::feature::Id::String(id)) => attrs.insert("id".to_string(), id.clone()),
+        Some(geojson::feature::Id::Number(id)) => attrs.insert("id".to_string(), id.to_string()),
+        None => None,
+    };
+
+    let data: Vec<Node> = feature
+        .properties_iter()
+        .filter(|(name, _)| !config.is_special(name))
+        .filter_map(|(name, value)| {
+            let value = json_text(value)?;
+            Some(Node::Element(Element {
+                name: "Data".to_string(),
+                attrs: HashMap::from([("name".to_string(), name.clone())]),
+                children: vec![text_element("value", &value).into()],
+                ..Default::default()
+            }))
+        })
+        .collect();
+    let mut children = Vec::new();
+    if !data.is_empty() {
+        children.push(Node::Element(Element {
+            name: "ExtendedData".to_string(),
+            children: data,
+            ..Default::default()
+        }));
+    }
+
+    Ok(Placemark {
+        name: text(&config.name_property),
+        description: text(&config.description_property),
+        geometry: feature
+            .geometry
+            .as_ref()
+            .map(|g| to_geometry(&g.value))
+            .transpose()?,
+        attrs,
+        children,
+        ..Default::default()
+    })
+}
+
+/// Returns the feature's style from the configured function or its style property, setting the
+/// placemark's `styleUrl` instead for style properties naming a style
+fn feature_style<T: CoordType>(
+    feature: &Feature,
+    config: &GeoJsonConfig,
+    placemark: &mut Placemark<T>,
+) -> Option<Style> {
+    if let Some(style) = config.style.as_ref().and_then(|s| (s.0)(feature)) {
+        return Some(style);
+    }
+    match feature.property(config.style_property.as_deref()?)? {
+        JsonValue::String(id) => {
+            placemark.style_url = Some(format!("#{}", id.trim_start_matches('#')));
+            None
+        }
+        JsonValue::Object(properties) => simplestyle(properties),
+        _ => None,
+    }
+}
+
+/// Converts [simplestyle](https://github.com/mapbox/simplestyle-spec) properties to a style
+fn simplestyle(properties: &geojson::JsonObject) -> Option<Style> {
+    let color = |color: &str, opacity: &str, default_opacity: f64| {
+        let color = properties.get(color)?.as_str()?;
+        let opacity = properties
+            .get(opacity)
+            .and_then(JsonValue::as_f64)
+            .unwrap_or(default_opacity);
+        kml_color(color, opacity)
+    };
+
+    let mut style = Style::default();
+    let stroke = color("stroke", "stroke-opacity", 1.);
+    let width = properties.get("stroke-width").and_then(JsonValue::as_f64);
+    if stroke.is_some() || width.is_some() {
+        let default = LineStyle::default();
+        style.line = Some(LineStyle {
+            color: stroke.unwrap_or(default.color.clone()),
+            width: width.unwrap_or(default.width),
+            ..default
+        });
+    }
+    if let Some(fill) = color("fill", "fill-opacity", 0.6) {
+        style.poly = Some(PolyStyle {
+            color: fill,
+            ..Default::default()
+        });
+    }
+    if let Some(marker) = color("marker-color", "", 1.) {
+        style.icon = Some(IconStyle {
+            color: marker,
+            ..Default::default()
+        });
+    }
+    (style != Style::default()).then_some(style)
+}
+
+/// Converts a CSS hex color like `#ff8800` or `#f80` and opacity to a KML `aabbggrr` color
+fn kml_color(color: &str, opacity: f64) -> Option<String> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let hex: String = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let (r, g, b) = (channel(0)?, channel(2)?, channel(4)?);
+    let a = (opacity.clamp(0., 1.) * 255.).round() as u8;
+    Some(format!("{a:02x}{b:02x}{g:02x}{r:02x}"))
+}
+
+fn text_element(name: &str, content: &str) -> Element {
+    Element {
+        name: name.to_string(),
+        content: Some(content.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Text of a property value, with strings unquoted, other values as JSON, and nulls skipped
+fn json_text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(s.clone()),
+        v => Some(v.to_string()),
+    }
+}
+
+/// Converts a GeoJSON geometry, with multi-part geometries and collections as `MultiGeometry`
+pub fn to_geometry<T>(value: &geojson::Value) -> Result<Geometry<T>, Error>
+where
+    T: CoordType + Default,
+{
+    let line_string = |positions: &[Position]| -> Result<LineString<T>, Error> {
+        Ok(LineString::from(to_coords(positions)?))
+    };
+    let polygon = |rings: &[Vec<Position>]| -> Result<Polygon<T>, Error> {
+        let mut rings = rings
+            .iter()
+            .map(|r| to_coords(r).map(LinearRing::from))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let outer = rings
+            .next()
+            .ok_or_else(|| Error::InvalidGeometry("Polygon without rings".to_string()))?;
+        Ok(Polygon::new(outer, rings.collect()))
+    };
+    let multi =
+        |geometries: Vec<Geometry<T>>| Geometry::MultiGeometry(MultiGeometry::new(geometries));
+
+    Ok(match value {
+        geojson::Value::Point(p) => Geometry::Point(Point::from(to_coord(p)?)),
+        geojson::Value::MultiPoint(points) => multi(
+            points
+                .iter()
+                .map(|p| Ok(Geometry::Point(Point::from(to_coord(p)?))))
+                .collect::<Result<_, Error>>()?,
+        ),
+        geojson::Value::LineString(l) => Geometry::LineString(line_string(l)?),
+        geojson::Value::MultiLineString(lines) => multi(
+            lines
+                .iter()
+                .map(|l| line_string(l).map(Geometry::LineString))
+                .collect::<Result<_, _>>()?,
+        ),
+        geojson::Value::Polygon(p) => Geometry::Polygon(polygon(p)?),
+        geojson::Value::MultiPolygon(polygons) => multi(
+            polygons
+                .iter()
+                .map(|p| polygon(p).map(Geometry::Polygon))
+                .collect::<Result<_, _>>()?,
+        ),
+        geojson::Value::GeometryCollection(geometries) => multi(
+            geometries
+                .iter()
+                .map(|g| to_geometry(&g.value))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+fn to_coords<T: CoordType + Default>(positions: &[Position]) -> Result<Vec<Coord<T>>, Error> {
+    positions.iter().map(|p| to_coord(p)).collect()
+}
+
+fn to_coord<T: CoordType + Default>(position: &[f64]) -> Result<Coord<T>, Error> {
+    let value = |v: f64| {
+        T::from(v).ok_or_else(|| Error::InvalidGeometry(format!("Invalid coordinate value {v}")))
+    };
+    match position {
+        [x, y] => Ok(Coord::new(value(*x)?, value(*y)?, None)),
+        [x, y, z, ..] => Ok(Coord::new(value(*x)?, value(*y)?, Some(value(*z)?))),
+        _ => Err(Error::InvalidGeometry(format!(
+            "Position with {} values",
+            position.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection(json: &str) -> FeatureCollection {
+        json.parse().unwrap()
+    }
+
+    fn geometry_value(json: &str) -> geojson::Value {
+        json.parse::<geojson::Geometry>().unwrap().value
+    }
+
+    #[test]
+    fn test_to_geometry() {
+        let value = geometry_value(
+            r#"{"type": "MultiPolygon", "coordinates": [[[[0, 0], [1, 0], [0, 0]], [[0.1, 0.1, 5], [0.2, 0.1, 5], [0.1, 0.1, 5]]]]}"#,
+        );
+        let ring = |coords: Vec<Coord>| LinearRing::from(coords);
+        assert_eq!(
+            to_geometry::<f64>(&value).unwrap(),
+            Geometry::MultiGeometry(MultiGeometry::new(vec![Geometry::Polygon(Polygon::new(
+                ring(vec![
+                    Coord::new(0., 0., None),
+                    Coord::new(1., 0., None),
+                    Coord::new(0., 0., None)
+                ]),
+                vec![ring(vec![
+                    Coord::new(0.1, 0.1, Some(5.)),
+                    Coord::new(0.2, 0.1, Some(5.)),
+                    Coord::new(0.1, 0.1, Some(5.))
+                ])]
+            ))]))
+        );
+
+        let value = geojson::Value::Point(vec![1.]);
+        assert!(matches!(
+            to_geometry::<f64>(&value),
+            Err(Error::InvalidGeometry(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_feature_collection() {
+        let geojson = collection(
+            r##"{
+              "type": "FeatureCollection",
+              "features": [
+                {
+                  "type": "Feature",
+                  "id": 7,
+                  "properties": {
+                    "name": "a",
+                    "folder": "Roads",
+                    "style": {"stroke": "#ff0000", "stroke-width": 3},
+                    "lanes": 2,
+                    "toll": null
+                  },
+                  "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}
+                },
+                {
+                  "type": "Feature",
+                  "properties": {
+                    "description": "b",
+                    "folder": "Roads",
+                    "style": {"stroke": "#f00", "stroke-width": 3}
+                  },
+                  "geometry": null
+                },
+                {
+                  "type": "Feature",
+                  "properties": {"style": "existing"},
+                  "geometry": {"type": "Point", "coordinates": [0, 0]}
+                }
+              ]
+            }"##,
+        );
+        let doc: KmlDocument =
+            from_feature_collection(&geojson, &GeoJsonConfig::default()).unwrap();
+        let [Kml::Document(Document { elements, .. })] = &doc.elements[..] else {
+            panic!("expected a Document");
+        };
+        assert_eq!(elements.len(), 3);
+        assert_eq!(
+            elements[0],
+            Kml::Style(Style {
+                id: Some("style-1".to_string()),
+                line: Some(LineStyle {
+                    color: "ff0000ff".to_string(),
+                    width: 3.,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        );
+
+        let Kml::Folder(Folder {
+            name,
+            elements: folder,
+            ..
+        }) = &elements[1]
+        else {
+            panic!("expected a Folder");
+        };
+        assert_eq!(name.as_deref(), Some("Roads"));
+        let placemarks: Vec<&Placemark> = folder
+            .iter()
+            .filter_map(|e| match e {
+                Kml::Placemark(p) => Some(p),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(placemarks.len(), 2);
+        assert_eq!(placemarks[0].name.as_deref(), Some("a"));
+        assert_eq!(placemarks[0].attrs["id"], "7");
+        assert_eq!(placemarks[0].style_url.as_deref(), Some("#style-1"));
+        assert_eq!(
+            placemarks[0].properties(),
+            HashMap::from([("lanes".to_string(), "2".to_string())])
+        );
+        assert_eq!(placemarks[1].description.as_deref(), Some("b"));
+        assert_eq!(placemarks[1].style_url.as_deref(), Some("#style-1"));
+        assert_eq!(placemarks[1].geometry, None);
+
+        let Kml::Placemark(point) = &elements[2] else {
+            panic!("expected a Placemark");
+        };
+        assert_eq!(point.style_url.as_deref(), Some("#existing"));
+    }
+
+    #[test]
+    fn test_style_callback() {
+        let geojson = collection(
+            r#"{"type": "FeatureCollection", "features": [
+              {"type": "Feature", "properties": {"kind": "a"}, "geometry": null},
+              {"type": "Feature", "properties": {"kind": "b"}, "geometry": null}
+            ]}"#,
+        );
+        let config = GeoJsonConfig::default().style(|feature| {
+            (feature.property("kind")?.as_str()? == "a").then(|| Style {
+                poly: Some(PolyStyle::default()),
+                ..Default::default()
+            })
+        });
+        let doc: KmlDocument = from_feature_collection(&geojson, &config).unwrap();
+        let [Kml::Document(Document { elements, .. })] = &doc.elements[..] else {
+            panic!("expected a Document");
+        };
+        assert!(matches!(&elements[0], Kml::Style(s) if s.poly.is_some()));
+        assert!(matches!(&elements[1], Kml::Placemark(p) if p.style_url.is_some()));
+        assert!(matches!(&elements[2], Kml::Placemark(p) if p.style_url.is_none()));
+    }
+
+    #[test]
+    fn test_kml_color() {
+        assert_eq!(kml_color("#ff8800", 1.).as_deref(), Some("ff0088ff"));
+        assert_eq!(kml_color("f80", 0.5).as_deref(), Some("800088ff"));
+        assert_eq!(kml_color("red", 1.), None);
+    }
+}
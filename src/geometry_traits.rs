@@ -0,0 +1,510 @@
+//! Implementations of the [`geo-traits`](https://docs.rs/geo-traits) interfaces for KML geometries,
+//! allowing algorithms written against the traits to use them without conversion
+//!
+//! `Geometry` implements `LineStringTrait` and `GeometryCollectionTrait` itself so its
+//! `LineString`, `LinearRing`, and `Track` variants can all be viewed as line strings, and its
+//! `MultiGeometry` and `Element` variants as (possibly empty) collections. Variants of other types
+//! have no coordinates or geometries in those views.
+use geo_traits::{
+    CoordTrait, Dimensions, GeometryCollectionTrait, GeometryTrait, GeometryType, LineStringTrait,
+    MultiLineStringTrait, PointTrait, PolygonTrait, UnimplementedLine, UnimplementedMultiPoint,
+    UnimplementedMultiPolygon, UnimplementedRect, UnimplementedTriangle,
+};
+
+use crate::types::{
+    Coord, CoordType, Geometry, LineString, LinearRing, MultiGeometry, MultiTrack, Point, Polygon,
+    Track,
+};
+
+/// Dimensions of coordinates, which only include `z` if every coordinate has one
+fn coords_dim<'a, T: CoordType + 'a>(mut coords: impl Iterator<Item = &'a Coord<T>>) -> Dimensions {
+    match coords.next() {
+        Some(first) if first.z.is_some() && coords.all(|c| c.z.is_some()) => Dimensions::Xyz,
+        _ => Dimensions::Xy,
+    }
+}
+
+/// Dimensions of geometries, which only include `z` if every geometry has it
+fn geometries_dim<'a, G: Dims + 'a>(mut geometries: impl Iterator<Item = &'a G>) -> Dimensions {
+    match geometries.next() {
+        Some(first)
+            if first.dims() == Dimensions::Xyz
+                && geometries.all(|g| g.dims() == Dimensions::Xyz) =>
+        {
+            Dimensions::Xyz
+        }
+        _ => Dimensions::Xy,
+    }
+}
+
+/// Coordinates of the variants of `Geometry` that are viewed as line strings
+fn geometry_coords<T: CoordType>(geometry: &Geometry<T>) -> &[Coord<T>] {
+    match geometry {
+        Geometry::LineString(l) => &l.coords,
+        Geometry::LinearRing(l) => &l.coords,
+        Geometry::Track(t) => &t.coords,
+        _ => &[],
+    }
+}
+
+/// Geometries of the variants of `Geometry` that are viewed as collections
+fn geometry_geometries<T: CoordType>(geometry: &Geometry<T>) -> &[Geometry<T>] {
+    match geometry {
+        Geometry::MultiGeometry(g) => &g.geometries,
+        _ => &[],
+    }
+}
+
+macro_rules! impl_coord_trait {
+    ($($ty:ty),*) => {$(
+        impl<T: CoordType> CoordTrait for $ty {
+            type T = T;
+
+            fn dim(&self) -> Dimensions {
+                if self.z.is_some() {
+                    Dimensions::Xyz
+                } else {
+                    Dimensions::Xy
+                }
+            }
+
+            fn x(&self) -> T {
+                self.x
+            }
+
+            fn y(&self) -> T {
+                self.y
+            }
+
+            fn nth_or_panic(&self, n: usize) -> T {
+                match (n, self.z) {
+                    (0, _) => self.x,
+                    (1, _) => self.y,
+                    (2, Some(z)) => z,
+                    _ => panic!("Coord doesn't have dimension {n}"),
+                }
+            }
+        }
+    )*};
+}
+
+impl_coord_trait!(Coord<T>, &Coord<T>);
+
+/// Dimensions of a geometry, shared by its `GeometryTrait` implementations for values and
+/// references
+trait Dims {
+    fn dims(&self) -> Dimensions;
+}
+
+impl<T: CoordType> Dims for Point<T> {
+    fn dims(&self) -> Dimensions {
+        self.coord.dim()
+    }
+}
+
+impl<T: CoordType> Dims for LineString<T> {
+    fn dims(&self) -> Dimensions {
+        coords_dim(self.coords.iter())
+    }
+}
+
+impl<T: CoordType> Dims for LinearRing<T> {
+    fn dims(&self) -> Dimensions {
+        coords_dim(self.coords.iter())
+    }
+}
+
+impl<T: CoordType> Dims for Track<T> {
+    fn dims(&self) -> Dimensions {
+        coords_dim(self.coords.iter())
+    }
+}
+
+impl<T: CoordType> Dims for Polygon<T> {
+    fn dims(&self) -> Dimensions {
+        let inner = self.inner.iter().flat_map(|r| &r.coords);
+        coords_dim(self.outer.coords.iter().chain(inner))
+    }
+}
+
+impl<T: CoordType> Dims for MultiTrack<T> {
+    fn dims(&self) -> Dimensions {
+        geometries_dim(self.tracks.iter())
+    }
+}
+
+impl<T: CoordType> Dims for MultiGeometry<T> {
+    fn dims(&self) -> Dimensions {
+        geometries_dim(self.geometries.iter())
+    }
+}
+
+impl<T: CoordType> Dims for Geometry<T> {
+    fn dims(&self) -> Dimensions {
+        match self {
+            Geometry::Point(p) => p.dims(),
+            Geometry::Polygon(p) => p.dims(),
+            Geometry::MultiTrack(t) => t.dims(),
+            Geometry::MultiGeometry(g) => g.dims(),
+            g => coords_dim(geometry_coords(g).iter()),
+        }
+    }
+}
+
+/// Implements `GeometryTrait` for a type and references to it, with `$variant` being the
+/// `GeometryType` it's viewed as
+macro_rules! impl_geometry_trait {
+    ($ty:ident, $line_string:ty, $collection:ty, $variant:ident) => {
+        impl_geometry_trait!(@impl $ty<T>, $line_string, $collection, $variant);
+        impl_geometry_trait!(@impl &$ty<T>, $line_string, $collection, $variant);
+    };
+    (@impl $ty:ty, $line_string:ty, $collection:ty, $variant:ident) => {
+        impl<T: CoordType> GeometryTrait for $ty {
+            type T = T;
+            type PointType<'b>
+                = Point<T>
+            where
+                Self: 'b;
+            type LineStringType<'b>
+                = $line_string
+            where
+                Self: 'b;
+            type PolygonType<'b>
+                = Polygon<T>
+            where
+                Self: 'b;
+            type MultiPointType<'b>
+                = UnimplementedMultiPoint<T>
+            where
+                Self: 'b;
+            type MultiLineStringType<'b>
+                = MultiTrack<T>
+            where
+                Self: 'b;
+            type MultiPolygonType<'b>
+                = UnimplementedMultiPolygon<T>
+            where
+                Self: 'b;
+            type GeometryCollectionType<'b>
+                = $collection
+            where
+                Self: 'b;
+            type RectType<'b>
+                = UnimplementedRect<T>
+            where
+                Self: 'b;
+            type TriangleType<'b>
+                = UnimplementedTriangle<T>
+            where
+                Self: 'b;
+            type LineType<'b>
+                = UnimplementedLine<T>
+            where
+                Self: 'b;
+
+            fn dim(&self) -> Dimensions {
+                self.dims()
+            }
+
+            fn as_type(
+                &self,
+            ) -> GeometryType<
+                '_,
+                Point<T>,
+                $line_string,
+                Polygon<T>,
+                UnimplementedMultiPoint<T>,
+                MultiTrack<T>,
+                UnimplementedMultiPolygon<T>,
+                $collection,
+                UnimplementedRect<T>,
+                UnimplementedTriangle<T>,
+                UnimplementedLine<T>,
+            > {
+                GeometryType::$variant(self)
+            }
+        }
+    };
+}
+
+impl_geometry_trait!(Point, LineString<T>, MultiGeometry<T>, Point);
+impl_geometry_trait!(LineString, LineString<T>, MultiGeometry<T>, LineString);
+impl_geometry_trait!(LinearRing, LinearRing<T>, MultiGeometry<T>, LineString);
+impl_geometry_trait!(Track, Track<T>, MultiGeometry<T>, LineString);
+impl_geometry_trait!(Polygon, LineString<T>, MultiGeometry<T>, Polygon);
+impl_geometry_trait!(MultiTrack, LineString<T>, MultiGeometry<T>, MultiLineString);
+impl_geometry_trait!(
+    MultiGeometry,
+    LineString<T>,
+    MultiGeometry<T>,
+    GeometryCollection
+);
+
+macro_rules! impl_geometry_trait_for_geometry {
+    ($($ty:ty),*) => {$(
+        impl<T: CoordType> GeometryTrait for $ty {
+            type T = T;
+            type PointType<'b>
+                = Point<T>
+            where
+                Self: 'b;
+            type LineStringType<'b>
+                = Geometry<T>
+            where
+                Self: 'b;
+            type PolygonType<'b>
+                = Polygon<T>
+            where
+                Self: 'b;
+            type MultiPointType<'b>
+                = UnimplementedMultiPoint<T>
+            where
+                Self: 'b;
+            type MultiLineStringType<'b>
+                = MultiTrack<T>
+            where
+                Self: 'b;
+            type MultiPolygonType<'b>
+                = UnimplementedMultiPolygon<T>
+            where
+                Self: 'b;
+            type GeometryCollectionType<'b>
+                = Geometry<T>
+            where
+                Self: 'b;
+            type RectType<'b>
+                = UnimplementedRect<T>
+            where
+                Self: 'b;
+            type TriangleType<'b>
+                = UnimplementedTriangle<T>
+            where
+                Self: 'b;
+            type LineType<'b>
+                = UnimplementedLine<T>
+            where
+                Self: 'b;
+
+            fn dim(&self) -> Dimensions {
+                self.dims()
+            }
+
+            fn as_type(
+                &self,
+            ) -> GeometryType<
+                '_,
+                Point<T>,
+                Geometry<T>,
+                Polygon<T>,
+                UnimplementedMultiPoint<T>,
+                MultiTrack<T>,
+                UnimplementedMultiPolygon<T>,
+                Geometry<T>,
+                UnimplementedRect<T>,
+                UnimplementedTriangle<T>,
+                UnimplementedLine<T>,
+            > {
+                let geometry: &Geometry<T> = self;
+                match geometry {
+                    Geometry::Point(p) => GeometryType::Point(p),
+                    Geometry::LineString(_) | Geometry::LinearRing(_) | Geometry::Track(_) => {
+                        GeometryType::LineString(geometry)
+                    }
+                    Geometry::Polygon(p) => GeometryType::Polygon(p),
+                    Geometry::MultiTrack(t) => GeometryType::MultiLineString(t),
+                    Geometry::MultiGeometry(_) | Geometry::Element(_) => {
+                        GeometryType::GeometryCollection(geometry)
+                    }
+                }
+            }
+        }
+    )*};
+}
+
+impl_geometry_trait_for_geometry!(Geometry<T>, &Geometry<T>);
+
+macro_rules! impl_point_trait {
+    ($($ty:ty),*) => {$(
+        impl<T: CoordType> PointTrait for $ty {
+            type CoordType<'a>
+                = &'a Coord<T>
+            where
+                Self: 'a;
+
+            fn coord(&self) -> Option<Self::CoordType<'_>> {
+                Some(&self.coord)
+            }
+        }
+    )*};
+}
+
+impl_point_trait!(Point<T>, &Point<T>);
+
+/// Implements `LineStringTrait` for types and references to them, with `$coords` getting the
+/// coordinates from `$geom`
+macro_rules! impl_line_string_trait {
+    ($($ty:ident => |$geom:ident| $coords:expr),*) => {$(
+        impl_line_string_trait!(@impl $ty<T>, |$geom| $coords);
+        impl_line_string_trait!(@impl &$ty<T>, |$geom| $coords);
+    )*};
+    (@impl $ty:ty, |$geom:ident| $coords:expr) => {
+        impl<T: CoordType> LineStringTrait for $ty {
+            type CoordType<'a>
+                = &'a Coord<T>
+            where
+                Self: 'a;
+
+            fn num_coords(&self) -> usize {
+                let $geom: &$ty = self;
+                $coords.len()
+            }
+
+            unsafe fn coord_unchecked(&self, i: usize) -> Self::CoordType<'_> {
+                let $geom: &$ty = self;
+                $coords.get_unchecked(i)
+            }
+        }
+    };
+}
+
+impl_line_string_trait!(
+    LineString => |l| l.coords,
+    LinearRing => |l| l.coords,
+    Track => |t| t.coords,
+    Geometry => |g| geometry_coords(g)
+);
+
+macro_rules! impl_polygon_trait {
+    ($($ty:ty),*) => {$(
+        impl<T: CoordType> PolygonTrait for $ty {
+            type RingType<'a>
+                = &'a LinearRing<T>
+            where
+                Self: 'a;
+
+            fn exterior(&self) -> Option<Self::RingType<'_>> {
+                Some(&self.outer)
+            }
+
+            fn num_interiors(&self) -> usize {
+                self.inner.len()
+            }
+
+            unsafe fn interior_unchecked(&self, i: usize) -> Self::RingType<'_> {
+                self.inner.get_unchecked(i)
+            }
+        }
+    )*};
+}
+
+impl_polygon_trait!(Polygon<T>, &Polygon<T>);
+
+macro_rules! impl_multi_line_string_trait {
+    ($($ty:ty),*) => {$(
+        impl<T: CoordType> MultiLineStringTrait for $ty {
+            type InnerLineStringType<'a>
+                = &'a Track<T>
+            where
+                Self: 'a;
+
+            fn num_line_strings(&self) -> usize {
+                self.tracks.len()
+            }
+
+            unsafe fn line_string_unchecked(&self, i: usize) -> Self::InnerLineStringType<'_> {
+                self.tracks.get_unchecked(i)
+            }
+        }
+    )*};
+}
+
+impl_multi_line_string_trait!(MultiTrack<T>, &MultiTrack<T>);
+
+/// Implements `GeometryCollectionTrait` for types and references to them, with `$geometries`
+/// getting the geometries from `$geom`
+macro_rules! impl_geometry_collection_trait {
+    ($($ty:ident => |$geom:ident| $geometries:expr),*) => {$(
+        impl_geometry_collection_trait!(@impl $ty<T>, |$geom| $geometries);
+        impl_geometry_collection_trait!(@impl &$ty<T>, |$geom| $geometries);
+    )*};
+    (@impl $ty:ty, |$geom:ident| $geometries:expr) => {
+        impl<T: CoordType> GeometryCollectionTrait for $ty {
+            type GeometryType<'a>
+                = &'a Geometry<T>
+            where
+                Self: 'a;
+
+            fn num_geometries(&self) -> usize {
+                let $geom: &$ty = self;
+                $geometries.len()
+            }
+
+            unsafe fn geometry_unchecked(&self, i: usize) -> Self::GeometryType<'_> {
+                let $geom: &$ty = self;
+                $geometries.get_unchecked(i)
+            }
+        }
+    };
+}
+
+impl_geometry_collection_trait!(
+    MultiGeometry => |g| g.geometries,
+    Geometry => |g| geometry_geometries(g)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum_x<G: GeometryTrait<T = f64>>(geometry: &G) -> f64 {
+        match geometry.as_type() {
+            GeometryType::Point(p) => p.coord().map_or(0., |c| c.x()),
+            GeometryType::LineString(l) => l.coords().map(|c| c.x()).sum(),
+            GeometryType::Polygon(p) => p
+                .exterior()
+                .into_iter()
+                .chain(p.interiors())
+                .flat_map(|r| r.coords().map(|c| c.x()).collect::<Vec<_>>())
+                .sum(),
+            GeometryType::MultiLineString(m) => m
+                .line_strings()
+                .flat_map(|l| l.coords().map(|c| c.x()).collect::<Vec<_>>())
+                .sum(),
+            GeometryType::GeometryCollection(g) => g.geometries().map(|g| sum_x(&g)).sum(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_geo_traits() {
+        let ring = |x: f64| {
+            LinearRing::from(vec![
+                Coord::new(x, 0., None),
+                Coord::new(x, 1., None),
+                Coord::new(x, 0., None),
+            ])
+        };
+        let geometry = Geometry::MultiGeometry(MultiGeometry::new(vec![
+            Geometry::Point(Point::new(1., 2., Some(3.))),
+            Geometry::LineString(LineString::from(vec![
+                Coord::new(2., 0., Some(1.)),
+                Coord::new(3., 0., None),
+            ])),
+            Geometry::LinearRing(ring(4.)),
+            Geometry::Polygon(Polygon::new(ring(5.), vec![ring(6.)])),
+            Geometry::MultiTrack(MultiTrack::new(vec![Track::from(vec![Coord::new(
+                7., 0., None,
+            )])])),
+            Geometry::Element(Default::default()),
+        ]));
+
+        assert_eq!(sum_x(&geometry), 1. + 5. + 12. + 15. + 18. + 7.);
+        assert_eq!(geometry.dim(), Dimensions::Xy);
+
+        let point = Point::new(1., 2., Some(3.));
+        assert_eq!(point.dim(), Dimensions::Xyz);
+        assert_eq!(point.coord().unwrap().nth(2), Some(3.));
+        let coord = Coord::new(1., 2., None);
+        assert_eq!(coord.nth(2), None);
+    }
+}
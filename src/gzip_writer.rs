@@ -0,0 +1,132 @@
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::io::Write;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::str::FromStr;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::errors::Error;
+use crate::types::CoordType;
+#[cfg(feature = "fs")]
+use crate::types::Kml;
+use crate::writer::{KmlWriter, KmlWriterConfig};
+
+#[cfg_attr(docsrs, doc(cfg(feature = "flate2")))]
+impl<W, T> KmlWriter<GzEncoder<W>, T>
+where
+    W: Write,
+    T: CoordType + FromStr + Default + fmt::Display,
+{
+    /// Create a [`KmlWriter`](struct.KmlWriter.html) that gzip-compresses everything written to
+    /// `w`, like a `.kml.gz` file or a response body. Call [`finish`](#method.finish) when done
+    /// to write the end of the gzip stream
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::Point, Kml, KmlWriter};
+    ///
+    /// let kml = Kml::Point(Point::new(1., 1., None));
+    ///
+    /// let mut writer = KmlWriter::from_gzip_writer(Vec::new());
+    /// writer.write_document(&kml).unwrap();
+    /// let compressed = writer.finish().unwrap();
+    /// ```
+    pub fn from_gzip_writer(w: W) -> KmlWriter<GzEncoder<W>, T> {
+        KmlWriter::from_writer(GzEncoder::new(w, Compression::default()))
+    }
+
+    /// Create a gzip-compressing [`KmlWriter`](struct.KmlWriter.html) like
+    /// [`from_gzip_writer`](#method.from_gzip_writer) with a
+    /// [`KmlWriterConfig`](struct.KmlWriterConfig.html)
+    pub fn from_gzip_writer_with_config(
+        w: W,
+        config: KmlWriterConfig,
+    ) -> KmlWriter<GzEncoder<W>, T> {
+        KmlWriter::from_writer_with_config(GzEncoder::new(w, Compression::default()), config)
+    }
+
+    /// Writes the end of the gzip stream and returns the underlying writer
+    pub fn finish(self) -> Result<W, Error> {
+        Ok(self.into_inner().finish()?)
+    }
+}
+
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "flate2", feature = "fs"))))]
+impl<T> KmlWriter<GzEncoder<BufWriter<File>>, T>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+{
+    /// Writes KML to a gzip-compressed file at `path` with the provided configuration as a
+    /// complete document like [`write_document`](#method.write_document), replacing the file if it
+    /// exists
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kml::{types::Point, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let kml = Kml::Point(Point::new(1., 1., None));
+    /// KmlWriter::to_gzip_path("point.kml.gz", &kml, KmlWriterConfig::default()).unwrap();
+    /// ```
+    pub fn to_gzip_path<P: AsRef<Path>>(
+        path: P,
+        kml: &Kml<T>,
+        config: KmlWriterConfig,
+    ) -> Result<(), Error> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut writer = KmlWriter::from_gzip_writer_with_config(file, config);
+        writer.write_document(kml)?;
+        writer.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Kml, Point};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn decompress(bytes: &[u8]) -> String {
+        let mut decompressed = String::new();
+        GzDecoder::new(bytes)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        decompressed
+    }
+
+    #[test]
+    fn test_write_gzip() {
+        let kml = Kml::Point(Point::new(1., 1., None));
+        let mut writer = KmlWriter::from_gzip_writer(Vec::new());
+        writer.write(&kml).unwrap();
+        let compressed = writer.finish().unwrap();
+
+        assert_eq!(decompress(&compressed), kml.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_to_gzip_path() {
+        let path = std::env::temp_dir().join(format!("kml-test-{}.kml.gz", std::process::id()));
+        let kml = Kml::Point(Point::new(1.23456, 1., None));
+        let config = KmlWriterConfig::default().precision(Some(2));
+        KmlWriter::to_gzip_path(&path, &kml, config).unwrap();
+        let written = std::fs::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            decompress(&written.unwrap()),
+            r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.2"><Point><coordinates>1.23,1</coordinates></Point></kml>"#
+        );
+    }
+}
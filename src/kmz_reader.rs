@@ -1,20 +1,303 @@
+#[cfg(feature = "fs")]
 use std::fs::File;
-use std::io::Cursor;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom, Take};
+#[cfg(feature = "fs")]
 use std::path::Path;
 use std::str::FromStr;
 
-use zip::ZipArchive;
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+use zip::result::ZipError;
+use zip::{CompressionMethod, ZipArchive};
 
 use crate::errors::Error;
-use crate::reader::KmlReader;
+use crate::reader::{KmlReader, KmlReaderConfig};
 use crate::types::CoordType;
 
+/// Largest buffer allocated up front for a KML file in a KMZ archive
+const MAX_PREALLOCATION: u64 = 64 * 1024 * 1024;
+
+/// KMZ archive opened for choosing among the KML files in it, like the many files of a
+/// regionated KMZ, and reading the icons, images, and models they link to
+///
+/// # Example
+///
+/// ```
+/// use std::fs::File;
+/// use std::path::Path;
+/// use kml::KmzArchive;
+///
+/// let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+///     .join("tests")
+///     .join("fixtures")
+///     .join("polygon.kmz");
+/// let mut archive = KmzArchive::new(File::open(kmz_path).unwrap()).unwrap();
+/// let names: Vec<String> = archive.kml_files().map(str::to_string).collect();
+/// for name in names {
+///     let kml = archive.kml_reader::<f64>(&name).unwrap().read().unwrap();
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+#[derive(Debug)]
+pub struct KmzArchive<R: Read + Seek> {
+    archive: ZipArchive<R>,
+    config: KmlReaderConfig,
+}
+
+impl<R: Read + Seek> KmzArchive<R> {
+    /// Opens a reader of KMZ data as an archive
+    pub fn new(reader: R) -> Result<Self, Error> {
+        KmzArchive::with_config(reader, KmlReaderConfig::default())
+    }
+
+    /// Opens a reader of KMZ data as an archive with a
+    /// [`KmlReaderConfig`](reader/struct.KmlReaderConfig.html), which is used for the
+    /// [`KmlReader`](struct.KmlReader.html)s it creates and for the limits on the size of files
+    /// read from it
+    pub fn with_config(reader: R, config: KmlReaderConfig) -> Result<Self, Error> {
+        Ok(KmzArchive {
+            archive: ZipArchive::new(reader)?,
+            config,
+        })
+    }
+
+    /// Names of the files in the archive with a KML extension, in the order they're stored
+    pub fn kml_files(&self) -> impl Iterator<Item = &str> {
+        (0..self.archive.len())
+            .filter_map(|i| self.archive.name_for_index(i))
+            .filter(|name| name.to_ascii_lowercase().ends_with(".kml"))
+    }
+
+    /// Name of the main KML file, which by convention is `doc.kml` at the root of the archive, or
+    /// otherwise the first KML file
+    pub fn main_kml_file(&self) -> Option<&str> {
+        self.kml_files()
+            .find(|name| name.eq_ignore_ascii_case("doc.kml"))
+            .or_else(|| self.kml_files().next())
+    }
+
+    /// Names of the files in the archive other than KML files, like icons, images, and COLLADA
+    /// models, in the order they're stored
+    pub fn resource_files(&self) -> impl Iterator<Item = &str> {
+        (0..self.archive.len())
+            .filter_map(|i| self.archive.name_for_index(i))
+            .filter(|name| !name.ends_with('/') && !name.to_ascii_lowercase().ends_with(".kml"))
+    }
+
+    /// Name of the file in the archive that `href`, like the `href` of an `Icon`, points to when
+    /// it's in the KML file named `kml_file`. Returns `None` for absolute URLs and paths and for
+    /// files that aren't in the archive
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::io::{Cursor, Write};
+    /// # use zip::{write::SimpleFileOptions, ZipWriter};
+    /// use kml::KmzArchive;
+    ///
+    /// # let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    /// # zip.start_file("tiles/0.kml", SimpleFileOptions::default()).unwrap();
+    /// # zip.start_file("files/icon.png", SimpleFileOptions::default()).unwrap();
+    /// # zip.write_all(b"PNG").unwrap();
+    /// # let kmz = zip.finish().unwrap();
+    /// let mut archive = KmzArchive::new(kmz).unwrap();
+    /// let icon = archive.resolve_href("tiles/0.kml", "../files/icon.png").unwrap();
+    /// assert_eq!(icon, "files/icon.png");
+    /// assert_eq!(archive.read_file(&icon).unwrap(), b"PNG");
+    /// ```
+    pub fn resolve_href(&self, kml_file: &str, href: &str) -> Option<String> {
+        if href.contains("://") || href.starts_with('/') {
+            return None;
+        }
+        let mut path: Vec<&str> = kml_file.split('/').collect();
+        path.pop();
+        for segment in href.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    path.pop()?;
+                }
+                segment => path.push(segment),
+            }
+        }
+        let name = path.join("/");
+        self.archive.index_for_name(&name).map(|_| name)
+    }
+
+    /// Reads the contents of the file in the archive named `name`, returning
+    /// [`Error::KmzLimitExceeded`](enum.Error.html#variant.KmzLimitExceeded) if it decompresses to
+    /// more than [`max_kmz_entry_size`](reader/struct.KmlReaderConfig.html#structfield.max_kmz_entry_size)
+    /// or [`max_kmz_compression_ratio`](reader/struct.KmlReaderConfig.html#structfield.max_kmz_compression_ratio)
+    /// allow
+    pub fn read_file(&mut self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut file = self.archive.by_name(name)?;
+        let limit = self.config.kmz_entry_limit(name, file.compressed_size());
+        // The size is read from the archive, so it's only trusted up to a limit and the amount
+        // actually decompressed is checked as well
+        let max = limit.as_ref().map_or(u64::MAX, |(max, _)| *max);
+        let mut buf = Vec::with_capacity(file.size().min(max).min(MAX_PREALLOCATION) as usize);
+        if file.size() <= max {
+            io::copy(&mut (&mut file).take(max.saturating_add(1)), &mut buf)?;
+        }
+        match limit {
+            Some((max, message)) if file.size() > max || buf.len() as u64 > max => {
+                Err(Error::KmzLimitExceeded(message))
+            }
+            _ => Ok(buf),
+        }
+    }
+
+    /// Create a [`KmlReader`](struct.KmlReader.html) for the file in the archive named `name`,
+    /// reading all of it into memory first so the archive can still be used. See
+    /// [`into_kml_reader`](#method.into_kml_reader) for reading it as it's decompressed instead
+    pub fn kml_reader<T>(&mut self, name: &str) -> Result<KmlReader<Cursor<Vec<u8>>, T>, Error>
+    where
+        T: CoordType + FromStr + Default,
+    {
+        Ok(KmlReader::from_reader_with_config(
+            Cursor::new(self.read_file(name)?),
+            self.config.clone(),
+        ))
+    }
+
+    /// Create a [`KmlReader`](struct.KmlReader.html) for the file in the archive named `name`
+    /// that decompresses it as it's read, so memory use doesn't depend on the size of the file.
+    /// Files compressed with methods other than deflate, which is what KMZ uses, are read into
+    /// memory first. The CRC-32 checksum of the file is verified once all of it has been read
+    pub fn into_kml_reader<T>(
+        mut self,
+        name: &str,
+    ) -> Result<KmlReader<BufReader<KmzEntry<R>>, T>, Error>
+    where
+        T: CoordType + FromStr + Default,
+    {
+        let file = self.archive.by_name(name)?;
+        let (method, data_start, compressed_size, size, crc32) = (
+            file.compression(),
+            file.data_start(),
+            file.compressed_size(),
+            file.size(),
+            file.crc32(),
+        );
+        if file.encrypted() {
+            return Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED).into());
+        }
+        drop(file);
+        let limit = self.config.kmz_entry_limit(name, compressed_size);
+        if let Some((max, message)) = &limit {
+            if size > *max {
+                return Err(Error::KmzLimitExceeded(message.clone()));
+            }
+        }
+        let config = self.config.clone();
+        let (reader, checksum) = if matches!(
+            method,
+            CompressionMethod::Stored | CompressionMethod::Deflated
+        ) {
+            let mut reader = self.archive.into_inner();
+            reader.seek(SeekFrom::Start(data_start))?;
+            let data = reader.take(compressed_size);
+            let reader = match method {
+                CompressionMethod::Stored => EntryReader::Stored(data),
+                _ => EntryReader::Deflated(DeflateDecoder::new(data)),
+            };
+            (
+                reader,
+                Some((crc32, format!("{name} doesn't match its CRC-32 checksum"))),
+            )
+        } else {
+            // Reading the whole file through the archive verifies its checksum
+            let reader = EntryReader::Buffered(Cursor::new(self.read_file(name)?));
+            (reader, None)
+        };
+        let entry = KmzEntry {
+            reader,
+            read: 0,
+            limit,
+            crc: Crc::new(),
+            checksum,
+        };
+        Ok(KmlReader::from_reader_with_config(
+            BufReader::new(entry),
+            config,
+        ))
+    }
+}
+
+impl KmlReaderConfig {
+    /// Largest size in bytes the file `name` in a KMZ archive is allowed to decompress to, along
+    /// with the message for exceeding it
+    fn kmz_entry_limit(&self, name: &str, compressed_size: u64) -> Option<(u64, String)> {
+        [
+            self.max_kmz_entry_size
+                .map(|max| (max, format!("{name} is larger than {max} bytes"))),
+            self.max_kmz_compression_ratio.map(|ratio| {
+                (
+                    compressed_size.saturating_mul(ratio),
+                    format!("{name} expands to more than {ratio} times its compressed size"),
+                )
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by_key(|(max, _)| *max)
+    }
+}
+
+/// File in a KMZ archive being decompressed as it's read, created by
+/// [`KmzArchive::into_kml_reader`](struct.KmzArchive.html#method.into_kml_reader). Reading more
+/// than the limits in the [`KmlReaderConfig`](reader/struct.KmlReaderConfig.html) allow returns
+/// an IO error wrapping [`Error::KmzLimitExceeded`](enum.Error.html#variant.KmzLimitExceeded),
+/// and reaching the end of a file that doesn't match its CRC-32 checksum returns an IO error of
+/// kind [`InvalidData`](std::io::ErrorKind::InvalidData)
 #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
-impl<T> KmlReader<Cursor<Vec<u8>>, T>
+#[derive(Debug)]
+pub struct KmzEntry<R: Read> {
+    reader: EntryReader<R>,
+    read: u64,
+    limit: Option<(u64, String)>,
+    crc: Crc,
+    /// Checksum the file is expected to have, along with the message for a mismatch, unless it
+    /// was verified when buffering it
+    checksum: Option<(u32, String)>,
+}
+
+#[derive(Debug)]
+enum EntryReader<R: Read> {
+    Stored(Take<R>),
+    Deflated(DeflateDecoder<Take<R>>),
+    Buffered(Cursor<Vec<u8>>),
+}
+
+impl<R: Read> Read for KmzEntry<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = match &mut self.reader {
+            EntryReader::Stored(r) => r.read(buf)?,
+            EntryReader::Deflated(r) => r.read(buf)?,
+            EntryReader::Buffered(r) => r.read(buf)?,
+        };
+        self.read += n as u64;
+        if let Some((max, message)) = &self.limit {
+            if self.read > *max {
+                return Err(io::Error::other(Error::KmzLimitExceeded(message.clone())));
+            }
+        }
+        self.crc.update(&buf[..n]);
+        match &self.checksum {
+            Some((crc32, message)) if n == 0 && !buf.is_empty() && self.crc.sum() != *crc32 => {
+                Err(io::Error::new(io::ErrorKind::InvalidData, message.clone()))
+            }
+            _ => Ok(n),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "zip", feature = "fs"))))]
+impl<T> KmlReader<BufReader<KmzEntry<File>>, T>
 where
     T: CoordType + FromStr + Default,
 {
-    #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
     /// Create a [`KmlReader`](struct.KmlReader.html) from a KMZ file path
     ///
     /// # Example
@@ -30,26 +313,141 @@ where
     /// let mut kml_reader = KmlReader::<_, f64>::from_kmz_path(kmz_path).unwrap();
     /// let kml = kml_reader.read().unwrap();
     /// ```
-    pub fn from_kmz_path<P: AsRef<Path>>(path: P) -> Result<KmlReader<Cursor<Vec<u8>>, T>, Error> {
-        let file = File::open(path)?;
-        let mut archive = ZipArchive::new(file)?;
-
-        // Should parse the first file with a KML extension
-        for i in 0..archive.len() {
-            let mut kml_file = archive
-                .by_index(i)
-                .map_err(|e| Error::InvalidInput(format!("{e:?}")))?;
-            if !kml_file.name().to_ascii_lowercase().ends_with(".kml") {
-                continue;
-            }
-            let mut buf = Vec::with_capacity(kml_file.size() as usize);
-            std::io::copy(&mut kml_file, &mut buf)?;
-            return Ok(KmlReader::from_reader(Cursor::new(buf)));
-        }
+    pub fn from_kmz_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        KmlReader::from_kmz_reader(File::open(path)?)
+    }
 
-        Err(Error::InvalidInput(
-            "Archive contains no elements".to_string(),
-        ))
+    /// Create a [`KmlReader`](struct.KmlReader.html) from a KMZ file path with a
+    /// [`KmlReaderConfig`](reader/struct.KmlReaderConfig.html)
+    pub fn from_kmz_path_with_config<P: AsRef<Path>>(
+        path: P,
+        config: KmlReaderConfig,
+    ) -> Result<Self, Error> {
+        KmlReader::from_kmz_reader_with_config(File::open(path)?, config)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+impl<R, T> KmlReader<BufReader<KmzEntry<R>>, T>
+where
+    R: Read + Seek,
+    T: CoordType + FromStr + Default,
+{
+    /// Create a [`KmlReader`](struct.KmlReader.html) from a reader of KMZ data, like a file or a
+    /// [`Cursor`] over downloaded bytes. The main KML file in the archive is
+    /// decompressed as it's read, like with
+    /// [`KmzArchive::into_kml_reader`](struct.KmzArchive.html#method.into_kml_reader)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::path::Path;
+    /// use kml::KmlReader;
+    ///
+    /// let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+    ///     .join("tests")
+    ///     .join("fixtures")
+    ///     .join("polygon.kmz");
+    /// let kmz_file = File::open(kmz_path).unwrap();
+    /// let mut kml_reader = KmlReader::<_, f64>::from_kmz_reader(kmz_file).unwrap();
+    /// let kml = kml_reader.read().unwrap();
+    /// ```
+    pub fn from_kmz_reader(reader: R) -> Result<Self, Error> {
+        KmlReader::from_kmz_reader_with_config(reader, KmlReaderConfig::default())
+    }
+
+    /// Create a [`KmlReader`](struct.KmlReader.html) from a reader of KMZ data with a
+    /// [`KmlReaderConfig`](reader/struct.KmlReaderConfig.html)
+    pub fn from_kmz_reader_with_config(reader: R, config: KmlReaderConfig) -> Result<Self, Error> {
+        let archive = KmzArchive::with_config(reader, config)?;
+        let name = archive
+            .main_kml_file()
+            .ok_or_else(|| Error::InvalidInput("Archive contains no elements".to_string()))?
+            .to_string();
+        archive.into_kml_reader(&name)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+impl<'a, T> KmlReader<BufReader<KmzEntry<Cursor<&'a [u8]>>>, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    /// Create a [`KmlReader`](struct.KmlReader.html) from the bytes of a KMZ archive already in
+    /// memory, like an upload
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use kml::KmlReader;
+    ///
+    /// let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+    ///     .join("tests")
+    ///     .join("fixtures")
+    ///     .join("polygon.kmz");
+    /// let kmz = std::fs::read(kmz_path).unwrap();
+    /// let mut kml_reader = KmlReader::<_, f64>::from_kmz_buf(&kmz).unwrap();
+    /// let kml = kml_reader.read().unwrap();
+    /// ```
+    pub fn from_kmz_buf(buf: &'a [u8]) -> Result<Self, Error> {
+        KmlReader::from_kmz_reader(Cursor::new(buf))
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+impl<T> KmlReader<BufReader<KmzEntry<Cursor<Vec<u8>>>>, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    /// Create a [`KmlReader`](struct.KmlReader.html) from an owned KMZ archive like
+    /// [`from_kmz_buf`](#method.from_kmz_buf)
+    pub fn from_kmz_vec(buf: Vec<u8>) -> Result<Self, Error> {
+        KmlReader::from_kmz_reader(Cursor::new(buf))
+    }
+
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    /// Create a [`KmlReader`](struct.KmlReader.html) by downloading a KMZ archive from `url`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kml::KmlReader;
+    ///
+    /// let mut kml_reader =
+    ///     KmlReader::<_, f64>::from_kmz_url("https://example.com/data/parks.kmz").unwrap();
+    /// let kml = kml_reader.read().unwrap();
+    /// ```
+    pub fn from_kmz_url(url: &str) -> Result<Self, Error> {
+        KmlReader::from_kmz_url_with_config(url, KmlReaderConfig::default())
+    }
+
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    /// Create a [`KmlReader`](struct.KmlReader.html) by downloading a KMZ archive from `url` with
+    /// a [`KmlReaderConfig`](reader/struct.KmlReaderConfig.html). Failed requests and error
+    /// statuses return [`Error::HttpError`](enum.Error.html#variant.HttpError), and archives
+    /// larger than [`max_kmz_download_size`](reader/struct.KmlReaderConfig.html#structfield.max_kmz_download_size)
+    /// return [`Error::KmzLimitExceeded`](enum.Error.html#variant.KmzLimitExceeded) without
+    /// downloading the rest of them
+    pub fn from_kmz_url_with_config(url: &str, config: KmlReaderConfig) -> Result<Self, Error> {
+        let response = ureq::get(url)
+            .call()
+            .map_err(|e| Error::HttpError(Box::new(e)))?;
+        let max = config.max_kmz_download_size.unwrap_or(u64::MAX);
+        let mut buf = Vec::new();
+        response
+            .into_reader()
+            .take(max.saturating_add(1))
+            .read_to_end(&mut buf)?;
+        if buf.len() as u64 > max {
+            return Err(Error::KmzLimitExceeded(format!(
+                "{url} is larger than {max} bytes"
+            )));
+        }
+        KmlReader::from_kmz_reader_with_config(Cursor::new(buf), config)
     }
 }
 
@@ -57,8 +455,10 @@ where
 mod tests {
     use super::*;
     use crate::types::Kml;
+    use std::path::Path;
 
     #[test]
+    #[cfg(feature = "fs")]
     fn test_read_kmz() {
         let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
             .join("tests")
@@ -69,4 +469,259 @@ mod tests {
 
         assert!(matches!(kml, Kml::Polygon(_)))
     }
+
+    #[test]
+    fn test_read_kmz_reader() {
+        let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("polygon.kmz");
+        let kmz = Cursor::new(std::fs::read(kmz_path).unwrap());
+        let mut kml_reader = KmlReader::<_, f64>::from_kmz_reader(kmz).unwrap();
+        let kml = kml_reader.read().unwrap();
+
+        assert!(matches!(kml, Kml::Polygon(_)))
+    }
+
+    #[test]
+    fn test_read_kmz_buf() {
+        let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("polygon.kmz");
+        let kmz = std::fs::read(kmz_path).unwrap();
+        let kml = KmlReader::<_, f64>::from_kmz_buf(&kmz).unwrap().read();
+        assert!(matches!(kml, Ok(Kml::Polygon(_))));
+
+        let kml = KmlReader::<_, f64>::from_kmz_vec(kmz).unwrap().read();
+        assert!(matches!(kml, Ok(Kml::Polygon(_))));
+        assert!(KmlReader::<_, f64>::from_kmz_buf(b"not a kmz").is_err());
+    }
+
+    /// Builds a KMZ archive with a file for each name and content
+    fn kmz(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_read_kmz_doc_kml() {
+        let point = "<Point><coordinates>1,1</coordinates></Point>";
+        let polygon = "<Polygon></Polygon>";
+        let read = |files: &[(&str, &str)]| KmlReader::<_, f64>::from_kmz_vec(kmz(files))?.read();
+
+        assert!(matches!(
+            read(&[
+                ("a.kml", polygon),
+                ("files/doc.kml", polygon),
+                ("doc.kml", point)
+            ]),
+            Ok(Kml::Point(_))
+        ));
+        assert!(matches!(
+            read(&[("files/icon.png", ""), ("a.KML", point), ("b.kml", polygon)]),
+            Ok(Kml::Point(_))
+        ));
+        assert!(matches!(
+            read(&[("files/icon.png", "")]),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_kmz_archive() {
+        let point = "<Point><coordinates>1,1</coordinates></Point>";
+        let polygon = "<Polygon></Polygon>";
+        let kmz = kmz(&[
+            ("doc.kml", "<NetworkLink></NetworkLink>"),
+            ("files/icon.png", ""),
+            ("tiles/0.kml", point),
+            ("tiles/1.KML", polygon),
+        ]);
+        let mut archive = KmzArchive::new(Cursor::new(kmz)).unwrap();
+
+        assert_eq!(
+            archive.kml_files().collect::<Vec<_>>(),
+            ["doc.kml", "tiles/0.kml", "tiles/1.KML"]
+        );
+        assert_eq!(archive.main_kml_file(), Some("doc.kml"));
+        assert!(matches!(
+            archive.kml_reader::<f64>("tiles/0.kml").unwrap().read(),
+            Ok(Kml::Point(_))
+        ));
+        assert!(matches!(
+            archive.kml_reader::<f64>("tiles/1.KML").unwrap().read(),
+            Ok(Kml::Polygon(_))
+        ));
+        assert!(matches!(
+            archive.kml_reader::<f64>("tiles/2.kml"),
+            Err(Error::ZipError(_))
+        ));
+    }
+
+    #[test]
+    fn test_kmz_archive_resources() {
+        let mut archive = KmzArchive::new(Cursor::new(kmz(&[
+            ("doc.kml", ""),
+            ("files/icon.png", "PNG"),
+            ("files/models/house.dae", "DAE"),
+            ("tiles/0.kml", ""),
+        ])))
+        .unwrap();
+
+        assert_eq!(
+            archive.resource_files().collect::<Vec<_>>(),
+            ["files/icon.png", "files/models/house.dae"]
+        );
+        assert_eq!(
+            archive.resolve_href("doc.kml", "files/icon.png").as_deref(),
+            Some("files/icon.png")
+        );
+        assert_eq!(
+            archive
+                .resolve_href("doc.kml", "./files/models/house.dae")
+                .as_deref(),
+            Some("files/models/house.dae")
+        );
+        assert_eq!(
+            archive
+                .resolve_href("tiles/0.kml", "../files/icon.png")
+                .as_deref(),
+            Some("files/icon.png")
+        );
+        assert_eq!(archive.resolve_href("tiles/0.kml", "files/icon.png"), None);
+        assert_eq!(archive.resolve_href("doc.kml", "../files/icon.png"), None);
+        assert_eq!(
+            archive.resolve_href("doc.kml", "https://example.com/files/icon.png"),
+            None
+        );
+        assert_eq!(archive.read_file("files/models/house.dae").unwrap(), b"DAE");
+        assert!(archive.read_file("files/missing.png").is_err());
+    }
+
+    #[test]
+    fn test_read_kmz_limits() {
+        let padding = " ".repeat(100_000);
+        let doc = format!("<Point><coordinates>1,1</coordinates></Point>{padding}");
+        let kmz = kmz(&[("doc.kml", &doc)]);
+        let read = |config: KmlReaderConfig| {
+            KmlReader::<_, f64>::from_kmz_reader_with_config(Cursor::new(&kmz), config)
+        };
+
+        assert!(matches!(
+            read(KmlReaderConfig::default().max_kmz_entry_size(Some(1000))),
+            Err(Error::KmzLimitExceeded(_))
+        ));
+        assert!(matches!(
+            read(KmlReaderConfig::default().max_kmz_compression_ratio(Some(10))),
+            Err(Error::KmzLimitExceeded(_))
+        ));
+        let config = KmlReaderConfig::default()
+            .max_kmz_entry_size(Some(doc.len() as u64))
+            .max_kmz_compression_ratio(Some(10_000));
+        assert!(matches!(read(config).unwrap().read(), Ok(Kml::Point(_))));
+
+        let mut archive = KmzArchive::with_config(
+            Cursor::new(&kmz),
+            KmlReaderConfig::default().max_kmz_entry_size(Some(doc.len() as u64 - 1)),
+        )
+        .unwrap();
+        assert!(matches!(
+            archive.read_file("doc.kml"),
+            Err(Error::KmzLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_kmz_archive_into_kml_reader() {
+        let point = "<Point><coordinates>1,1</coordinates></Point>";
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for method in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+            let options = zip::write::SimpleFileOptions::default().compression_method(method);
+            zip.start_file(format!("{method}.kml"), options).unwrap();
+            std::io::Write::write_all(&mut zip, point.as_bytes()).unwrap();
+        }
+        let kmz = zip.finish().unwrap().into_inner();
+
+        for name in ["Stored.kml", "Deflated.kml"] {
+            let archive = KmzArchive::new(Cursor::new(&kmz)).unwrap();
+            let mut reader = archive.into_kml_reader::<f64>(name).unwrap();
+            assert!(matches!(reader.read(), Ok(Kml::Point(_))), "{name}");
+        }
+
+        // Changes the coordinates of the stored file without updating its checksum
+        let mut corrupt = kmz.clone();
+        let start = corrupt
+            .windows(point.len())
+            .position(|w| w == point.as_bytes())
+            .unwrap();
+        corrupt[start + "<Point><coordinates>".len()] = b'2';
+        let archive = KmzArchive::new(Cursor::new(&corrupt)).unwrap();
+        let mut reader = archive.into_kml_reader::<f64>("Stored.kml").unwrap();
+        let err = reader.read().unwrap_err();
+        assert!(err.to_string().contains("CRC-32"), "{err}");
+    }
+
+    /// Serves `status` and `body` to the first request on a local port, returning its URL
+    #[cfg(feature = "http")]
+    fn serve_once(status: &'static str, body: Vec<u8>) -> String {
+        use std::io::{BufRead, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/doc.kmz", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut request = std::io::BufReader::new(&stream);
+            let mut line = String::new();
+            while request.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let mut stream = &stream;
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn test_read_kmz_url() {
+        let point = "<Point><coordinates>1,1</coordinates></Point>";
+        let archive = kmz(&[("doc.kml", point)]);
+        let url = serve_once("200 OK", archive.clone());
+        let kml = KmlReader::<_, f64>::from_kmz_url(&url).unwrap().read();
+        assert!(matches!(kml, Ok(Kml::Point(_))));
+
+        let url = serve_once("200 OK", archive);
+        let config = KmlReaderConfig::default().max_kmz_download_size(Some(100));
+        assert!(matches!(
+            KmlReader::<_, f64>::from_kmz_url_with_config(&url, config),
+            Err(Error::KmzLimitExceeded(_))
+        ));
+
+        // Archives larger than the entry limit are fine as long as each entry is within it
+        let archive = kmz(&[("doc.kml", point), ("files/other.kml", point)]);
+        let config = KmlReaderConfig::default().max_kmz_entry_size(Some(point.len() as u64));
+        assert!(archive.len() > point.len());
+        let url = serve_once("200 OK", archive);
+        let kml = KmlReader::<_, f64>::from_kmz_url_with_config(&url, config).map(|mut r| r.read());
+        assert!(matches!(kml, Ok(Ok(Kml::Point(_)))));
+
+        let url = serve_once("404 Not Found", Vec::new());
+        assert!(matches!(
+            KmlReader::<_, f64>::from_kmz_url(&url),
+            Err(Error::HttpError(_))
+        ));
+    }
 }
@@ -0,0 +1,383 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{Seek, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::str::FromStr;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::errors::Error;
+use crate::types::{CoordType, Document, Element, Folder, Kml, Node};
+use crate::writer::{KmlWriter, KmlWriterConfig};
+
+/// How a file is compressed in a KMZ archive written by [`KmzWriter`](struct.KmzWriter.html)
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KmzCompression {
+    /// Stored as is, for files that are already compressed like JPEG and PNG images
+    Stored,
+    /// Compressed with deflate at a level from 0 for the fastest to 9 for the smallest
+    Deflated(u8),
+}
+
+impl Default for KmzCompression {
+    fn default() -> Self {
+        KmzCompression::Deflated(6)
+    }
+}
+
+impl KmzCompression {
+    fn options(self) -> Result<SimpleFileOptions, Error> {
+        let options = SimpleFileOptions::default();
+        match self {
+            KmzCompression::Stored => Ok(options.compression_method(CompressionMethod::Stored)),
+            KmzCompression::Deflated(level @ 0..=9) => Ok(options
+                .compression_method(CompressionMethod::Deflated)
+                .compression_level(Some(level.into()))),
+            KmzCompression::Deflated(level) => Err(Error::InvalidInput(format!(
+                "Deflate compression level must be from 0 to 9, not {level}"
+            ))),
+        }
+    }
+}
+
+/// Writer for KMZ archives holding a `doc.kml` file along with local resources like icons,
+/// overlays, and models, which are stored under `files/` with the `href`s pointing to them
+/// rewritten to their paths in the archive
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use kml::{types::{Icon, IconStyle, Style}, Kml, KmzWriter};
+///
+/// let kml: Kml = Kml::Style(Style {
+///     icon: Some(IconStyle {
+///         icon: Some(Icon {
+///             href: "/home/user/icons/pin.png".to_string(),
+///             ..Default::default()
+///         }),
+///         ..Default::default()
+///     }),
+///     ..Default::default()
+/// });
+///
+/// let mut kmz_writer = KmzWriter::new(Cursor::new(Vec::new()));
+/// let path = kmz_writer
+///     .add_resource("/home/user/icons/pin.png", b"PNG")
+///     .unwrap();
+/// assert_eq!(path, "files/pin.png");
+/// let kmz = kmz_writer.write(&kml).unwrap().into_inner();
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+#[derive(Debug)]
+pub struct KmzWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    config: KmlWriterConfig,
+    compression: KmzCompression,
+    /// Paths of the resources in the archive keyed by the `href` they replace
+    hrefs: HashMap<String, String>,
+}
+
+impl<W: Write + Seek> KmzWriter<W> {
+    /// Create a [`KmzWriter`](struct.KmzWriter.html) writing an archive to `w`
+    pub fn new(w: W) -> Self {
+        KmzWriter::with_config(w, KmlWriterConfig::default())
+    }
+
+    /// Create a [`KmzWriter`](struct.KmzWriter.html) writing `doc.kml` with a
+    /// [`KmlWriterConfig`](writer/struct.KmlWriterConfig.html)
+    pub fn with_config(w: W, config: KmlWriterConfig) -> Self {
+        KmzWriter {
+            zip: ZipWriter::new(w),
+            config,
+            compression: KmzCompression::default(),
+            hrefs: HashMap::new(),
+        }
+    }
+
+    /// Sets the compression of `doc.kml` and of resources added without one, which is deflate at
+    /// level 6 by default
+    pub fn compression(mut self, compression: KmzCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Stores `data` in the archive under `files/` with the file name of `href`, so `href`s in
+    /// `Icon`, `Link`, and `Alias` elements matching it are written as the returned path in the
+    /// archive. File names already used are given a suffix like `icon-2.png`, and adding the same
+    /// `href` again returns the path it was first stored at
+    pub fn add_resource(&mut self, href: &str, data: &[u8]) -> Result<String, Error> {
+        self.add_resource_with_compression(href, data, self.compression)
+    }
+
+    /// Stores `data` in the archive like [`add_resource`](#method.add_resource) with its own
+    /// compression, like storing a JPEG overlay without compressing it again
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use kml::{KmzCompression, KmzWriter};
+    ///
+    /// let mut kmz_writer =
+    ///     KmzWriter::new(Cursor::new(Vec::new())).compression(KmzCompression::Deflated(9));
+    /// kmz_writer
+    ///     .add_resource_with_compression("overlay.jpg", b"JPEG", KmzCompression::Stored)
+    ///     .unwrap();
+    /// ```
+    pub fn add_resource_with_compression(
+        &mut self,
+        href: &str,
+        data: &[u8],
+        compression: KmzCompression,
+    ) -> Result<String, Error> {
+        let options = compression.options()?;
+        if let Some(path) = self.hrefs.get(href) {
+            return Ok(path.clone());
+        }
+        let file_name = href.rsplit(['/', '\\']).next().unwrap_or_default();
+        let (stem, extension) = match file_name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+            _ => (file_name, String::new()),
+        };
+        let path = (1..)
+            .map(|n| match n {
+                1 => format!("files/{stem}{extension}"),
+                n => format!("files/{stem}-{n}{extension}"),
+            })
+            .find(|path| !self.hrefs.values().any(|p| p == path))
+            .unwrap();
+        self.zip.start_file(path.as_str(), options)?;
+        self.zip.write_all(data)?;
+        self.hrefs.insert(href.to_string(), path.clone());
+        Ok(path)
+    }
+
+    /// Stores the file at `path` in the archive like [`add_resource`](#method.add_resource), with
+    /// `path` as the `href` that's rewritten
+    #[cfg(feature = "fs")]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "zip", feature = "fs"))))]
+    pub fn add_resource_file<P: AsRef<Path>>(&mut self, path: P) -> Result<String, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        self.add_resource(&path.to_string_lossy(), &data)
+    }
+
+    /// Writes `kml` to `doc.kml` as a complete document like
+    /// [`KmlWriter::write_document`](writer/struct.KmlWriter.html#method.write_document), then
+    /// finishes the archive and returns the underlying writer
+    pub fn write<T>(mut self, kml: &Kml<T>) -> Result<W, Error>
+    where
+        T: CoordType + FromStr + Default + fmt::Display,
+    {
+        let mut kml = Cow::Borrowed(kml);
+        if !self.hrefs.is_empty() {
+            rewrite_hrefs(kml.to_mut(), &self.hrefs);
+        }
+        self.zip
+            .start_file("doc.kml", self.compression.options()?)?;
+        KmlWriter::from_writer_with_config(&mut self.zip, self.config).write_document(&kml)?;
+        Ok(self.zip.finish()?)
+    }
+}
+
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "zip", feature = "fs"))))]
+impl KmzWriter<File> {
+    /// Create a [`KmzWriter`](struct.KmzWriter.html) writing to a file at `path`, replacing the
+    /// file if it exists
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(KmzWriter::new(File::create(path)?))
+    }
+}
+
+/// Replaces `href`s in `kml` and the elements in it that are keys of `hrefs` with their values
+fn rewrite_hrefs<T: CoordType>(kml: &mut Kml<T>, hrefs: &HashMap<String, String>) {
+    let rewrite = |href: &mut String| {
+        if let Some(path) = hrefs.get(href.as_str()) {
+            *href = path.clone();
+        }
+    };
+    match kml {
+        Kml::Icon(i) => rewrite(&mut i.href),
+        Kml::IconStyle(s) => s.icon.iter_mut().for_each(|i| rewrite(&mut i.href)),
+        Kml::Style(s) => s
+            .icon
+            .iter_mut()
+            .filter_map(|s| s.icon.as_mut())
+            .for_each(|i| rewrite(&mut i.href)),
+        Kml::LinkTypeIcon(i) => i.href.iter_mut().for_each(rewrite),
+        Kml::Link(l) => l.href.iter_mut().for_each(rewrite),
+        Kml::Alias(a) => a.target_href.iter_mut().for_each(rewrite),
+        Kml::ResourceMap(r) => r
+            .aliases
+            .iter_mut()
+            .filter_map(|a| a.target_href.as_mut())
+            .for_each(rewrite),
+        Kml::Placemark(p) => p
+            .children
+            .iter_mut()
+            .filter_map(Node::as_element_mut)
+            .for_each(|e| rewrite_element_hrefs(e, hrefs)),
+        Kml::Element(e) => rewrite_element_hrefs(e, hrefs),
+        Kml::KmlDocument(d) => d.elements.iter_mut().for_each(|e| rewrite_hrefs(e, hrefs)),
+        Kml::Document(Document {
+            children, elements, ..
+        })
+        | Kml::Folder(Folder {
+            children, elements, ..
+        }) => {
+            children
+                .iter_mut()
+                .filter_map(Node::as_element_mut)
+                .for_each(|e| rewrite_element_hrefs(e, hrefs));
+            elements.iter_mut().for_each(|e| rewrite_hrefs(e, hrefs))
+        }
+        _ => {}
+    }
+}
+
+/// Replaces the content of `href` and `targetHref` elements in `e` that are keys of `hrefs`, like
+/// those of overlays and models read into an `Element`
+fn rewrite_element_hrefs(e: &mut Element, hrefs: &HashMap<String, String>) {
+    if e.name == "href" || e.name == "targetHref" {
+        if let Some(path) = e.content.as_deref().and_then(|href| hrefs.get(href.trim())) {
+            e.content = Some(path.clone());
+        }
+    }
+    e.children
+        .iter_mut()
+        .filter_map(Node::as_element_mut)
+        .for_each(|c| rewrite_element_hrefs(c, hrefs));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Icon, IconStyle, Placemark, Style};
+    use crate::KmzArchive;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_write_kmz() {
+        let element = |name: &str, content: Option<&str>, children: Vec<Element>| Element {
+            name: name.to_string(),
+            content: content.map(str::to_string),
+            children: children.into_iter().map(Node::from).collect(),
+            ..Default::default()
+        };
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Style(Style {
+                    id: Some("pin".to_string()),
+                    icon: Some(IconStyle {
+                        icon: Some(Icon {
+                            href: "icons/pin.png".to_string(),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                Kml::Placemark(Placemark {
+                    style_url: Some("#pin".to_string()),
+                    ..Default::default()
+                }),
+                Kml::Element(element(
+                    "GroundOverlay",
+                    None,
+                    vec![element(
+                        "Icon",
+                        None,
+                        vec![element("href", Some("overlays/pin.png"), vec![])],
+                    )],
+                )),
+                Kml::Element(element(
+                    "GroundOverlay",
+                    None,
+                    vec![element(
+                        "Icon",
+                        None,
+                        vec![element("href", Some("https://example.com/map.png"), vec![])],
+                    )],
+                )),
+            ],
+            ..Default::default()
+        });
+
+        let mut kmz_writer = KmzWriter::new(Cursor::new(Vec::new()));
+        assert_eq!(
+            kmz_writer.add_resource("icons/pin.png", b"pin").unwrap(),
+            "files/pin.png"
+        );
+        assert_eq!(
+            kmz_writer
+                .add_resource("overlays/pin.png", b"overlay")
+                .unwrap(),
+            "files/pin-2.png"
+        );
+        assert_eq!(
+            kmz_writer.add_resource("icons/pin.png", b"pin").unwrap(),
+            "files/pin.png"
+        );
+        let kmz = kmz_writer.write(&kml).unwrap();
+
+        let mut archive = KmzArchive::new(kmz).unwrap();
+        assert_eq!(archive.main_kml_file(), Some("doc.kml"));
+        assert_eq!(
+            archive.resource_files().collect::<Vec<_>>(),
+            ["files/pin.png", "files/pin-2.png"]
+        );
+        assert_eq!(archive.read_file("files/pin-2.png").unwrap(), b"overlay");
+        let doc = String::from_utf8(archive.read_file("doc.kml").unwrap()).unwrap();
+        assert!(doc.contains("<href>files/pin.png</href>"));
+        assert!(doc.contains("<href>files/pin-2.png</href>"));
+        assert!(doc.contains("<href>https://example.com/map.png</href>"));
+        assert!(!doc.contains("icons/pin.png"));
+    }
+
+    #[test]
+    fn test_write_kmz_compression() {
+        let kml: Kml = Kml::Placemark(Placemark::default());
+        let data = vec![b'a'; 1000];
+        let mut kmz_writer =
+            KmzWriter::new(Cursor::new(Vec::new())).compression(KmzCompression::Stored);
+        kmz_writer.add_resource("stored.txt", &data).unwrap();
+        kmz_writer
+            .add_resource_with_compression("fast.txt", &data, KmzCompression::Deflated(1))
+            .unwrap();
+        assert!(matches!(
+            kmz_writer.add_resource_with_compression(
+                "invalid.txt",
+                &data,
+                KmzCompression::Deflated(10)
+            ),
+            Err(Error::InvalidInput(_))
+        ));
+        let kmz = kmz_writer.write(&kml).unwrap();
+
+        let mut archive = zip::ZipArchive::new(kmz).unwrap();
+        let compression =
+            |archive: &mut zip::ZipArchive<_>, name| archive.by_name(name).unwrap().compression();
+        assert_eq!(
+            compression(&mut archive, "doc.kml"),
+            CompressionMethod::Stored
+        );
+        assert_eq!(
+            compression(&mut archive, "files/stored.txt"),
+            CompressionMethod::Stored
+        );
+        assert_eq!(
+            compression(&mut archive, "files/fast.txt"),
+            CompressionMethod::Deflated
+        );
+        assert!(archive.by_name("files/fast.txt").unwrap().compressed_size() < 1000);
+        assert!(archive.by_name("files/invalid.txt").is_err());
+    }
+}
@@ -30,16 +30,18 @@
 //! // Parse from a string
 //! let kml: Kml = kml_str.parse().unwrap();
 //!
-//! // Read from a file path
+//! // Read from a file path with the `fs` feature or default features enabled
+//! # #[cfg(feature = "fs")] {
 //! let kml_path = Path::new(env!("CARGO_MANIFEST_DIR"))
 //!     .join("tests")
 //!     .join("fixtures")
 //!     .join("polygon.kml");
 //! let mut kml_reader = KmlReader::<_, f64>::from_path(kml_path).unwrap();
 //! let kml_data = kml_reader.read().unwrap();
+//! # }
 //!
 //! // Read KMZ files with the `zip` feature or default features enabled
-//! # #[cfg(feature = "zip")] {
+//! # #[cfg(all(feature = "zip", feature = "fs"))] {
 //! let kmz_path = Path::new(env!("CARGO_MANIFEST_DIR"))
 //!     .join("tests")
 //!     .join("fixtures")
@@ -91,6 +93,18 @@
 //! let geom_coll: GeometryCollection<f64> = kml_folder.try_into().unwrap();
 //! # }
 //! ```
+//!
+//! ## WebAssembly
+//!
+//! Reading KMZ archives compressed with bzip2 or zstd depends on C libraries, so for targets like
+//! `wasm32-unknown-unknown` disable default features and enable the pure Rust `zip` feature for
+//! deflate-compressed KMZ archives, which is what KMZ uses. Input can be read from bytes with
+//! [`KmlReader::from_string`], [`KmlReader::from_reader`] with a `&[u8]`, or
+//! `KmlReader::from_kmz_buf`.
+//!
+//! ```toml
+//! kml = { version = "0.8", default-features = false, features = ["geo-types", "zip"] }
+//! ```
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
@@ -99,14 +113,16 @@ pub mod types;
 pub use crate::types::{Kml, KmlDocument, KmlVersion};
 
 mod errors;
-pub use crate::errors::Error;
+pub use crate::errors::{Error, Position, Warning};
 
 pub mod reader;
-pub use crate::reader::KmlReader;
+pub use crate::reader::{Features, KmlEvent, KmlReader};
 
 pub mod writer;
 pub use crate::writer::KmlWriter;
 
+pub mod visitor;
+
 #[cfg(feature = "geo-types")]
 pub mod conversion;
 
@@ -114,9 +130,54 @@ pub mod conversion;
 #[allow(deprecated)]
 pub use conversion::quick_collection;
 
+pub mod csv;
+
+pub mod split;
+
+pub mod super_overlay;
+
+pub mod symbology;
+
+#[cfg(feature = "geo")]
+mod measure;
+
+#[cfg(feature = "geo-traits")]
+mod geometry_traits;
+
+#[cfg(feature = "arrow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+pub mod arrow;
+
+#[cfg(feature = "geojson")]
+#[cfg_attr(docsrs, doc(cfg(feature = "geojson")))]
+pub mod geojson;
+
+#[cfg(feature = "shapefile")]
+#[cfg_attr(docsrs, doc(cfg(feature = "shapefile")))]
+pub mod shapefile;
+
+#[cfg(feature = "rstar")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rstar")))]
+pub mod rstar;
+
+#[cfg(feature = "encoding")]
+mod encoding;
+
 #[cfg(feature = "zip")]
 mod kmz_reader;
 
 #[allow(unused_imports)]
 #[cfg(feature = "zip")]
 pub use kmz_reader::*;
+
+#[cfg(feature = "zip")]
+mod kmz_writer;
+
+#[cfg(feature = "zip")]
+pub use kmz_writer::{KmzCompression, KmzWriter};
+
+#[cfg(feature = "flate2")]
+mod gzip_writer;
+
+#[cfg(test)]
+mod test_utils;
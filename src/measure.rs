@@ -0,0 +1,227 @@
+//! Length, area, and bounding box methods on the geometry types, converting to `geo-types` and
+//! using the `geo` algorithms
+//!
+//! KML coordinates are longitude and latitude on WGS 84, so lengths and areas are geodesic, in
+//! meters and square meters.
+use geo::{BoundingRect, Geodesic, GeodesicArea, Length};
+
+use crate::types::{
+    CoordType, Geometry, LineString, LinearRing, MultiGeometry, MultiTrack, Point, Polygon, Track,
+};
+
+/// Adds `length` and `bounding_rect` methods to types converting to a `geo-types` line string
+macro_rules! impl_line_measures {
+    ($($name:ident),*) => {
+        $(
+            #[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+            impl $name<f64> {
+                /// Geodesic length in meters
+                pub fn length(&self) -> f64 {
+                    Geodesic.length(&geo_types::LineString::from(self))
+                }
+            }
+
+            #[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+            impl<T: CoordType> $name<T> {
+                /// Bounding box of the coordinates, or `None` if there aren't any
+                pub fn bounding_rect(&self) -> Option<geo_types::Rect<T>> {
+                    geo_types::LineString::from(self).bounding_rect()
+                }
+            }
+        )*
+    };
+}
+
+impl_line_measures!(LineString, LinearRing, Track);
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl<T: CoordType> Point<T> {
+    /// Bounding box of the point, which has no width or height
+    pub fn bounding_rect(&self) -> geo_types::Rect<T> {
+        geo_types::Point::from(self).bounding_rect()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl MultiTrack<f64> {
+    /// Geodesic length of the tracks in meters
+    pub fn length(&self) -> f64 {
+        Geodesic.length(&geo_types::MultiLineString::from(self))
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl<T: CoordType> MultiTrack<T> {
+    /// Bounding box of the tracks, or `None` if they don't have any coordinates
+    pub fn bounding_rect(&self) -> Option<geo_types::Rect<T>> {
+        geo_types::MultiLineString::from(self).bounding_rect()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl Polygon<f64> {
+    /// Geodesic area in square meters, excluding the inner rings
+    pub fn area(&self) -> f64 {
+        geo_types::Polygon::from(self).geodesic_area_unsigned()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl<T: CoordType> Polygon<T> {
+    /// Bounding box of the outer ring, or `None` if it has no coordinates
+    pub fn bounding_rect(&self) -> Option<geo_types::Rect<T>> {
+        geo_types::Polygon::from(self).bounding_rect()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl MultiGeometry<f64> {
+    /// Sum of the geodesic lengths of the geometries in meters, see
+    /// [`Geometry::length`](enum.Geometry.html#method.length)
+    pub fn length(&self) -> f64 {
+        self.geometries.iter().map(Geometry::length).sum()
+    }
+
+    /// Sum of the geodesic areas of the geometries in square meters, see
+    /// [`Geometry::area`](enum.Geometry.html#method.area)
+    pub fn area(&self) -> f64 {
+        self.geometries.iter().map(Geometry::area).sum()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl<T: CoordType> MultiGeometry<T> {
+    /// Bounding box of the geometries, or `None` if they don't have any coordinates
+    pub fn bounding_rect(&self) -> Option<geo_types::Rect<T>> {
+        self.geometries
+            .iter()
+            .filter_map(Geometry::bounding_rect)
+            .reduce(|a, b| {
+                geo_types::Rect::new(
+                    geo_types::coord! { x: a.min().x.min(b.min().x), y: a.min().y.min(b.min().y) },
+                    geo_types::coord! { x: a.max().x.max(b.max().x), y: a.max().y.max(b.max().y) },
+                )
+            })
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl Geometry<f64> {
+    /// Geodesic length in meters of line strings, linear rings, and tracks, including those in a
+    /// `MultiGeometry`, and zero for points, polygons, and models
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::types::{Coord, Geometry, LineString};
+    ///
+    /// let line = Geometry::LineString(LineString::from(vec![
+    ///     Coord::new(0., 0., None),
+    ///     Coord::new(0., 1., None),
+    /// ]));
+    /// assert_eq!(line.length().round(), 110574.);
+    /// ```
+    pub fn length(&self) -> f64 {
+        match self {
+            Geometry::LineString(l) => l.length(),
+            Geometry::LinearRing(l) => l.length(),
+            Geometry::Track(t) => t.length(),
+            Geometry::MultiTrack(t) => t.length(),
+            Geometry::MultiGeometry(g) => g.length(),
+            Geometry::Point(_) | Geometry::Polygon(_) | Geometry::Element(_) => 0.,
+        }
+    }
+
+    /// Geodesic area in square meters of polygons, including those in a `MultiGeometry`, and zero
+    /// for other geometries
+    pub fn area(&self) -> f64 {
+        match self {
+            Geometry::Polygon(p) => p.area(),
+            Geometry::MultiGeometry(g) => g.area(),
+            _ => 0.,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+impl<T: CoordType> Geometry<T> {
+    /// Bounding box of the geometry, or `None` if it has no coordinates or is a model
+    pub fn bounding_rect(&self) -> Option<geo_types::Rect<T>> {
+        match self {
+            Geometry::Point(p) => Some(p.bounding_rect()),
+            Geometry::LineString(l) => l.bounding_rect(),
+            Geometry::LinearRing(l) => l.bounding_rect(),
+            Geometry::Track(t) => t.bounding_rect(),
+            Geometry::MultiTrack(t) => t.bounding_rect(),
+            Geometry::Polygon(p) => p.bounding_rect(),
+            Geometry::MultiGeometry(g) => g.bounding_rect(),
+            Geometry::Element(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Coord;
+
+    fn square(min: f64, max: f64) -> LinearRing {
+        LinearRing::from(vec![
+            Coord::new(min, min, None),
+            Coord::new(max, min, None),
+            Coord::new(max, max, None),
+            Coord::new(min, max, None),
+            Coord::new(min, min, None),
+        ])
+    }
+
+    #[test]
+    fn test_length() {
+        let track = Track {
+            coords: vec![Coord::new(0., 0., None), Coord::new(1., 0., None)],
+            ..Default::default()
+        };
+        assert_eq!(track.length().round(), 111319.);
+        let multi = Geometry::MultiGeometry(MultiGeometry::new(vec![
+            Geometry::Track(track.clone()),
+            Geometry::Point(Point::new(5., 5., None)),
+            Geometry::Track(track),
+        ]));
+        assert_eq!(multi.length().round(), 222639.);
+    }
+
+    #[test]
+    fn test_area() {
+        let polygon = Polygon::new(square(0., 1.), vec![]);
+        let with_hole = Polygon::new(square(0., 1.), vec![square(0.25, 0.75)]);
+        assert_eq!((polygon.area() / 1e6).round(), 12309.);
+        assert!(with_hole.area() < polygon.area() * 0.8);
+        assert_eq!(
+            Geometry::MultiGeometry(MultiGeometry::new(vec![
+                Geometry::Polygon(polygon.clone()),
+                Geometry::LinearRing(square(0., 1.)),
+            ]))
+            .area(),
+            polygon.area()
+        );
+    }
+
+    #[test]
+    fn test_bounding_rect() {
+        let multi = Geometry::MultiGeometry(MultiGeometry::new(vec![
+            Geometry::Point(Point::new(-5., 2., None)),
+            Geometry::Polygon(Polygon::new(square(0., 1.), vec![])),
+        ]));
+        assert_eq!(
+            multi.bounding_rect(),
+            Some(geo_types::Rect::new(
+                geo_types::coord! { x: -5., y: 0. },
+                geo_types::coord! { x: 1., y: 2. }
+            ))
+        );
+        assert_eq!(
+            Geometry::<f64>::MultiGeometry(MultiGeometry::new(vec![])).bounding_rect(),
+            None
+        );
+    }
+}
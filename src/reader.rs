@@ -1,32 +1,867 @@
 //! Module for reading KML sources into Rust types
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+#[cfg(feature = "fs")]
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read};
 use std::marker::PhantomData;
+#[cfg(feature = "fs")]
 use std::path::Path;
 use std::str;
 use std::str::FromStr;
+#[cfg(feature = "encoding")]
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Arc;
 
 use num_traits::{Float, One, Zero};
 use quick_xml::events::attributes::Attributes;
-use quick_xml::events::{BytesStart, Event};
+use quick_xml::events::{BytesEnd, BytesStart, Event};
+use quick_xml::name::{QName, ResolveResult};
+use quick_xml::NsReader;
 
-use crate::errors::Error;
+use crate::errors::{Error, Position, Warning};
 use crate::types::geom_props::GeomProps;
 use crate::types::{
-    self, coords_from_str, Alias, BalloonStyle, ColorMode, Coord, CoordType, Element, Geometry,
-    Icon, IconStyle, Kml, KmlDocument, KmlVersion, LabelStyle, LineString, LineStyle, LinearRing,
-    Link, LinkTypeIcon, ListStyle, Location, MultiGeometry, Orientation, Pair, Placemark, Point,
-    PolyStyle, Polygon, RefreshMode, ResourceMap, Scale, SchemaData, SimpleArrayData, SimpleData,
-    Style, StyleMap, Units, Vec2, ViewRefreshMode,
+    self, Alias, BalloonStyle, ColorMode, Coord, CoordType, Document, Element, Extension, Folder,
+    Geometry, Icon, IconStyle, Kml, KmlDocument, KmlExtension, KmlVersion, LabelStyle, LineString,
+    LineStyle, LinearRing, Link, LinkTypeIcon, ListStyle, Location, MultiGeometry, MultiTrack,
+    Node, Orientation, Pair, Placemark, Point, PolyStyle, Polygon, RefreshMode, ResourceMap, Scale,
+    SchemaData, SimpleArrayData, SimpleData, Style, StyleMap, StyleState, Tour, Track, Units, Vec2,
+    ViewRefreshMode,
 };
 
+/// Namespaces with elements that are read into typed KML structs rather than
+/// [`Element`](struct.Element.html)
+const KML_NAMESPACES: [&[u8]; 6] = [
+    b"http://www.opengis.net/kml/2.2",
+    b"http://www.opengis.net/kml/2.3",
+    b"http://earth.google.com/kml/2.0",
+    b"http://earth.google.com/kml/2.1",
+    b"http://earth.google.com/kml/2.2",
+    b"http://www.google.com/kml/ext/2.2",
+];
+
+/// Input to the XML reader, which is transcoded to UTF-8 when the `encoding` feature is enabled
+/// and the input declares another encoding
+enum Source<B> {
+    Bytes(B),
+    #[cfg(feature = "encoding")]
+    Decoded(
+        Box<BufReader<encoding_rs_io::DecodeReaderBytes<CountingReader<B>, Vec<u8>>>>,
+        Arc<AtomicU64>,
+    ),
+}
+
+impl<B: BufRead> Source<B> {
+    #[cfg(feature = "encoding")]
+    fn new(mut inner: B) -> Self {
+        match inner
+            .fill_buf()
+            .ok()
+            .and_then(crate::encoding::detect_encoding)
+        {
+            Some(encoding) => {
+                let count = Arc::new(AtomicU64::new(0));
+                let inner = CountingReader {
+                    inner,
+                    count: Arc::clone(&count),
+                };
+                Source::Decoded(
+                    Box::new(BufReader::new(
+                        encoding_rs_io::DecodeReaderBytesBuilder::new()
+                            .encoding(Some(encoding))
+                            .build(inner),
+                    )),
+                    count,
+                )
+            }
+            None => Source::Bytes(inner),
+        }
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    fn new(inner: B) -> Self {
+        Source::Bytes(inner)
+    }
+
+    /// Returns the number of bytes read from the original input if it's being transcoded
+    fn transcoded_bytes_read(&self) -> Option<u64> {
+        match self {
+            Source::Bytes(_) => None,
+            #[cfg(feature = "encoding")]
+            Source::Decoded(_, count) => Some(count.load(AtomicOrdering::Relaxed)),
+        }
+    }
+}
+
+/// `Read` wrapper counting the bytes read from input that's being transcoded
+#[cfg(feature = "encoding")]
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "encoding")]
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, AtomicOrdering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<B: BufRead> Read for Source<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::Bytes(inner) => inner.read(buf),
+            #[cfg(feature = "encoding")]
+            Source::Decoded(inner, _) => inner.read(buf),
+        }
+    }
+}
+
+impl<B: BufRead> BufRead for Source<B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Source::Bytes(inner) => inner.fill_buf(),
+            #[cfg(feature = "encoding")]
+            Source::Decoded(inner, _) => inner.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Source::Bytes(inner) => inner.consume(amt),
+            #[cfg(feature = "encoding")]
+            Source::Decoded(inner, _) => inner.consume(amt),
+        }
+    }
+}
+
+/// `BufRead` wrapper that tracks the line and column of the input consumed by the XML reader
+struct PositionReader<B> {
+    inner: B,
+    position: Position,
+}
+
+impl<B> PositionReader<B> {
+    fn new(inner: B) -> Self {
+        PositionReader {
+            inner,
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+
+    fn advance(position: &mut Position, bytes: &[u8]) {
+        position.offset += bytes.len() as u64;
+        for b in bytes {
+            if *b == b'\n' {
+                position.line += 1;
+                position.column = 1;
+            } else if b & 0xC0 != 0x80 {
+                // Only count the first byte of UTF-8 sequences so columns are in characters
+                position.column += 1;
+            }
+        }
+    }
+}
+
+impl<B: BufRead> Read for PositionReader<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        Self::advance(&mut self.position, &buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<B: BufRead> BufRead for PositionReader<B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // Data being consumed is already buffered, so this doesn't read from the source
+        if let Ok(buf) = self.inner.fill_buf() {
+            Self::advance(&mut self.position, &buf[..amt.min(buf.len())]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Typed fields of a `Document` or `Folder`, read along with its elements
+#[derive(Default)]
+struct ContainerFields {
+    name: Option<String>,
+    description: Option<String>,
+    style_url: Option<String>,
+    children: Vec<Node>,
+}
+
+/// Whether an element in a container is one of the fields shared by all features rather than
+/// one of its elements. Styles stay with the elements so they can be shared
+fn is_container_child(name: &[u8]) -> bool {
+    matches!(
+        name,
+        b"visibility"
+            | b"open"
+            | b"author"
+            | b"link"
+            | b"address"
+            | b"AddressDetails"
+            | b"phoneNumber"
+            | b"Snippet"
+            | b"snippet"
+            | b"Camera"
+            | b"LookAt"
+            | b"TimeStamp"
+            | b"TimeSpan"
+            | b"Region"
+            | b"Metadata"
+            | b"ExtendedData"
+    )
+}
+
+/// Whether the element is a feature, as opposed to a style, schema, or other element that can
+/// appear in a container
+fn is_feature<T: CoordType>(kml: &Kml<T>) -> bool {
+    let name = match kml {
+        Kml::Placemark(_) | Kml::Tour(_) | Kml::Document(_) | Kml::Folder(_) => return true,
+        Kml::Element(e) => Cow::Borrowed(e.name.as_str()),
+        Kml::Extension(e) => Cow::Owned(e.to_element().name),
+        _ => return false,
+    };
+    matches!(
+        name.rsplit(':').next(),
+        Some("NetworkLink" | "GroundOverlay" | "ScreenOverlay" | "PhotoOverlay" | "Tour")
+    )
+}
+
+/// Names of the elements read into typed structs, used to find the expected spelling of an element
+/// name when `case_insensitive` is set
+const ELEMENT_NAMES: [&[u8]; 78] = [
+    b"Alias",
+    b"BalloonStyle",
+    b"Document",
+    b"Folder",
+    b"Icon",
+    b"IconStyle",
+    b"LabelStyle",
+    b"LineString",
+    b"LineStyle",
+    b"LinearRing",
+    b"Link",
+    b"ListStyle",
+    b"Location",
+    b"MultiGeometry",
+    b"MultiTrack",
+    b"Orientation",
+    b"Pair",
+    b"Placemark",
+    b"Playlist",
+    b"Point",
+    b"PolyStyle",
+    b"Polygon",
+    b"ResourceMap",
+    b"Scale",
+    b"SchemaData",
+    b"SimpleArrayData",
+    b"SimpleData",
+    b"Style",
+    b"StyleMap",
+    b"Tour",
+    b"Track",
+    b"altitude",
+    b"altitudeMode",
+    b"angles",
+    b"bgColor",
+    b"color",
+    b"colorMode",
+    b"coord",
+    b"coordinates",
+    b"description",
+    b"displayMode",
+    b"extrude",
+    b"fill",
+    b"heading",
+    b"hotSpot",
+    b"href",
+    b"httpQuery",
+    b"innerBoundaryIs",
+    b"interpolate",
+    b"key",
+    b"kml",
+    b"latitude",
+    b"longitude",
+    b"maxSnippetLines",
+    b"name",
+    b"outerBoundaryIs",
+    b"outline",
+    b"refreshInterval",
+    b"refreshMode",
+    b"roll",
+    b"scale",
+    b"sourceHref",
+    b"styleUrl",
+    b"targetHref",
+    b"tessellate",
+    b"text",
+    b"textColor",
+    b"tilt",
+    b"value",
+    b"viewBoundScale",
+    b"viewFormat",
+    b"viewRefreshMode",
+    b"viewRefreshTime",
+    b"when",
+    b"width",
+    b"x",
+    b"y",
+    b"z",
+];
+
+/// Names of the other elements in the KML 2.2 and Google extension schemas, which are kept as
+/// [`Element`](../types/struct.Element.html) even when `strict` is set
+const SCHEMA_ELEMENT_NAMES: [&[u8]; 97] = [
+    b"AnimatedUpdate",
+    b"Camera",
+    b"Change",
+    b"Create",
+    b"Data",
+    b"Delete",
+    b"ExtendedData",
+    b"FlyTo",
+    b"GroundOverlay",
+    b"ImagePyramid",
+    b"ItemIcon",
+    b"LatLonAltBox",
+    b"LatLonBox",
+    b"LatLonQuad",
+    b"Lod",
+    b"LookAt",
+    b"Metadata",
+    b"Model",
+    b"NetworkLink",
+    b"NetworkLinkControl",
+    b"PhotoOverlay",
+    b"Region",
+    b"Schema",
+    b"ScreenOverlay",
+    b"SimpleArrayField",
+    b"SimpleField",
+    b"Snippet",
+    b"SoundCue",
+    b"TimeSpan",
+    b"TimeStamp",
+    b"TourControl",
+    b"Update",
+    b"Url",
+    b"ViewVolume",
+    b"ViewerOptions",
+    b"Wait",
+    b"address",
+    b"altitudeOffset",
+    b"balloonVisibility",
+    b"begin",
+    b"bottomFov",
+    b"cookie",
+    b"delayedStart",
+    b"displayName",
+    b"drawOrder",
+    b"duration",
+    b"east",
+    b"end",
+    b"expires",
+    b"flyToMode",
+    b"flyToView",
+    b"gridOrigin",
+    b"h",
+    b"horizFov",
+    b"labelVisibility",
+    b"leftFov",
+    b"linkDescription",
+    b"linkName",
+    b"linkSnippet",
+    b"listItemType",
+    b"maxAltitude",
+    b"maxFadeExtent",
+    b"maxHeight",
+    b"maxLodPixels",
+    b"maxSessionLength",
+    b"maxWidth",
+    b"message",
+    b"minAltitude",
+    b"minFadeExtent",
+    b"minLodPixels",
+    b"minRefreshPeriod",
+    b"near",
+    b"north",
+    b"open",
+    b"option",
+    b"outerColor",
+    b"outerWidth",
+    b"overlayXY",
+    b"phoneNumber",
+    b"physicalWidth",
+    b"playMode",
+    b"range",
+    b"refreshVisibility",
+    b"rightFov",
+    b"rotation",
+    b"rotationXY",
+    b"screenXY",
+    b"shape",
+    b"size",
+    b"snippet",
+    b"south",
+    b"state",
+    b"tileSize",
+    b"topFov",
+    b"visibility",
+    b"w",
+    b"west",
+];
+
+/// Configuration for [`KmlReader`](struct.KmlReader.html)
+///
+/// # Example
+///
+/// ```
+/// use kml::{reader::KmlReaderConfig, KmlReader};
+///
+/// let config = KmlReaderConfig::default()
+///     .lenient(true)
+///     .max_depth(Some(64))
+///     .tolerant_coordinates(true);
+/// let mut reader = KmlReader::<_, f64>::from_string_with_config(
+///     "<Point><coordinates>1, 2, 3</coordinates></Point>",
+///     config,
+/// );
+/// assert!(reader.read().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct KmlReaderConfig {
+    /// Trim whitespace surrounding free text values like names and descriptions, which is the
+    /// default. Numbers, enums, and other typed values are always trimmed
+    pub trim_text: bool,
+    /// Maximum depth of nested containers, multi-geometries, and unrecognized elements before
+    /// returning [`Error::MaxDepthExceeded`](../enum.Error.html#variant.MaxDepthExceeded),
+    /// which is 64 by default. Raising it or setting `None` allows deeply nested input to
+    /// overflow the stack
+    pub max_depth: Option<usize>,
+    /// Recover from problems like invalid numbers and unknown enum values by using default values,
+    /// and from geometries missing valid coordinates by leaving them out, recording a
+    /// [`Warning`](../struct.Warning.html) instead of returning an error
+    pub lenient: bool,
+    /// Return [`Error::UnexpectedElement`](../enum.Error.html#variant.UnexpectedElement) for
+    /// elements that aren't part of the KML 2.2 or Google extension schemas, like misspelled
+    /// names and elements in other namespaces, instead of keeping them as
+    /// [`Element`](../types/struct.Element.html). Schema elements without a typed struct, like
+    /// `ExtendedData`, are still kept as `Element`
+    pub strict: bool,
+    /// Accept whitespace around the commas separating values within a coordinate tuple, like
+    /// `1, 2, 3`, which would otherwise be split into separate tuples, as well as trailing commas
+    /// and semicolons between tuples. See
+    /// [`coords_from_str_tolerant`](../types/fn.coords_from_str_tolerant.html)
+    pub tolerant_coordinates: bool,
+    /// Skip coordinate tuples that can't be parsed, recording a [`Warning`](../struct.Warning.html)
+    /// for each instead of returning an error, while keeping the rest of the geometry
+    pub skip_invalid_coordinates: bool,
+    /// Match element names regardless of their namespace, so elements in foreign namespaces are
+    /// read into typed structs if their local name matches a KML element
+    pub ignore_namespaces: bool,
+    /// Match KML element names regardless of case, so names written by legacy producers like
+    /// `placemark` or `COORDINATES` are still recognized. Where names only differ by case, like
+    /// `Scale` and `scale`, the one with the same case of its first letter is used
+    pub case_insensitive: bool,
+    /// Skip the contents of `coordinates` and `gx:coord` elements without parsing them, for
+    /// quickly scanning names, styles, and data in large files. Geometries are read without
+    /// coordinates, except for `Point`s, which are left out rather than given a made-up one
+    pub skip_coordinates: bool,
+    /// Only read elements inside `kml`, `Document`, and `Folder` containers that the filter
+    /// accepts, skipping others along with their children. Containers need to be accepted for
+    /// their contents to be read, and their `name` is always read to build
+    /// [`ElementInfo::path`](struct.ElementInfo.html#structfield.path)
+    pub filter: Option<ElementFilter>,
+    /// Return [`Error::InvalidInput`](../enum.Error.html#variant.InvalidInput) for documents with
+    /// a DOCTYPE instead of skipping it. Entities declared in a DOCTYPE are never expanded either
+    /// way, so external entities are never resolved and references to them are kept as written
+    pub reject_doctype: bool,
+    /// Skip `Placemark`s that can't be read, like ones with invalid coordinates, recording a
+    /// [`Warning`](../struct.Warning.html) and reading the rest of the document instead of
+    /// returning an error. Malformed XML is still an error
+    pub skip_invalid_features: bool,
+    /// Parsers for extension elements keyed by local name, set with
+    /// [`register_parser`](#method.register_parser)
+    pub parsers: HashMap<String, ElementParser>,
+    /// Transform applied to every coordinate as it's read, set with
+    /// [`coord_transform`](#method.coord_transform)
+    pub coord_transform: Option<CoordTransform>,
+    /// Keep XML comments in containers as [`Kml::Comment`](../enum.Kml.html#variant.Comment) and
+    /// in placemarks and other elements as [`Node::Comment`](../types/enum.Node.html#variant.Comment)
+    /// children instead of skipping them
+    pub comments: bool,
+    /// Largest size in bytes a file in a KMZ archive can decompress to before returning
+    /// [`Error::KmzLimitExceeded`](../enum.Error.html#variant.KmzLimitExceeded), for reading
+    /// untrusted archives. Unlimited by default
+    #[cfg(feature = "zip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+    pub max_kmz_entry_size: Option<u64>,
+    /// Largest ratio of a file's decompressed size to its compressed size in a KMZ archive before
+    /// returning [`Error::KmzLimitExceeded`](../enum.Error.html#variant.KmzLimitExceeded), for
+    /// rejecting zip bombs. Unlimited by default
+    #[cfg(feature = "zip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+    pub max_kmz_compression_ratio: Option<u64>,
+    /// Largest size in bytes of a KMZ archive downloaded with
+    /// [`KmlReader::from_kmz_url`](struct.KmlReader.html#method.from_kmz_url) before returning
+    /// [`Error::KmzLimitExceeded`](../enum.Error.html#variant.KmzLimitExceeded) without
+    /// downloading the rest of it. Unlimited by default
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    pub max_kmz_download_size: Option<u64>,
+}
+
+/// Element about to be read, passed to a [`KmlReaderConfig::filter`](struct.KmlReaderConfig.html#method.filter)
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ElementInfo<'a> {
+    /// Local name of the element, like `Placemark`
+    pub name: &'a str,
+    pub attrs: &'a HashMap<String, String>,
+    /// Names of the enclosing `Document` and `Folder` elements from the outermost, which are empty
+    /// for containers without a `name`
+    pub path: &'a [String],
+}
+
+/// Predicate deciding which elements are read, set with
+/// [`KmlReaderConfig::filter`](struct.KmlReaderConfig.html#method.filter)
+#[derive(Clone)]
+pub struct ElementFilter(Arc<dyn Fn(&ElementInfo) -> bool + Send + Sync>);
+
+impl ElementFilter {
+    pub fn new(filter: impl Fn(&ElementInfo) -> bool + Send + Sync + 'static) -> Self {
+        ElementFilter(Arc::new(filter))
+    }
+
+    /// Whether the element should be read
+    pub fn accepts(&self, element: &ElementInfo) -> bool {
+        (self.0)(element)
+    }
+}
+
+impl fmt::Debug for ElementFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ElementFilter").finish_non_exhaustive()
+    }
+}
+
+/// Parser converting an extension element into an [`Extension`](../types/struct.Extension.html),
+/// set with [`KmlReaderConfig::register_parser`](struct.KmlReaderConfig.html#method.register_parser)
+#[derive(Clone)]
+pub struct ElementParser(Arc<dyn Fn(Element) -> Result<Extension, Error> + Send + Sync>);
+
+impl ElementParser {
+    pub fn new<E: KmlExtension>(
+        parser: impl Fn(Element) -> Result<E, Error> + Send + Sync + 'static,
+    ) -> Self {
+        ElementParser(Arc::new(move |element| parser(element).map(Extension::new)))
+    }
+
+    /// Parses the element
+    pub fn parse(&self, element: Element) -> Result<Extension, Error> {
+        (self.0)(element)
+    }
+}
+
+impl fmt::Debug for ElementParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ElementParser").finish_non_exhaustive()
+    }
+}
+
+/// Transform applied to coordinates as they're read, set with
+/// [`KmlReaderConfig::coord_transform`](struct.KmlReaderConfig.html#method.coord_transform)
+///
+/// It takes `f64` coordinates, which readers of other coordinate types convert their coordinates
+/// to and back from, so the same configuration can be used for any of them
+#[derive(Clone)]
+pub struct CoordTransform(Arc<dyn Fn(Coord<f64>) -> Coord<f64> + Send + Sync>);
+
+impl CoordTransform {
+    pub fn new(transform: impl Fn(Coord<f64>) -> Coord<f64> + Send + Sync + 'static) -> Self {
+        CoordTransform(Arc::new(transform))
+    }
+
+    /// Applies the transform
+    pub fn apply<T: CoordType>(&self, coord: Coord<T>) -> Coord<T> {
+        (self.0)(coord.cast()).cast()
+    }
+}
+
+impl fmt::Debug for CoordTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CoordTransform").finish_non_exhaustive()
+    }
+}
+
+impl Default for KmlReaderConfig {
+    fn default() -> Self {
+        KmlReaderConfig {
+            trim_text: true,
+            max_depth: Some(64),
+            lenient: false,
+            strict: false,
+            tolerant_coordinates: false,
+            skip_invalid_coordinates: false,
+            ignore_namespaces: false,
+            case_insensitive: false,
+            skip_coordinates: false,
+            filter: None,
+            reject_doctype: false,
+            skip_invalid_features: false,
+            parsers: HashMap::new(),
+            coord_transform: None,
+            comments: false,
+            #[cfg(feature = "zip")]
+            max_kmz_entry_size: None,
+            #[cfg(feature = "zip")]
+            max_kmz_compression_ratio: None,
+            #[cfg(feature = "http")]
+            max_kmz_download_size: None,
+        }
+    }
+}
+
+impl KmlReaderConfig {
+    /// Sets [`trim_text`](#structfield.trim_text)
+    pub fn trim_text(mut self, trim_text: bool) -> Self {
+        self.trim_text = trim_text;
+        self
+    }
+
+    /// Sets [`max_depth`](#structfield.max_depth)
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets [`lenient`](#structfield.lenient)
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Sets [`strict`](#structfield.strict)
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets [`tolerant_coordinates`](#structfield.tolerant_coordinates)
+    pub fn tolerant_coordinates(mut self, tolerant_coordinates: bool) -> Self {
+        self.tolerant_coordinates = tolerant_coordinates;
+        self
+    }
+
+    /// Sets [`skip_invalid_coordinates`](#structfield.skip_invalid_coordinates)
+    pub fn skip_invalid_coordinates(mut self, skip_invalid_coordinates: bool) -> Self {
+        self.skip_invalid_coordinates = skip_invalid_coordinates;
+        self
+    }
+
+    /// Sets [`ignore_namespaces`](#structfield.ignore_namespaces)
+    pub fn ignore_namespaces(mut self, ignore_namespaces: bool) -> Self {
+        self.ignore_namespaces = ignore_namespaces;
+        self
+    }
+
+    /// Sets [`case_insensitive`](#structfield.case_insensitive)
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Sets [`skip_coordinates`](#structfield.skip_coordinates)
+    pub fn skip_coordinates(mut self, skip_coordinates: bool) -> Self {
+        self.skip_coordinates = skip_coordinates;
+        self
+    }
+
+    /// Sets [`reject_doctype`](#structfield.reject_doctype)
+    pub fn reject_doctype(mut self, reject_doctype: bool) -> Self {
+        self.reject_doctype = reject_doctype;
+        self
+    }
+
+    /// Sets [`comments`](#structfield.comments)
+    pub fn comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Sets [`max_kmz_entry_size`](#structfield.max_kmz_entry_size)
+    #[cfg(feature = "zip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+    pub fn max_kmz_entry_size(mut self, max_kmz_entry_size: Option<u64>) -> Self {
+        self.max_kmz_entry_size = max_kmz_entry_size;
+        self
+    }
+
+    /// Sets [`max_kmz_compression_ratio`](#structfield.max_kmz_compression_ratio)
+    #[cfg(feature = "zip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+    pub fn max_kmz_compression_ratio(mut self, max_kmz_compression_ratio: Option<u64>) -> Self {
+        self.max_kmz_compression_ratio = max_kmz_compression_ratio;
+        self
+    }
+
+    /// Sets [`max_kmz_download_size`](#structfield.max_kmz_download_size)
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    pub fn max_kmz_download_size(mut self, max_kmz_download_size: Option<u64>) -> Self {
+        self.max_kmz_download_size = max_kmz_download_size;
+        self
+    }
+
+    /// Sets [`skip_invalid_features`](#structfield.skip_invalid_features)
+    pub fn skip_invalid_features(mut self, skip_invalid_features: bool) -> Self {
+        self.skip_invalid_features = skip_invalid_features;
+        self
+    }
+
+    /// Sets [`filter`](#structfield.filter)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{reader::KmlReaderConfig, Kml, KmlReader};
+    ///
+    /// let kml_str = r#"
+    /// <Document>
+    ///   <Folder><name>Roads</name><Placemark><name>Road</name></Placemark></Folder>
+    ///   <Folder><name>Rivers</name><Placemark><name>River</name></Placemark></Folder>
+    /// </Document>"#;
+    /// // Only read Placemarks in the Roads folder
+    /// let config = KmlReaderConfig::default().filter(|e| {
+    ///     e.name != "Placemark" || e.path.last().map(String::as_str) == Some("Roads")
+    /// });
+    /// let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+    /// let placemarks = reader
+    ///     .features()
+    ///     .filter_map(|f| match f.unwrap() {
+    ///         Kml::Placemark(p) => p.name,
+    ///         _ => None,
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(placemarks, vec!["Road".to_string()]);
+    /// ```
+    pub fn filter(mut self, filter: impl Fn(&ElementInfo) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(ElementFilter::new(filter));
+        self
+    }
+
+    /// Registers a parser for extension elements with the local name `name`, like `MyExt` for
+    /// `<vendor:MyExt>`, reading them as [`Kml::Extension`](../enum.Kml.html#variant.Extension)
+    /// instead of [`Kml::Element`](../enum.Kml.html#variant.Element). Parsers only apply to
+    /// elements that aren't otherwise read into typed structs, and are used in
+    /// [`strict`](#structfield.strict) mode too
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::types::{Element, KmlExtension};
+    /// use kml::{reader::KmlReaderConfig, Error, Kml, KmlReader};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Rating(u8);
+    ///
+    /// impl KmlExtension for Rating {
+    ///     fn to_element(&self) -> Element {
+    ///         Element {
+    ///             name: "Rating".to_string(),
+    ///             content: Some(self.0.to_string()),
+    ///             ..Default::default()
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let config = KmlReaderConfig::default().register_parser("Rating", |e: Element| {
+    ///     let content = e.content.unwrap_or_default();
+    ///     content
+    ///         .parse()
+    ///         .map(Rating)
+    ///         .map_err(|_| Error::InvalidInput(content))
+    /// });
+    /// let mut reader = KmlReader::<_, f64>::from_string_with_config(
+    ///     r#"<Rating xmlns="http://example.com/rating">5</Rating>"#,
+    ///     config,
+    /// );
+    /// let Kml::Extension(extension) = reader.read().unwrap() else {
+    ///     panic!("expected an extension");
+    /// };
+    /// assert_eq!(extension.downcast_ref::<Rating>(), Some(&Rating(5)));
+    /// ```
+    pub fn register_parser<E: KmlExtension>(
+        mut self,
+        name: impl Into<String>,
+        parser: impl Fn(Element) -> Result<E, Error> + Send + Sync + 'static,
+    ) -> Self {
+        self.parsers.insert(name.into(), ElementParser::new(parser));
+        self
+    }
+
+    /// Sets a transform applied to every coordinate as it's read, like for clamping values,
+    /// swapping axes, or shifting datums, without another pass over the parsed elements
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{reader::KmlReaderConfig, types::Coord, Kml, KmlReader};
+    ///
+    /// // Swap coordinates written as latitude, longitude
+    /// let config = KmlReaderConfig::default().coord_transform(|c| Coord::new(c.y, c.x, c.z));
+    /// let mut reader = KmlReader::<_, f64>::from_string_with_config(
+    ///     "<Point><coordinates>52,13</coordinates></Point>",
+    ///     config,
+    /// );
+    /// let Ok(Kml::Point(point)) = reader.read() else {
+    ///     panic!("expected a Point");
+    /// };
+    /// assert_eq!(point.coord, Coord::new(13., 52., None));
+    /// ```
+    pub fn coord_transform(
+        mut self,
+        transform: impl Fn(Coord<f64>) -> Coord<f64> + Send + Sync + 'static,
+    ) -> Self {
+        self.coord_transform = Some(CoordTransform::new(transform));
+        self
+    }
+}
+
 /// Main struct for reading KML documents
 pub struct KmlReader<B: BufRead, T: CoordType + FromStr + Default = f64> {
-    reader: quick_xml::Reader<B>,
+    reader: NsReader<PositionReader<Source<B>>>,
     buf: Vec<u8>,
-    _version: KmlVersion, // TODO: How to incorporate this so it can be set before parsing?
+    config: KmlReaderConfig,
+    warnings: Vec<Warning>,
+    depth: usize,
+    version: KmlVersion,
+    path: Vec<String>,
+    open_elements: usize,
+    /// Namespace of the most recently started element, taken by the reader of its typed struct
+    start_namespace: Option<Cow<'static, str>>,
+    /// KML namespace of unprefixed elements without a namespace inside a `kml` root that doesn't
+    /// declare a default namespace
+    unbound_namespace: Option<&'static str>,
+    /// Events read ahead of a container's fields by `next_event`
+    pending_events: VecDeque<KmlEvent<T>>,
     _phantom: PhantomData<T>,
 }
 
@@ -45,10 +880,20 @@ where
     /// let kml_point: Kml<f64> = KmlReader::from_string(point_str).read().unwrap();
     /// ```
     pub fn from_string(s: &str) -> KmlReader<&[u8], T> {
-        KmlReader::<&[u8], T>::from_xml_reader(quick_xml::Reader::<&[u8]>::from_str(s))
+        // Strings are already UTF-8 regardless of any declared encoding
+        KmlReader::<&[u8], T>::from_source(Source::Bytes(s.as_bytes()))
+    }
+
+    /// Parse KML from string with a [`KmlReaderConfig`](struct.KmlReaderConfig.html)
+    pub fn from_string_with_config(s: &str, config: KmlReaderConfig) -> KmlReader<&[u8], T> {
+        let mut reader = KmlReader::<&[u8], T>::from_string(s);
+        reader.config = config;
+        reader
     }
 }
 
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
 impl<T> KmlReader<BufReader<File>, T>
 where
     T: CoordType + FromStr + Default,
@@ -69,10 +914,46 @@ where
     /// let kml = kml_reader.read().unwrap();
     /// ```
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<KmlReader<BufReader<File>, T>, Error> {
-        Ok(KmlReader::<BufReader<File>, T>::from_xml_reader(
-            quick_xml::Reader::from_file(path)?,
+        Ok(KmlReader::<BufReader<File>, T>::from_reader(
+            BufReader::new(File::open(path)?),
         ))
     }
+
+    /// Read KML from a file path with a [`KmlReaderConfig`](struct.KmlReaderConfig.html)
+    pub fn from_path_with_config<P: AsRef<Path>>(
+        path: P,
+        config: KmlReaderConfig,
+    ) -> Result<KmlReader<BufReader<File>, T>, Error> {
+        let mut reader = KmlReader::<BufReader<File>, T>::from_path(path)?;
+        reader.config = config;
+        Ok(reader)
+    }
+}
+
+impl<R: Read, T> KmlReader<BufReader<R>, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    /// Read from an unbuffered reader like [`Stdin`](std::io::Stdin), which is wrapped in a
+    /// [`BufReader`]
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let kml: Kml = KmlReader::<_, f64>::from_read(std::io::stdin()).read().unwrap();
+    /// ```
+    pub fn from_read(r: R) -> KmlReader<BufReader<R>, T> {
+        KmlReader::<BufReader<R>, T>::from_reader(BufReader::new(r))
+    }
+
+    /// Read from an unbuffered reader with a [`KmlReaderConfig`](struct.KmlReaderConfig.html)
+    pub fn from_read_with_config(r: R, config: KmlReaderConfig) -> KmlReader<BufReader<R>, T> {
+        let mut reader = KmlReader::<BufReader<R>, T>::from_read(r);
+        reader.config = config;
+        reader
+    }
 }
 
 impl<B: BufRead, T> KmlReader<B, T>
@@ -80,23 +961,49 @@ where
     T: CoordType + FromStr + Default,
 {
     /// Read from any generic reader type
+    ///
+    /// With the `encoding` feature enabled, input that starts with a UTF-16 byte order mark or
+    /// declares a non-UTF-8 encoding like `<?xml version="1.0" encoding="ISO-8859-1"?>` is
+    /// transcoded to UTF-8 before parsing, so positions in errors refer to the transcoded input
     pub fn from_reader(r: B) -> KmlReader<B, T> {
-        KmlReader::<B, T>::from_xml_reader(quick_xml::Reader::from_reader(r))
+        KmlReader::<B, T>::from_source(Source::new(r))
+    }
+
+    /// Read from any generic reader type with a [`KmlReaderConfig`](struct.KmlReaderConfig.html)
+    pub fn from_reader_with_config(r: B, config: KmlReaderConfig) -> KmlReader<B, T> {
+        let mut reader = KmlReader::<B, T>::from_reader(r);
+        reader.config = config;
+        reader
     }
 
-    fn from_xml_reader(mut reader: quick_xml::Reader<B>) -> KmlReader<B, T> {
+    fn from_source(source: Source<B>) -> KmlReader<B, T> {
+        let mut reader = NsReader::from_reader(PositionReader::new(source));
         let config = reader.config_mut();
         config.trim_text(true);
+        // Self-closing elements like `<tessellate/>` are read as a start followed by an end
+        config.expand_empty_elements = true;
         KmlReader {
             reader,
             buf: Vec::new(),
-            _version: KmlVersion::Unknown,
+            config: KmlReaderConfig::default(),
+            warnings: Vec::new(),
+            depth: 0,
+            version: KmlVersion::Unknown,
+            path: Vec::new(),
+            open_elements: 0,
+            start_namespace: None,
+            unbound_namespace: None,
+            pending_events: VecDeque::new(),
             _phantom: PhantomData,
         }
     }
 
     /// Read content into [`Kml`](enum.Kml.html)
     ///
+    /// Reading stops at the end of a `kml` root element, so for input with multiple concatenated
+    /// documents each call reads the next one, returning
+    /// [`Error::NoElements`](../enum.Error.html#variant.NoElements) after the last.
+    ///
     /// # Example
     ///
     /// ```
@@ -106,7 +1013,9 @@ where
     /// let kml_point: Kml<f64> = KmlReader::from_string(point_str).read().unwrap();
     /// ```
     pub fn read(&mut self) -> Result<Kml<T>, Error> {
-        let mut result = self.read_elements()?;
+        let mut result = self
+            .read_elements(true, None)
+            .map_err(|e| self.locate(e, None))?;
         // Converts multiple items at the same level to KmlDocument
         match result.len().cmp(&1) {
             Ordering::Greater => Ok(Kml::KmlDocument(KmlDocument {
@@ -118,148 +1027,731 @@ where
         }
     }
 
-    fn read_elements(&mut self) -> Result<Vec<Kml<T>>, Error> {
-        let mut elements: Vec<Kml<T>> = Vec::new();
-        loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
-            match e {
-                Event::Start(ref mut e) => {
-                    let attrs = Self::read_attrs(e.attributes());
-                    match e.local_name().as_ref() {
-                        b"kml" => elements.push(Kml::KmlDocument(self.read_kml_document()?)),
-                        b"Scale" => elements.push(Kml::Scale(self.read_scale(attrs)?)),
-                        b"Orientation" => {
-                            elements.push(Kml::Orientation(self.read_orientation(attrs)?))
-                        }
-                        b"Point" => elements.push(Kml::Point(self.read_point(attrs)?)),
-                        b"Location" => elements.push(Kml::Location(self.read_location(attrs)?)),
-                        b"LineString" => {
-                            elements.push(Kml::LineString(self.read_line_string(attrs)?))
-                        }
-                        b"LinearRing" => {
-                            elements.push(Kml::LinearRing(self.read_linear_ring(attrs)?))
-                        }
-                        b"Polygon" => elements.push(Kml::Polygon(self.read_polygon(attrs)?)),
-                        b"MultiGeometry" => {
-                            elements.push(Kml::MultiGeometry(self.read_multi_geometry(attrs)?))
-                        }
-                        b"Placemark" => elements.push(Kml::Placemark(self.read_placemark(attrs)?)),
-                        b"Document" => elements.push(Kml::Document {
-                            attrs,
-                            elements: self.read_elements()?,
-                        }),
-                        b"Folder" => elements.push(Kml::Folder {
-                            attrs,
-                            elements: self.read_elements()?,
-                        }),
-                        b"Style" => elements.push(Kml::Style(self.read_style(attrs)?)),
-                        b"StyleMap" => elements.push(Kml::StyleMap(self.read_style_map(attrs)?)),
-                        b"Pair" => elements.push(Kml::Pair(self.read_pair(attrs)?)),
-                        b"BalloonStyle" => {
-                            elements.push(Kml::BalloonStyle(self.read_balloon_style(attrs)?))
-                        }
-                        b"IconStyle" => elements.push(Kml::IconStyle(self.read_icon_style(attrs)?)),
-                        b"Link" => elements.push(Kml::Link(self.read_link(attrs)?)),
-                        b"Icon" => {
-                            elements.push(Kml::LinkTypeIcon(self.read_link_type_icon(attrs)?))
-                        }
-                        b"ResourceMap" => {
-                            elements.push(Kml::ResourceMap(self.read_resource_map(attrs)?))
-                        }
-                        b"Alias" => elements.push(Kml::Alias(self.read_alias(attrs)?)),
-                        b"SchemaData" => {
-                            elements.push(Kml::SchemaData(self.read_schema_data(attrs)?))
-                        }
-                        b"SimpleArrayData" => {
-                            elements.push(Kml::SimpleArrayData(self.read_simple_array_data(attrs)?))
-                        }
-                        b"SimpleData" => {
-                            elements.push(Kml::SimpleData(self.read_simple_data(attrs)?))
-                        }
-                        b"LabelStyle" => {
-                            elements.push(Kml::LabelStyle(self.read_label_style(attrs)?))
-                        }
-                        b"LineStyle" => elements.push(Kml::LineStyle(self.read_line_style(attrs)?)),
-                        b"PolyStyle" => elements.push(Kml::PolyStyle(self.read_poly_style(attrs)?)),
-                        b"ListStyle" => elements.push(Kml::ListStyle(self.read_list_style(attrs)?)),
-                        _ => {
-                            let start = e.to_owned();
-                            elements.push(Kml::Element(self.read_element(&start, attrs)?));
-                        }
-                    };
-                }
-                Event::End(ref mut e) => match e.local_name().as_ref() {
-                    b"Folder" | b"Document" => break,
-                    _ => {}
-                },
-                Event::Decl(_) | Event::CData(_) | Event::Empty(_) | Event::Text(_) => {}
-                Event::Eof => break,
-                Event::Comment(_) => {}
-                x => return Err(Error::InvalidInput(format!("{:?}", x))),
-            };
-        }
+    /// Read content into a [`KmlDocument`](struct.KmlDocument.html), wrapping the root element in
+    /// one if it isn't a `kml` element, so fragments and full documents can be handled the same
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let point_str = "<Point><coordinates>1,1,1</coordinates></Point>";
+    /// let doc = KmlReader::<_, f64>::from_string(point_str).read_document().unwrap();
+    /// assert!(matches!(doc.elements.as_slice(), [Kml::Point(_)]));
+    /// ```
+    pub fn read_document(&mut self) -> Result<KmlDocument<T>, Error> {
+        Ok(match self.read()? {
+            Kml::KmlDocument(doc) => doc,
+            kml => KmlDocument {
+                elements: vec![kml],
+                ..Default::default()
+            },
+        })
+    }
 
-        Ok(elements)
+    /// Read all top-level elements, without wrapping multiple elements in a
+    /// [`KmlDocument`](struct.KmlDocument.html) like [`read`](#method.read)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let kml_str = "<Point><coordinates>1,1</coordinates></Point><name>Fragment</name>";
+    /// let elements: Vec<Kml<f64>> = KmlReader::from_string(kml_str).read_all().unwrap();
+    /// assert_eq!(elements.len(), 2);
+    /// ```
+    pub fn read_all(&mut self) -> Result<Vec<Kml<T>>, Error> {
+        self.read_elements(false, None)
+            .map_err(|e| self.locate(e, None))
     }
 
-    fn read_kml_document(&mut self) -> Result<KmlDocument<T>, Error> {
-        // TODO: Should parse version, change version based on NS
-        Ok(KmlDocument {
-            elements: self.read_elements()?,
-            ..Default::default()
-        })
+    /// Returns the configuration of the reader
+    pub fn config(&self) -> &KmlReaderConfig {
+        &self.config
     }
 
-    fn read_scale(&mut self, attrs: HashMap<String, String>) -> Result<Scale<T>, Error> {
-        let mut x = One::one();
-        let mut y = One::one();
-        let mut z = One::one();
+    /// Returns a mutable reference to the configuration of the reader
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let mut reader = KmlReader::<_, f64>::from_string(
+    ///     "<Point><altitudeMode>underground</altitudeMode><coordinates>1,1</coordinates></Point>",
+    /// );
+    /// reader.config_mut().lenient = true;
+    /// assert!(matches!(reader.read(), Ok(Kml::Point(_))));
+    /// assert_eq!(reader.warnings().len(), 1);
+    /// ```
+    pub fn config_mut(&mut self) -> &mut KmlReaderConfig {
+        &mut self.config
+    }
 
-        loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
-            match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
-                    b"x" => x = self.read_float()?,
-                    b"y" => y = self.read_float()?,
-                    b"z" => z = self.read_float()?,
-                    _ => {}
-                },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Scale" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
-            }
-        }
-        Ok(Scale { x, y, z, attrs })
+    /// Returns the problems recovered from while reading in lenient mode
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
     }
 
-    fn read_orientation(
-        &mut self,
-        attrs: HashMap<String, String>,
-    ) -> Result<Orientation<T>, Error> {
+    /// Returns the position in the input the reader has parsed up to
+    pub fn position(&self) -> Position {
+        self.reader.get_ref().position
+    }
+
+    /// Returns the number of bytes of input consumed so far, which can be compared to the size of
+    /// the input to report progress
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::KmlReader;
+    ///
+    /// let kml_str = "<Folder><Placemark></Placemark><Placemark></Placemark></Folder>";
+    /// let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+    /// let mut features = reader.features();
+    /// while let Some(Ok(_feature)) = features.next() {
+    ///     let progress = features.bytes_read() as f64 / kml_str.len() as f64;
+    ///     // Stop iterating early to cancel parsing
+    ///     if progress > 0.5 {
+    ///         break;
+    ///     }
+    /// }
+    /// ```
+    pub fn bytes_read(&self) -> u64 {
+        let position_reader = self.reader.get_ref();
+        position_reader
+            .inner
+            .transcoded_bytes_read()
+            .unwrap_or(position_reader.position.offset)
+    }
+
+    /// Returns the KML version detected from the namespace of the `kml` root element, which is
+    /// `KmlVersion::Unknown` until one has been read
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::KmlVersion, KmlReader};
+    ///
+    /// let kml_str = r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document></Document></kml>"#;
+    /// let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+    /// reader.read().unwrap();
+    /// assert_eq!(reader.version(), KmlVersion::V22);
+    /// ```
+    pub fn version(&self) -> KmlVersion {
+        self.version
+    }
+
+    /// Returns a reference to the underlying reader, or `None` if the input is being transcoded
+    /// from another encoding with the `encoding` feature
+    pub fn get_ref(&self) -> Option<&B> {
+        match &self.reader.get_ref().inner {
+            Source::Bytes(inner) => Some(inner),
+            #[cfg(feature = "encoding")]
+            Source::Decoded(..) => None,
+        }
+    }
+
+    /// Returns the underlying reader, positioned right after the last element that was read, so
+    /// input following a KML document embedded in a larger stream can still be read. Returns
+    /// `None` if the input is being transcoded from another encoding with the `encoding` feature,
+    /// since the decoder reads ahead
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let input = "<kml><Placemark></Placemark></kml>\nTRAILER";
+    /// let mut reader = KmlReader::<_, f64>::from_string(input);
+    /// assert!(matches!(reader.read(), Ok(Kml::KmlDocument(_))));
+    /// let rest = reader.into_inner().unwrap();
+    /// assert_eq!(rest, b"\nTRAILER");
+    /// ```
+    pub fn into_inner(self) -> Option<B> {
+        match self.reader.into_inner().inner {
+            Source::Bytes(inner) => Some(inner),
+            #[cfg(feature = "encoding")]
+            Source::Decoded(..) => None,
+        }
+    }
+
+    /// Starts reading a new document from `reader`, keeping the configuration and reusing the
+    /// internal buffer. Warnings and the detected version are cleared
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let mut reader = KmlReader::<_, f64>::from_string("<Placemark></Placemark>");
+    /// assert!(matches!(reader.read(), Ok(Kml::Placemark(_))));
+    /// reader.reset("<Folder></Folder>".as_bytes());
+    /// assert!(matches!(reader.read(), Ok(Kml::Folder(_))));
+    /// ```
+    pub fn reset(&mut self, reader: B) {
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.clear();
+        let config = std::mem::take(&mut self.config);
+        *self = KmlReader::from_reader_with_config(reader, config);
+        self.buf = buf;
+    }
+
+    /// Returns an iterator over elements as they are parsed, without building the full tree
+    ///
+    /// Containers (`kml`, `Document`, and `Folder`) are descended into rather than returned, so
+    /// memory use is bounded by the largest individual element like a `Placemark`. Only features
+    /// are returned, such as placemarks, network links, overlays, and tours, leaving out styles,
+    /// schemas, and the fields of containers, which are available through
+    /// [`next_event`](#method.next_event). Iteration stops after the first error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlReader};
+    ///
+    /// let kml_str = r#"
+    /// <Document>
+    ///   <Folder>
+    ///     <Placemark><Point><coordinates>1,1</coordinates></Point></Placemark>
+    ///     <Placemark><Point><coordinates>2,2</coordinates></Point></Placemark>
+    ///   </Folder>
+    /// </Document>"#;
+    /// let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+    /// let placemarks = reader
+    ///     .features()
+    ///     .filter(|k| matches!(k, Ok(Kml::Placemark(_))))
+    ///     .count();
+    /// assert_eq!(placemarks, 2);
+    /// ```
+    pub fn features(&mut self) -> Features<'_, B, T> {
+        Features {
+            reader: self,
+            features_only: true,
+            done: false,
+        }
+    }
+
+    /// Returns an iterator like [`features`](#method.features) that also returns elements other
+    /// than features, like styles and geometries outside of placemarks
+    #[cfg(feature = "geo-types")]
+    pub(crate) fn elements(&mut self) -> Features<'_, B, T> {
+        Features {
+            reader: self,
+            features_only: false,
+            done: false,
+        }
+    }
+
+    /// Reads the next [`KmlEvent`](enum.KmlEvent.html), returning `None` at the end of the input
+    ///
+    /// Containers (`kml`, `Document`, and `Folder`) produce start and end events, while all other
+    /// elements are read in full, allowing documents to be processed without building the full tree.
+    /// The start of a `Document` or `Folder` carries its fields, like its `name`, which are read
+    /// along with the element following them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{KmlEvent, KmlReader};
+    ///
+    /// let kml_str = r#"
+    /// <Document>
+    ///   <Folder>
+    ///     <name>Folder</name>
+    ///     <Placemark><name>1</name></Placemark>
+    ///   </Folder>
+    /// </Document>"#;
+    /// let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+    /// let mut depth = 0;
+    /// while let Some(event) = reader.next_event().unwrap() {
+    ///     match event {
+    ///         KmlEvent::StartDocument(_) | KmlEvent::StartFolder(_) => depth += 1,
+    ///         KmlEvent::End => depth -= 1,
+    ///         KmlEvent::Placemark(p) => assert_eq!((p.name.as_deref(), depth), (Some("1"), 2)),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn next_event(&mut self) -> Result<Option<KmlEvent<T>>, Error> {
+        self.read_event().map_err(|e| self.locate(e, None))
+    }
+
+    fn read_event(&mut self) -> Result<Option<KmlEvent<T>>, Error> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(Some(event));
+        }
+        loop {
+            let e = self.read_xml_event()?.into_owned();
+            match e {
+                Event::Start(start) => {
+                    if !self.accepts(&start) {
+                        self.skip_element()?;
+                        continue;
+                    }
+                    if let Some(event) = self.read_start_event(&start)? {
+                        return Ok(Some(event));
+                    }
+                }
+                Event::End(ref e)
+                    if matches!(e.local_name().as_ref(), b"kml" | b"Document" | b"Folder")
+                        && Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    return Ok(Some(self.end_event(e)));
+                }
+                e @ Event::Eof if self.open_elements > 0 => {
+                    return Err(Error::InvalidXmlEvent(format!("{e:?}")))
+                }
+                Event::Eof => return Ok(None),
+                Event::DocType(_) if self.config.reject_doctype => {
+                    return Err(Error::InvalidInput(format!("{:?}", e)))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the event for the element opened by `start`, or `None` if it's skipped
+    fn read_start_event(&mut self, start: &BytesStart) -> Result<Option<KmlEvent<T>>, Error> {
+        if !Self::is_kml_element(&self.reader, &self.config, start.name()) {
+            return Ok(self.read_kml(start)?.map(KmlEvent::Other));
+        }
+        let mut attrs = Self::read_attrs(start.attributes());
+        Ok(Some(match start.local_name().as_ref() {
+            b"kml" => {
+                self.start_kml(start, &mut attrs);
+                KmlEvent::StartKml {
+                    version: self.version,
+                    attrs,
+                }
+            }
+            b"Document" => {
+                let namespace = self.take_namespace();
+                let fields = self.read_nested(Self::read_leading_fields)?;
+                KmlEvent::StartDocument(Document {
+                    name: fields.name,
+                    description: fields.description,
+                    style_url: fields.style_url,
+                    attrs,
+                    namespace,
+                    children: fields.children,
+                    elements: Vec::new(),
+                })
+            }
+            b"Folder" => {
+                let namespace = self.take_namespace();
+                let fields = self.read_nested(Self::read_leading_fields)?;
+                KmlEvent::StartFolder(Folder {
+                    name: fields.name,
+                    description: fields.description,
+                    style_url: fields.style_url,
+                    attrs,
+                    namespace,
+                    children: fields.children,
+                    elements: Vec::new(),
+                })
+            }
+            _ => match self.read_feature(start)? {
+                Some(Kml::Placemark(p)) => KmlEvent::Placemark(p),
+                Some(k) => KmlEvent::Other(k),
+                None => return Ok(None),
+            },
+        }))
+    }
+
+    fn end_event(&mut self, end: &BytesEnd) -> KmlEvent<T> {
+        if end.local_name().as_ref() == b"kml" {
+            self.unbound_namespace = None;
+        } else {
+            self.path.pop();
+        }
+        KmlEvent::End
+    }
+
+    /// Reads the fields at the start of a container opened by `next_event`, up to its first
+    /// element, whose event is queued to be returned next
+    fn read_leading_fields(&mut self) -> Result<ContainerFields, Error> {
+        let mut fields = ContainerFields::default();
+        self.path.push(String::new());
+        loop {
+            let e = self.read_xml_event()?.into_owned();
+            let event = match e {
+                Event::Start(start) => {
+                    if !self.accepts(&start) {
+                        self.skip_element()?;
+                        continue;
+                    }
+                    if self.read_container_field(&start, &mut fields)? {
+                        continue;
+                    }
+                    match self.read_start_event(&start)? {
+                        Some(event) => event,
+                        None => continue,
+                    }
+                }
+                // Elements are read in full, so this is the end of the container
+                Event::End(ref e) => self.end_event(e),
+                // Truncated input is reported by the next call to `next_event`
+                Event::Eof => break,
+                _ => continue,
+            };
+            self.pending_events.push_front(event);
+            break;
+        }
+        Ok(fields)
+    }
+
+    /// Reads elements up to the end of the current container or input, or after a `kml` element
+    /// if `until_document` is set. The typed fields of a container are read into `fields` instead
+    fn read_elements(
+        &mut self,
+        until_document: bool,
+        mut fields: Option<&mut ContainerFields>,
+    ) -> Result<Vec<Kml<T>>, Error> {
+        let mut elements: Vec<Kml<T>> = Vec::new();
+        let reject_doctype = self.config.reject_doctype;
+        let comments = self.config.comments;
+        loop {
+            let mut e = self.read_xml_event()?;
+            match e {
+                Event::Start(e) => {
+                    let start = e.into_owned();
+                    if !self.accepts(&start) {
+                        self.skip_element()?;
+                        continue;
+                    }
+                    if let Some(fields) = fields.as_deref_mut() {
+                        if self.read_container_field(&start, fields)? {
+                            continue;
+                        }
+                    }
+                    let Some(element) = self.read_feature(&start)? else {
+                        continue;
+                    };
+                    let is_document = matches!(element, Kml::KmlDocument(_));
+                    elements.push(element);
+                    if until_document && is_document {
+                        break;
+                    }
+                }
+                Event::End(ref mut e) => match e.local_name().as_ref() {
+                    b"kml" | b"Folder" | b"Document" => break,
+                    _ => {}
+                },
+                Event::Decl(_) | Event::CData(_) | Event::Empty(_) | Event::Text(_) => {}
+                Event::Eof => {
+                    // Input ending inside a container is truncated
+                    if self.open_elements > 0 {
+                        return Err(Error::InvalidXmlEvent(format!("{:?}", Event::Eof)));
+                    }
+                    break;
+                }
+                Event::Comment(ref e) if comments => {
+                    elements.push(Kml::Comment(String::from_utf8_lossy(e).into_owned()))
+                }
+                Event::Comment(_) | Event::PI(_) => {}
+                Event::DocType(_) if !reject_doctype => {}
+                x => return Err(Error::InvalidInput(format!("{:?}", x))),
+            };
+        }
+
+        Ok(elements)
+    }
+
+    /// Reads the fields and elements of a `Document` or `Folder`, tracking its path
+    fn read_container(&mut self) -> Result<(ContainerFields, Vec<Kml<T>>), Error> {
+        let mut fields = ContainerFields::default();
+        self.path.push(String::new());
+        let elements = self.read_elements(false, Some(&mut fields));
+        self.path.pop();
+        Ok((fields, elements?))
+    }
+
+    /// Reads the element opened by `start` into `fields` if it's a field of a container rather
+    /// than one of its elements, recording the `name` for [`ElementInfo::path`]
+    fn read_container_field(
+        &mut self,
+        start: &BytesStart,
+        fields: &mut ContainerFields,
+    ) -> Result<bool, Error> {
+        let is_kml = Self::is_kml_element(&self.reader, &self.config, start.name());
+        match start.local_name().as_ref() {
+            b"name" if is_kml => {
+                let name = self.read_text()?;
+                if let Some(path) = self.path.last_mut().filter(|p| p.is_empty()) {
+                    path.clone_from(&name);
+                }
+                fields.name = Some(name);
+            }
+            b"description" if is_kml => fields.description = Some(self.read_mixed_str()?),
+            b"styleUrl" if is_kml => fields.style_url = Some(self.read_str()?),
+            name if is_container_child(name) => {
+                let attrs = Self::read_attrs(start.attributes());
+                fields
+                    .children
+                    .push(self.read_element(start, attrs)?.into());
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Whether the filter accepts the element opened by `start`, which is always the case for the
+    /// `name` of a container
+    fn accepts(&self, start: &BytesStart) -> bool {
+        let Some(filter) = &self.config.filter else {
+            return true;
+        };
+        let is_kml = Self::is_kml_element(&self.reader, &self.config, start.name());
+        if is_kml && !self.path.is_empty() && start.local_name().as_ref() == b"name" {
+            return true;
+        }
+        filter.accepts(&ElementInfo {
+            name: &String::from_utf8_lossy(start.local_name().as_ref()),
+            attrs: &Self::read_attrs(start.attributes()),
+            path: &self.path,
+        })
+    }
+
+    /// Reads the element opened by `start`, skipping it and recording a warning instead if it's a
+    /// `Placemark` that can't be read and `skip_invalid_features` is set
+    fn read_feature(&mut self, start: &BytesStart) -> Result<Option<Kml<T>>, Error> {
+        let depth = self.open_elements.saturating_sub(1);
+        let err = match self.read_kml(start) {
+            Ok(element) => return Ok(element),
+            Err(err) => err,
+        };
+        let recoverable = !matches!(
+            err.without_location(),
+            Error::MalformedXml(_) | Error::IoError(_)
+        );
+        if !(self.config.skip_invalid_features
+            && recoverable
+            && start.local_name().as_ref() == b"Placemark")
+        {
+            return Err(err);
+        }
+        if self.skip_to_depth(depth).is_err() {
+            return Err(err);
+        }
+        let (error, position) = match err {
+            Error::Located {
+                source, position, ..
+            } => (*source, position),
+            err => (err, self.position()),
+        };
+        self.warnings.push(Warning { error, position });
+        Ok(None)
+    }
+
+    /// Reads the element opened by `start` and all of its children, returning `None` for a geometry
+    /// dropped in lenient mode
+    fn read_kml(&mut self, start: &BytesStart) -> Result<Option<Kml<T>>, Error> {
+        self.read_nested(|r| r.read_kml_unlocated(start))
+            .map_err(|e| self.locate(e, Some(start.local_name().as_ref())))
+    }
+
+    fn read_kml_unlocated(&mut self, start: &BytesStart) -> Result<Option<Kml<T>>, Error> {
+        let attrs = Self::read_attrs(start.attributes());
+        if !Self::is_kml_element(&self.reader, &self.config, start.name()) {
+            return self.read_unrecognized(start, attrs).map(Some);
+        }
+        // Containers are matched separately to keep the stack frames of nested containers small
+        Ok(Some(match start.local_name().as_ref() {
+            b"kml" => Kml::KmlDocument(self.read_kml_document(start, attrs)?),
+            b"Document" => {
+                let namespace = self.take_namespace();
+                let (fields, elements) = self.read_container()?;
+                Kml::Document(Document {
+                    name: fields.name,
+                    description: fields.description,
+                    style_url: fields.style_url,
+                    attrs,
+                    namespace,
+                    children: fields.children,
+                    elements,
+                })
+            }
+            b"Folder" => {
+                let namespace = self.take_namespace();
+                let (fields, elements) = self.read_container()?;
+                Kml::Folder(Folder {
+                    name: fields.name,
+                    description: fields.description,
+                    style_url: fields.style_url,
+                    attrs,
+                    namespace,
+                    children: fields.children,
+                    elements,
+                })
+            }
+            _ => return self.read_kml_element(start, attrs),
+        }))
+    }
+
+    fn read_kml_element(
+        &mut self,
+        start: &BytesStart,
+        attrs: HashMap<String, String>,
+    ) -> Result<Option<Kml<T>>, Error> {
+        Ok(Some(match start.local_name().as_ref() {
+            b"Scale" => Kml::Scale(self.read_scale(attrs)?),
+            b"Orientation" => Kml::Orientation(self.read_orientation(attrs)?),
+            b"Point" => return Ok(self.read_point(attrs)?.map(Kml::Point)),
+            b"Location" => Kml::Location(self.read_location(attrs)?),
+            b"LineString" => return Ok(self.read_line_string(attrs)?.map(Kml::LineString)),
+            b"LinearRing" => return Ok(self.read_linear_ring(attrs)?.map(Kml::LinearRing)),
+            b"Polygon" => return Ok(self.read_polygon(attrs)?.map(Kml::Polygon)),
+            b"MultiGeometry" => Kml::MultiGeometry(self.read_multi_geometry(attrs)?),
+            b"Track" => Kml::Track(self.read_track(attrs)?),
+            b"MultiTrack" => Kml::MultiTrack(self.read_multi_track(attrs)?),
+            b"Placemark" => Kml::Placemark(self.read_placemark(attrs)?),
+            b"Tour" => Kml::Tour(self.read_tour(attrs)?),
+            b"Style" => Kml::Style(self.read_style(attrs)?),
+            b"StyleMap" => Kml::StyleMap(self.read_style_map(attrs)?),
+            b"Pair" => Kml::Pair(self.read_pair(attrs)?),
+            b"BalloonStyle" => Kml::BalloonStyle(self.read_balloon_style(attrs)?),
+            b"IconStyle" => Kml::IconStyle(self.read_icon_style(attrs)?),
+            b"Link" => Kml::Link(self.read_link(attrs)?),
+            b"Icon" => Kml::LinkTypeIcon(self.read_link_type_icon(attrs)?),
+            b"ResourceMap" => Kml::ResourceMap(self.read_resource_map(attrs)?),
+            b"Alias" => Kml::Alias(self.read_alias(attrs)?),
+            b"SchemaData" => Kml::SchemaData(self.read_schema_data(attrs)?),
+            b"SimpleArrayData" => Kml::SimpleArrayData(self.read_simple_array_data(attrs)?),
+            b"SimpleData" => Kml::SimpleData(self.read_simple_data(attrs)?),
+            b"LabelStyle" => Kml::LabelStyle(self.read_label_style(attrs)?),
+            b"LineStyle" => Kml::LineStyle(self.read_line_style(attrs)?),
+            b"PolyStyle" => Kml::PolyStyle(self.read_poly_style(attrs)?),
+            b"ListStyle" => Kml::ListStyle(self.read_list_style(attrs)?),
+            _ => self.read_unrecognized(start, attrs)?,
+        }))
+    }
+
+    /// Applies the configured `coord_transform`, if any
+    fn transform_coord(&self, coord: Coord<T>) -> Coord<T> {
+        match &self.config.coord_transform {
+            Some(transform) => transform.apply(coord),
+            None => coord,
+        }
+    }
+
+    /// Reads an element without a typed struct, using a registered parser if there is one
+    fn read_unrecognized(
+        &mut self,
+        start: &BytesStart,
+        attrs: HashMap<String, String>,
+    ) -> Result<Kml<T>, Error> {
+        let name = String::from_utf8_lossy(start.local_name().into_inner());
+        let Some(parser) = self.config.parsers.get(name.as_ref()).cloned() else {
+            return Ok(Kml::Element(self.read_element(start, attrs)?));
+        };
+        let element = self.read_generic_element(start, attrs)?;
+        Ok(Kml::Extension(parser.parse(element)?))
+    }
+
+    fn read_kml_document(
+        &mut self,
+        start: &BytesStart,
+        attrs: HashMap<String, String>,
+    ) -> Result<KmlDocument<T>, Error> {
+        let mut attrs = attrs;
+        self.start_kml(start, &mut attrs);
+        let elements = self.read_elements(false, None);
+        self.unbound_namespace = None;
+        Ok(KmlDocument {
+            version: self.version,
+            attrs,
+            elements: elements?,
+        })
+    }
+
+    /// Reads the version of a `kml` root. Roots without a default namespace are read as declaring
+    /// the namespace of their version, which their unprefixed elements are read in, like the
+    /// writer declares for them, so they read the same after being written
+    fn start_kml(&mut self, start: &BytesStart, attrs: &mut HashMap<String, String>) {
+        self.version = Self::detect_version(&self.reader, start.name());
+        if attrs.contains_key("xmlns") {
+            return;
+        }
+        if let Some(ns) = self.version.namespace() {
+            attrs.insert("xmlns".to_string(), ns.to_string());
+            self.unbound_namespace = Some(ns);
+        }
+    }
+
+    /// Returns the version for the namespace of the `kml` element, treating documents without a
+    /// namespace as KML 2.2
+    fn detect_version(reader: &NsReader<PositionReader<Source<B>>>, name: QName) -> KmlVersion {
+        match reader.resolve_element(name).0 {
+            ResolveResult::Bound(ns) => str::from_utf8(ns.into_inner())
+                .ok()
+                .and_then(|ns| ns.parse::<KmlVersion>().ok())
+                .unwrap_or_default(),
+            _ => KmlVersion::V22,
+        }
+    }
+
+    fn read_scale(&mut self, attrs: HashMap<String, String>) -> Result<Scale<T>, Error> {
+        let namespace = self.take_namespace();
+        let mut x = One::one();
+        let mut y = One::one();
+        let mut z = One::one();
+
+        loop {
+            let e = self.read_xml_event()?.into_owned();
+            match e {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
+                    b"x" => x = self.read_float()?,
+                    b"y" => y = self.read_float()?,
+                    b"z" => z = self.read_float()?,
+                    _ => self.skip_unknown_element(e)?,
+                },
+                Event::End(ref e) if e.local_name().as_ref() == b"Scale" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
+            }
+        }
+        Ok(Scale {
+            x,
+            y,
+            z,
+            attrs,
+            namespace,
+        })
+    }
+
+    fn read_orientation(
+        &mut self,
+        attrs: HashMap<String, String>,
+    ) -> Result<Orientation<T>, Error> {
+        let namespace = self.take_namespace();
         let mut roll = Zero::zero();
         let mut tilt = Zero::zero();
         let mut heading = Zero::zero();
 
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"roll" => roll = self.read_float()?,
                     b"tilt" => tilt = self.read_float()?,
                     b"heading" => heading = self.read_float()?,
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Orientation" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Orientation" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(Orientation {
@@ -267,40 +1759,50 @@ where
             tilt,
             heading,
             attrs,
+            namespace,
         })
     }
 
-    fn read_point(&mut self, attrs: HashMap<String, String>) -> Result<Point<T>, Error> {
-        let mut props = self.read_geom_props(b"Point")?;
-        Ok(Point {
-            coord: props.coords.remove(0),
+    /// Reads a point, returning `None` rather than making up a coordinate if it has none, which is
+    /// only the case in lenient mode or when skipping coordinates
+    fn read_point(&mut self, attrs: HashMap<String, String>) -> Result<Option<Point<T>>, Error> {
+        let namespace = self.take_namespace();
+        let props = self.read_geom_props(b"Point")?;
+        let Some(coord) = props.coords.into_iter().next() else {
+            return Ok(None);
+        };
+        Ok(Some(Point {
+            coord,
             altitude_mode: props.altitude_mode,
             extrude: props.extrude,
             attrs,
-        })
+            namespace,
+        }))
     }
 
     fn read_location(&mut self, attrs: HashMap<String, String>) -> Result<Location<T>, Error> {
+        let namespace = self.take_namespace();
         let mut longitude = Zero::zero();
         let mut latitude = Zero::zero();
         let mut altitude = Zero::zero();
 
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"longitude" => longitude = self.read_float()?,
                     b"latitude" => latitude = self.read_float()?,
                     b"altitude" => altitude = self.read_float()?,
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Location" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Location" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(Location {
@@ -308,156 +1810,220 @@ where
             latitude,
             altitude,
             attrs,
+            namespace,
         })
     }
 
-    fn read_line_string(&mut self, attrs: HashMap<String, String>) -> Result<LineString<T>, Error> {
-        let props = self.read_geom_props(b"LineString")?;
-        Ok(LineString {
+    fn read_line_string(
+        &mut self,
+        attrs: HashMap<String, String>,
+    ) -> Result<Option<LineString<T>>, Error> {
+        let namespace = self.take_namespace();
+        let Some(props) = self.read_valid_geom_props(b"LineString")? else {
+            return Ok(None);
+        };
+        Ok(Some(LineString {
             coords: props.coords,
             altitude_mode: props.altitude_mode,
             extrude: props.extrude,
             tessellate: props.tessellate,
             attrs,
-        })
+            namespace,
+        }))
     }
 
-    fn read_linear_ring(&mut self, attrs: HashMap<String, String>) -> Result<LinearRing<T>, Error> {
-        let props = self.read_geom_props(b"LinearRing")?;
-        Ok(LinearRing {
+    fn read_linear_ring(
+        &mut self,
+        attrs: HashMap<String, String>,
+    ) -> Result<Option<LinearRing<T>>, Error> {
+        let namespace = self.take_namespace();
+        let Some(props) = self.read_valid_geom_props(b"LinearRing")? else {
+            return Ok(None);
+        };
+        Ok(Some(LinearRing {
             coords: props.coords,
             altitude_mode: props.altitude_mode,
             extrude: props.extrude,
             tessellate: props.tessellate,
             attrs,
-        })
+            namespace,
+        }))
     }
 
-    fn read_polygon(&mut self, attrs: HashMap<String, String>) -> Result<Polygon<T>, Error> {
-        let mut outer: LinearRing<T> = LinearRing::default();
+    /// Reads a polygon, returning `None` if its outer boundary is invalid in lenient mode
+    fn read_polygon(
+        &mut self,
+        attrs: HashMap<String, String>,
+    ) -> Result<Option<Polygon<T>>, Error> {
+        let namespace = self.take_namespace();
+        let mut outer: Option<LinearRing<T>> = Some(LinearRing::default());
         let mut inner: Vec<LinearRing<T>> = Vec::new();
-        let mut altitude_mode = types::AltitudeMode::default();
-        let mut extrude = false;
-        let mut tessellate = false;
+        let mut altitude_mode = None;
+        let mut extrude = None;
+        let mut tessellate = None;
 
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"outerBoundaryIs" => {
                         let mut outer_ring = self.read_boundary(b"outerBoundaryIs")?;
                         if outer_ring.is_empty() {
-                            return Err(Error::InvalidGeometry(
+                            self.recover::<()>(Err(Error::InvalidGeometry(
                                 "Polygon must have an outer boundary".to_string(),
-                            ));
+                            )))?;
+                            outer = None;
+                        } else {
+                            outer = Some(outer_ring.remove(0));
                         }
-                        outer = outer_ring.remove(0);
                     }
                     b"innerBoundaryIs" => {
                         inner.append(&mut self.read_boundary(b"innerBoundaryIs")?);
                     }
                     b"altitudeMode" => {
-                        altitude_mode = types::AltitudeMode::from_str(&self.read_str()?)?
+                        altitude_mode = {
+                            let mode = types::AltitudeMode::from_str(&self.read_str()?);
+                            Some(self.recover(mode)?)
+                        }
                     }
-                    b"extrude" => extrude = self.read_str()? == "1",
-                    b"tessellate" => tessellate = self.read_str()? == "1",
-                    _ => {}
+                    b"extrude" => extrude = Some(self.read_str()? == "1"),
+                    b"tessellate" => tessellate = Some(self.read_str()? == "1"),
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Polygon" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Polygon" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
-        Ok(Polygon {
+        let Some(outer) = outer else {
+            return Ok(None);
+        };
+        Ok(Some(Polygon {
             outer,
             inner,
             altitude_mode,
             extrude,
             tessellate,
             attrs,
-        })
+            namespace,
+        }))
     }
 
     fn read_multi_geometry(
         &mut self,
         attrs: HashMap<String, String>,
     ) -> Result<MultiGeometry<T>, Error> {
+        let namespace = self.take_namespace();
         let mut geometries: Vec<Geometry<T>> = Vec::new();
+        let mut children: Vec<Node> = Vec::new();
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    let start = e.to_owned();
+                    let start_attrs = Self::read_attrs(start.attributes());
+                    children.push(self.read_element(&start, start_attrs)?.into());
+                }
                 Event::Start(ref e) => {
                     let attrs = Self::read_attrs(e.attributes());
                     match e.local_name().as_ref() {
-                        b"Point" => geometries.push(Geometry::Point(self.read_point(attrs)?)),
-                        b"LineString" => {
-                            geometries.push(Geometry::LineString(self.read_line_string(attrs)?))
+                        b"Point" => geometries.extend(self.read_point(attrs)?.map(Geometry::Point)),
+                        b"LineString" => geometries
+                            .extend(self.read_line_string(attrs)?.map(Geometry::LineString)),
+                        b"LinearRing" => geometries
+                            .extend(self.read_linear_ring(attrs)?.map(Geometry::LinearRing)),
+                        b"Polygon" => {
+                            geometries.extend(self.read_polygon(attrs)?.map(Geometry::Polygon))
                         }
-                        b"LinearRing" => {
-                            geometries.push(Geometry::LinearRing(self.read_linear_ring(attrs)?))
+                        b"MultiGeometry" => geometries.push(Geometry::MultiGeometry(
+                            self.read_nested(|r| r.read_multi_geometry(attrs))?,
+                        )),
+                        b"Track" => geometries.push(Geometry::Track(self.read_track(attrs)?)),
+                        b"MultiTrack" => {
+                            geometries.push(Geometry::MultiTrack(self.read_multi_track(attrs)?))
+                        }
+                        _ => {
+                            let start = e.to_owned();
+                            let start_attrs = Self::read_attrs(start.attributes());
+                            children.push(self.read_element(&start, start_attrs)?.into());
                         }
-                        b"Polygon" => geometries.push(Geometry::Polygon(self.read_polygon(attrs)?)),
-                        b"MultiGeometry" => geometries
-                            .push(Geometry::MultiGeometry(self.read_multi_geometry(attrs)?)),
-                        _ => {}
                     }
                 }
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"MultiGeometry" {
-                        break;
-                    }
+                Event::End(ref e) if e.local_name().as_ref() == b"MultiGeometry" => break,
+                Event::Comment(ref e) if self.config.comments => {
+                    children.push(Node::Comment(String::from_utf8_lossy(e).into_owned()))
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
-        Ok(MultiGeometry { geometries, attrs })
+        Ok(MultiGeometry {
+            geometries,
+            attrs,
+            namespace,
+            children,
+        })
     }
 
     fn read_placemark(&mut self, attrs: HashMap<String, String>) -> Result<Placemark<T>, Error> {
+        let namespace = self.take_namespace();
         let mut name: Option<String> = None;
         let mut description: Option<String> = None;
         let mut geometry: Option<Geometry<T>> = None;
-        let mut children: Vec<Element> = Vec::new();
+        let mut children: Vec<Node> = Vec::new();
         let mut style_url: Option<String> = None;
 
         loop {
-            let e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    let start = e.to_owned();
+                    let start_attrs = Self::read_attrs(start.attributes());
+                    children.push(self.read_element(&start, start_attrs)?.into());
+                }
                 Event::Start(ref e) => {
                     let attrs = Self::read_attrs(e.attributes());
                     match e.local_name().as_ref() {
-                        b"name" => name = Some(self.read_str()?),
-                        b"description" => description = Some(self.read_str()?),
+                        b"name" => name = Some(self.read_text()?),
+                        b"description" => description = Some(self.read_mixed_str()?),
                         b"styleUrl" => style_url = Some(self.read_str()?),
-                        b"Point" => geometry = Some(Geometry::Point(self.read_point(attrs)?)),
+                        b"Point" => geometry = self.read_point(attrs)?.map(Geometry::Point),
                         b"LineString" => {
-                            geometry = Some(Geometry::LineString(self.read_line_string(attrs)?))
+                            geometry = self.read_line_string(attrs)?.map(Geometry::LineString)
                         }
                         b"LinearRing" => {
-                            geometry = Some(Geometry::LinearRing(self.read_linear_ring(attrs)?))
+                            geometry = self.read_linear_ring(attrs)?.map(Geometry::LinearRing)
                         }
-                        b"Polygon" => geometry = Some(Geometry::Polygon(self.read_polygon(attrs)?)),
+                        b"Polygon" => geometry = self.read_polygon(attrs)?.map(Geometry::Polygon),
                         b"MultiGeometry" => {
                             geometry =
                                 Some(Geometry::MultiGeometry(self.read_multi_geometry(attrs)?))
                         }
+                        b"Track" => geometry = Some(Geometry::Track(self.read_track(attrs)?)),
+                        b"MultiTrack" => {
+                            geometry = Some(Geometry::MultiTrack(self.read_multi_track(attrs)?))
+                        }
                         _ => {
                             let start = e.to_owned();
                             let start_attrs = Self::read_attrs(start.attributes());
-                            children.push(self.read_element(&start, start_attrs)?);
+                            children.push(self.read_element(&start, start_attrs)?.into());
                         }
                     }
                 }
-                Event::End(ref e) => {
-                    if e.local_name().as_ref() == b"Placemark" {
-                        break;
-                    }
+                Event::End(ref e) if e.local_name().as_ref() == b"Placemark" => break,
+                Event::Comment(ref e) if self.config.comments => {
+                    children.push(Node::Comment(String::from_utf8_lossy(e).into_owned()))
                 }
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
                 _ => {}
             }
         }
@@ -467,20 +2033,180 @@ where
             style_url,
             geometry,
             attrs,
+            namespace,
             children,
         })
     }
 
+    fn read_track(&mut self, attrs: HashMap<String, String>) -> Result<Track<T>, Error> {
+        let namespace = self.take_namespace();
+        let mut track = Track {
+            attrs,
+            namespace,
+            ..Default::default()
+        };
+        let skip_coordinates = self.config.skip_coordinates;
+        loop {
+            let e = self.read_xml_event()?.into_owned();
+            match e {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    let start_attrs = Self::read_attrs(e.attributes());
+                    track
+                        .children
+                        .push(self.read_element(e, start_attrs)?.into());
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
+                    b"altitudeMode" => {
+                        track.altitude_mode = {
+                            let mode = types::AltitudeMode::from_str(&self.read_str()?);
+                            Some(self.recover(mode)?)
+                        }
+                    }
+                    b"when" => track.whens.push(self.read_str()?),
+                    b"coord" if skip_coordinates => self.skip_element()?,
+                    b"coord" => {
+                        let coord = Self::read_space_separated_floats(&self.read_str()?).and_then(
+                            |values| {
+                                let mut values = values.into_iter();
+                                let x = values.next().ok_or(Error::CoordEmpty)?;
+                                let y = values.next().ok_or(Error::CoordEmpty)?;
+                                Ok(Some(Coord::new(x, y, values.next())))
+                            },
+                        );
+                        if let Some(coord) = self.recover_coord(coord)? {
+                            track.coords.push(self.transform_coord(coord));
+                        }
+                    }
+                    b"angles" => {
+                        let angles = Self::read_space_separated_floats(&self.read_str()?);
+                        let mut values = self.recover(angles)?.into_iter();
+                        track.angles.push(Orientation {
+                            heading: values.next().unwrap_or_else(Zero::zero),
+                            tilt: values.next().unwrap_or_else(Zero::zero),
+                            roll: values.next().unwrap_or_else(Zero::zero),
+                            ..Default::default()
+                        });
+                    }
+                    _ => {
+                        let start = e.to_owned();
+                        let start_attrs = Self::read_attrs(start.attributes());
+                        track
+                            .children
+                            .push(self.read_element(&start, start_attrs)?.into());
+                    }
+                },
+                Event::End(ref e) if e.local_name().as_ref() == b"Track" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
+            }
+        }
+        Ok(track)
+    }
+
+    fn read_multi_track(&mut self, attrs: HashMap<String, String>) -> Result<MultiTrack<T>, Error> {
+        let namespace = self.take_namespace();
+        let mut multi_track = MultiTrack {
+            attrs,
+            namespace,
+            ..Default::default()
+        };
+        loop {
+            let e = self.read_xml_event()?.into_owned();
+            match e {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => {
+                    let attrs = Self::read_attrs(e.attributes());
+                    match e.local_name().as_ref() {
+                        b"altitudeMode" => {
+                            multi_track.altitude_mode = {
+                                let mode = types::AltitudeMode::from_str(&self.read_str()?);
+                                Some(self.recover(mode)?)
+                            }
+                        }
+                        b"interpolate" => multi_track.interpolate = self.read_str()? == "1",
+                        b"Track" => multi_track.tracks.push(self.read_track(attrs)?),
+                        _ => self.skip_unknown_element(e)?,
+                    }
+                }
+                Event::End(ref e) if e.local_name().as_ref() == b"MultiTrack" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
+            }
+        }
+        Ok(multi_track)
+    }
+
+    fn read_tour(&mut self, attrs: HashMap<String, String>) -> Result<Tour, Error> {
+        let namespace = self.take_namespace();
+        let mut tour = Tour {
+            attrs,
+            namespace,
+            ..Default::default()
+        };
+        loop {
+            let e = self.read_xml_event()?.into_owned();
+            match e {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    let start_attrs = Self::read_attrs(e.attributes());
+                    tour.children
+                        .push(self.read_element(e, start_attrs)?.into());
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
+                    b"name" => tour.name = Some(self.read_text()?),
+                    b"description" => tour.description = Some(self.read_mixed_str()?),
+                    b"Playlist" => loop {
+                        let e = self.read_xml_event()?;
+                        match e {
+                            Event::Start(e) => {
+                                let start = e.to_owned();
+                                let start_attrs = Self::read_attrs(start.attributes());
+                                tour.playlist.push(self.read_element(&start, start_attrs)?);
+                            }
+                            Event::End(_) => break,
+                            e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                            _ => {}
+                        }
+                    },
+                    _ => {
+                        let start = e.to_owned();
+                        let start_attrs = Self::read_attrs(start.attributes());
+                        tour.children
+                            .push(self.read_element(&start, start_attrs)?.into());
+                    }
+                },
+                Event::End(ref e) if e.local_name().as_ref() == b"Tour" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
+            }
+        }
+        Ok(tour)
+    }
+
     fn read_style(&mut self, mut attrs: HashMap<String, String>) -> Result<Style, Error> {
+        let namespace = self.take_namespace();
         let mut style = Style {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => {
                     let attrs = Self::read_attrs(e.attributes());
                     match e.local_name().as_ref() {
                         b"BalloonStyle" => style.balloon = Some(self.read_balloon_style(attrs)?),
@@ -489,84 +2215,92 @@ where
                         b"LineStyle" => style.line = Some(self.read_line_style(attrs)?),
                         b"PolyStyle" => style.poly = Some(self.read_poly_style(attrs)?),
                         b"ListStyle" => style.list = Some(self.read_list_style(attrs)?),
-                        _ => {}
-                    }
-                }
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Style" {
-                        break;
+                        _ => self.skip_unknown_element(e)?,
                     }
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Style" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(style)
     }
 
     fn read_style_map(&mut self, mut attrs: HashMap<String, String>) -> Result<StyleMap, Error> {
+        let namespace = self.take_namespace();
         let mut style_map = StyleMap {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => {
-                    if e.local_name().as_ref() == b"Pair" {
-                        let pair_attrs = Self::read_attrs(e.attributes());
-                        style_map.pairs.push(self.read_pair(pair_attrs)?);
-                    }
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
                 }
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"StyleMap" {
-                        break;
-                    }
+                Event::Start(ref e) if e.local_name().as_ref() == b"Pair" => {
+                    let pair_attrs = Self::read_attrs(e.attributes());
+                    style_map.pairs.push(self.read_pair(pair_attrs)?);
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::Start(ref e) => self.skip_unknown_element(e)?,
+                Event::End(ref e) if e.local_name().as_ref() == b"StyleMap" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(style_map)
     }
 
     fn read_pair(&mut self, attrs: HashMap<String, String>) -> Result<Pair, Error> {
+        let namespace = self.take_namespace();
         let mut pair = Pair {
             attrs,
+            namespace,
             ..Pair::default()
         };
 
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
-                    b"key" => pair.key = self.read_str()?,
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
+                    b"key" => pair.key = self.read_str()?.parse::<StyleState>()?,
                     b"styleUrl" => pair.style_url = self.read_str()?,
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Pair" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Pair" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(pair)
     }
 
     fn read_icon_style(&mut self, mut attrs: HashMap<String, String>) -> Result<IconStyle, Error> {
+        let namespace = self.take_namespace();
         let mut icon_style = IconStyle {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => {
                     let attrs = Self::read_attrs(e.attributes());
                     match e.local_name().as_ref() {
                         b"scale" => icon_style.scale = self.read_float()?,
@@ -577,16 +2311,20 @@ where
                             let xunits = attrs.get("xunits");
                             let yunits = attrs.get("yunits");
                             if let (Some(x_str), Some(y_str)) = (x_val, y_val) {
-                                let x: f64 = x_str
-                                    .parse()
-                                    .map_err(|_| Error::NumParse(x_str.to_string()))?;
-                                let y: f64 = y_str
-                                    .parse()
-                                    .map_err(|_| Error::NumParse(y_str.to_string()))?;
+                                let x = x_str
+                                    .parse::<f64>()
+                                    .map_err(|_| Error::NumParse(x_str.to_string()));
+                                let x = self.recover(x)?;
+                                let y = y_str
+                                    .parse::<f64>()
+                                    .map_err(|_| Error::NumParse(y_str.to_string()));
+                                let y = self.recover(y)?;
                                 let xunits = xunits
-                                    .map_or_else(|| Ok(Units::default()), |units| units.parse())?;
+                                    .map_or_else(|| Ok(Units::default()), |units| units.parse());
+                                let xunits = self.recover(xunits)?;
                                 let yunits = yunits
-                                    .map_or_else(|| Ok(Units::default()), |units| units.parse())?;
+                                    .map_or_else(|| Ok(Units::default()), |units| units.parse());
+                                let yunits = self.recover(yunits)?;
                                 icon_style.hot_spot = Some(Vec2 {
                                     x,
                                     y,
@@ -595,147 +2333,163 @@ where
                                 });
                             }
                         }
-                        b"Icon" => icon_style.icon = self.read_basic_link_type_icon(attrs)?,
+                        b"Icon" => icon_style.icon = Some(self.read_basic_link_type_icon(attrs)?),
                         b"color" => icon_style.color = self.read_str()?,
                         b"colorMode" => {
-                            icon_style.color_mode = self.read_str()?.parse::<ColorMode>()?
+                            icon_style.color_mode = {
+                                let mode = self.read_str()?.parse::<ColorMode>();
+                                self.recover(mode)?
+                            }
                         }
-                        _ => {}
-                    }
-                }
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"IconStyle" {
-                        break;
+                        _ => self.skip_unknown_element(e)?,
                     }
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"IconStyle" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(icon_style)
     }
 
     fn read_basic_link_type_icon(&mut self, attrs: HashMap<String, String>) -> Result<Icon, Error> {
+        let namespace = self.take_namespace();
         let mut href = String::new();
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => {
-                    if e.local_name().as_ref() == b"href" {
-                        href = self.read_str()?;
-                    }
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
                 }
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Icon" {
-                        break;
-                    }
+                Event::Start(ref e) if e.local_name().as_ref() == b"href" => {
+                    href = self.read_str()?;
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::Start(ref e) => self.skip_unknown_element(e)?,
+                Event::End(ref e) if e.local_name().as_ref() == b"Icon" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
-        Ok(Icon { href, attrs })
+        Ok(Icon {
+            href,
+            attrs,
+            namespace,
+        })
     }
 
     fn read_link_type_icon(
         &mut self,
         attrs: HashMap<String, String>,
     ) -> Result<LinkTypeIcon, Error> {
+        let namespace = self.take_namespace();
         let mut icon = LinkTypeIcon {
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"href" => icon.href = Some(self.read_str()?),
                     b"refreshMode" => {
-                        icon.refresh_mode = Some(RefreshMode::from_str(&self.read_str()?)?);
+                        let mode = RefreshMode::from_str(&self.read_str()?);
+                        icon.refresh_mode = Some(self.recover(mode)?);
                     }
                     b"refreshInterval" => icon.refresh_interval = self.read_float()?,
                     b"viewRefreshMode" => {
-                        icon.view_refresh_mode = Some(ViewRefreshMode::from_str(&self.read_str()?)?)
+                        let mode = ViewRefreshMode::from_str(&self.read_str()?);
+                        icon.view_refresh_mode = Some(self.recover(mode)?);
                     }
                     b"viewRefreshTime" => icon.view_refresh_time = self.read_float()?,
                     b"viewBoundScale" => icon.view_bound_scale = self.read_float()?,
                     b"viewFormat" => icon.view_format = Some(self.read_str()?),
                     b"httpQuery" => icon.http_query = Some(self.read_str()?),
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Icon" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Icon" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(icon)
     }
 
     fn read_link(&mut self, attrs: HashMap<String, String>) -> Result<Link, Error> {
+        let namespace = self.take_namespace();
         let mut link = Link {
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"href" => link.href = Some(self.read_str()?),
                     b"refreshMode" => {
-                        link.refresh_mode = Some(RefreshMode::from_str(&self.read_str()?)?);
+                        let mode = RefreshMode::from_str(&self.read_str()?);
+                        link.refresh_mode = Some(self.recover(mode)?);
                     }
                     b"refreshInterval" => link.refresh_interval = self.read_float()?,
                     b"viewRefreshMode" => {
-                        link.view_refresh_mode = Some(ViewRefreshMode::from_str(&self.read_str()?)?)
+                        let mode = ViewRefreshMode::from_str(&self.read_str()?);
+                        link.view_refresh_mode = Some(self.recover(mode)?);
                     }
                     b"viewRefreshTime" => link.view_refresh_time = self.read_float()?,
                     b"viewBoundScale" => link.view_bound_scale = self.read_float()?,
                     b"viewFormat" => link.view_format = Some(self.read_str()?),
                     b"httpQuery" => link.http_query = Some(self.read_str()?),
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"Link" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"Link" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(link)
     }
 
     fn read_resource_map(&mut self, attrs: HashMap<String, String>) -> Result<ResourceMap, Error> {
+        let namespace = self.take_namespace();
         let mut resource_map = ResourceMap {
             attrs,
+            namespace,
             ..Default::default()
         };
 
         let mut aliases = Vec::new();
 
         loop {
-            let e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(e) => {
-                    if e.local_name().as_ref() == b"Alias" {
-                        let attrs = Self::read_attrs(e.attributes());
-                        if let Ok(alias) = self.read_alias(attrs) {
-                            aliases.push(alias);
-                        }
-                    }
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
                 }
-                Event::End(e) => {
-                    if e.local_name().as_ref() == b"ResourceMap" {
-                        break;
+                Event::Start(ref e) if e.local_name().as_ref() == b"Alias" => {
+                    let attrs = Self::read_attrs(e.attributes());
+                    if let Ok(alias) = self.read_alias(attrs) {
+                        aliases.push(alias);
                     }
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::Start(ref e) => self.skip_unknown_element(e)?,
+                Event::End(e) if e.local_name().as_ref() == b"ResourceMap" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
 
@@ -745,42 +2499,56 @@ where
     }
 
     fn read_alias(&mut self, attrs: HashMap<String, String>) -> Result<Alias, Error> {
+        let namespace = self.take_namespace();
         let mut alias = Alias {
             attrs,
+            namespace,
             ..Default::default()
         };
 
         loop {
-            let e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"targetHref" => alias.target_href = Some(self.read_str()?),
                     b"sourceHref" => alias.source_href = Some(self.read_str()?),
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(e) => {
-                    if e.local_name().as_ref() == b"Alias" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(e) if e.local_name().as_ref() == b"Alias" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
 
         Ok(alias)
     }
 
-    fn read_schema_data(&mut self, attrs: HashMap<String, String>) -> Result<SchemaData, Error> {
+    fn read_schema_data(
+        &mut self,
+        mut attrs: HashMap<String, String>,
+    ) -> Result<SchemaData, Error> {
+        let namespace = self.take_namespace();
         let mut schema_data = SchemaData {
+            schema_url: attrs.remove("schemaUrl"),
             attrs,
+            namespace,
             ..Default::default()
         };
 
         loop {
-            let e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"SimpleData" => {
                         let attrs = Self::read_attrs(e.attributes());
                         if let Ok(simple_data) = self.read_simple_data(attrs) {
@@ -793,13 +2561,10 @@ where
                             schema_data.arrays.push(simple_array_data);
                         }
                     }
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(e) => {
-                    if e.local_name().as_ref() == b"SchemaData" {
-                        break;
-                    }
-                }
+                Event::End(e) if e.local_name().as_ref() == b"SchemaData" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
                 _ => {}
             }
         }
@@ -811,12 +2576,14 @@ where
         &mut self,
         mut attrs: HashMap<String, String>,
     ) -> Result<SimpleArrayData, Error> {
+        let namespace = self.take_namespace();
         let mut simple_array_data = SimpleArrayData::default();
 
         // Move required `name` attribute into designated field
         if let Some(name) = attrs.remove("name") {
             simple_array_data.name = name;
             simple_array_data.attrs = attrs;
+            simple_array_data.namespace = namespace;
         } else {
             return Err(Error::InvalidInput(
                 "Required \"name\" attribute not present".to_string(),
@@ -824,20 +2591,20 @@ where
         }
 
         loop {
-            let e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(e) => {
-                    if let b"value" = e.local_name().as_ref() {
-                        simple_array_data.values.push(self.read_str()?);
-                    }
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
                 }
-                Event::End(e) => {
-                    if e.local_name().as_ref() == b"SimpleArrayData" {
-                        break;
-                    }
+                Event::Start(ref e) if e.local_name().as_ref() == b"value" => {
+                    simple_array_data.values.push(self.read_text()?);
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::Start(ref e) => self.skip_unknown_element(e)?,
+                Event::End(e) if e.local_name().as_ref() == b"SimpleArrayData" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
 
@@ -848,12 +2615,14 @@ where
         &mut self,
         mut attrs: HashMap<String, String>,
     ) -> Result<SimpleData, Error> {
+        let namespace = self.take_namespace();
         // Move required `name` attribute into designated field
         if let Some(name) = attrs.remove("name") {
             Ok(SimpleData {
                 name,
-                value: self.read_str()?,
+                value: self.read_text()?,
                 attrs,
+                namespace,
             })
         } else {
             Err(Error::InvalidInput(
@@ -866,28 +2635,31 @@ where
         &mut self,
         mut attrs: HashMap<String, String>,
     ) -> Result<BalloonStyle, Error> {
+        let namespace = self.take_namespace();
         let mut balloon_style = BalloonStyle {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"bgColor" => balloon_style.bg_color = Some(self.read_str()?),
                     b"textColor" => balloon_style.text_color = self.read_str()?,
-                    b"text" => balloon_style.text = Some(self.read_str()?),
+                    b"text" => balloon_style.text = Some(self.read_mixed_str()?),
                     b"displayMode" => balloon_style.display = self.read_str()? != "hide",
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"BalloonStyle" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"BalloonStyle" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(balloon_style)
@@ -897,107 +2669,133 @@ where
         &mut self,
         mut attrs: HashMap<String, String>,
     ) -> Result<LabelStyle, Error> {
+        let namespace = self.take_namespace();
         let mut label_style = LabelStyle {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"color" => label_style.color = self.read_str()?,
                     b"colorMode" => {
-                        label_style.color_mode = self.read_str()?.parse::<ColorMode>()?;
+                        label_style.color_mode = {
+                            let mode = self.read_str()?.parse::<ColorMode>();
+                            self.recover(mode)?
+                        };
                     }
                     b"scale" => label_style.scale = self.read_float()?,
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"LabelStyle" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"LabelStyle" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(label_style)
     }
 
     fn read_line_style(&mut self, mut attrs: HashMap<String, String>) -> Result<LineStyle, Error> {
+        let namespace = self.take_namespace();
         let mut line_style = LineStyle {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"color" => line_style.color = self.read_str()?,
                     b"colorMode" => {
-                        line_style.color_mode = self.read_str()?.parse::<ColorMode>()?;
+                        line_style.color_mode = {
+                            let mode = self.read_str()?.parse::<ColorMode>();
+                            self.recover(mode)?
+                        };
                     }
                     b"width" => line_style.width = self.read_float()?,
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"LineStyle" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"LineStyle" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(line_style)
     }
 
     fn read_list_style(&mut self, mut attrs: HashMap<String, String>) -> Result<ListStyle, Error> {
+        let namespace = self.take_namespace();
         let mut list_style = ListStyle {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"bgColor" => list_style.bg_color = self.read_str()?,
                     b"maxSnippetLines" => {
                         let line_str = self.read_str()?;
-                        list_style.max_snippet_lines = line_str
+                        let lines = line_str
                             .parse::<u32>()
-                            .map_err(|_| Error::NumParse(line_str))?;
+                            .map_err(|_| Error::NumParse(line_str));
+                        list_style.max_snippet_lines = self.recover(lines)?;
                     }
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"ListStyle" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"ListStyle" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(list_style)
     }
 
     fn read_poly_style(&mut self, mut attrs: HashMap<String, String>) -> Result<PolyStyle, Error> {
+        let namespace = self.take_namespace();
         let mut poly_style = PolyStyle {
             id: attrs.remove("id"),
             attrs,
+            namespace,
             ..Default::default()
         };
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
                     b"color" => poly_style.color = self.read_str()?,
                     b"colorMode" => {
-                        poly_style.color_mode = self.read_str()?.parse::<ColorMode>()?;
+                        poly_style.color_mode = {
+                            let mode = self.read_str()?.parse::<ColorMode>();
+                            self.recover(mode)?
+                        };
                     }
                     b"fill" => {
                         let fill_str = self.read_str()?;
@@ -1007,53 +2805,91 @@ where
                         let outline_str = self.read_str()?;
                         poly_style.outline = outline_str != "false" && outline_str != "0"
                     }
-                    _ => {}
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == b"PolyStyle" {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name().as_ref() == b"PolyStyle" => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(poly_style)
     }
 
+    /// Reads an element that isn't supported as a typed struct, which is an error in strict mode
+    /// unless it's part of the KML or Google extension schemas
     fn read_element(
         &mut self,
         start: &BytesStart,
         attrs: HashMap<String, String>,
+    ) -> Result<Element, Error> {
+        self.check_schema_element(start)?;
+        self.read_generic_element(start, attrs)
+    }
+
+    /// Skips a child that isn't a field of the typed struct being read, which is an error in strict
+    /// mode unless it's part of the KML or Google extension schemas
+    fn skip_unknown_element(&mut self, start: &BytesStart) -> Result<(), Error> {
+        self.check_schema_element(start)?;
+        self.skip_element()
+    }
+
+    /// Rejects elements outside the KML and Google extension schemas in strict mode
+    fn check_schema_element(&self, start: &BytesStart) -> Result<(), Error> {
+        let local_name = start.local_name();
+        let local_name = local_name.as_ref();
+        if self.config.strict
+            && !((ELEMENT_NAMES.contains(&local_name)
+                || SCHEMA_ELEMENT_NAMES.contains(&local_name))
+                && Self::is_kml_element(&self.reader, &self.config, start.name()))
+        {
+            return Err(Error::UnexpectedElement(
+                String::from_utf8_lossy(start.name().as_ref()).to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn read_generic_element(
+        &mut self,
+        start: &BytesStart,
+        attrs: HashMap<String, String>,
     ) -> Result<Element, Error> {
         let mut element = Element::default();
         let tag = start.local_name();
         element.name = String::from_utf8_lossy(tag.into_inner()).to_string();
+        element.prefix = start
+            .name()
+            .prefix()
+            .map(|p| String::from_utf8_lossy(p.into_inner()).to_string());
+        element.namespace =
+            Self::resolve_namespace(&self.reader, self.unbound_namespace, start.name())
+                .map(Cow::into_owned);
         element.attrs = attrs;
+        let comments = self.config.comments;
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
                 Event::Start(e) => {
                     let start = e.to_owned();
                     let start_attrs = Self::read_attrs(start.attributes());
-                    element
-                        .children
-                        .push(self.read_element(&start, start_attrs)?);
+                    element.children.push(
+                        self.read_nested(|r| r.read_element(&start, start_attrs))?
+                            .into(),
+                    );
                 }
-                Event::Text(ref mut e) => {
+                Event::Text(ref e) => {
                     element.content = Some(
                         e.unescape()
                             .map(|s| s.to_string())
                             .unwrap_or_else(|_| e.escape_ascii().to_string()),
                     )
                 }
-                Event::End(ref mut e) => {
-                    if e.local_name() == tag {
-                        break;
-                    }
-                }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::End(ref e) if e.local_name() == tag => break,
+                Event::Comment(ref e) if comments => element
+                    .children
+                    .push(Node::Comment(String::from_utf8_lossy(e).into_owned())),
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(element)
@@ -1062,21 +2898,21 @@ where
     fn read_boundary(&mut self, end_tag: &[u8]) -> Result<Vec<LinearRing<T>>, Error> {
         let mut boundary: Vec<LinearRing<T>> = Vec::new();
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => {
-                    let attrs = Self::read_attrs(e.attributes());
-                    if e.local_name().as_ref() == b"LinearRing" {
-                        boundary.push(self.read_linear_ring(attrs)?);
-                    }
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
                 }
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == end_tag {
-                        break;
-                    }
+                Event::Start(ref e) if e.local_name().as_ref() == b"LinearRing" => {
+                    let attrs = Self::read_attrs(e.attributes());
+                    boundary.extend(self.read_linear_ring(attrs)?);
                 }
-                Event::Comment(_) => {}
-                _ => break,
+                Event::Start(ref e) => self.skip_unknown_element(e)?,
+                Event::End(ref e) if e.local_name().as_ref() == end_tag => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                _ => {}
             }
         }
         Ok(boundary)
@@ -1084,97 +2920,508 @@ where
 
     fn read_geom_props(&mut self, end_tag: &[u8]) -> Result<GeomProps<T>, Error> {
         let mut coords: Vec<Coord<T>> = Vec::new();
-        let mut altitude_mode = types::AltitudeMode::default();
-        let mut extrude = false;
-        let mut tessellate = false;
+        let mut altitude_mode = None;
+        let mut extrude = None;
+        let mut tessellate = None;
+        let skip_coordinates = self.config.skip_coordinates;
 
         loop {
-            let mut e = self.reader.read_event_into(&mut self.buf)?;
+            let e = self.read_xml_event()?.into_owned();
             match e {
-                Event::Start(ref mut e) => match e.local_name().as_ref() {
+                Event::Start(ref e)
+                    if !Self::is_kml_element(&self.reader, &self.config, e.name()) =>
+                {
+                    self.skip_unknown_element(e)?
+                }
+                Event::Start(ref e) => match e.local_name().as_ref() {
+                    b"coordinates" if skip_coordinates => self.skip_element()?,
                     b"coordinates" => {
-                        coords = coords_from_str(&self.read_str()?)?;
+                        let coords_str = self.read_str()?;
+                        let tuples = if self.config.tolerant_coordinates {
+                            types::coord::tolerant_tuples(&coords_str)
+                        } else {
+                            coords_str.split_whitespace().map(String::from).collect()
+                        };
+                        coords = Vec::new();
+                        for tuple in tuples {
+                            let coord = Coord::from_str(&tuple).map(Some);
+                            if let Some(coord) = self.recover_coord(coord)? {
+                                coords.push(self.transform_coord(coord));
+                            }
+                        }
                     }
                     b"altitudeMode" => {
-                        altitude_mode = types::AltitudeMode::from_str(&self.read_str()?)?
+                        altitude_mode = {
+                            let mode = types::AltitudeMode::from_str(&self.read_str()?);
+                            Some(self.recover(mode)?)
+                        }
                     }
-                    b"extrude" => extrude = self.read_str()? == "1",
-                    b"tessellate" => tessellate = self.read_str()? == "1",
-                    _ => {}
+                    b"extrude" => extrude = Some(self.read_str()? == "1"),
+                    b"tessellate" => tessellate = Some(self.read_str()? == "1"),
+                    _ => self.skip_unknown_element(e)?,
                 },
-                Event::End(ref mut e) => {
-                    if e.local_name().as_ref() == end_tag {
-                        break;
-                    }
-                }
+                Event::End(ref e) if e.local_name().as_ref() == end_tag => break,
+                e @ Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
                 _ => {}
             }
         }
-        if coords.is_empty() {
-            Err(Error::InvalidGeometry(
+        if coords.is_empty() && !skip_coordinates {
+            self.recover::<()>(Err(Error::InvalidGeometry(
                 "Geometry must contain coordinates element".to_string(),
-            ))
-        } else {
-            Ok(GeomProps {
-                coords,
-                altitude_mode,
-                extrude,
-                tessellate,
-            })
+            )))?;
+        }
+        Ok(GeomProps {
+            coords,
+            altitude_mode,
+            extrude,
+            tessellate,
+        })
+    }
+
+    /// Reads the properties of a geometry, returning `None` if it's missing coordinates in lenient
+    /// mode so the geometry is dropped. Geometries are kept without coordinates when skipping them
+    fn read_valid_geom_props(&mut self, end_tag: &[u8]) -> Result<Option<GeomProps<T>>, Error> {
+        let props = self.read_geom_props(end_tag)?;
+        if props.coords.is_empty() && !self.config.skip_coordinates {
+            return Ok(None);
         }
+        Ok(Some(props))
     }
 
-    fn read_float<F: Float + FromStr>(&mut self) -> Result<F, Error> {
+    fn read_float<F: Float + FromStr + Default>(&mut self) -> Result<F, Error> {
         let float_str = self.read_str()?;
-        float_str
+        let float = float_str
             .parse::<F>()
-            .map_err(|_| Error::NumParse(float_str))
+            .map_err(|_| Error::NumParse(float_str));
+        self.recover(float)
+    }
+
+    fn read_space_separated_floats(s: &str) -> Result<Vec<T>, Error> {
+        s.split_whitespace()
+            .map(|v| v.parse::<T>().map_err(|_| Error::NumParse(v.to_string())))
+            .collect()
+    }
+
+    /// Skips the rest of the current element without reading its content
+    fn skip_element(&mut self) -> Result<(), Error> {
+        self.skip_to_depth(self.open_elements.saturating_sub(1))
+    }
+
+    /// Skips events until only `depth` elements are open
+    fn skip_to_depth(&mut self, depth: usize) -> Result<(), Error> {
+        while self.open_elements > depth {
+            if let e @ Event::Eof = self.read_xml_event()? {
+                return Err(Error::InvalidXmlEvent(format!("{e:?}")));
+            }
+        }
+        Ok(())
     }
 
+    /// Reads the trimmed text of the current element up to its end tag, joining text and CDATA
+    /// sections split up by comments
     fn read_str(&mut self) -> Result<String, Error> {
-        let e = self.reader.read_event_into(&mut self.buf)?;
-        match e {
-            Event::Text(e) => Ok(e
-                .unescape()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|_| e.escape_ascii().to_string())),
-            Event::CData(e) => {
-                Ok(String::from_utf8(e.to_vec()).unwrap_or_else(|_| e.escape_ascii().to_string()))
+        // Whitespace between sections is significant, like in `1,1 <!-- --> 2,2`
+        let text = self.read_untrimmed(Self::read_str_untrimmed)?;
+        Ok(text.trim().to_string())
+    }
+
+    fn read_str_untrimmed(&mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        loop {
+            match self.read_xml_event()? {
+                Event::Text(e) => match e.unescape() {
+                    Ok(s) => text.push_str(&s),
+                    Err(_) => text.push_str(&e.escape_ascii().to_string()),
+                },
+                Event::CData(e) => match str::from_utf8(&e) {
+                    Ok(s) => text.push_str(s),
+                    Err(_) => text.push_str(&e.escape_ascii().to_string()),
+                },
+                Event::Comment(_) | Event::PI(_) => {}
+                Event::End(_) => break,
+                e => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
             }
-            Event::End(_) => Ok("".to_string()),
-            e => Err(Error::InvalidXmlEvent(format!("{e:?}"))),
         }
+        Ok(text)
     }
 
-    fn read_attrs(attrs: Attributes) -> HashMap<String, String> {
-        attrs
-            .filter_map(Result::ok)
-            .map(|a| {
-                (
-                    String::from_utf8_lossy(a.key.into_inner()).to_string(),
-                    String::from_utf8_lossy(&a.value).to_string(),
-                )
-            })
-            .collect()
+    /// Reads a free text value, keeping surrounding whitespace if `trim_text` is disabled
+    fn read_text(&mut self) -> Result<String, Error> {
+        if self.config.trim_text {
+            self.read_str()
+        } else {
+            let (inner_xml, text) = self.read_inner_content()?;
+            Ok(text.unwrap_or(inner_xml))
+        }
     }
-}
 
-impl<T> FromStr for Kml<T>
-where
-    T: CoordType + FromStr + Default,
-{
-    type Err = Error;
+    /// Reads mixed content like HTML descriptions. Text and CDATA are unescaped as in `read_str`,
+    /// but if any child elements are present the verbatim inner XML is returned instead
+    fn read_mixed_str(&mut self) -> Result<String, Error> {
+        let (inner_xml, text) = self.read_inner_content()?;
+        let content = text.unwrap_or(inner_xml);
+        if self.config.trim_text {
+            Ok(content.trim().to_string())
+        } else {
+            Ok(content)
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        KmlReader::<&[u8], T>::from_string(s).read()
+    /// Returns the verbatim inner XML of the current element along with its unescaped text if it
+    /// doesn't contain any child markup
+    fn read_inner_content(&mut self) -> Result<(String, Option<String>), Error> {
+        // Keep self-closing markup like `<br/>` as written
+        self.reader.config_mut().expand_empty_elements = false;
+        // Whitespace is significant between inline markup like `<b>bold</b> text`
+        let content = self.read_untrimmed(Self::read_inner_content_untrimmed);
+        self.reader.config_mut().expand_empty_elements = true;
+        // The end tag was read without going through `read_xml_event`
+        self.open_elements = self.open_elements.saturating_sub(1);
+        content
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Calls `read` with trimming of text events disabled
+    fn read_untrimmed<V>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<V, Error>,
+    ) -> Result<V, Error> {
+        let config = self.reader.config_mut();
+        let (trim_text_start, trim_text_end) = (config.trim_text_start, config.trim_text_end);
+        config.trim_text(false);
+        let result = read(self);
+        let config = self.reader.config_mut();
+        config.trim_text_start = trim_text_start;
+        config.trim_text_end = trim_text_end;
+        result
+    }
 
-    #[test]
+    fn read_inner_content_untrimmed(&mut self) -> Result<(String, Option<String>), Error> {
+        let mut inner: Vec<u8> = Vec::new();
+        let mut text = String::new();
+        let mut has_markup = false;
+        let mut depth = 0;
+        loop {
+            self.buf.clear();
+            let e = self.reader.read_event_into(&mut self.buf)?;
+            match e {
+                Event::Start(e) => {
+                    depth += 1;
+                    has_markup = true;
+                    inner.push(b'<');
+                    inner.extend_from_slice(&e);
+                    inner.push(b'>');
+                }
+                Event::End(e) => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                    inner.extend_from_slice(b"</");
+                    inner.extend_from_slice(&e);
+                    inner.push(b'>');
+                }
+                Event::Empty(e) => {
+                    has_markup = true;
+                    inner.push(b'<');
+                    inner.extend_from_slice(&e);
+                    inner.extend_from_slice(b"/>");
+                }
+                Event::Text(e) => {
+                    inner.extend_from_slice(&e);
+                    match e.unescape() {
+                        Ok(s) => text.push_str(&s),
+                        Err(_) => text.push_str(&e.escape_ascii().to_string()),
+                    }
+                }
+                Event::CData(e) => {
+                    inner.extend_from_slice(b"<![CDATA[");
+                    inner.extend_from_slice(&e);
+                    inner.extend_from_slice(b"]]>");
+                    text.push_str(&String::from_utf8_lossy(&e));
+                }
+                Event::Comment(e) => {
+                    inner.extend_from_slice(b"<!--");
+                    inner.extend_from_slice(&e);
+                    inner.extend_from_slice(b"-->");
+                }
+                Event::PI(e) => {
+                    has_markup = true;
+                    inner.extend_from_slice(b"<?");
+                    inner.extend_from_slice(&e);
+                    inner.extend_from_slice(b"?>");
+                }
+                Event::Eof => return Err(Error::InvalidXmlEvent(format!("{e:?}"))),
+                Event::Decl(_) | Event::DocType(_) => {}
+            }
+        }
+        let inner =
+            String::from_utf8(inner).unwrap_or_else(|e| e.as_bytes().escape_ascii().to_string());
+        Ok((inner, if has_markup { None } else { Some(text) }))
+    }
+
+    /// Reads the next event, respelling KML element names as expected when `case_insensitive` is set
+    fn read_xml_event(&mut self) -> Result<Event<'_>, Error> {
+        // Events are converted or copied before the next one is read, so the buffer only needs to
+        // hold one event at a time
+        self.buf.clear();
+        let event = self.reader.read_event_into(&mut self.buf)?;
+        match event {
+            Event::Start(ref e) => {
+                self.open_elements += 1;
+                self.start_namespace =
+                    Self::resolve_namespace(&self.reader, self.unbound_namespace, e.name());
+            }
+            Event::End(_) => self.open_elements = self.open_elements.saturating_sub(1),
+            _ => {}
+        }
+        if !self.config.case_insensitive {
+            return Ok(event);
+        }
+        Ok(match event {
+            Event::Start(mut e) => {
+                if let Some(name) = Self::expected_name(&self.reader, &self.config, e.name()) {
+                    e.set_name(&name);
+                }
+                Event::Start(e)
+            }
+            Event::End(e) => match Self::expected_name(&self.reader, &self.config, e.name()) {
+                Some(name) => Event::End(BytesEnd::new(String::from_utf8_lossy(&name).to_string())),
+                None => Event::End(e),
+            },
+            e => e,
+        })
+    }
+
+    /// Returns the name of a KML element with its local name spelled as expected, if it's spelled
+    /// differently
+    fn expected_name(
+        reader: &NsReader<PositionReader<Source<B>>>,
+        config: &KmlReaderConfig,
+        name: QName,
+    ) -> Option<Vec<u8>> {
+        let local_name = name.local_name();
+        let local_name = local_name.as_ref();
+        if ELEMENT_NAMES.contains(&local_name) || !Self::is_kml_element(reader, config, name) {
+            return None;
+        }
+        let candidates: Vec<&[u8]> = ELEMENT_NAMES
+            .into_iter()
+            .filter(|n| n.eq_ignore_ascii_case(local_name))
+            .collect();
+        let expected = candidates
+            .iter()
+            .find(|n| n[0].is_ascii_uppercase() == local_name[0].is_ascii_uppercase())
+            .or(candidates.first())?;
+        let mut qualified = Vec::new();
+        if let Some(prefix) = name.prefix() {
+            qualified.extend_from_slice(prefix.as_ref());
+            qualified.push(b':');
+        }
+        qualified.extend_from_slice(expected);
+        Some(qualified)
+    }
+
+    /// Whether an element should be read as KML, which includes elements in the KML and Google
+    /// extension namespaces, elements without a namespace, and elements with undeclared `kml` or
+    /// `gx` prefixes as commonly seen in fragments
+    fn is_kml_element(
+        reader: &NsReader<PositionReader<Source<B>>>,
+        config: &KmlReaderConfig,
+        name: QName,
+    ) -> bool {
+        if config.ignore_namespaces {
+            return true;
+        }
+        match reader.resolve_element(name).0 {
+            ResolveResult::Bound(ns) => KML_NAMESPACES.contains(&ns.into_inner()),
+            ResolveResult::Unbound => true,
+            ResolveResult::Unknown(prefix) => prefix == b"kml" || prefix == b"gx",
+        }
+    }
+
+    /// Returns the namespace URI an element's name resolves to, borrowing the KML namespaces
+    /// rather than allocating them for every element. Elements without a namespace resolve to
+    /// `unbound`
+    fn resolve_namespace(
+        reader: &NsReader<PositionReader<Source<B>>>,
+        unbound: Option<&'static str>,
+        name: QName,
+    ) -> Option<Cow<'static, str>> {
+        match reader.resolve_element(name).0 {
+            ResolveResult::Bound(ns) => Some(
+                match KML_NAMESPACES.iter().find(|kml| **kml == ns.into_inner()) {
+                    Some(kml) => String::from_utf8_lossy(kml),
+                    None => Cow::Owned(String::from_utf8_lossy(ns.into_inner()).into_owned()),
+                },
+            ),
+            ResolveResult::Unbound => unbound.map(Cow::Borrowed),
+            ResolveResult::Unknown(_) => None,
+        }
+    }
+
+    /// Takes the namespace of the element whose typed struct is being read, which must be called
+    /// before reading any of its children
+    fn take_namespace(&mut self) -> Option<String> {
+        self.start_namespace.take().map(Cow::into_owned)
+    }
+
+    /// Reads an element nested one level deeper, enforcing `max_depth`
+    fn read_nested<V>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<V, Error>,
+    ) -> Result<V, Error> {
+        self.depth += 1;
+        let result = match self.config.max_depth {
+            Some(max_depth) if self.depth > max_depth => Err(Error::MaxDepthExceeded(max_depth)),
+            _ => read(self),
+        };
+        self.depth -= 1;
+        result
+    }
+
+    /// Replaces errors with the default value in lenient mode, recording them as warnings
+    fn recover<V: Default>(&mut self, result: Result<V, Error>) -> Result<V, Error> {
+        match result {
+            Err(error) if self.config.lenient => {
+                self.warnings.push(Warning {
+                    error,
+                    position: self.position(),
+                });
+                Ok(V::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Skips invalid coordinate tuples when `skip_invalid_coordinates` is set, recording them as
+    /// warnings, and otherwise recovers from them like other errors
+    fn recover_coord(
+        &mut self,
+        result: Result<Option<Coord<T>>, Error>,
+    ) -> Result<Option<Coord<T>>, Error> {
+        match result {
+            Err(error) if self.config.skip_invalid_coordinates => {
+                self.warnings.push(Warning {
+                    error,
+                    position: self.position(),
+                });
+                Ok(None)
+            }
+            result => self.recover(result),
+        }
+    }
+
+    /// Attaches the current position and enclosing element to an error, keeping the innermost
+    /// location if one was already attached
+    fn locate(&self, err: Error, element: Option<&[u8]>) -> Error {
+        match err {
+            Error::Located { .. } => err,
+            err => Error::Located {
+                source: Box::new(err),
+                position: self.position(),
+                element: element.map(|e| String::from_utf8_lossy(e).to_string()),
+            },
+        }
+    }
+
+    fn read_attrs(attrs: Attributes) -> HashMap<String, String> {
+        attrs
+            .filter_map(Result::ok)
+            .map(|a| {
+                (
+                    String::from_utf8_lossy(a.key.into_inner()).to_string(),
+                    String::from_utf8_lossy(&a.value).to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Event read by [`KmlReader::next_event`](struct.KmlReader.html#method.next_event)
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum KmlEvent<T: CoordType = f64> {
+    /// Start of the `kml` root element
+    StartKml {
+        version: KmlVersion,
+        attrs: HashMap<String, String>,
+    },
+    /// Start of a `Document`, with the fields before its first element and no `elements`
+    StartDocument(Document<T>),
+    /// Start of a `Folder`, with the fields before its first element and no `elements`
+    StartFolder(Folder<T>),
+    /// End of the most recently started `kml`, `Document`, or `Folder`
+    End,
+    Placemark(Placemark<T>),
+    /// Any other element read in full
+    Other(Kml<T>),
+}
+
+/// Iterator over elements parsed by a [`KmlReader`](struct.KmlReader.html), created by
+/// [`KmlReader::features`](struct.KmlReader.html#method.features)
+pub struct Features<'a, B: BufRead, T: CoordType + FromStr + Default = f64> {
+    reader: &'a mut KmlReader<B, T>,
+    features_only: bool,
+    done: bool,
+}
+
+impl<B: BufRead, T> Features<'_, B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    fn read_next(&mut self) -> Result<Option<Kml<T>>, Error> {
+        loop {
+            match self.reader.next_event()? {
+                Some(KmlEvent::Placemark(p)) => return Ok(Some(Kml::Placemark(p))),
+                Some(KmlEvent::Other(k)) if !self.features_only || is_feature(&k) => {
+                    return Ok(Some(k))
+                }
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Returns the number of bytes of input consumed so far, see
+    /// [`KmlReader::bytes_read`](struct.KmlReader.html#method.bytes_read)
+    pub fn bytes_read(&self) -> u64 {
+        self.reader.bytes_read()
+    }
+}
+
+impl<B: BufRead, T> Iterator for Features<'_, B, T>
+where
+    T: CoordType + FromStr + Default,
+{
+    type Item = Result<Kml<T>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.read_next().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+impl<T> FromStr for Kml<T>
+where
+    T: CoordType + FromStr + Default,
+{
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KmlReader::<&[u8], T>::from_string(s).read()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
     fn test_parse_point() {
         let kml_str = "<Point><coordinates>1,1,1</coordinates><altitudeMode>relativeToGround</altitudeMode></Point>";
         let p: Kml = kml_str.parse().unwrap();
@@ -1186,7 +3433,7 @@ mod tests {
                     y: 1.,
                     z: Some(1.)
                 },
-                altitude_mode: types::AltitudeMode::RelativeToGround,
+                altitude_mode: Some(types::AltitudeMode::RelativeToGround),
                 ..Default::default()
             })
         );
@@ -1296,6 +3543,7 @@ mod tests {
             target_href: Some("../images/foo1.jpg".to_string()),
             source_href: Some("in-geometry-file/foo1.jpg".to_string()),
             attrs: alias1_attrs,
+            namespace: None,
         };
 
         // Expected Alias 2
@@ -1306,6 +3554,7 @@ mod tests {
             target_href: Some("../images/foo2.jpg".to_string()),
             source_href: Some("in-geometry-file/foo2.jpg".to_string()),
             attrs: alias2_attrs,
+            namespace: None,
         };
 
         // Expected ResourceMap
@@ -1317,6 +3566,7 @@ mod tests {
             Kml::ResourceMap(ResourceMap {
                 aliases: vec![alias1, alias2],
                 attrs: resource_map_attrs,
+                namespace: None,
             })
         );
 
@@ -1326,233 +3576,1437 @@ mod tests {
             Kml::ResourceMap(ResourceMap {
                 aliases: Vec::new(),
                 attrs: HashMap::new(),
+                namespace: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_alias() {
+        let kml_str = r#"<Alias id="Some ID">
+            <!-- comment -->
+            <targetHref>../images/foo.jpg</targetHref>
+            <sourceHref>in-geometry-file/foo.jpg</sourceHref>
+        </Alias>"#;
+
+        let mut attrs = HashMap::new();
+        attrs.insert("id".to_string(), "Some ID".to_string());
+
+        let a: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            a,
+            Kml::Alias(Alias {
+                target_href: Some("../images/foo.jpg".to_string()),
+                source_href: Some("in-geometry-file/foo.jpg".to_string()),
+                attrs,
+                namespace: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_schema_data() {
+        let kml_str = r##"<SchemaData schemaUrl="#TrailHeadTypeId">
+            <SimpleData name="TrailHeadName" anyAttribute="anySimpleType">Pi in the sky</SimpleData>
+            <SimpleData name="TrailLength" anyAttribute="anySimpleType">3.14159</SimpleData>
+            <SimpleArrayData name="cadence" anyAttribute="anySimpleType">
+                <value>86</value>
+                <!-- comment -->
+                <value>113</value>
+                <value>113</value>
+            </SimpleArrayData>
+            <SimpleArrayData name="heartrate">
+                <value>181</value>
+            </SimpleArrayData>
+        </SchemaData>"##;
+
+        let a: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            a,
+            Kml::SchemaData(SchemaData {
+                data: vec![
+                    SimpleData {
+                        name: "TrailHeadName".to_string(),
+                        value: "Pi in the sky".to_string(),
+                        attrs: [("anyAttribute".to_string(), "anySimpleType".to_string())]
+                            .iter()
+                            .cloned()
+                            .collect(),
+                        namespace: None,
+                    },
+                    SimpleData {
+                        name: "TrailLength".to_string(),
+                        value: "3.14159".to_string(),
+                        attrs: [("anyAttribute".to_string(), "anySimpleType".to_string())]
+                            .iter()
+                            .cloned()
+                            .collect(),
+                        namespace: None,
+                    },
+                ],
+                arrays: vec![
+                    SimpleArrayData {
+                        name: "cadence".to_string(),
+                        values: vec!["86".to_string(), "113".to_string(), "113".to_string()],
+                        attrs: [("anyAttribute".to_string(), "anySimpleType".to_string())]
+                            .iter()
+                            .cloned()
+                            .collect(),
+                        namespace: None,
+                    },
+                    SimpleArrayData {
+                        name: "heartrate".to_string(),
+                        values: vec!["181".to_string()],
+                        ..Default::default()
+                    },
+                ],
+                schema_url: Some("#TrailHeadTypeId".to_string()),
+                attrs: HashMap::new(),
+                namespace: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_scale() {
+        let kml_str = r#"<Scale>
+            <x>1.2</x>
+            <y>3.5</y>
+            <!-- comment -->
+            <z>2.5</z>
+        </Scale>"#;
+        let s: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            s,
+            Kml::Scale(Scale {
+                x: 1.2,
+                y: 3.5,
+                z: 2.5,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_orientation() {
+        let kml_str = r#"<Orientation>
+            <heading>45.01</heading>
+            <tilt>-10.02</tilt>
+            <roll>0.0</roll>
+        </Orientation>"#;
+        let l: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            l,
+            Kml::Orientation(Orientation {
+                roll: 0.,
+                tilt: -10.02,
+                heading: 45.01,
+                ..Default::default()
             })
         );
     }
 
     #[test]
-    fn test_read_alias() {
-        let kml_str = r#"<Alias id="Some ID">
-            <!-- comment -->
-            <targetHref>../images/foo.jpg</targetHref>
-            <sourceHref>in-geometry-file/foo.jpg</sourceHref>
-        </Alias>"#;
+    fn test_parse_line_string() {
+        let kml_str = r#"<LineString>
+            <coordinates>1,1 2,1 3,1</coordinates>
+            <altitudeMode>relativeToGround</altitudeMode>
+        </LineString>"#;
+        let l: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            l,
+            Kml::LineString(LineString {
+                coords: vec![
+                    Coord {
+                        x: 1.,
+                        y: 1.,
+                        z: None
+                    },
+                    Coord {
+                        x: 2.,
+                        y: 1.,
+                        z: None
+                    },
+                    Coord {
+                        x: 3.,
+                        y: 1.,
+                        z: None
+                    }
+                ],
+                altitude_mode: Some(types::AltitudeMode::RelativeToGround),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_polygon() {
+        let poly_str = r#"<Polygon>
+        <outerBoundaryIs>
+          <LinearRing>
+            <tessellate>1</tessellate>
+            <coordinates>
+              -1,2,0
+              -1.5,3,0
+              -1.5,2,0
+              -1,2,0
+            </coordinates>
+          </LinearRing>
+        </outerBoundaryIs>
+      </Polygon>"#;
+        let mut r = KmlReader::from_string(poly_str);
+
+        let p: Kml = r.read().unwrap();
+        assert_eq!(
+            p,
+            Kml::Polygon(Polygon {
+                outer: LinearRing {
+                    coords: vec![
+                        Coord {
+                            x: -1.,
+                            y: 2.,
+                            z: Some(0.)
+                        },
+                        Coord {
+                            x: -1.5,
+                            y: 3.,
+                            z: Some(0.)
+                        },
+                        Coord {
+                            x: -1.5,
+                            y: 2.,
+                            z: Some(0.)
+                        },
+                        Coord {
+                            x: -1.,
+                            y: 2.,
+                            z: Some(0.)
+                        },
+                    ],
+                    tessellate: Some(true),
+                    ..Default::default()
+                },
+                inner: vec![],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_style_map() {
+        let kml_str = r#"
+        <StyleMap id="id" test="test">
+        </StyleMap>
+        "#;
+        let s: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            s,
+            Kml::StyleMap(StyleMap {
+                id: Some("id".to_string()),
+                attrs: HashMap::from([("test".to_string(), "test".to_string())]),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_balloon_style_text_markup() {
+        let kml_str = r#"<BalloonStyle>
+            <text><h3>$[name]</h3> <p>Population: <b>$[population]</b> &amp; more<br/></p></text>
+        </BalloonStyle>"#;
+        let b: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            b,
+            Kml::BalloonStyle(BalloonStyle {
+                text: Some(
+                    "<h3>$[name]</h3> <p>Population: <b>$[population]</b> &amp; more<br/></p>"
+                        .to_string()
+                ),
+                ..Default::default()
+            })
+        );
+
+        for (kml_str, text) in [
+            (
+                "<BalloonStyle><text><![CDATA[<b>$[name]</b>]]></text></BalloonStyle>",
+                "<b>$[name]</b>",
+            ),
+            (
+                "<BalloonStyle><text>A &amp; B</text></BalloonStyle>",
+                "A & B",
+            ),
+        ] {
+            let Kml::BalloonStyle(b) = kml_str.parse::<Kml>().unwrap() else {
+                panic!("expected BalloonStyle for {}", kml_str);
+            };
+            assert_eq!(b.text.as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_read_description_markup() {
+        let kml_str = r#"<Placemark>
+            <description>
+                <p>Trail <b>open</b> &amp; <a href="https://example.com">maintained</a></p>
+            </description>
+        </Placemark>"#;
+        let p: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            p,
+            Kml::Placemark(Placemark {
+                description: Some(
+                    r#"<p>Trail <b>open</b> &amp; <a href="https://example.com">maintained</a></p>"#
+                        .to_string()
+                ),
+                ..Default::default()
+            })
+        );
+
+        let kml_str = "<Placemark><description><![CDATA[<p>A & B</p>]]></description></Placemark>";
+        let p: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            p,
+            Kml::Placemark(Placemark {
+                description: Some("<p>A & B</p>".to_string()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_track() {
+        let gx_str = r#"<Placemark>
+            <gx:Track>
+                <when>2010-05-28T02:02:09Z</when>
+                <when>2010-05-28T02:02:35Z</when>
+                <gx:coord>-122.207881 37.371915 156.000000</gx:coord>
+                <gx:coord>-122.205712 37.373288 152.000000</gx:coord>
+                <gx:angles>45.54676 66.2342 77.0</gx:angles>
+            </gx:Track>
+        </Placemark>"#;
+        let track = Track {
+            whens: vec![
+                "2010-05-28T02:02:09Z".to_string(),
+                "2010-05-28T02:02:35Z".to_string(),
+            ],
+            coords: vec![
+                Coord::new(-122.207881, 37.371915, Some(156.)),
+                Coord::new(-122.205712, 37.373288, Some(152.)),
+            ],
+            angles: vec![Orientation::new(77., 66.2342, 45.54676)],
+            ..Default::default()
+        };
+        assert_eq!(
+            gx_str.parse::<Kml>().unwrap(),
+            Kml::Placemark(Placemark {
+                geometry: Some(Geometry::Track(track.clone())),
+                ..Default::default()
+            })
+        );
+
+        let kml23_str = gx_str.replace("gx:", "");
+        assert_eq!(
+            kml23_str.parse::<Kml>().unwrap(),
+            Kml::Placemark(Placemark {
+                geometry: Some(Geometry::Track(track)),
+                ..Default::default()
+            })
+        );
+
+        // Children in other namespaces are kept as elements rather than read as track fields
+        let kml_str = r#"<gx:Track xmlns:foo="http://example.com/foo">
+            <when>2010-05-28T02:02:09Z</when>
+            <foo:when>never</foo:when>
+            <foo:coord>1 2 3</foo:coord>
+        </gx:Track>"#;
+        let Kml::Track(track) = kml_str.parse::<Kml>().unwrap() else {
+            panic!("expected Track");
+        };
+        assert_eq!(track.whens, vec!["2010-05-28T02:02:09Z".to_string()]);
+        assert!(track.coords.is_empty());
+        let names: Vec<_> = track
+            .children
+            .iter()
+            .filter_map(|c| c.as_element())
+            .map(|e| (e.prefix.as_deref(), e.name.as_str()))
+            .collect();
+        assert_eq!(names, vec![(Some("foo"), "when"), (Some("foo"), "coord")]);
+    }
+
+    #[test]
+    fn test_parse_tour() {
+        let kml_str = r#"<gx:Tour>
+            <name>Play me!</name>
+            <gx:Playlist>
+                <gx:Wait><gx:duration>2.4</gx:duration></gx:Wait>
+            </gx:Playlist>
+        </gx:Tour>"#;
+        let t: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            t,
+            Kml::Tour(Tour {
+                name: Some("Play me!".to_string()),
+                playlist: vec![Element {
+                    name: "Wait".to_string(),
+                    prefix: Some("gx".to_string()),
+                    children: vec![Node::Element(Element {
+                        name: "duration".to_string(),
+                        prefix: Some("gx".to_string()),
+                        content: Some("2.4".to_string()),
+                        ..Default::default()
+                    })],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+        );
+
+        let kml_str =
+            r#"<gx:Tour xmlns:foo="http://example.com/foo"><foo:name>x</foo:name></gx:Tour>"#;
+        let Kml::Tour(tour) = kml_str.parse::<Kml>().unwrap() else {
+            panic!("expected Tour");
+        };
+        assert_eq!(tour.name, None);
+        assert_eq!(tour.children[0].as_element().unwrap().name, "name");
+    }
+
+    #[test]
+    fn test_parse_style_map_pairs() {
+        let kml_str = r##"
+        <StyleMap id="pushpin">
+            <Pair><key>normal</key><styleUrl>#pushpin-normal</styleUrl></Pair>
+            <Pair><key>highlight</key><styleUrl>#pushpin-highlight</styleUrl></Pair>
+        </StyleMap>
+        "##;
+        let s: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            s,
+            Kml::StyleMap(StyleMap {
+                id: Some("pushpin".to_string()),
+                pairs: vec![
+                    Pair {
+                        key: StyleState::Normal,
+                        style_url: "#pushpin-normal".to_string(),
+                        ..Default::default()
+                    },
+                    Pair {
+                        key: StyleState::Highlight,
+                        style_url: "#pushpin-highlight".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_features() {
+        let kml_str = r#"<kml>
+        <Document>
+            <Style id="s"><LineStyle><width>2</width></LineStyle></Style>
+            <Folder>
+                <name>Folder</name>
+                <visibility>0</visibility>
+                <Placemark><name>1</name><Point><coordinates>1,1</coordinates></Point></Placemark>
+                <Schema id="schema"/>
+                <NetworkLink><Link><href>a.kml</href></Link></NetworkLink>
+                <Placemark><name>2</name><Point><coordinates>2,2</coordinates></Point></Placemark>
+            </Folder>
+        </Document>
+        </kml>"#;
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        let features = reader.features().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(features.len(), 3);
+        assert!(matches!(&features[0], Kml::Placemark(p) if p.name == Some("1".to_string())));
+        assert!(matches!(&features[1], Kml::Element(e) if e.name == "NetworkLink"));
+        assert!(matches!(&features[2], Kml::Placemark(p) if p.name == Some("2".to_string())));
+
+        let mut reader = KmlReader::<_, f64>::from_string(
+            "<Folder><Point><coordinates>a,b</coordinates></Point><Point></Point></Folder>",
+        );
+        let mut features = reader.features();
+        assert!(
+            matches!(features.next(), Some(Err(e)) if matches!(e.without_location(), Error::NumParse(_)))
+        );
+        assert!(features.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_namespaces() {
+        let kml_str = r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2" xmlns:ext="http://example.com/ext">
+        <Placemark>
+            <name>Namespaced</name>
+            <ext:Point><ext:coordinates>0,0</ext:coordinates></ext:Point>
+            <gx:Track><gx:coord>1 1 0</gx:coord></gx:Track>
+        </Placemark>
+        <ext:Placemark><ext:name>Other</ext:name></ext:Placemark>
+        <MultiGeometry>
+            <Point><coordinates>0,0</coordinates></Point>
+            <ext:Point><ext:coordinates>1,1</ext:coordinates></ext:Point>
+        </MultiGeometry>
+        </kml>"#;
+        let kml: Kml<f64> = kml_str.parse().unwrap();
+        let elements = match kml {
+            Kml::KmlDocument(d) => d.elements,
+            _ => unreachable!(),
+        };
+        match &elements[0] {
+            Kml::Placemark(p) => {
+                assert_eq!(
+                    p.namespace.as_deref(),
+                    Some("http://www.opengis.net/kml/2.2")
+                );
+                let Some(Geometry::Track(track)) = &p.geometry else {
+                    panic!("expected Track, got {:?}", p.geometry);
+                };
+                assert_eq!(
+                    track.namespace.as_deref(),
+                    Some("http://www.google.com/kml/ext/2.2")
+                );
+                assert_eq!(p.children.len(), 1);
+                let point = p.children[0].as_element().unwrap();
+                assert_eq!(point.name, "Point");
+                assert_eq!(point.prefix, Some("ext".to_string()));
+                assert_eq!(point.namespace, Some("http://example.com/ext".to_string()));
+            }
+            _ => unreachable!(),
+        }
+        match &elements[1] {
+            Kml::Element(e) => {
+                assert_eq!(e.name, "Placemark");
+                assert_eq!(e.namespace, Some("http://example.com/ext".to_string()));
+                assert_eq!(
+                    e.elements().next().unwrap().content,
+                    Some("Other".to_string())
+                );
+            }
+            _ => unreachable!(),
+        }
+        let Kml::MultiGeometry(g) = &elements[2] else {
+            panic!("expected MultiGeometry, got {:?}", elements[2]);
+        };
+        assert_eq!(g.geometries.len(), 1);
+        assert_eq!(g.children.len(), 1);
+        let point = g.children[0].as_element().unwrap();
+        assert_eq!(point.name, "Point");
+        assert_eq!(point.namespace, Some("http://example.com/ext".to_string()));
+        assert!(Kml::MultiGeometry(g.clone())
+            .to_string()
+            .contains("<ext:coordinates>1,1</ext:coordinates></ext:Point></MultiGeometry>"));
+    }
+
+    #[test]
+    fn test_parse_kml_document_version() {
+        let kml_str = r#"<kml xmlns="http://www.opengis.net/kml/2.3" xmlns:gx="http://www.google.com/kml/ext/2.2"><Document></Document></kml>"#;
+        let doc = match kml_str.parse::<Kml>().unwrap() {
+            Kml::KmlDocument(d) => d,
+            _ => unreachable!(),
+        };
+        assert_eq!(doc.version, KmlVersion::V23);
+        assert_eq!(
+            doc.attrs,
+            HashMap::from([
+                (
+                    "xmlns".to_string(),
+                    "http://www.opengis.net/kml/2.3".to_string()
+                ),
+                (
+                    "xmlns:gx".to_string(),
+                    "http://www.google.com/kml/ext/2.2".to_string()
+                ),
+            ])
+        );
+
+        let doc = match "<kml><Document></Document></kml>".parse::<Kml>().unwrap() {
+            Kml::KmlDocument(d) => d,
+            _ => unreachable!(),
+        };
+        assert_eq!(doc.version, KmlVersion::V22);
+
+        let kml_str = r#"<kml:kml xmlns:kml="http://earth.google.com/kml/2.1"></kml:kml>"#;
+        let doc = match kml_str.parse::<Kml>().unwrap() {
+            Kml::KmlDocument(d) => d,
+            _ => unreachable!(),
+        };
+        assert_eq!(doc.version, KmlVersion::V22);
+
+        let kml_str = r#"<kml xmlns="http://example.com/kml"></kml>"#;
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(
+            kml_str,
+            KmlReaderConfig::default().ignore_namespaces(true),
+        );
+        reader.read().unwrap();
+        assert_eq!(reader.version(), KmlVersion::Unknown);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let kml_str = "<Document>\n  <Placemark>\n    <Point><coordinates>1,a</coordinates></Point>\n  </Placemark>\n</Document>";
+        let err = KmlReader::<_, f64>::from_string(kml_str)
+            .read()
+            .unwrap_err();
+        assert!(matches!(err.without_location(), Error::NumParse(_)));
+        assert_eq!(
+            err.position(),
+            Some(Position {
+                offset: 66,
+                line: 3,
+                column: 42,
+            })
+        );
+        assert!(matches!(err, Error::Located { element: Some(ref e), .. } if e == "Placemark"));
+        assert_eq!(
+            err.to_string(),
+            "Error parsing number from: a at line 3, column 42 (byte 66) in Placemark"
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let kml_str = r#"<Document>
+            <Placemark>
+                <Point>
+                    <altitudeMode>underground</altitudeMode>
+                    <coordinates>1,1 a,b</coordinates>
+                </Point>
+            </Placemark>
+            <Style><LineStyle><width>wide</width></LineStyle></Style>
+            <Polygon><outerBoundaryIs></outerBoundaryIs></Polygon>
+        </Document>"#;
+
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        assert!(reader.read().is_err());
+
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        reader.config_mut().lenient = true;
+        let elements = match reader.read().unwrap() {
+            Kml::Document(Document { elements, .. }) => elements,
+            _ => unreachable!(),
+        };
+        match &elements[0] {
+            Kml::Placemark(p) => {
+                let point = Point {
+                    altitude_mode: Some(types::AltitudeMode::ClampToGround),
+                    ..Point::new(1., 1., None)
+                };
+                assert_eq!(p.geometry, Some(Geometry::Point(point)))
+            }
+            _ => unreachable!(),
+        }
+        match &elements[1] {
+            Kml::Style(s) => assert_eq!(s.line.as_ref().unwrap().width, 0.),
+            _ => unreachable!(),
+        }
+        // The polygon without a valid outer boundary is left out
+        assert_eq!(elements.len(), 2);
+
+        let warnings = reader.warnings();
+        assert_eq!(warnings.len(), 4);
+        assert!(matches!(warnings[0].error, Error::InvalidAltitudeMode(_)));
+        assert_eq!(warnings[0].position.line, 4);
+        assert!(matches!(warnings[1].error, Error::NumParse(_)));
+        assert!(matches!(warnings[2].error, Error::NumParse(_)));
+        assert!(matches!(warnings[3].error, Error::InvalidGeometry(_)));
+
+        // Geometries without any valid coordinates are dropped rather than given made-up ones
+        let kml_str = r#"<Document>
+            <Placemark><Point><coordinates>a,b</coordinates></Point></Placemark>
+            <Placemark>
+                <MultiGeometry>
+                    <Point><coordinates>1,1</coordinates></Point>
+                    <LineString><coordinates></coordinates></LineString>
+                </MultiGeometry>
+            </Placemark>
+            <Point><coordinates>c,d</coordinates></Point>
+        </Document>"#;
+        let config = KmlReaderConfig::default().lenient(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        let elements = match reader.read().unwrap() {
+            Kml::Document(Document { elements, .. }) => elements,
+            _ => unreachable!(),
+        };
+        assert_eq!(elements.len(), 2);
+        assert!(matches!(&elements[0], Kml::Placemark(p) if p.geometry.is_none()));
+        let Kml::Placemark(Placemark {
+            geometry: Some(Geometry::MultiGeometry(g)),
+            ..
+        }) = &elements[1]
+        else {
+            panic!("expected MultiGeometry, got {:?}", elements[1]);
+        };
+        assert!(
+            matches!(&g.geometries[..], [Geometry::Point(p)] if p.coord == Coord::new(1., 1., None))
+        );
+        let errors: Vec<_> = reader.warnings().iter().map(|w| &w.error).collect();
+        assert!(matches!(
+            errors[..],
+            [
+                Error::NumParse(_),
+                Error::InvalidGeometry(_),
+                Error::InvalidGeometry(_),
+                Error::NumParse(_),
+                Error::InvalidGeometry(_)
+            ]
+        ));
+
+        let kml_str = r#"<Link>
+            <href>a.kml</href>
+            <refreshMode>bogus</refreshMode>
+            <viewRefreshMode>bogus</viewRefreshMode>
+        </Link>"#;
+        assert!(KmlReader::<_, f64>::from_string(kml_str).read().is_err());
+
+        let config = KmlReaderConfig::default().lenient(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        let Kml::Link(link) = reader.read().unwrap() else {
+            panic!("expected Link");
+        };
+        assert_eq!(link.href.as_deref(), Some("a.kml"));
+        assert_eq!(link.refresh_mode, Some(RefreshMode::default()));
+        assert_eq!(link.view_refresh_mode, Some(ViewRefreshMode::default()));
+        let errors: Vec<_> = reader.warnings().iter().map(|w| &w.error).collect();
+        assert!(matches!(
+            errors[..],
+            [
+                Error::InvalidRefreshMode(_),
+                Error::InvalidViewRefreshMode(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_strict() {
+        let kml_str = r#"<Document>
+            <Placemark>
+                <name>Placemark</name>
+                <ExtendedData><Data name="a"><value>1</value></Data></ExtendedData>
+            </Placemark>
+        </Document>"#;
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        reader.config_mut().strict = true;
+        let Kml::Document(Document { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Document");
+        };
+        let Kml::Placemark(placemark) = &elements[0] else {
+            panic!("expected Placemark");
+        };
+        assert_eq!(
+            placemark.children[0].as_element().unwrap().name,
+            "ExtendedData"
+        );
+
+        let kml_str = r#"<Document>
+            <Placemark>
+                <ExtendedData><Data name="a"><valeu>1</valeu></Data></ExtendedData>
+            </Placemark>
+        </Document>"#;
+        assert!(KmlReader::<_, f64>::from_string(kml_str).read().is_ok());
+
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        reader.config_mut().strict = true;
+        let err = reader.read().unwrap_err();
+        assert!(matches!(
+            err.without_location(),
+            Error::UnexpectedElement(name) if name == "valeu"
+        ));
+        assert_eq!(err.position().unwrap().line, 3);
+
+        for kml_str in [
+            r#"<Placemark><Foo/></Placemark>"#,
+            r#"<Placemark xmlns:x="http://example.com/x"><x:name/></Placemark>"#,
+        ] {
+            let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+            reader.config_mut().strict = true;
+            assert!(reader.read().is_err(), "{kml_str}");
+        }
+
+        let mut reader = KmlReader::<_, f64>::from_string(
+            "<gx:Tour><gx:Playlist><gx:Wait><gx:duration>1</gx:duration></gx:Wait></gx:Playlist></gx:Tour>",
+        );
+        reader.config_mut().strict = true;
+        assert!(matches!(reader.read(), Ok(Kml::Tour(_))));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_read_non_utf8() {
+        let expected = Kml::Placemark(Placemark {
+            name: Some("Café".to_string()),
+            ..Default::default()
+        });
+
+        let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><Placemark><name>Caf\xE9</name></Placemark>";
+        let kml: Kml = KmlReader::from_reader(&latin1[..]).read().unwrap();
+        assert_eq!(kml, expected);
+
+        let utf16: Vec<u8> = [0xFEFF_u16]
+            .into_iter()
+            .chain(
+                r#"<?xml version="1.0" encoding="UTF-16"?><Placemark><name>Café</name></Placemark>"#
+                    .encode_utf16(),
+            )
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let mut reader = KmlReader::from_reader(&utf16[..]);
+        assert_eq!(reader.read().unwrap(), expected);
+        assert_eq!(reader.bytes_read(), utf16.len() as u64);
+
+        let kml: Kml = KmlReader::from_string(
+            r#"<?xml version="1.0" encoding="ISO-8859-1"?><Placemark><name>Café</name></Placemark>"#,
+        )
+        .read()
+        .unwrap();
+        assert_eq!(kml, expected);
+    }
+
+    #[test]
+    fn test_reader_config() {
+        let kml_str = "<Placemark><name> Padded </name></Placemark>";
+        let config = KmlReaderConfig::default().trim_text(false);
+        match KmlReader::<_, f64>::from_string_with_config(kml_str, config).read() {
+            Ok(Kml::Placemark(p)) => assert_eq!(p.name, Some(" Padded ".to_string())),
+            _ => unreachable!(),
+        }
+
+        let kml_str = "<Folder><Folder><Folder></Folder></Folder></Folder>";
+        let config = KmlReaderConfig::default().max_depth(Some(2));
+        let err = KmlReader::<_, f64>::from_string_with_config(kml_str, config)
+            .read()
+            .unwrap_err();
+        assert!(matches!(err.without_location(), Error::MaxDepthExceeded(2)));
+        let config = KmlReaderConfig::default().max_depth(Some(3));
+        assert!(
+            KmlReader::<_, f64>::from_string_with_config(kml_str, config)
+                .read()
+                .is_ok()
+        );
+
+        let kml_str = "<LineString><coordinates>1, 2, 3 4 ,5</coordinates></LineString>";
+        assert!(KmlReader::<_, f64>::from_string(kml_str).read().is_err());
+        let config = KmlReaderConfig::default().tolerant_coordinates(true);
+        match KmlReader::<_, f64>::from_string_with_config(kml_str, config).read() {
+            Ok(Kml::LineString(l)) => assert_eq!(
+                l.coords,
+                vec![Coord::new(1., 2., Some(3.)), Coord::new(4., 5., None)]
+            ),
+            _ => unreachable!(),
+        }
+
+        let kml_str = "<LineString><coordinates>1,2,0; 3,4,0,;a,b 5,6</coordinates></LineString>";
+        let config = KmlReaderConfig::default()
+            .tolerant_coordinates(true)
+            .skip_invalid_coordinates(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        match reader.read() {
+            Ok(Kml::LineString(l)) => assert_eq!(
+                l.coords,
+                vec![
+                    Coord::new(1., 2., Some(0.)),
+                    Coord::new(3., 4., Some(0.)),
+                    Coord::new(5., 6., None)
+                ]
+            ),
+            _ => unreachable!(),
+        }
+        assert_eq!(reader.warnings().len(), 1);
+        assert!(matches!(reader.warnings()[0].error, Error::NumParse(_)));
+
+        let kml_str = r#"<ext:Point xmlns:ext="http://example.com"><coordinates>1,1</coordinates></ext:Point>"#;
+        assert!(matches!(
+            KmlReader::<_, f64>::from_string(kml_str).read(),
+            Ok(Kml::Element(_))
+        ));
+        let config = KmlReaderConfig::default().ignore_namespaces(true);
+        assert!(matches!(
+            KmlReader::<_, f64>::from_string_with_config(kml_str, config).read(),
+            Ok(Kml::Point(_))
+        ));
+    }
+
+    #[test]
+    fn test_bytes_read() {
+        let kml_str = "<Folder>\n<Placemark><name>1</name></Placemark>\n<Placemark><name>2</name></Placemark>\n</Folder>";
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        assert_eq!(reader.bytes_read(), 0);
+        let mut features = reader.features();
+        features.next().unwrap().unwrap();
+        assert_eq!(features.bytes_read(), 46);
+        assert!(features.all(|f| f.is_ok()));
+        assert_eq!(reader.bytes_read(), kml_str.len() as u64);
+    }
+
+    #[test]
+    fn test_next_event() {
+        let kml_str = r#"<kml xmlns="http://www.opengis.net/kml/2.2">
+<Document id="d">
+  <name>Doc</name>
+  <open>1</open>
+  <Style id="s"></Style>
+  <Folder><name>Folder</name><Placemark><name>1</name></Placemark></Folder>
+  <Folder></Folder>
+</Document>
+</kml>"#;
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        let mut events = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 10);
+        assert!(matches!(
+            events[0],
+            KmlEvent::StartKml {
+                version: KmlVersion::V22,
+                ..
+            }
+        ));
+        let KmlEvent::StartDocument(document) = &events[1] else {
+            panic!("expected StartDocument, got {:?}", events[1]);
+        };
+        assert_eq!(document.name.as_deref(), Some("Doc"));
+        assert_eq!(document.attrs["id"], "d");
+        assert_eq!(document.children[0].as_element().unwrap().name, "open");
+        assert!(matches!(events[2], KmlEvent::Other(Kml::Style(_))));
+        assert!(matches!(
+            &events[3],
+            KmlEvent::StartFolder(Folder { name: Some(name), .. }) if name == "Folder"
+        ));
+        assert!(matches!(&events[4], KmlEvent::Placemark(p) if p.name.as_deref() == Some("1")));
+        assert_eq!(events[5], KmlEvent::End);
+        assert!(matches!(&events[6], KmlEvent::StartFolder(f) if f.name.is_none()));
+        assert!(events[7..].iter().all(|e| *e == KmlEvent::End));
+
+        let mut reader = KmlReader::<_, f64>::from_string("<Document><Folder>");
+        assert!(reader.next_event().unwrap().is_some());
+        assert!(reader.next_event().unwrap().is_some());
+        assert!(reader.next_event().is_err());
+    }
+
+    #[test]
+    fn test_read_empty_elements() {
+        let kml_str = r#"<Document>
+<Style id="s">
+  <IconStyle><hotSpot x="0.5" y="0.5" xunits="fraction" yunits="fraction"/><Icon/><scale>2</scale></IconStyle>
+  <BalloonStyle><text>a<br/>b</text></BalloonStyle>
+  <LineStyle><foo>x</foo><bar/><width>2</width></LineStyle>
+</Style>
+<Folder/>
+<Placemark>
+  <name/>
+  <LineString><tessellate/><coordinates>1,1 2,2</coordinates><extrude>1</extrude></LineString>
+  <Link><href>a.kml</href><viewFormat/><httpQuery>q</httpQuery></Link>
+</Placemark>
+</Document>"#;
+        let doc: Kml = kml_str.parse().unwrap();
+        let Kml::Document(Document { elements, .. }) = doc else {
+            panic!("expected Document, got {:?}", doc);
+        };
+        assert_eq!(elements.len(), 3);
+        let Kml::Style(style) = &elements[0] else {
+            panic!("expected Style, got {:?}", elements[0]);
+        };
+        let icon_style = style.icon.as_ref().unwrap();
+        assert_eq!(icon_style.hot_spot.as_ref().unwrap().x, 0.5);
+        assert_eq!(icon_style.icon, Some(Icon::default()));
+        assert_eq!(icon_style.scale, 2.);
+        assert_eq!(
+            style.balloon.as_ref().unwrap().text.as_deref(),
+            Some("a<br/>b")
+        );
+        assert_eq!(style.line.as_ref().unwrap().width, 2.);
+        assert_eq!(
+            elements[1],
+            Kml::Folder(Folder {
+                elements: Vec::new(),
+                ..Default::default()
+            })
+        );
+        let Kml::Placemark(placemark) = &elements[2] else {
+            panic!("expected Placemark, got {:?}", elements[2]);
+        };
+        assert_eq!(placemark.name.as_deref(), Some(""));
+        let Some(Geometry::LineString(line_string)) = &placemark.geometry else {
+            panic!("expected LineString, got {:?}", placemark.geometry);
+        };
+        assert_eq!(line_string.extrude, Some(true));
+        assert_eq!(line_string.coords.len(), 2);
+    }
+
+    #[test]
+    fn test_read_unknown_style_children() {
+        let kml_str = r#"<Document xmlns:gx="http://www.google.com/kml/ext/2.2" xmlns:x="http://example.com/x">
+<Style id="a">
+  <foo>x</foo>
+  <LineStyle><color>ff0000ff</color><gx:labelVisibility>1</gx:labelVisibility><width>4</width></LineStyle>
+  <PolyStyle><x:color>00000000</x:color><color>ff00ff00</color><fill>0</fill></PolyStyle>
+</Style>
+<Placemark/>
+</Document>"#;
+        let doc: Kml = kml_str.parse().unwrap();
+        let Kml::Document(Document { elements, .. }) = doc else {
+            panic!("expected Document, got {:?}", doc);
+        };
+        assert_eq!(elements.len(), 2);
+        let Kml::Style(style) = &elements[0] else {
+            panic!("expected Style, got {:?}", elements[0]);
+        };
+        let line_style = style.line.as_ref().unwrap();
+        assert_eq!(line_style.color, "ff0000ff");
+        assert_eq!(line_style.width, 4.);
+        let poly_style = style.poly.as_ref().unwrap();
+        assert_eq!(poly_style.color, "ff00ff00");
+        assert!(!poly_style.fill);
+        assert!(matches!(elements[1], Kml::Placemark(_)));
+
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        reader.config_mut().strict = true;
+        assert!(matches!(
+            reader.read().unwrap_err().without_location(),
+            Error::UnexpectedElement(name) if name == "foo"
+        ));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let kml_str = r#"<document>
+<style><iconstyle><scale>2</scale></iconstyle></style>
+<placemark><NAME>1</NAME><point><COORDINATES>1,2</COORDINATES></point></placemark>
+</document>"#;
+        let config = KmlReaderConfig::default().case_insensitive(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        let Kml::Document(Document { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Document");
+        };
+        let Kml::Style(style) = &elements[0] else {
+            panic!("expected Style, got {:?}", elements[0]);
+        };
+        assert_eq!(style.icon.as_ref().unwrap().scale, 2.);
+        let Kml::Placemark(placemark) = &elements[1] else {
+            panic!("expected Placemark, got {:?}", elements[1]);
+        };
+        assert_eq!(placemark.name.as_deref(), Some("1"));
+        assert_eq!(
+            placemark.geometry,
+            Some(Geometry::Point(Point::new(1., 2., None)))
+        );
+
+        let doc: Kml = kml_str.parse().unwrap();
+        assert!(matches!(doc, Kml::Element(e) if e.name == "document"));
+    }
+
+    #[test]
+    fn test_read_split_text() {
+        let kml_str = r#"<Placemark>
+  <name>A &amp; <![CDATA[<B>]]> <!-- comment -->C</name>
+  <LineString><coordinates>1,1 <!-- comment -->
+    2,2</coordinates><altitudeMode><!-- comment -->absolute</altitudeMode></LineString>
+</Placemark>"#;
+        let placemark: Kml = kml_str.parse().unwrap();
+        let Kml::Placemark(placemark) = placemark else {
+            panic!("expected Placemark, got {:?}", placemark);
+        };
+        assert_eq!(placemark.name.as_deref(), Some("A & <B> C"));
+        let Some(Geometry::LineString(line_string)) = placemark.geometry else {
+            panic!("expected LineString, got {:?}", placemark.geometry);
+        };
+        assert_eq!(
+            line_string.coords,
+            vec![Coord::new(1., 1., None), Coord::new(2., 2., None)]
+        );
+        assert_eq!(
+            line_string.altitude_mode,
+            Some(types::AltitudeMode::Absolute)
+        );
+    }
+
+    #[test]
+    fn test_skip_coordinates() {
+        let kml_str = r#"<Folder>
+<Placemark><name>Line</name><LineString><coordinates>1,1 2,2</coordinates></LineString></Placemark>
+<Placemark><name>Point</name><Point><coordinates>1,1</coordinates></Point></Placemark>
+<Placemark><gx:Track><when>2010-05-28T02:02:09Z</when><gx:coord>1 1 0</gx:coord></gx:Track></Placemark>
+</Folder>"#;
+        let config = KmlReaderConfig::default().skip_coordinates(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        let geometries = reader
+            .features()
+            .map(|f| match f.unwrap() {
+                Kml::Placemark(p) => p.geometry,
+                f => panic!("expected Placemark, got {:?}", f),
+            })
+            .collect::<Vec<_>>();
+        assert!(matches!(&geometries[0], Some(Geometry::LineString(l)) if l.coords.is_empty()));
+        assert_eq!(geometries[1], None);
+        assert!(matches!(
+            &geometries[2],
+            Some(Geometry::Track(t)) if t.coords.is_empty() && t.whens.len() == 1
+        ));
+        assert!(reader.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_filter() {
+        let kml_str = r#"<Document>
+<name>Doc</name>
+<Style id="s"/>
+<Folder><name>Roads</name>
+  <Placemark id="a"><name>A</name></Placemark>
+  <Folder><name>Highways</name><Placemark id="b"><name>B</name></Placemark></Folder>
+</Folder>
+<Placemark id="c"><name>C</name></Placemark>
+</Document>"#;
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&paths);
+        let config = KmlReaderConfig::default().filter(move |e| {
+            if e.name == "Placemark" {
+                seen.lock().unwrap().push(e.path.join("/"));
+            }
+            e.name != "Style" && e.attrs.get("id").map(String::as_str) != Some("b")
+        });
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        let Kml::Document(Document { name, elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Document");
+        };
+        assert_eq!(name.as_deref(), Some("Doc"));
+        assert_eq!(elements.len(), 2);
+        let Kml::Folder(Folder {
+            elements: roads, ..
+        }) = &elements[0]
+        else {
+            panic!("expected Folder, got {:?}", elements[0]);
+        };
+        assert!(matches!(&roads[0], Kml::Placemark(p) if p.name.as_deref() == Some("A")));
+        assert!(matches!(
+            &roads[1],
+            Kml::Folder(Folder { name: Some(name), elements, .. })
+                if name == "Highways" && elements.is_empty()
+        ));
+        assert_eq!(
+            *paths.lock().unwrap(),
+            vec!["Doc/Roads", "Doc/Roads/Highways", "Doc"]
+        );
+    }
+
+    #[test]
+    fn test_read_all() {
+        let kml_str = "<Point><coordinates>1,1</coordinates></Point><Style id=\"s\"></Style>";
+        let elements = KmlReader::<_, f64>::from_string(kml_str)
+            .read_all()
+            .unwrap();
+        assert_eq!(elements.len(), 2);
+        assert!(matches!(elements[0], Kml::Point(_)));
+        assert!(matches!(elements[1], Kml::Style(_)));
+
+        let elements = KmlReader::<_, f64>::from_string("<!-- empty -->")
+            .read_all()
+            .unwrap();
+        assert!(elements.is_empty());
+    }
+
+    #[test]
+    fn test_doctype_and_processing_instructions() {
+        let kml_str = r#"<?xml version="1.0"?>
+<!DOCTYPE kml [<!ENTITY xxe SYSTEM "file:///etc/passwd">]>
+<?xml-stylesheet href="style.xsl"?>
+<Placemark><?pi?><name>&xxe;</name><Point><?pi?><coordinates>1,1</coordinates></Point></Placemark>"#;
+        let placemark = match kml_str.parse::<Kml>().unwrap() {
+            Kml::Placemark(p) => p,
+            k => panic!("expected Placemark, got {:?}", k),
+        };
+        assert_eq!(placemark.name.as_deref(), Some("&xxe;"));
+        assert!(matches!(placemark.geometry, Some(Geometry::Point(_))));
+
+        let config = KmlReaderConfig::default().reject_doctype(true);
+        let err = KmlReader::<_, f64>::from_string_with_config(kml_str, config)
+            .read()
+            .unwrap_err();
+        assert!(matches!(err.without_location(), Error::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_read_concatenated_documents() {
+        let kml_str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2"><Placemark><name>1</name></Placemark></kml>
+<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.3"><Placemark><name>2</name></Placemark></kml>
+"#;
+        let mut reader = KmlReader::<_, f64>::from_string(kml_str);
+        for version in [KmlVersion::V22, KmlVersion::V23] {
+            let doc = match reader.read().unwrap() {
+                Kml::KmlDocument(d) => d,
+                k => panic!("expected KmlDocument, got {:?}", k),
+            };
+            assert_eq!(doc.version, version);
+            assert_eq!(doc.elements.len(), 1);
+        }
+        assert!(matches!(
+            reader.read().unwrap_err().without_location(),
+            Error::NoElements
+        ));
+    }
 
-        let mut attrs = HashMap::new();
-        attrs.insert("id".to_string(), "Some ID".to_string());
+    #[test]
+    fn test_from_read() {
+        let kml_bytes: &[u8] = b"<Point><coordinates>1,1</coordinates></Point>";
+        let kml = KmlReader::<_, f64>::from_read(io::Cursor::new(kml_bytes))
+            .read()
+            .unwrap();
+        assert!(matches!(kml, Kml::Point(_)));
+    }
 
-        let a: Kml = kml_str.parse().unwrap();
-        assert_eq!(
-            a,
-            Kml::Alias(Alias {
-                target_href: Some("../images/foo.jpg".to_string()),
-                source_href: Some("in-geometry-file/foo.jpg".to_string()),
-                attrs,
+    #[test]
+    fn test_skip_invalid_features() {
+        let kml_str = r#"<Folder>
+<Placemark><name>1</name><Point><coordinates>1,1</coordinates></Point></Placemark>
+<Placemark><name>2</name><MultiGeometry><Point><coordinates>a,1</coordinates></Point><Point><coordinates>2,2</coordinates></Point></MultiGeometry></Placemark>
+<Placemark><name>3</name><Polygon><outerBoundaryIs></outerBoundaryIs></Polygon></Placemark>
+<Placemark><name>4</name><Point><coordinates>4,4</coordinates></Point></Placemark>
+</Folder>"#;
+        let config = KmlReaderConfig::default().skip_invalid_features(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config.clone());
+        let Kml::Folder(Folder { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Folder");
+        };
+        let names = elements
+            .iter()
+            .map(|e| match e {
+                Kml::Placemark(p) => p.name.as_deref().unwrap(),
+                e => panic!("expected Placemark, got {:?}", e),
             })
-        );
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["1", "4"]);
+        let warnings = reader.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(warnings[0].error, Error::NumParse(_)));
+        assert_eq!(warnings[0].position.line, 3);
+        assert!(matches!(warnings[1].error, Error::InvalidGeometry(_)));
+
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        assert_eq!(reader.features().filter(|f| f.is_ok()).count(), 2);
+        assert!(KmlReader::<_, f64>::from_string(kml_str).read().is_err());
     }
 
     #[test]
-    fn test_read_schema_data() {
-        let kml_str = r##"<SchemaData schemaUrl="#TrailHeadTypeId">
-            <SimpleData name="TrailHeadName" anyAttribute="anySimpleType">Pi in the sky</SimpleData>
-            <SimpleData name="TrailLength" anyAttribute="anySimpleType">3.14159</SimpleData>
-            <SimpleArrayData name="cadence" anyAttribute="anySimpleType">
-                <value>86</value>
-                <!-- comment -->
-                <value>113</value>
-                <value>113</value>
-            </SimpleArrayData>
-            <SimpleArrayData name="heartrate">
-                <value>181</value>
-            </SimpleArrayData>
-        </SchemaData>"##;
+    fn test_register_parser() {
+        #[derive(Debug, PartialEq)]
+        struct Rating(u8);
 
-        let a: Kml = kml_str.parse().unwrap();
+        impl KmlExtension for Rating {
+            fn to_element(&self) -> Element {
+                Element {
+                    name: "Rating".to_string(),
+                    prefix: Some("v".to_string()),
+                    namespace: Some("http://example.com/v".to_string()),
+                    content: Some(self.0.to_string()),
+                    ..Default::default()
+                }
+            }
+        }
+
+        let kml_str = r#"<Document xmlns:v="http://example.com/v">
+<v:Rating>4</v:Rating>
+<v:Other>x</v:Other>
+</Document>"#;
+        let config = KmlReaderConfig::default().register_parser("Rating", |e: Element| {
+            e.content
+                .as_deref()
+                .unwrap_or_default()
+                .parse()
+                .map(Rating)
+                .map_err(|_| Error::InvalidInput(format!("{:?}", e.content)))
+        });
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config.clone());
+        let Kml::Document(Document { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Document");
+        };
+        let Kml::Extension(extension) = &elements[0] else {
+            panic!("expected Extension, got {:?}", elements[0]);
+        };
+        assert_eq!(extension.downcast_ref::<Rating>(), Some(&Rating(4)));
         assert_eq!(
-            a,
-            Kml::SchemaData(SchemaData {
-                data: vec![
-                    SimpleData {
-                        name: "TrailHeadName".to_string(),
-                        value: "Pi in the sky".to_string(),
-                        attrs: [("anyAttribute".to_string(), "anySimpleType".to_string())]
-                            .iter()
-                            .cloned()
-                            .collect()
-                    },
-                    SimpleData {
-                        name: "TrailLength".to_string(),
-                        value: "3.14159".to_string(),
-                        attrs: [("anyAttribute".to_string(), "anySimpleType".to_string())]
-                            .iter()
-                            .cloned()
-                            .collect()
-                    },
-                ],
-                arrays: vec![
-                    SimpleArrayData {
-                        name: "cadence".to_string(),
-                        values: vec!["86".to_string(), "113".to_string(), "113".to_string()],
-                        attrs: [("anyAttribute".to_string(), "anySimpleType".to_string())]
-                            .iter()
-                            .cloned()
-                            .collect()
-                    },
-                    SimpleArrayData {
-                        name: "heartrate".to_string(),
-                        values: vec!["181".to_string()],
-                        ..Default::default()
-                    },
-                ],
-                attrs: [("schemaUrl".to_string(), "#TrailHeadTypeId".to_string())]
-                    .iter()
-                    .cloned()
-                    .collect(),
-            })
+            elements[0].to_string(),
+            r#"<v:Rating xmlns:v="http://example.com/v">4</v:Rating>"#
         );
+        assert!(matches!(&elements[1], Kml::Element(e) if e.name == "Other"));
+
+        let mut reader =
+            KmlReader::<_, f64>::from_string_with_config(kml_str, config.clone().strict(true));
+        assert!(matches!(
+            reader.read(),
+            Err(e) if matches!(e.without_location(), Error::UnexpectedElement(_))
+        ));
+
+        let invalid = KmlReader::<_, f64>::from_string_with_config(
+            "<Rating xmlns=\"http://example.com/v\">a</Rating>",
+            config,
+        )
+        .read();
+        assert!(matches!(
+            invalid,
+            Err(e) if matches!(e.without_location(), Error::InvalidInput(_))
+        ));
     }
 
     #[test]
-    fn test_parse_scale() {
-        let kml_str = r#"<Scale>
-            <x>1.2</x>
-            <y>3.5</y>
-            <!-- comment -->
-            <z>2.5</z>
-        </Scale>"#;
-        let s: Kml = kml_str.parse().unwrap();
+    fn test_into_inner_and_reset() {
+        let placemarks = "<Placemark><name>p</name></Placemark>".repeat(1000);
+        let input = format!("<kml><Folder>{placemarks}</Folder></kml><kml><Folder></Folder></kml>");
+        let mut reader = KmlReader::<_, f64>::from_string(&input);
+        assert!(reader.read().is_ok());
+        // The buffer only holds one event at a time
+        assert!(reader.buf.capacity() < 1024);
         assert_eq!(
-            s,
-            Kml::Scale(Scale {
-                x: 1.2,
-                y: 3.5,
-                z: 2.5,
-                ..Default::default()
-            })
+            reader.get_ref(),
+            Some(&"<kml><Folder></Folder></kml>".as_bytes())
         );
+
+        reader.config_mut().strict = true;
+        reader.reset("<Folder><Unknown/></Folder>".as_bytes());
+        assert!(reader.read().is_err());
+        assert_eq!(reader.into_inner(), Some("</Folder>".as_bytes()));
     }
 
     #[test]
-    fn test_parse_orientation() {
-        let kml_str = r#"<Orientation>
-            <heading>45.01</heading>
-            <tilt>-10.02</tilt>
-            <roll>0.0</roll>
-        </Orientation>"#;
-        let l: Kml = kml_str.parse().unwrap();
-        assert_eq!(
-            l,
-            Kml::Orientation(Orientation {
-                roll: 0.,
-                tilt: -10.02,
-                heading: 45.01,
-                ..Default::default()
-            })
-        );
+    fn test_read_document() {
+        let kml_str = r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document></Document></kml>"#;
+        let doc = KmlReader::<_, f64>::from_string(kml_str)
+            .read_document()
+            .unwrap();
+        assert_eq!(doc.version, KmlVersion::V22);
+        assert!(matches!(doc.elements.as_slice(), [Kml::Document(_)]));
+
+        let doc = KmlReader::<_, f64>::from_string("<Document></Document>")
+            .read_document()
+            .unwrap();
+        assert_eq!(doc.version, KmlVersion::Unknown);
+        assert!(matches!(doc.elements.as_slice(), [Kml::Document(_)]));
+
+        let doc = KmlReader::<_, f64>::from_string("<Placemark/><Placemark/>")
+            .read_document()
+            .unwrap();
+        assert_eq!(doc.elements.len(), 2);
+
+        assert!(matches!(
+            KmlReader::<_, f64>::from_string("").read_document(),
+            Err(Error::NoElements)
+        ));
     }
 
     #[test]
-    fn test_parse_line_string() {
-        let kml_str = r#"<LineString>
-            <coordinates>1,1 2,1 3,1</coordinates>
-            <altitudeMode>relativeToGround</altitudeMode>
-        </LineString>"#;
-        let l: Kml = kml_str.parse().unwrap();
-        assert_eq!(
-            l,
-            Kml::LineString(LineString {
-                coords: vec![
-                    Coord {
-                        x: 1.,
-                        y: 1.,
-                        z: None
-                    },
-                    Coord {
-                        x: 2.,
-                        y: 1.,
-                        z: None
-                    },
-                    Coord {
-                        x: 3.,
-                        y: 1.,
-                        z: None
-                    }
-                ],
-                altitude_mode: types::AltitudeMode::RelativeToGround,
-                ..Default::default()
-            })
-        );
+    fn test_coord_transform() {
+        let kml_str = r#"<Folder>
+<LineString><coordinates>1.26,2.71 3.14,4</coordinates></LineString>
+<gx:Track><gx:coord>5.5 6.5 7</gx:coord></gx:Track>
+</Folder>"#;
+        let config = KmlReaderConfig::default()
+            .coord_transform(|c| Coord::new(c.x.round(), c.y.round(), c.z));
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config.clone());
+        let Kml::Folder(Folder { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Folder");
+        };
+        assert!(matches!(
+            &elements[0],
+            Kml::LineString(l) if l.coords == vec![Coord::new(1., 3., None), Coord::new(3., 4., None)]
+        ));
+        assert!(matches!(
+            &elements[1],
+            Kml::Track(t) if t.coords == vec![Coord::new(6., 7., Some(7.))]
+        ));
+
+        // The same config works for readers of other coordinate types
+        let mut reader = KmlReader::<_, f32>::from_string_with_config(kml_str, config);
+        let Kml::Folder(Folder { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Folder");
+        };
+        assert!(matches!(
+            &elements[1],
+            Kml::Track(t) if t.coords == vec![Coord::new(6f32, 7., Some(7.))]
+        ));
     }
 
     #[test]
-    fn test_parse_polygon() {
-        let poly_str = r#"<Polygon>
-        <outerBoundaryIs>
-          <LinearRing>
-            <tessellate>1</tessellate>
-            <coordinates>
-              -1,2,0
-              -1.5,3,0
-              -1.5,2,0
-              -1,2,0
-            </coordinates>
-          </LinearRing>
-        </outerBoundaryIs>
-      </Polygon>"#;
-        let mut r = KmlReader::from_string(poly_str);
-
-        let p: Kml = r.read().unwrap();
+    fn test_read_comments() {
+        let kml_str = r#"<Folder><!-- Generated -->
+<Placemark><!--a--><ExtendedData><!--b--></ExtendedData></Placemark>
+</Folder>"#;
+        let config = KmlReaderConfig::default().comments(true);
+        let mut reader = KmlReader::<_, f64>::from_string_with_config(kml_str, config);
+        let Kml::Folder(Folder { elements, .. }) = reader.read().unwrap() else {
+            panic!("expected Folder");
+        };
+        assert_eq!(elements[0], Kml::Comment(" Generated ".to_string()));
+        let Kml::Placemark(placemark) = &elements[1] else {
+            panic!("expected Placemark");
+        };
         assert_eq!(
-            p,
-            Kml::Polygon(Polygon {
-                outer: LinearRing {
-                    coords: vec![
-                        Coord {
-                            x: -1.,
-                            y: 2.,
-                            z: Some(0.)
-                        },
-                        Coord {
-                            x: -1.5,
-                            y: 3.,
-                            z: Some(0.)
-                        },
-                        Coord {
-                            x: -1.5,
-                            y: 2.,
-                            z: Some(0.)
-                        },
-                        Coord {
-                            x: -1.,
-                            y: 2.,
-                            z: Some(0.)
-                        },
-                    ],
-                    tessellate: true,
+            placemark.children,
+            vec![
+                Node::Comment("a".to_string()),
+                Node::Element(Element {
+                    name: "ExtendedData".to_string(),
+                    children: vec![Node::Comment("b".to_string())],
                     ..Default::default()
-                },
-                inner: vec![],
-                ..Default::default()
-            })
+                })
+            ]
         );
+
+        let Kml::Folder(Folder { elements, .. }) = kml_str.parse::<Kml>().unwrap() else {
+            panic!("expected Folder");
+        };
+        assert!(matches!(&elements[..], [Kml::Placemark(p)] if p.children.len() == 1));
     }
 
     #[test]
-    fn test_parse_style_map() {
-        let kml_str = r#"
-        <StyleMap id="id" test="test">
-        </StyleMap>
-        "#;
-        let s: Kml = kml_str.parse().unwrap();
-        assert_eq!(
-            s,
-            Kml::StyleMap(StyleMap {
-                id: Some("id".to_string()),
-                attrs: HashMap::from([("test".to_string(), "test".to_string())]),
-                ..Default::default()
-            })
-        );
+    fn test_comments_round_trip() {
+        let kml_str = "<Placemark><name>A</name><!--visibility--><visibility>0</visibility>\
+            <!--data--><ExtendedData><Data name=\"a\"><value>1</value></Data></ExtendedData>\
+            <!--point--><Point><coordinates>1,1</coordinates></Point></Placemark>";
+        let config = KmlReaderConfig::default().comments(true);
+        let kml = KmlReader::<_, f64>::from_string_with_config(kml_str, config)
+            .read()
+            .unwrap();
+        assert_eq!(kml.to_string(), kml_str);
+
+        // Comments the writer can't write as read are broken up rather than rejected
+        let config = KmlReaderConfig::default().comments(true);
+        let kml = KmlReader::<_, f64>::from_string_with_config("<!-- a -- b -->", config)
+            .read()
+            .unwrap();
+        assert_eq!(kml, Kml::Comment(" a -- b ".to_string()));
+        assert_eq!(kml.to_string(), "<!-- a - - b -->");
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        for kml_str in [
+            "<Placemark><name>1</name>",
+            "<Point><coordinates>1,1</coordinates>",
+            "<gx:Track><when>2010-05-28T02:02:09Z</when>",
+            "<SchemaData><SimpleData name=\"a\">1</SimpleData>",
+            "<Style><IconStyle>",
+            "<Document><Folder>",
+            "<StyleMap><Pair>",
+            "<Link>",
+            "<Icon>",
+            "<ResourceMap><Alias>",
+            "<Scale>",
+            "<Location>",
+            "<Orientation>",
+            "<LineStyle>",
+            "<foo><bar>",
+            "<Polygon><outerBoundaryIs>",
+            "<MultiGeometry>",
+            "<gx:Tour><gx:Playlist>",
+            "<SchemaData><gx:SimpleArrayData name=\"a\">",
+        ] {
+            let err = kml_str.parse::<Kml>().unwrap_err();
+            assert!(
+                matches!(err.without_location(), Error::InvalidXmlEvent(e) if e == "Eof"),
+                "{}: {:?}",
+                kml_str,
+                err
+            );
+        }
     }
 
     #[test]
@@ -1596,6 +5050,37 @@ mod tests {
         }))
     }
 
+    #[test]
+    fn test_read_unnamespaced_kml_round_trip() {
+        for kml_str in [
+            "<kml><Placemark><name>a</name><Point><coordinates>1,2</coordinates></Point></Placemark></kml>",
+            r#"<kml xmlns:kml="http://earth.google.com/kml/2.2"><Placemark><ExtendedData/></Placemark></kml>"#,
+            r#"<kml:kml xmlns:kml="http://www.opengis.net/kml/2.3"><kml:Placemark/></kml:kml>"#,
+        ] {
+            let kml: Kml = kml_str.parse().unwrap();
+            let Kml::KmlDocument(doc) = &kml else {
+                panic!("expected KmlDocument, got {:?}", kml);
+            };
+            let ns = doc.version.namespace();
+            assert_eq!(doc.attrs.get("xmlns").map(String::as_str), ns);
+            let Kml::Placemark(placemark) = &doc.elements[0] else {
+                panic!("expected Placemark, got {:?}", doc.elements[0]);
+            };
+            assert_eq!(placemark.namespace.as_deref(), ns);
+            assert_eq!(kml.to_string().parse::<Kml>().unwrap(), kml, "{kml_str}");
+        }
+
+        // Elements outside a kml root keep having no namespace
+        let kml: Kml = "<Placemark/>".parse().unwrap();
+        assert!(matches!(
+            kml,
+            Kml::Placemark(Placemark {
+                namespace: None,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_read_str_lossy() {
         let kml_str = r#"
@@ -1641,13 +5126,7 @@ mod tests {
         let doc = doc.unwrap();
 
         assert_eq!(doc.elements.len(), 2);
-        assert!(doc.elements.iter().all(|e| matches!(
-            e,
-            Kml::Folder {
-                attrs: _,
-                elements: _
-            }
-        )));
+        assert!(doc.elements.iter().all(|e| matches!(e, Kml::Folder(_))));
     }
 
     #[test]
@@ -1670,7 +5149,7 @@ mod tests {
 
         let elements: Option<Vec<Kml<_>>> = match f {
             Kml::KmlDocument(d) => match &d.elements[0] {
-                Kml::Document { attrs: _, elements } => Some(elements.to_vec()),
+                Kml::Document(Document { elements, .. }) => Some(elements.to_vec()),
                 _ => None,
             },
             _ => None,
@@ -1678,13 +5157,41 @@ mod tests {
 
         let elements = elements.unwrap();
         assert_eq!(elements.len(), 2);
-        assert!(elements.iter().all(|e| matches!(
-            e,
-            Kml::Folder {
-                attrs: _,
-                elements: _
-            }
-        )));
+        assert!(elements.iter().all(|e| matches!(e, Kml::Folder(_))));
+    }
+
+    #[test]
+    fn test_read_container_fields() {
+        let kml_str = r#"<Document xmlns:atom="http://www.w3.org/2005/Atom">
+<name>Trails</name>
+<visibility>0</visibility>
+<atom:author><atom:name>Parks</atom:name></atom:author>
+<description><![CDATA[<b>All</b> trails]]></description>
+<styleUrl>#doc</styleUrl>
+<Style id="s"/>
+<ExtendedData><Data name="region"><value>North</value></Data></ExtendedData>
+<Folder><name>Closed</name><open>1</open></Folder>
+</Document>"#;
+        let Kml::Document(document) = kml_str.parse::<Kml>().unwrap() else {
+            panic!("expected Document");
+        };
+        assert_eq!(document.name.as_deref(), Some("Trails"));
+        assert_eq!(document.description.as_deref(), Some("<b>All</b> trails"));
+        assert_eq!(document.style_url.as_deref(), Some("#doc"));
+        let children: Vec<_> = document
+            .children
+            .iter()
+            .filter_map(Node::as_element)
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(children, vec!["visibility", "author", "ExtendedData"]);
+        assert!(matches!(&document.elements[0], Kml::Style(_)));
+        assert!(matches!(
+            &document.elements[1],
+            Kml::Folder(Folder { name: Some(name), children, elements, .. })
+                if name == "Closed" && children.len() == 1 && elements.is_empty()
+        ));
+        assert_eq!(document.elements.len(), 2);
     }
 
     #[test]
@@ -1706,4 +5213,15 @@ mod tests {
             Kml::KmlDocument(_)
         ));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let kml_str = include_str!("../tests/fixtures/sample.kml");
+        let kml = Kml::<f64>::from_str(kml_str).unwrap();
+
+        let json = serde_json::to_string(&kml).unwrap();
+        let parsed: Kml = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, kml);
+    }
 }
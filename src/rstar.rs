@@ -0,0 +1,131 @@
+//! Module for indexing placemarks in an [`rstar`](https://docs.rs/rstar) R-tree
+//!
+//! Each placemark with coordinates is stored by the bounding box of its geometry, along with its
+//! index in the features it was built from, so nearest neighbor and bounding box queries can find
+//! placemarks in large documents without scanning all of them. Distances are measured to the
+//! bounding box, which is exact for points, in the units of the coordinates.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::Kml;
+//! use rstar::AABB;
+//!
+//! let kml: Kml = r#"
+//! <Document>
+//!   <Placemark><name>a</name><Point><coordinates>0,0</coordinates></Point></Placemark>
+//!   <Placemark><name>b</name><Point><coordinates>10,10</coordinates></Point></Placemark>
+//!   <Placemark>
+//!     <name>c</name>
+//!     <LineString><coordinates>20,0 30,5</coordinates></LineString>
+//!   </Placemark>
+//! </Document>"#
+//!     .parse()
+//!     .unwrap();
+//! let tree = kml::rstar::build_rtree(kml.features());
+//!
+//! let nearest = tree.nearest_neighbor(&[9., 8.]).unwrap();
+//! assert_eq!(nearest.placemark.name.as_deref(), Some("b"));
+//! assert_eq!(nearest.index, 1);
+//!
+//! let in_box = tree.locate_in_envelope_intersecting(&AABB::from_corners([15., -1.], [25., 1.]));
+//! assert_eq!(in_box.count(), 1);
+//! ```
+use rstar::{PointDistance, RTree, RTreeNum, RTreeObject, AABB};
+
+use crate::types::{CoordType, Feature, Placemark};
+
+/// Placemark stored in an R-tree by the bounding box of its geometry
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedPlacemark<'a, T: CoordType + RTreeNum = f64> {
+    /// Position of the placemark in the features the tree was built from
+    pub index: usize,
+    pub placemark: &'a Placemark<T>,
+    /// Bounding box of the placemark's geometry as `[x, y]` points
+    pub envelope: AABB<[T; 2]>,
+}
+
+impl<T> RTreeObject for IndexedPlacemark<'_, T>
+where
+    T: CoordType + RTreeNum,
+{
+    type Envelope = AABB<[T; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+/// Squared distance to the placemark's bounding box, which is zero inside of it
+impl<T> PointDistance for IndexedPlacemark<'_, T>
+where
+    T: CoordType + RTreeNum,
+{
+    fn distance_2(&self, point: &[T; 2]) -> T {
+        self.envelope.distance_2(point)
+    }
+}
+
+/// Builds an R-tree over features, such as those from
+/// [`Kml::features`](../enum.Kml.html#method.features), skipping those without coordinates
+pub fn build_rtree<'a, T, I>(features: I) -> RTree<IndexedPlacemark<'a, T>>
+where
+    T: CoordType + RTreeNum,
+    I: IntoIterator<Item = Feature<'a, T>>,
+{
+    let placemarks = features
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, feature)| {
+            let coords: Vec<[T; 2]> = feature.geometry?.coords().map(|c| [c.x, c.y]).collect();
+            if coords.is_empty() {
+                return None;
+            }
+            Some(IndexedPlacemark {
+                index,
+                placemark: feature.placemark,
+                envelope: AABB::from_points(&coords),
+            })
+        })
+        .collect();
+    RTree::bulk_load(placemarks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kml;
+
+    #[test]
+    fn test_build_rtree() {
+        let kml: Kml = r#"<Document>
+            <Placemark><name>a</name><Point><coordinates>0,0</coordinates></Point></Placemark>
+            <Placemark><name>empty</name></Placemark>
+            <Folder>
+              <Placemark>
+                <name>b</name>
+                <Polygon><outerBoundaryIs><LinearRing>
+                  <coordinates>10,10 20,10 20,20 10,10</coordinates>
+                </LinearRing></outerBoundaryIs></Polygon>
+              </Placemark>
+            </Folder>
+          </Document>"#
+            .parse()
+            .unwrap();
+        let tree = build_rtree(kml.features());
+        assert_eq!(tree.size(), 2);
+
+        let b = tree.nearest_neighbor(&[15., 12.]).unwrap();
+        assert_eq!(b.index, 2);
+        assert_eq!(b.placemark.name.as_deref(), Some("b"));
+        assert_eq!(b.envelope, AABB::from_corners([10., 10.], [20., 20.]));
+        assert_eq!(b.distance_2(&[15., 12.]), 0.);
+        assert_eq!(b.distance_2(&[23., 24.]), 25.);
+
+        let names: Vec<_> = tree
+            .locate_in_envelope_intersecting(&AABB::from_corners([-1., -1.], [1., 1.]))
+            .map(|p| p.placemark.name.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("a")]);
+    }
+}
@@ -0,0 +1,933 @@
+//! Module for converting between placemarks and [Esri shapefiles](https://www.esri.com/content/dam/esrisites/sitecore-archive/Files/Pdfs/library/whitepapers/pdfs/shapefile.pdf)
+//!
+//! Reading converts each shape record into a `Placemark`, with the matching `.dbf` record as
+//! `SchemaData` referencing a `Schema` declared at the start of the document. Writing does the
+//! reverse, with placemark names in a `name` column and `ExtendedData` properties as text columns.
+//! The files themselves are read and written with the [`shapefile`](https://docs.rs/shapefile)
+//! crate.
+//!
+//! Coordinates are read and written as they are, so shapefiles should be in longitude and
+//! latitude (WGS 84) to be valid KML. Measures (`M` values) are ignored, and `MultiPatch` shapes
+//! aren't supported. Text in `.dbf` files is written as UTF-8.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::Cursor;
+//!
+//! use kml::shapefile::{read_shapefile, write_shapefile};
+//! use kml::types::{Document, Geometry, Placemark, Point};
+//! use kml::Kml;
+//!
+//! let placemark = Placemark {
+//!     name: Some("Trailhead".to_string()),
+//!     geometry: Some(Geometry::Point(Point::new(1., 2., None))),
+//!     ..Default::default()
+//! };
+//! let (mut shp, mut shx, mut dbf) = (Cursor::new(Vec::new()), Cursor::new(Vec::new()), Cursor::new(Vec::new()));
+//! write_shapefile([&placemark], &mut shp, &mut shx, &mut dbf).unwrap();
+//! shp.set_position(0);
+//! dbf.set_position(0);
+//!
+//! let kml: Kml = read_shapefile(shp, dbf, "trails", None).unwrap();
+//! let Kml::Document(Document { elements, .. }) = kml else {
+//!     unreachable!()
+//! };
+//! let Kml::Placemark(read) = &elements[1] else {
+//!     unreachable!()
+//! };
+//! assert_eq!(read.name.as_deref(), Some("Trailhead"));
+//! assert_eq!(read.geometry, placemark.geometry);
+//! ```
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Read, Seek, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use ::shapefile::dbase::encoding::DynEncoding;
+use ::shapefile::dbase::{self, FieldIndex, FieldName, FieldType, FieldValue, TableWriterBuilder};
+use ::shapefile::record::polygon::GenericPolygon;
+use ::shapefile::record::polyline::GenericPolyline;
+use ::shapefile::record::traits::{GrowablePoint, HasXY, ShrinkablePoint};
+use ::shapefile::record::EsriShape;
+use ::shapefile::{
+    Multipoint, MultipointZ, PolygonRing, Shape, ShapeReader, ShapeType, ShapeWriter, NO_DATA,
+};
+
+use crate::errors::Error;
+use crate::types::{
+    Coord, CoordType, Document, Element, Geometry, LineString, LinearRing, MultiGeometry, Node,
+    Placemark, Point, Polygon,
+};
+use crate::Kml;
+
+/// Maximum length of a `.dbf` field name
+const FIELD_NAME_LEN: usize = 10;
+/// Maximum length of a `.dbf` character field
+const FIELD_LEN: usize = 254;
+
+/// Reads the shapes in a `.shp` file and the attributes in its `.dbf` file into a `Document`
+///
+/// The document starts with a `Schema` with the id `schema_id`, declaring a `SimpleField` for each
+/// `.dbf` field, followed by a `Placemark` for each shape. A field named `name` becomes the
+/// placemark's name. Empty values and records marked as deleted are skipped.
+///
+/// Points become `Point`s, multipoints become a `MultiGeometry` of points, polylines become a
+/// `LineString` or a `MultiGeometry` of them, and polygons a `Polygon` or a `MultiGeometry` of
+/// them. Each outer ring starts a polygon, and holes belong to the polygon before them.
+///
+/// `code_page` is the content of the shapefile's `.cpg` file, such as `UTF-8` or `1252`, naming
+/// the encoding of text in the `.dbf` file. Without it, text is decoded with the code page in the
+/// `.dbf` header's language driver ID, or as UTF-8 if that isn't set.
+pub fn read_shapefile<T, S, D>(
+    shp: S,
+    dbf: D,
+    schema_id: &str,
+    code_page: Option<&str>,
+) -> Result<Kml<T>, Error>
+where
+    T: CoordType + Default,
+    S: Read + Seek,
+    D: Read + Seek,
+{
+    let shapes = ShapeReader::new(shp)?.read()?;
+    let mut dbf = match code_page {
+        Some(code_page) => {
+            let encoding = DynEncoding::from_name(code_page).ok_or_else(|| {
+                Error::InvalidShapefile(format!("Unsupported code page {}", code_page.trim()))
+            })?;
+            dbase::File::open_with_encoding(dbf, encoding)?
+        }
+        None => dbase::File::open(dbf)?,
+    };
+    if dbf.num_records() != shapes.len() {
+        return Err(Error::InvalidShapefile(format!(
+            "{} shapes but {} records",
+            shapes.len(),
+            dbf.num_records()
+        )));
+    }
+
+    let fields: Vec<String> = dbf.fields().iter().map(|f| f.name().to_string()).collect();
+    let simple_fields = dbf
+        .fields()
+        .iter()
+        .map(|f| {
+            Node::Element(Element {
+                name: "SimpleField".to_string(),
+                attrs: HashMap::from([
+                    ("name".to_string(), f.name().to_string()),
+                    ("type".to_string(), kml_type(f.field_type()).to_string()),
+                ]),
+                ..Default::default()
+            })
+        })
+        .collect();
+    let mut elements = vec![Kml::Element(Element {
+        name: "Schema".to_string(),
+        attrs: HashMap::from([
+            ("name".to_string(), schema_id.to_string()),
+            ("id".to_string(), schema_id.to_string()),
+        ]),
+        children: simple_fields,
+        ..Default::default()
+    })];
+
+    let mut records = dbf.records();
+    for shape in shapes {
+        let Some(mut record) = records.next() else {
+            break;
+        };
+        if record.is_deleted()? {
+            continue;
+        }
+        let mut placemark = Placemark {
+            geometry: geometry(shape)?,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let Some(value) = text(record.read_field(FieldIndex(i))?) else {
+                continue;
+            };
+            if field == "name" {
+                placemark.name = Some(value.clone());
+            }
+            data.push(Node::Element(Element {
+                name: "SimpleData".to_string(),
+                attrs: HashMap::from([("name".to_string(), field.clone())]),
+                content: Some(value),
+                ..Default::default()
+            }));
+        }
+        if !data.is_empty() {
+            placemark.children.push(Node::Element(Element {
+                name: "ExtendedData".to_string(),
+                children: vec![Node::Element(Element {
+                    name: "SchemaData".to_string(),
+                    attrs: HashMap::from([("schemaUrl".to_string(), format!("#{schema_id}"))]),
+                    children: data,
+                    ..Default::default()
+                })],
+                ..Default::default()
+            }));
+        }
+        elements.push(Kml::Placemark(placemark));
+    }
+
+    Ok(Kml::Document(Document {
+        elements,
+        ..Default::default()
+    }))
+}
+
+/// Reads the `.shp` file at `path` and the `.dbf` and optional `.cpg` files next to it, with the
+/// file stem as the schema id, see [`read_shapefile`](fn.read_shapefile.html)
+#[cfg(feature = "fs")]
+pub fn read_shapefile_path<T: CoordType + Default, P: AsRef<Path>>(
+    path: P,
+) -> Result<Kml<T>, Error> {
+    let path = path.as_ref();
+    let schema_id = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let code_page = match std::fs::read_to_string(path.with_extension("cpg")) {
+        Ok(code_page) => Some(code_page),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    read_shapefile(
+        std::io::BufReader::new(std::fs::File::open(path)?),
+        std::io::BufReader::new(std::fs::File::open(path.with_extension("dbf"))?),
+        &schema_id,
+        code_page.as_deref(),
+    )
+}
+
+/// Writes placemarks, such as those from [`Kml::features`](../enum.Kml.html#method.features), as
+/// a shapefile's `.shp`, `.shx`, and `.dbf` files
+///
+/// All geometries must map to the same shape type: points to points, `MultiGeometry`s of points to
+/// multipoints, lines and tracks to polylines, and polygons to polygons, with points written as
+/// multipoints if there are both. Shapes have a `z` coordinate if any geometry has altitudes.
+/// Placemarks without a geometry are skipped, and models aren't supported.
+///
+/// The `.dbf` file has a `name` column if any placemark has a name and no property is called
+/// `name`, followed by a text column for each property. Names longer than 10 bytes are truncated,
+/// with a numeric suffix if they'd clash.
+pub fn write_shapefile<'a, T, I, S, D>(placemarks: I, shp: S, shx: S, dbf: D) -> Result<(), Error>
+where
+    T: CoordType + 'a,
+    I: IntoIterator<Item = &'a Placemark<T>>,
+    S: Write + Seek,
+    D: Write + Seek,
+{
+    let mut placemarks_and_shapes = Vec::new();
+    for placemark in placemarks {
+        if let Some(geometry) = &placemark.geometry {
+            placemarks_and_shapes.push((placemark, shape(geometry)?));
+        }
+    }
+    let (placemarks, shapes): (Vec<_>, Vec<_>) = placemarks_and_shapes.into_iter().unzip();
+
+    let mut shape_type = None;
+    for shape in &shapes {
+        shape_type = match (shape_type, shape.shape_type) {
+            (None, t) => Some(t),
+            (Some(t), u) if t == u => Some(t),
+            (Some(ShapeType::Point | ShapeType::Multipoint), ShapeType::Point)
+            | (Some(ShapeType::Point), ShapeType::Multipoint) => Some(ShapeType::Multipoint),
+            _ => {
+                return Err(Error::InvalidShapefile(
+                    "Geometries with different shape types".to_string(),
+                ))
+            }
+        };
+    }
+    let shape_type = shape_type
+        .ok_or_else(|| Error::InvalidShapefile("No placemarks with a geometry".to_string()))?;
+    let z = placemarks
+        .iter()
+        .any(|p| p.geometry.as_ref().is_some_and(Geometry::has_z));
+
+    let mut writer = ShapeWriter::with_shx(shp, shx);
+    match (shape_type, z) {
+        (ShapeType::Point, false) => {
+            write_shapes(&mut writer, &shapes, |s| Ok(xy(&s.parts[0].coords[0])))
+        }
+        (ShapeType::Point, true) => {
+            write_shapes(&mut writer, &shapes, |s| Ok(xyz(&s.parts[0].coords[0])))
+        }
+        (ShapeType::Multipoint, false) => write_shapes(&mut writer, &shapes, |s| {
+            Ok(Multipoint::new(s.points().map(xy).collect()))
+        }),
+        (ShapeType::Multipoint, true) => write_shapes(&mut writer, &shapes, |s| {
+            Ok(MultipointZ::new(s.points().map(xyz).collect()))
+        }),
+        (ShapeType::Polyline, false) => write_shapes(&mut writer, &shapes, |s| polyline(s, xy)),
+        (ShapeType::Polyline, true) => write_shapes(&mut writer, &shapes, |s| polyline(s, xyz)),
+        (_, false) => write_shapes(&mut writer, &shapes, |s| polygon(s, xy)),
+        (_, true) => write_shapes(&mut writer, &shapes, |s| polygon(s, xyz)),
+    }?;
+    writer.finalize()?;
+    write_dbf(&placemarks, dbf)
+}
+
+/// Writes placemarks to the `.shp` file at `path`, with the `.shx`, `.dbf`, and a `.cpg` file
+/// declaring the UTF-8 encoding next to it, see [`write_shapefile`](fn.write_shapefile.html)
+#[cfg(feature = "fs")]
+pub fn write_shapefile_path<'a, T, I, P>(placemarks: I, path: P) -> Result<(), Error>
+where
+    T: CoordType + 'a,
+    I: IntoIterator<Item = &'a Placemark<T>>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let file = |extension| {
+        std::fs::File::create(path.with_extension(extension)).map(std::io::BufWriter::new)
+    };
+    write_shapefile(placemarks, file("shp")?, file("shx")?, file("dbf")?)?;
+    file("cpg")?.write_all(b"UTF-8")?;
+    Ok(())
+}
+
+fn geometry<T: CoordType + Default>(shape: Shape) -> Result<Option<Geometry<T>>, Error> {
+    Ok(Some(match shape {
+        Shape::NullShape => return Ok(None),
+        Shape::Point(p) => Geometry::Point(Point::from(coord(p.x, p.y, None)?)),
+        Shape::PointM(p) => Geometry::Point(Point::from(coord(p.x, p.y, None)?)),
+        Shape::PointZ(p) => Geometry::Point(Point::from(coord(p.x, p.y, Some(p.z))?)),
+        Shape::Multipoint(m) => points(m.points(), |p| (p.x, p.y, None))?,
+        Shape::MultipointM(m) => points(m.points(), |p| (p.x, p.y, None))?,
+        Shape::MultipointZ(m) => points(m.points(), |p| (p.x, p.y, Some(p.z)))?,
+        Shape::Polyline(l) => lines(l.parts(), |p| (p.x, p.y, None))?,
+        Shape::PolylineM(l) => lines(l.parts(), |p| (p.x, p.y, None))?,
+        Shape::PolylineZ(l) => lines(l.parts(), |p| (p.x, p.y, Some(p.z)))?,
+        Shape::Polygon(p) => polygons(p.rings(), |p| (p.x, p.y, None))?,
+        Shape::PolygonM(p) => polygons(p.rings(), |p| (p.x, p.y, None))?,
+        Shape::PolygonZ(p) => polygons(p.rings(), |p| (p.x, p.y, Some(p.z)))?,
+        Shape::Multipatch(_) => {
+            return Err(Error::InvalidShapefile(
+                "Unsupported shape type Multipatch".to_string(),
+            ))
+        }
+    }))
+}
+
+/// `x`, `y`, and optional `z` values of a shapefile point
+type Xyz<P> = fn(&P) -> (f64, f64, Option<f64>);
+
+fn coords<T: CoordType, P>(points: &[P], xyz: Xyz<P>) -> Result<Vec<Coord<T>>, Error> {
+    points
+        .iter()
+        .map(|p| {
+            let (x, y, z) = xyz(p);
+            coord(x, y, z)
+        })
+        .collect()
+}
+
+fn points<T: CoordType + Default, P>(points: &[P], xyz: Xyz<P>) -> Result<Geometry<T>, Error> {
+    let points = coords(points, xyz)?
+        .into_iter()
+        .map(|c| Geometry::Point(Point::from(c)))
+        .collect();
+    Ok(Geometry::MultiGeometry(MultiGeometry::new(points)))
+}
+
+fn lines<T: CoordType + Default, P>(parts: &[Vec<P>], xyz: Xyz<P>) -> Result<Geometry<T>, Error> {
+    let lines = parts
+        .iter()
+        .map(|part| Ok(Geometry::LineString(LineString::from(coords(part, xyz)?))))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(single_or_multi(lines))
+}
+
+fn polygons<T: CoordType + Default, P>(
+    rings: &[PolygonRing<P>],
+    xyz: Xyz<P>,
+) -> Result<Geometry<T>, Error> {
+    let mut polygons: Vec<Polygon<T>> = Vec::new();
+    for ring in rings {
+        let coords = LinearRing::from(coords(ring.points(), xyz)?);
+        match (ring, polygons.last_mut()) {
+            (PolygonRing::Inner(_), Some(polygon)) => polygon.inner.push(coords),
+            _ => polygons.push(Polygon::new(coords, vec![])),
+        }
+    }
+    Ok(single_or_multi(
+        polygons.into_iter().map(Geometry::Polygon).collect(),
+    ))
+}
+
+fn single_or_multi<T: CoordType + Default>(mut geometries: Vec<Geometry<T>>) -> Geometry<T> {
+    if geometries.len() == 1 {
+        geometries.remove(0)
+    } else {
+        Geometry::MultiGeometry(MultiGeometry::new(geometries))
+    }
+}
+
+fn coord<T: CoordType>(x: f64, y: f64, z: Option<f64>) -> Result<Coord<T>, Error> {
+    let value =
+        |v: f64| T::from(v).ok_or_else(|| Error::InvalidShapefile(format!("Invalid value {v}")));
+    Ok(Coord::new(value(x)?, value(y)?, z.map(value).transpose()?))
+}
+
+/// Type of a `.dbf` field in a KML `SimpleField`
+fn kml_type(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::Integer => "int",
+        FieldType::Numeric | FieldType::Float | FieldType::Double | FieldType::Currency => "double",
+        FieldType::Logical => "bool",
+        _ => "string",
+    }
+}
+
+/// Text of a `.dbf` value, or `None` if it's empty
+fn text(value: FieldValue) -> Option<String> {
+    match value {
+        FieldValue::Character(v) => v.filter(|v| !v.is_empty()),
+        FieldValue::Memo(v) => Some(v).filter(|v| !v.is_empty()),
+        FieldValue::Numeric(v) => v.map(|v| v.to_string()),
+        FieldValue::Float(v) => v.map(|v| v.to_string()),
+        FieldValue::Logical(v) => v.map(|v| v.to_string()),
+        FieldValue::Date(v) => {
+            v.map(|d| format!("{:04}-{:02}-{:02}", d.year(), d.month(), d.day()))
+        }
+        FieldValue::Integer(v) => Some(v.to_string()),
+        FieldValue::Currency(v) | FieldValue::Double(v) => Some(v.to_string()),
+        FieldValue::DateTime(v) => {
+            let (d, t) = (v.date(), v.time());
+            Some(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                d.year(),
+                d.month(),
+                d.day(),
+                t.hours(),
+                t.minutes(),
+                t.seconds()
+            ))
+        }
+    }
+}
+
+/// Part of a geometry as written to a shapefile
+struct Part<'a, T: CoordType> {
+    coords: &'a [Coord<T>],
+    hole: bool,
+}
+
+/// Parts of a geometry as written to a shapefile
+struct Parts<'a, T: CoordType> {
+    shape_type: ShapeType,
+    parts: Vec<Part<'a, T>>,
+}
+
+impl<'a, T: CoordType> Parts<'a, T> {
+    fn points(&self) -> impl Iterator<Item = &'a Coord<T>> + '_ {
+        self.parts.iter().flat_map(|p| p.coords)
+    }
+}
+
+fn shape<T: CoordType>(geometry: &Geometry<T>) -> Result<Parts<'_, T>, Error> {
+    fn parts<T: CoordType>(shape_type: ShapeType, coords: &[Coord<T>]) -> Parts<'_, T> {
+        Parts {
+            shape_type,
+            parts: vec![Part {
+                coords,
+                hole: false,
+            }],
+        }
+    }
+    Ok(match geometry {
+        Geometry::Point(p) => parts(ShapeType::Point, std::slice::from_ref(&p.coord)),
+        Geometry::LineString(l) => parts(ShapeType::Polyline, &l.coords),
+        Geometry::LinearRing(l) => parts(ShapeType::Polyline, &l.coords),
+        Geometry::Track(t) => parts(ShapeType::Polyline, &t.coords),
+        Geometry::MultiTrack(t) => Parts {
+            shape_type: ShapeType::Polyline,
+            parts: t
+                .tracks
+                .iter()
+                .map(|t| Part {
+                    coords: &t.coords,
+                    hole: false,
+                })
+                .collect(),
+        },
+        Geometry::Polygon(p) => Parts {
+            shape_type: ShapeType::Polygon,
+            parts: std::iter::once(Part {
+                coords: &p.outer.coords,
+                hole: false,
+            })
+            .chain(p.inner.iter().map(|r| Part {
+                coords: &r.coords,
+                hole: true,
+            }))
+            .collect(),
+        },
+        Geometry::MultiGeometry(g) => {
+            let mut shapes = g
+                .geometries
+                .iter()
+                .map(shape)
+                .collect::<Result<Vec<_>, _>>()?;
+            let shape_type = match shapes.first().map(|s| s.shape_type) {
+                Some(ShapeType::Point) | None => ShapeType::Multipoint,
+                Some(t) => t,
+            };
+            if shapes.iter().any(|s| {
+                s.shape_type != shape_type
+                    && !(shape_type == ShapeType::Multipoint && s.shape_type == ShapeType::Point)
+            }) {
+                return Err(Error::InvalidShapefile(
+                    "MultiGeometry with different geometry types".to_string(),
+                ));
+            }
+            Parts {
+                shape_type,
+                parts: shapes.iter_mut().flat_map(|s| s.parts.drain(..)).collect(),
+            }
+        }
+        Geometry::Element(e) => {
+            return Err(Error::InvalidShapefile(format!(
+                "Unsupported geometry {}",
+                e.name
+            )))
+        }
+    })
+}
+
+fn write_shapes<T, W, S, F>(
+    writer: &mut ShapeWriter<W>,
+    shapes: &[Parts<'_, T>],
+    shape: F,
+) -> Result<(), Error>
+where
+    T: CoordType,
+    W: Write + Seek,
+    S: EsriShape,
+    F: Fn(&Parts<'_, T>) -> Result<S, Error>,
+{
+    for s in shapes {
+        writer.write_shape(&shape(s)?)?;
+    }
+    Ok(())
+}
+
+fn xy<T: CoordType>(c: &Coord<T>) -> ::shapefile::Point {
+    ::shapefile::Point::new(
+        c.x.to_f64().unwrap_or(f64::NAN),
+        c.y.to_f64().unwrap_or(f64::NAN),
+    )
+}
+
+fn xyz<T: CoordType>(c: &Coord<T>) -> ::shapefile::PointZ {
+    ::shapefile::PointZ::new(
+        c.x.to_f64().unwrap_or(f64::NAN),
+        c.y.to_f64().unwrap_or(f64::NAN),
+        c.z.and_then(|z| z.to_f64()).unwrap_or(0.),
+        NO_DATA,
+    )
+}
+
+fn polyline<T, P>(
+    shape: &Parts<'_, T>,
+    point: fn(&Coord<T>) -> P,
+) -> Result<GenericPolyline<P>, Error>
+where
+    T: CoordType,
+    P: ShrinkablePoint + GrowablePoint + Copy,
+{
+    if shape.parts.iter().any(|p| p.coords.len() < 2) {
+        return Err(Error::InvalidShapefile(
+            "Line with fewer than 2 coordinates".to_string(),
+        ));
+    }
+    Ok(GenericPolyline::with_parts(
+        shape
+            .parts
+            .iter()
+            .map(|p| p.coords.iter().map(point).collect())
+            .collect(),
+    ))
+}
+
+/// Converts the polygon's rings, which the `shapefile` crate closes and orders clockwise for outer
+/// rings and counterclockwise for holes
+fn polygon<T, P>(
+    shape: &Parts<'_, T>,
+    point: fn(&Coord<T>) -> P,
+) -> Result<GenericPolygon<P>, Error>
+where
+    T: CoordType,
+    P: ShrinkablePoint + GrowablePoint + HasXY + PartialEq + Copy,
+{
+    if shape.parts.iter().any(|p| p.coords.len() < 3) {
+        return Err(Error::InvalidShapefile(
+            "Polygon ring with fewer than 3 coordinates".to_string(),
+        ));
+    }
+    Ok(GenericPolygon::with_rings(
+        shape
+            .parts
+            .iter()
+            .map(|p| {
+                let points = p.coords.iter().map(point).collect();
+                if p.hole {
+                    PolygonRing::Inner(points)
+                } else {
+                    PolygonRing::Outer(points)
+                }
+            })
+            .collect(),
+    ))
+}
+
+fn write_dbf<T: CoordType, D: Write + Seek>(
+    placemarks: &[&Placemark<T>],
+    dbf: D,
+) -> Result<(), Error> {
+    let properties: Vec<HashMap<String, String>> =
+        placemarks.iter().map(|p| p.properties()).collect();
+    let mut keys: Vec<&str> = properties
+        .iter()
+        .flat_map(|p| p.keys().map(String::as_str))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let names = !keys.contains(&"name") && placemarks.iter().any(|p| p.name.is_some());
+    if names {
+        keys.insert(0, "name");
+    }
+    let values: Vec<Vec<&str>> = placemarks
+        .iter()
+        .zip(&properties)
+        .map(|(placemark, properties)| {
+            keys.iter()
+                .map(|&k| match properties.get(k) {
+                    Some(v) => v.as_str(),
+                    None if names && k == "name" => placemark.name.as_deref().unwrap_or_default(),
+                    None => "",
+                })
+                .map(|v| truncate(v, FIELD_LEN))
+                .collect()
+        })
+        .collect();
+
+    let mut fields: Vec<String> = Vec::with_capacity(keys.len());
+    let mut builder = TableWriterBuilder::new();
+    for (i, key) in keys.iter().enumerate() {
+        let mut name = truncate(key, FIELD_NAME_LEN).to_string();
+        let mut suffix = 1;
+        while fields.contains(&name) {
+            let suffix_str = format!("_{suffix}");
+            name = format!(
+                "{}{suffix_str}",
+                truncate(key, FIELD_NAME_LEN - suffix_str.len())
+            );
+            suffix += 1;
+        }
+        let len = values.iter().map(|v| v[i].len()).max().unwrap_or(0).max(1);
+        let field_name = FieldName::try_from(name.as_str())
+            .map_err(|e| Error::InvalidShapefile(format!("Invalid field name {name}: {e}")))?;
+        builder = builder.add_character_field(field_name, len as u8);
+        fields.push(name);
+    }
+
+    let mut writer = builder.build_with_dest(dbf);
+    for record in &values {
+        let mut row = dbase::Record::default();
+        for (field, value) in fields.iter().zip(record) {
+            let value = Some(value.to_string()).filter(|v| !v.is_empty());
+            row.insert(field.clone(), FieldValue::Character(value));
+        }
+        writer.write_record(&row)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Truncates the string to at most `len` bytes without splitting a character
+fn truncate(s: &str, len: usize) -> &str {
+    let mut end = len.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::text;
+
+    /// `ExtendedData` with a `Data` element for each name and value
+    fn extended_data(data: &[(&str, &str)]) -> Element {
+        Element {
+            name: "ExtendedData".to_string(),
+            children: data
+                .iter()
+                .map(|(name, value)| {
+                    Node::Element(Element {
+                        name: "Data".to_string(),
+                        attrs: HashMap::from([("name".to_string(), name.to_string())]),
+                        children: vec![text("value", value).into()],
+                        ..Default::default()
+                    })
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Placemark written as a shape and its record
+    fn feature(name: Option<&str>, geometry: Geometry, data: &[(&str, &str)]) -> Placemark {
+        Placemark {
+            name: name.map(str::to_string),
+            geometry: Some(geometry),
+            children: vec![extended_data(data).into()],
+            ..Default::default()
+        }
+    }
+
+    fn write(placemarks: &[Placemark]) -> (Vec<u8>, Vec<u8>) {
+        let (mut shp, mut shx, mut dbf) = (
+            Cursor::new(Vec::new()),
+            Cursor::new(Vec::new()),
+            Cursor::new(Vec::new()),
+        );
+        write_shapefile(placemarks, &mut shp, &mut shx, &mut dbf).unwrap();
+        let (shp, shx) = (shp.into_inner(), shx.into_inner());
+        assert_eq!(shx.len(), 100 + 8 * placemarks.len());
+        assert_eq!(
+            i32::from_be_bytes(shp[24..28].try_into().unwrap()) as usize * 2,
+            shp.len()
+        );
+        (shp, dbf.into_inner())
+    }
+
+    fn read(shp: &[u8], dbf: &[u8], code_page: Option<&str>) -> Result<Vec<Kml>, Error> {
+        match read_shapefile(Cursor::new(shp), Cursor::new(dbf), "s", code_page)? {
+            Kml::Document(Document { elements, .. }) => Ok(elements),
+            _ => unreachable!(),
+        }
+    }
+
+    fn round_trip(placemarks: &[Placemark]) -> Vec<Kml> {
+        let (shp, dbf) = write(placemarks);
+        read(&shp, &dbf, None).unwrap()
+    }
+
+    #[test]
+    fn test_polygons() {
+        let square = |size: f64, z: Option<f64>| {
+            LinearRing::from(vec![
+                Coord::new(0., 0., z),
+                Coord::new(size, 0., z),
+                Coord::new(size, size, z),
+                Coord::new(0., size, z),
+                Coord::new(0., 0., z),
+            ])
+        };
+        let mut hole = square(1., Some(1.));
+        hole.coords.reverse();
+        let polygon = Polygon::new(square(4., Some(1.)), vec![hole]);
+        let multi = Geometry::MultiGeometry(MultiGeometry::new(vec![
+            Geometry::Polygon(polygon.clone()),
+            Geometry::Polygon(Polygon::new(square(2., Some(2.)), vec![])),
+        ]));
+        let elements = round_trip(&[
+            feature(
+                Some("a"),
+                Geometry::Polygon(polygon.clone()),
+                &[("kind", "park")],
+            ),
+            feature(None, multi.clone(), &[("length", "5")]),
+        ]);
+
+        let Kml::Element(schema) = &elements[0] else {
+            panic!("expected a Schema");
+        };
+        let fields: Vec<&str> = schema
+            .elements()
+            .map(|f| f.attrs["name"].as_str())
+            .collect();
+        assert_eq!(fields, vec!["name", "kind", "length"]);
+
+        let [Kml::Placemark(a), Kml::Placemark(b)] = &elements[1..] else {
+            panic!("expected two placemarks");
+        };
+        assert_eq!(a.name.as_deref(), Some("a"));
+        assert_eq!(
+            a.properties(),
+            HashMap::from([
+                ("name".to_string(), "a".to_string()),
+                ("kind".to_string(), "park".to_string())
+            ])
+        );
+        // Outer rings are written clockwise and holes counterclockwise
+        let Some(Geometry::Polygon(read)) = &a.geometry else {
+            panic!("expected a Polygon");
+        };
+        let mut outer = polygon.outer.coords.clone();
+        outer.reverse();
+        assert_eq!(read.outer.coords, outer);
+        assert_eq!(read.inner, vec![square(1., Some(1.))]);
+        assert_eq!(read.outer.coords[0].z, Some(1.));
+
+        assert_eq!(b.name, None);
+        assert_eq!(b.properties()["length"], "5");
+        assert!(matches!(&b.geometry, Some(Geometry::MultiGeometry(g)) if g.geometries.len() == 2));
+    }
+
+    #[test]
+    fn test_points_and_lines() {
+        let point = Geometry::Point(Point::new(1., 2., None));
+        let points = Geometry::MultiGeometry(MultiGeometry::new(vec![
+            Geometry::Point(Point::new(3., 4., None)),
+            Geometry::Point(Point::new(5., 6., None)),
+        ]));
+        let elements = round_trip(&[
+            feature(Some("a"), point, &[]),
+            feature(Some("b"), points.clone(), &[]),
+        ]);
+        let Kml::Placemark(a) = &elements[1] else {
+            panic!("expected a Placemark");
+        };
+        assert_eq!(
+            a.geometry,
+            Some(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                Geometry::Point(Point::new(1., 2., None))
+            ])))
+        );
+        assert!(matches!(&elements[2], Kml::Placemark(b) if b.geometry == Some(points.clone())));
+
+        let line = Geometry::LineString(LineString::from(vec![
+            Coord::new(0., 0., None),
+            Coord::new(1., 1., None),
+        ]));
+        let elements = round_trip(&[feature(None, line.clone(), &[])]);
+        assert!(matches!(&elements[1], Kml::Placemark(p) if p.geometry == Some(line.clone())));
+
+        let mixed = [
+            feature(None, line, &[]),
+            feature(None, Geometry::Point(Point::new(1., 2., None)), &[]),
+        ];
+        let (mut shp, mut shx) = (Cursor::new(Vec::new()), Cursor::new(Vec::new()));
+        assert!(matches!(
+            write_shapefile(&mixed, &mut shp, &mut shx, Cursor::new(Vec::new())),
+            Err(Error::InvalidShapefile(_))
+        ));
+    }
+
+    #[test]
+    fn test_field_names() {
+        let geometry = Geometry::Point(Point::new(0., 0., None));
+        let elements = round_trip(&[feature(
+            None,
+            geometry,
+            &[("population_2020", "1"), ("population_2021", "2")],
+        )]);
+        let Kml::Placemark(p) = &elements[1] else {
+            panic!("expected a Placemark");
+        };
+        assert_eq!(
+            p.properties(),
+            HashMap::from([
+                ("population".to_string(), "1".to_string()),
+                ("populati_1".to_string(), "2".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let point = |name| feature(Some(name), Geometry::Point(Point::new(1., 2., None)), &[]);
+        let (shp, dbf) = write(&[point("a"), point("b")]);
+        assert!(read(&shp, &dbf, None).is_ok());
+
+        let mut bad_file_code = shp.clone();
+        bad_file_code[3] = 0;
+        assert!(matches!(
+            read(&bad_file_code, &dbf, None),
+            Err(Error::ShapefileError(::shapefile::Error::InvalidFileCode(
+                _
+            )))
+        ));
+        assert!(matches!(
+            read(&shp[..50], &dbf, None),
+            Err(Error::ShapefileError(_))
+        ));
+        assert!(matches!(
+            read(&shp[..shp.len() - 4], &dbf, None),
+            Err(Error::ShapefileError(_))
+        ));
+        assert!(matches!(
+            read(&shp, &dbf[..20], None),
+            Err(Error::DbaseError(_))
+        ));
+        assert!(matches!(
+            read(&shp, &dbf[..dbf.len() - 4], None),
+            Err(Error::DbaseError(_))
+        ));
+
+        let (_, one_record) = write(&[point("a")]);
+        assert!(matches!(
+            read(&shp, &one_record, None),
+            Err(Error::InvalidShapefile(e)) if e == "2 shapes but 1 records"
+        ));
+    }
+
+    #[test]
+    fn test_code_page() {
+        let (shp, _) = write(&[feature(
+            Some("Café"),
+            Geometry::Point(Point::new(1., 2., None)),
+            &[],
+        )]);
+        let encoding = dbase::encoding::EncodingRs::from(dbase::encoding_rs::WINDOWS_1252);
+        let mut dbf = Cursor::new(Vec::new());
+        let mut writer = TableWriterBuilder::with_encoding(encoding)
+            .add_character_field(FieldName::try_from("name").unwrap(), 10)
+            .build_with_dest(&mut dbf);
+        let mut record = dbase::Record::default();
+        record.insert(
+            "name".to_string(),
+            FieldValue::Character(Some("Café".to_string())),
+        );
+        writer.write_record(&record).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+        let dbf = dbf.into_inner();
+
+        let name = |code_page| {
+            let elements = read(&shp, &dbf, code_page)?;
+            let Kml::Placemark(p) = &elements[1] else {
+                panic!("expected a Placemark");
+            };
+            Ok::<_, Error>(p.name.clone().unwrap())
+        };
+        // The language driver ID declares Windows-1252, and a .cpg file overrides it
+        assert_eq!(name(None).unwrap(), "Café");
+        assert_eq!(name(Some("1252\n")).unwrap(), "Café");
+        assert_eq!(name(Some("UTF-8")).unwrap(), "Caf\u{FFFD}");
+        assert!(matches!(
+            name(Some("EBCDIC")),
+            Err(Error::InvalidShapefile(e)) if e == "Unsupported code page EBCDIC"
+        ));
+
+        let (_, utf8) = write(&[feature(
+            Some("Café"),
+            Geometry::Point(Point::new(1., 2., None)),
+            &[],
+        )]);
+        // Written with the UTF-8 language driver ID
+        assert_eq!(utf8[29], 0xF0);
+    }
+}
@@ -0,0 +1,674 @@
+//! Module for splitting large documents into smaller files linked from a parent document
+//!
+//! Google Earth and other clients load a document in one go, so very large files can be split
+//! into parts by folder, by number of placemarks, or by a spatial grid, with a parent document of
+//! `NetworkLink`s loading each part. Nested documents are split like folders. Styles and style
+//! maps with ids are shared by copying the ones each part uses into it, along with any
+//! `StyleMap`'s styles, so local `#id` style URLs keep working. `Schema` declarations, including
+//! those in folders, are copied into every part for its `SchemaData`.
+//!
+//! The parent document is written as `doc.kml` next to the parts, so the result can be written
+//! to a directory with [`write_split`](fn.write_split.html) or a KMZ archive with
+//! [`write_kmz`](fn.write_kmz.html).
+//!
+//! # Example
+//!
+//! ```
+//! use kml::split::{split, SplitBy};
+//! use kml::Kml;
+//!
+//! let kml: Kml = r##"
+//! <kml xmlns="http://www.opengis.net/kml/2.2">
+//! <Document>
+//!   <name>Parks</name>
+//!   <Style id="green"><PolyStyle><color>ff00ff00</color></PolyStyle></Style>
+//!   <Folder>
+//!     <name>North</name>
+//!     <Placemark><styleUrl>#green</styleUrl><Point><coordinates>1,2</coordinates></Point></Placemark>
+//!   </Folder>
+//!   <Folder>
+//!     <name>South</name>
+//!     <Placemark><Point><coordinates>1,-2</coordinates></Point></Placemark>
+//!   </Folder>
+//! </Document>
+//! </kml>"##
+//!     .parse()
+//!     .unwrap();
+//! let Kml::KmlDocument(document) = kml else {
+//!     unreachable!()
+//! };
+//! let split = split(document, &SplitBy::Folder);
+//! assert_eq!(split.parts.len(), 2);
+//! assert_eq!(split.parts[0].name.as_deref(), Some("North"));
+//!
+//! let parent = Kml::KmlDocument(split.parent).to_string();
+//! assert!(parent.contains("<name>Parks</name>"));
+//! assert!(parent.contains("<href>part-2.kml</href>"));
+//! let north = Kml::KmlDocument(split.parts[0].document.clone()).to_string();
+//! assert!(north.contains(r#"<Style id="green">"#));
+//! ```
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(any(feature = "fs", feature = "zip"))]
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(any(feature = "fs", feature = "zip"))]
+use std::str::FromStr;
+
+#[cfg(any(feature = "fs", feature = "zip"))]
+use crate::errors::Error;
+use crate::super_overlay::{center, element, extent, text_element};
+use crate::types::{
+    CoordType, Document, Folder, Kml, KmlDocument, KmlFeature, KmlVersion, Placemark,
+};
+#[cfg(any(feature = "fs", feature = "zip"))]
+use crate::writer::{KmlWriter, KmlWriterConfig};
+
+/// How to divide a document's features between parts
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum SplitBy {
+    /// One part for each folder or nested document at the top level of the document, and one
+    /// more for any placemarks outside of them
+    Folder,
+    /// Parts of up to this many placemarks each, in document order
+    FeatureCount(usize),
+    /// One part for each non-empty cell of a grid over the bounds of the placemarks, numbered
+    /// from the north west corner by row, by the center of each placemark's geometry, and one
+    /// more for placemarks without coordinates
+    Grid { columns: usize, rows: usize },
+}
+
+/// Part of a split document with the path the parent document links to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Part<T: CoordType = f64> {
+    /// Name of the folder or nested document the part was made from, if it had one
+    pub name: Option<String>,
+    pub path: String,
+    pub document: KmlDocument<T>,
+}
+
+/// Document split into parts, see [`split`](fn.split.html)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitDocument<T: CoordType = f64> {
+    /// Document with a `NetworkLink` to each of the parts, to be written as `doc.kml`
+    pub parent: KmlDocument<T>,
+    pub parts: Vec<Part<T>>,
+}
+
+/// Splits a document into parts and a parent document linking to them
+///
+/// The fields of the document, such as its `name`, and elements at its top level other than
+/// features, styles, and schemas stay in the parent. With
+/// [`SplitBy::FeatureCount`](enum.SplitBy.html) and [`SplitBy::Grid`](enum.SplitBy.html) the
+/// placemarks are taken out of their folders and nested documents, and anything else in them is
+/// left out.
+///
+/// The parent and the parts keep the version and namespace declarations of the document.
+pub fn split<T: CoordType>(document: KmlDocument<T>, by: &SplitBy) -> SplitDocument<T> {
+    let version = document.version;
+    let namespaces: HashMap<String, String> = document
+        .attrs
+        .into_iter()
+        .filter(|(k, _)| k == "xmlns" || k.starts_with("xmlns:"))
+        .collect();
+    let mut container = match <[Kml<T>; 1]>::try_from(document.elements) {
+        Ok([Kml::Document(d)]) => d,
+        Ok(elements) => Document {
+            elements: elements.into(),
+            ..Default::default()
+        },
+        Err(elements) => Document {
+            elements,
+            ..Default::default()
+        },
+    };
+    let elements = std::mem::take(&mut container.elements);
+
+    let mut shared = Shared::default();
+    let mut parent = Vec::new();
+    let mut features = Vec::new();
+    for element in elements {
+        match element {
+            Kml::Style(_)
+            | Kml::StyleMap(_)
+            | Kml::Placemark(_)
+            | Kml::Folder(_)
+            | Kml::Document(_) => features.push(element),
+            Kml::Element(e) if e.name == "Schema" => shared.schemas.push(Kml::Element(e)),
+            element => parent.push(element),
+        }
+    }
+    let features = shared.take(features);
+
+    let groups: Vec<(Option<String>, Vec<Kml<T>>)> = match by {
+        SplitBy::Folder => {
+            let mut groups = Vec::new();
+            let mut rest = Vec::new();
+            for feature in features {
+                match feature {
+                    Kml::Folder(_) | Kml::Document(_) => {
+                        groups.push((feature.name().map(String::from), vec![feature]))
+                    }
+                    feature => rest.push(feature),
+                }
+            }
+            if !rest.is_empty() {
+                groups.push((None, rest));
+            }
+            groups
+        }
+        SplitBy::FeatureCount(count) => {
+            let mut groups: Vec<(Option<String>, Vec<Kml<T>>)> = Vec::new();
+            for placemark in placemarks(features) {
+                match groups.last_mut() {
+                    Some((_, group)) if group.len() < (*count).max(1) => group.push(placemark),
+                    _ => groups.push((None, vec![placemark])),
+                }
+            }
+            groups
+        }
+        SplitBy::Grid { columns, rows } => grid(placemarks(features), *columns, *rows),
+    };
+
+    let mut parts = Vec::new();
+    for (i, (name, features)) in groups.into_iter().enumerate() {
+        let path = format!("part-{}.kml", i + 1);
+        let mut link = Vec::new();
+        if let Some(name) = &name {
+            link.push(text_element("name", name));
+        }
+        link.push(element("Link", vec![text_element("href", &path)]));
+        parent.push(Kml::Element(element("NetworkLink", link)));
+
+        let mut elements = shared.used_by(&features);
+        elements.extend(features);
+        parts.push(Part {
+            name,
+            path,
+            document: document_of(
+                version,
+                &namespaces,
+                Document {
+                    elements,
+                    ..Default::default()
+                },
+            ),
+        });
+    }
+
+    SplitDocument {
+        parent: document_of(
+            version,
+            &namespaces,
+            Document {
+                elements: parent,
+                ..container
+            },
+        ),
+        parts,
+    }
+}
+
+/// Writes the parent document to `doc.kml` in `dir` and the parts next to it
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub fn write_split<T, P>(split: &SplitDocument<T>, dir: P) -> Result<(), Error>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+    P: AsRef<Path>,
+{
+    std::fs::create_dir_all(dir.as_ref())?;
+    for (path, document) in files(split) {
+        let file = std::io::BufWriter::new(std::fs::File::create(dir.as_ref().join(path))?);
+        write_document(document, file)?;
+    }
+    Ok(())
+}
+
+/// Writes the parent document as `doc.kml` in a KMZ archive with the parts next to it, returning
+/// the underlying writer
+#[cfg(feature = "zip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+pub fn write_kmz<T, W>(split: &SplitDocument<T>, writer: W) -> Result<W, Error>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+    W: std::io::Write + std::io::Seek,
+{
+    let mut zip = zip::ZipWriter::new(writer);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (path, document) in files(split) {
+        zip.start_file(path, options)?;
+        write_document(document, &mut zip)?;
+    }
+    Ok(zip.finish()?)
+}
+
+#[cfg(any(feature = "fs", feature = "zip"))]
+fn files<T: CoordType>(split: &SplitDocument<T>) -> impl Iterator<Item = (&str, &KmlDocument<T>)> {
+    let parts = split.parts.iter().map(|p| (p.path.as_str(), &p.document));
+    std::iter::once(("doc.kml", &split.parent)).chain(parts)
+}
+
+#[cfg(any(feature = "fs", feature = "zip"))]
+fn write_document<T, W>(document: &KmlDocument<T>, writer: W) -> Result<(), Error>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+    W: std::io::Write,
+{
+    KmlWriter::from_writer_with_config(writer, KmlWriterConfig::default())
+        .write_document(&Kml::KmlDocument(document.clone()))
+}
+
+/// Styles and schemas copied into the parts that use them
+struct Shared<T: CoordType> {
+    styles: Vec<(String, Kml<T>)>,
+    schemas: Vec<Kml<T>>,
+}
+
+impl<T: CoordType> Default for Shared<T> {
+    fn default() -> Self {
+        Shared {
+            styles: Vec::new(),
+            schemas: Vec::new(),
+        }
+    }
+}
+
+impl<T: CoordType> Shared<T> {
+    /// Removes schemas, and styles and style maps with ids, from the elements and any folders
+    /// and documents in them
+    fn take(&mut self, elements: Vec<Kml<T>>) -> Vec<Kml<T>> {
+        let mut kept = Vec::new();
+        for element in elements {
+            match element {
+                Kml::Style(s) if s.id.is_some() => self
+                    .styles
+                    .push((s.id.clone().unwrap_or_default(), Kml::Style(s))),
+                Kml::StyleMap(s) if s.id.is_some() => self
+                    .styles
+                    .push((s.id.clone().unwrap_or_default(), Kml::StyleMap(s))),
+                Kml::Element(e) if e.name == "Schema" => self.schemas.push(Kml::Element(e)),
+                Kml::Folder(folder) => kept.push(Kml::Folder(Folder {
+                    elements: self.take(folder.elements),
+                    ..folder
+                })),
+                Kml::Document(document) => kept.push(Kml::Document(Document {
+                    elements: self.take(document.elements),
+                    ..document
+                })),
+                element => kept.push(element),
+            }
+        }
+        kept
+    }
+
+    /// Schemas and the styles referenced by the placemarks in the elements, directly or through
+    /// a style map, in the order they were declared
+    fn used_by(&self, elements: &[Kml<T>]) -> Vec<Kml<T>> {
+        let mut ids = HashSet::new();
+        let mut pending: Vec<&str> = elements
+            .iter()
+            .flat_map(Kml::features)
+            .filter_map(|f| f.style_url?.strip_prefix('#'))
+            .collect();
+        while let Some(id) = pending.pop() {
+            if !ids.insert(id) {
+                continue;
+            }
+            for (style_id, style) in &self.styles {
+                if let Kml::StyleMap(map) = style {
+                    if style_id == id {
+                        let urls = map.pairs.iter().map(|p| p.style_url.as_str());
+                        pending.extend(urls.filter_map(|url| url.strip_prefix('#')));
+                    }
+                }
+            }
+        }
+
+        let styles = self
+            .styles
+            .iter()
+            .filter(|(id, _)| ids.contains(id.as_str()));
+        let mut used = self.schemas.clone();
+        used.extend(styles.map(|(_, style)| style.clone()));
+        used
+    }
+}
+
+/// Placemarks in the elements and any folders and documents in them
+fn placemarks<T: CoordType>(elements: Vec<Kml<T>>) -> Vec<Kml<T>> {
+    elements
+        .into_iter()
+        .flat_map(|element| match element {
+            Kml::Placemark(_) => vec![element],
+            Kml::Folder(Folder { elements, .. }) | Kml::Document(Document { elements, .. }) => {
+                placemarks(elements)
+            }
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+fn grid<T: CoordType>(
+    placemarks: Vec<Kml<T>>,
+    columns: usize,
+    rows: usize,
+) -> Vec<(Option<String>, Vec<Kml<T>>)> {
+    let (columns, rows) = (columns.max(1), rows.max(1));
+    let geometries = placemarks.iter().filter_map(|p| match p {
+        Kml::Placemark(Placemark {
+            geometry: Some(g), ..
+        }) => Some(g),
+        _ => None,
+    });
+    let bounds = extent(geometries.flat_map(|g| g.coords())).unwrap_or_default();
+
+    // Only cells holding placemarks are created, so large grids don't allocate every cell
+    let mut cells: BTreeMap<(usize, usize), Vec<Kml<T>>> = BTreeMap::new();
+    let mut unlocated = Vec::new();
+    for placemark in placemarks {
+        let center = match &placemark {
+            Kml::Placemark(Placemark {
+                geometry: Some(g), ..
+            }) => center(g.coords()),
+            _ => None,
+        };
+        match center {
+            Some([x, y]) => {
+                let column = (x - bounds.west) / (bounds.east - bounds.west) * columns as f64;
+                let row = (bounds.north - y) / (bounds.north - bounds.south) * rows as f64;
+                // Points on the east and south edges, and bounds with no width or height
+                let column = (column as usize).min(columns - 1);
+                let row = (row as usize).min(rows - 1);
+                cells.entry((row, column)).or_default().push(placemark);
+            }
+            None => unlocated.push(placemark),
+        }
+    }
+    cells
+        .into_values()
+        .chain(Some(unlocated).filter(|cell| !cell.is_empty()))
+        .map(|cell| (None, cell))
+        .collect()
+}
+
+fn document_of<T: CoordType>(
+    version: KmlVersion,
+    namespaces: &HashMap<String, String>,
+    document: Document<T>,
+) -> KmlDocument<T> {
+    KmlDocument {
+        version,
+        attrs: namespaces.clone(),
+        elements: vec![Kml::Document(document)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::{Element, Pair, Style, StyleMap};
+
+    /// Point placemark using a style
+    fn point(x: f64, y: f64, style_url: Option<&str>) -> Kml {
+        Kml::Placemark(Placemark {
+            style_url: style_url.map(String::from),
+            ..test_utils::point(x, y)
+        })
+    }
+
+    fn style(id: &str) -> Kml {
+        Kml::Style(Style {
+            id: Some(id.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn contents(document: &KmlDocument) -> &[Kml] {
+        match &document.elements[..] {
+            [Kml::Document(Document { elements, .. })] => elements,
+            _ => panic!("expected a Document"),
+        }
+    }
+
+    fn document(elements: Vec<Kml>) -> KmlDocument {
+        KmlDocument {
+            elements: vec![Kml::Document(Document {
+                elements,
+                ..Default::default()
+            })],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_by_feature_count() {
+        let style_map = Kml::StyleMap(StyleMap {
+            id: Some("map".to_string()),
+            pairs: vec![Pair {
+                style_url: "#highlight".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        let input = KmlDocument {
+            elements: vec![Kml::Document(Document {
+                name: Some("Points".to_string()),
+                elements: vec![
+                    style("unused"),
+                    style("highlight"),
+                    style_map.clone(),
+                    point(0., 0., Some("#map")),
+                    Kml::Folder(Folder {
+                        name: Some("Folder".to_string()),
+                        elements: vec![
+                            style("plain"),
+                            point(1., 1., None),
+                            point(2., 2., Some("#plain")),
+                        ],
+                        ..Default::default()
+                    }),
+                ],
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+        let split = split(input, &SplitBy::FeatureCount(2));
+
+        assert_eq!(split.parts.len(), 2);
+        assert_eq!(
+            contents(&split.parts[0].document),
+            &[
+                style("highlight"),
+                style_map,
+                point(0., 0., Some("#map")),
+                point(1., 1., None)
+            ]
+        );
+        assert_eq!(
+            contents(&split.parts[1].document),
+            &[style("plain"), point(2., 2., Some("#plain"))]
+        );
+        assert!(matches!(
+            &split.parent.elements[..],
+            [Kml::Document(Document { name: Some(name), .. })] if name == "Points"
+        ));
+        assert_eq!(
+            contents(&split.parent),
+            &[
+                Kml::Element(element(
+                    "NetworkLink",
+                    vec![element("Link", vec![text_element("href", "part-1.kml")])]
+                )),
+                Kml::Element(element(
+                    "NetworkLink",
+                    vec![element("Link", vec![text_element("href", "part-2.kml")])]
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_by_folder() {
+        let schema = Kml::Element(Element {
+            name: "Schema".to_string(),
+            ..Default::default()
+        });
+        let folder = |name: &str| {
+            Kml::Folder(Folder {
+                name: Some(name.to_string()),
+                elements: vec![point(0., 0., None)],
+                ..Default::default()
+            })
+        };
+        let input = document(vec![
+            schema.clone(),
+            folder("a"),
+            point(5., 5., None),
+            folder("b"),
+        ]);
+        let split = split(input, &SplitBy::Folder);
+
+        let names: Vec<_> = split.parts.iter().map(|p| p.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("a"), Some("b"), None]);
+        assert_eq!(
+            contents(&split.parts[1].document),
+            &[schema.clone(), folder("b")]
+        );
+        assert_eq!(
+            contents(&split.parts[2].document),
+            &[schema, point(5., 5., None)]
+        );
+        let kml = Kml::KmlDocument(split.parent).to_string();
+        assert!(kml.contains(
+            "<NetworkLink><name>a</name><Link><href>part-1.kml</href></Link></NetworkLink>"
+        ));
+    }
+
+    #[test]
+    fn test_split_nested_documents() {
+        let schema = Kml::Element(Element {
+            name: "Schema".to_string(),
+            ..Default::default()
+        });
+        let nested = Kml::Document(Document {
+            name: Some("nested".to_string()),
+            elements: vec![point(0., 0., Some("#a")), point(1., 1., None)],
+            ..Default::default()
+        });
+        let input = document(vec![
+            Kml::Folder(Folder {
+                name: Some("folder".to_string()),
+                elements: vec![schema.clone(), style("a"), point(2., 2., None)],
+                ..Default::default()
+            }),
+            nested.clone(),
+        ]);
+
+        let by_folder = super::split(input.clone(), &SplitBy::Folder);
+        let names: Vec<_> = by_folder.parts.iter().map(|p| p.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("folder"), Some("nested")]);
+        assert_eq!(
+            contents(&by_folder.parts[1].document),
+            &[schema.clone(), style("a"), nested]
+        );
+
+        let by_count = super::split(input, &SplitBy::FeatureCount(3));
+        assert_eq!(
+            contents(&by_count.parts[0].document),
+            &[
+                schema,
+                style("a"),
+                point(2., 2., None),
+                point(0., 0., Some("#a")),
+                point(1., 1., None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_by_grid() {
+        let input = document(vec![
+            point(0., 0., None),
+            point(10., 10., None),
+            point(9., 1., None),
+            Kml::Placemark(Placemark::default()),
+            point(1., 9., None),
+        ]);
+        let split = split(
+            input,
+            &SplitBy::Grid {
+                columns: 2,
+                rows: 2,
+            },
+        );
+        let parts: Vec<_> = split.parts.iter().map(|p| contents(&p.document)).collect();
+        assert_eq!(
+            parts,
+            vec![
+                &[point(1., 9., None)][..],
+                &[point(10., 10., None)],
+                &[point(0., 0., None)],
+                &[point(9., 1., None)],
+                &[Kml::Placemark(Placemark::default())],
+            ]
+        );
+
+        // Cells are only created for placemarks, however large the grid
+        let input = document(vec![point(0., 0., None), point(10., 10., None)]);
+        let by = SplitBy::Grid {
+            columns: usize::MAX,
+            rows: usize::MAX,
+        };
+        assert_eq!(super::split(input, &by).parts.len(), 2);
+    }
+
+    #[test]
+    fn test_split_keeps_version() {
+        let kml: Kml = r#"<kml xmlns="http://www.opengis.net/kml/2.3">
+            <Document>
+                <Folder>
+                    <name>Overlays</name>
+                    <GroundOverlay>
+                        <LatLonQuad><coordinates>0,0 1,0 1,1 0,1</coordinates></LatLonQuad>
+                    </GroundOverlay>
+                </Folder>
+            </Document>
+        </kml>"#
+            .parse()
+            .unwrap();
+        let Kml::KmlDocument(input) = kml else {
+            panic!("expected a KmlDocument");
+        };
+        let split = split(input, &SplitBy::Folder);
+
+        let documents =
+            std::iter::once(&split.parent).chain(split.parts.iter().map(|p| &p.document));
+        for document in documents {
+            assert_eq!(document.version, KmlVersion::V23);
+            assert_eq!(
+                document.attrs.get("xmlns").map(String::as_str),
+                KmlVersion::V23.namespace()
+            );
+        }
+        let part = Kml::KmlDocument(split.parts[0].document.clone());
+        let written = part.to_string();
+        assert!(written.contains("<LatLonQuad>"));
+        assert_eq!(written.parse::<Kml>().unwrap().to_string(), written);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_write_kmz() {
+        let input = document(vec![point(0., 0., None), point(1., 1., None)]);
+        let split = split(input, &SplitBy::FeatureCount(1));
+        let kmz = write_kmz(&split, std::io::Cursor::new(Vec::new())).unwrap();
+        let mut archive = zip::ZipArchive::new(kmz).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.by_index(0).unwrap().name(), "doc.kml");
+        assert!(archive.by_name("part-2.kml").is_ok());
+    }
+}
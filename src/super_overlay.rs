@@ -0,0 +1,616 @@
+//! Module for generating super-overlays, hierarchies of regionated KML tiles
+//!
+//! Large datasets are split into a quadtree of tiles, each with a `Region` that Google Earth only
+//! loads once it covers enough of the screen, and `NetworkLink`s to the tiles a level below it.
+//! The root tile is `doc.kml`, and the rest are at `{level}/{x}/{y}.kml` counting from the north
+//! west corner, so a tile hierarchy can be written to a directory with
+//! [`write_tiles`](fn.write_tiles.html) or a single KMZ archive with
+//! [`write_kmz`](fn.write_kmz.html).
+//!
+//! [`from_placemarks`](fn.from_placemarks.html) keeps up to
+//! [`max_features`](struct.SuperOverlayConfig.html#structfield.max_features) placemarks in each
+//! tile in the order they're given, so more important placemarks should come first, and
+//! distributes the rest to the tiles below by the center of their geometry.
+//! [`raster`](fn.raster.html) builds the same hierarchy over the footprint of an image pyramid,
+//! with a `GroundOverlay` in each tile.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::super_overlay::{from_placemarks, SuperOverlayConfig};
+//! use kml::types::{Geometry, Placemark, Point};
+//!
+//! let placemarks = (0..100).map(|i| Placemark {
+//!     geometry: Some(Geometry::Point(Point::new(i as f64, i as f64 / 2., None))),
+//!     ..Default::default()
+//! });
+//! let tiles = from_placemarks(placemarks, &SuperOverlayConfig::default().max_features(40));
+//! assert_eq!(tiles[0].path(), "doc.kml");
+//! assert_eq!(tiles[1].path(), "1/1/0.kml");
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(any(feature = "fs", feature = "zip"))]
+use std::str::FromStr;
+
+#[cfg(any(feature = "fs", feature = "zip"))]
+use crate::errors::Error;
+use crate::types::{CoordType, Document, Element, Kml, KmlDocument, KmlVersion, Node, Placemark};
+#[cfg(any(feature = "fs", feature = "zip"))]
+use crate::writer::{KmlWriter, KmlWriterConfig};
+
+/// Geographic bounds of a tile in degrees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub north: f64,
+    pub south: f64,
+    pub east: f64,
+    pub west: f64,
+}
+
+impl Default for Bounds {
+    /// The whole world
+    fn default() -> Self {
+        Bounds::new(90., -90., 180., -180.)
+    }
+}
+
+impl Bounds {
+    pub fn new(north: f64, south: f64, east: f64, west: f64) -> Self {
+        Bounds {
+            north,
+            south,
+            east,
+            west,
+        }
+    }
+
+    /// Returns the bounds split at their center, in the order north west, north east, south west,
+    /// and south east
+    fn quadrants(&self) -> [Bounds; 4] {
+        let lat = (self.north + self.south) / 2.;
+        let lon = (self.east + self.west) / 2.;
+        [
+            Bounds::new(self.north, lat, lon, self.west),
+            Bounds::new(self.north, lat, self.east, lon),
+            Bounds::new(lat, self.south, lon, self.west),
+            Bounds::new(lat, self.south, self.east, lon),
+        ]
+    }
+
+    /// Index of the quadrant containing the point
+    fn quadrant(&self, [lon, lat]: [f64; 2]) -> usize {
+        let east = lon >= (self.east + self.west) / 2.;
+        let south = lat < (self.north + self.south) / 2.;
+        usize::from(east) + 2 * usize::from(south)
+    }
+
+    /// `LatLonAltBox` or `LatLonBox` element with the bounds
+    fn element(&self, name: &str) -> Element {
+        element(
+            name,
+            vec![
+                text_element("north", self.north),
+                text_element("south", self.south),
+                text_element("east", self.east),
+                text_element("west", self.west),
+            ],
+        )
+    }
+}
+
+/// Position of a tile in the hierarchy, in a grid of 2<sup>`level`</sup> by
+/// 2<sup>`level`</sup> tiles from the north west corner
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub level: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// Path of the tile, `doc.kml` for the root and `{level}/{x}/{y}.kml` below it
+    pub fn path(&self) -> String {
+        match self.level {
+            0 => "doc.kml".to_string(),
+            level => format!("{}/{}/{}.kml", level, self.x, self.y),
+        }
+    }
+
+    /// Tile in the given quadrant one level below, see `Bounds::quadrants`
+    fn child(&self, quadrant: usize) -> TileId {
+        TileId {
+            level: self.level + 1,
+            x: self.x * 2 + (quadrant % 2) as u32,
+            y: self.y * 2 + (quadrant / 2) as u32,
+        }
+    }
+
+    /// Path of `other` relative to this tile
+    fn href(&self, other: &TileId) -> String {
+        match self.level {
+            0 => other.path(),
+            _ => format!("../../{}", other.path()),
+        }
+    }
+}
+
+/// Tile in a super-overlay with the document to write to its [`path`](#method.path)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile<T: CoordType = f64> {
+    pub id: TileId,
+    pub bounds: Bounds,
+    pub document: KmlDocument<T>,
+}
+
+impl<T: CoordType> Tile<T> {
+    /// Path of the tile relative to the root, see [`TileId::path`](struct.TileId.html#method.path)
+    pub fn path(&self) -> String {
+        self.id.path()
+    }
+}
+
+/// Configuration for generating super-overlays
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SuperOverlayConfig {
+    /// Maximum number of placemarks in a tile, except at
+    /// [`max_level`](#structfield.max_level), 100 by default
+    pub max_features: usize,
+    /// Deepest level of tiles below the root, 10 by default
+    pub max_level: u32,
+    /// Size in pixels a tile's region must cover on screen before it's loaded, 128 by default
+    pub min_lod_pixels: f64,
+    /// Size in pixels above which a tile's region is hidden again, or -1 to never hide it, which
+    /// is the default
+    pub max_lod_pixels: f64,
+    /// Bounds of the root tile, or `None` to use the bounds of the placemarks' coordinates
+    pub bounds: Option<Bounds>,
+}
+
+impl Default for SuperOverlayConfig {
+    fn default() -> Self {
+        SuperOverlayConfig {
+            max_features: 100,
+            max_level: 10,
+            min_lod_pixels: 128.,
+            max_lod_pixels: -1.,
+            bounds: None,
+        }
+    }
+}
+
+impl SuperOverlayConfig {
+    /// Sets [`max_features`](#structfield.max_features)
+    pub fn max_features(mut self, max_features: usize) -> Self {
+        self.max_features = max_features;
+        self
+    }
+
+    /// Sets [`max_level`](#structfield.max_level)
+    pub fn max_level(mut self, max_level: u32) -> Self {
+        self.max_level = max_level;
+        self
+    }
+
+    /// Sets [`min_lod_pixels`](#structfield.min_lod_pixels)
+    pub fn min_lod_pixels(mut self, min_lod_pixels: f64) -> Self {
+        self.min_lod_pixels = min_lod_pixels;
+        self
+    }
+
+    /// Sets [`max_lod_pixels`](#structfield.max_lod_pixels)
+    pub fn max_lod_pixels(mut self, max_lod_pixels: f64) -> Self {
+        self.max_lod_pixels = max_lod_pixels;
+        self
+    }
+
+    /// Sets [`bounds`](#structfield.bounds)
+    pub fn bounds(mut self, bounds: Option<Bounds>) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// `Region` element for a tile with the bounds
+    fn region(&self, bounds: &Bounds) -> Element {
+        element(
+            "Region",
+            vec![
+                bounds.element("LatLonAltBox"),
+                element(
+                    "Lod",
+                    vec![
+                        text_element("minLodPixels", self.min_lod_pixels),
+                        text_element("maxLodPixels", self.max_lod_pixels),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    /// Document for a tile with its region, its contents, and links to the tiles below it
+    fn tile<T: CoordType>(
+        &self,
+        id: TileId,
+        bounds: Bounds,
+        contents: Vec<Kml<T>>,
+        children: &[(TileId, Bounds)],
+    ) -> Tile<T> {
+        let mut elements = vec![Kml::Element(self.region(&bounds))];
+        elements.extend(contents);
+        for (child, child_bounds) in children {
+            elements.push(Kml::Element(element(
+                "NetworkLink",
+                vec![
+                    self.region(child_bounds),
+                    element(
+                        "Link",
+                        vec![
+                            text_element("href", id.href(child)),
+                            text_element("viewRefreshMode", "onRegion"),
+                        ],
+                    ),
+                ],
+            )));
+        }
+        Tile {
+            id,
+            bounds,
+            document: KmlDocument {
+                version: KmlVersion::V22,
+                elements: vec![Kml::Document(Document {
+                    elements,
+                    ..Default::default()
+                })],
+                attrs: HashMap::new(),
+            },
+        }
+    }
+}
+
+/// Splits placemarks into a hierarchy of tiles, returned with the root first and each tile before
+/// the tiles below it
+///
+/// Placemarks without coordinates are kept in the root tile.
+pub fn from_placemarks<T, I>(placemarks: I, config: &SuperOverlayConfig) -> Vec<Tile<T>>
+where
+    T: CoordType,
+    I: IntoIterator<Item = Placemark<T>>,
+{
+    let mut placed = Vec::new();
+    let mut unplaced = Vec::new();
+    for placemark in placemarks {
+        match placemark.geometry.as_ref().and_then(|g| center(g.coords())) {
+            Some(center) => placed.push((center, placemark)),
+            None => unplaced.push(Kml::Placemark(placemark)),
+        }
+    }
+
+    let bounds = config.bounds.unwrap_or_else(|| {
+        let coords = placed.iter().filter_map(|(_, p)| p.geometry.as_ref());
+        let coords = coords.flat_map(|g| g.coords());
+        extent(coords).unwrap_or_default()
+    });
+    let mut tiles = Vec::new();
+    add_tiles(
+        TileId::default(),
+        bounds,
+        unplaced,
+        placed,
+        config,
+        &mut tiles,
+    );
+    tiles
+}
+
+fn add_tiles<T: CoordType>(
+    id: TileId,
+    bounds: Bounds,
+    mut contents: Vec<Kml<T>>,
+    mut placemarks: Vec<([f64; 2], Placemark<T>)>,
+    config: &SuperOverlayConfig,
+    tiles: &mut Vec<Tile<T>>,
+) {
+    let rest = if placemarks.len() > config.max_features && id.level < config.max_level {
+        placemarks.split_off(config.max_features)
+    } else {
+        Vec::new()
+    };
+    contents.extend(placemarks.into_iter().map(|(_, p)| Kml::Placemark(p)));
+
+    let quadrants = bounds.quadrants();
+    let mut children: [Vec<_>; 4] = Default::default();
+    for (center, placemark) in rest {
+        children[bounds.quadrant(center)].push((center, placemark));
+    }
+    let links: Vec<(TileId, Bounds)> = (0..4)
+        .filter(|&q| !children[q].is_empty())
+        .map(|q| (id.child(q), quadrants[q]))
+        .collect();
+    tiles.push(config.tile(id, bounds, contents, &links));
+
+    for (q, placemarks) in children.into_iter().enumerate() {
+        if !placemarks.is_empty() {
+            add_tiles(
+                id.child(q),
+                quadrants[q],
+                Vec::new(),
+                placemarks,
+                config,
+                tiles,
+            );
+        }
+    }
+}
+
+/// Builds a hierarchy of `levels` levels of tiles over `bounds`, each with a `GroundOverlay` of the
+/// image at the `href` returned for it, drawn above the levels before it
+///
+/// The bounds in the config are ignored.
+///
+/// # Example
+///
+/// ```
+/// use kml::super_overlay::{raster, Bounds, SuperOverlayConfig};
+///
+/// let tiles = raster(
+///     Bounds::new(10., 0., 10., 0.),
+///     2,
+///     |id| format!("images/{}/{}/{}.png", id.level, id.x, id.y),
+///     &SuperOverlayConfig::default(),
+/// );
+/// assert_eq!(tiles.len(), 5);
+/// assert_eq!(tiles[4].path(), "1/1/1.kml");
+/// ```
+pub fn raster(
+    bounds: Bounds,
+    levels: u32,
+    mut href: impl FnMut(&TileId) -> String,
+    config: &SuperOverlayConfig,
+) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    let mut level = vec![(TileId::default(), bounds)];
+    for depth in 0..levels {
+        let mut next = Vec::new();
+        for (id, bounds) in level {
+            let overlay = element(
+                "GroundOverlay",
+                vec![
+                    text_element("drawOrder", depth),
+                    element("Icon", vec![text_element("href", href(&id))]),
+                    bounds.element("LatLonBox"),
+                ],
+            );
+            let children: Vec<(TileId, Bounds)> = if depth + 1 < levels {
+                let quadrants = bounds.quadrants();
+                (0..4).map(|q| (id.child(q), quadrants[q])).collect()
+            } else {
+                Vec::new()
+            };
+            tiles.push(config.tile(id, bounds, vec![Kml::Element(overlay)], &children));
+            next.extend(children);
+        }
+        level = next;
+    }
+    tiles
+}
+
+/// Writes the tiles to their paths in `dir`, creating the directories for each level
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub fn write_tiles<T, P>(tiles: &[Tile<T>], dir: P) -> Result<(), Error>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+    P: AsRef<Path>,
+{
+    for tile in tiles {
+        let path = dir.as_ref().join(tile.path());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write_tile(tile, file)?;
+    }
+    Ok(())
+}
+
+/// Writes the tiles to their paths in a KMZ archive, with the root as `doc.kml`, returning the
+/// underlying writer
+#[cfg(feature = "zip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+pub fn write_kmz<T, W>(tiles: &[Tile<T>], writer: W) -> Result<W, Error>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+    W: std::io::Write + std::io::Seek,
+{
+    let mut zip = zip::ZipWriter::new(writer);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for tile in tiles {
+        zip.start_file(tile.path(), options)?;
+        write_tile(tile, &mut zip)?;
+    }
+    Ok(zip.finish()?)
+}
+
+#[cfg(any(feature = "fs", feature = "zip"))]
+fn write_tile<T, W>(tile: &Tile<T>, writer: W) -> Result<(), Error>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+    W: std::io::Write,
+{
+    KmlWriter::from_writer_with_config(writer, KmlWriterConfig::default())
+        .write_document(&Kml::KmlDocument(tile.document.clone()))
+}
+
+/// Center of the bounding box of the coordinates, if there are any
+pub(crate) fn center<'a, T: CoordType + 'a>(
+    coords: impl Iterator<Item = &'a crate::types::Coord<T>>,
+) -> Option<[f64; 2]> {
+    let bounds = extent(coords)?;
+    Some([
+        (bounds.east + bounds.west) / 2.,
+        (bounds.north + bounds.south) / 2.,
+    ])
+}
+
+/// Bounds of the coordinates, if there are any
+pub(crate) fn extent<'a, T: CoordType + 'a>(
+    coords: impl Iterator<Item = &'a crate::types::Coord<T>>,
+) -> Option<Bounds> {
+    coords
+        .filter_map(|c| Some((c.x.to_f64()?, c.y.to_f64()?)))
+        .fold(None, |bounds: Option<Bounds>, (x, y)| {
+            Some(match bounds {
+                Some(b) => {
+                    Bounds::new(b.north.max(y), b.south.min(y), b.east.max(x), b.west.min(x))
+                }
+                None => Bounds::new(y, y, x, x),
+            })
+        })
+}
+
+pub(crate) fn element(name: &str, children: Vec<Element>) -> Element {
+    Element {
+        name: name.to_string(),
+        children: children.into_iter().map(Node::from).collect(),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn text_element(name: &str, content: impl fmt::Display) -> Element {
+    Element {
+        name: name.to_string(),
+        content: Some(content.to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::point;
+
+    fn placemarks(tile: &Tile) -> usize {
+        match &tile.document.elements[..] {
+            [Kml::Document(Document { elements, .. })] => elements
+                .iter()
+                .filter(|e| matches!(e, Kml::Placemark(_)))
+                .count(),
+            _ => panic!("expected a Document"),
+        }
+    }
+
+    fn hrefs(tile: &Tile) -> Vec<String> {
+        let [Kml::Document(Document { elements, .. })] = &tile.document.elements[..] else {
+            panic!("expected a Document");
+        };
+        elements
+            .iter()
+            .filter_map(|e| match e {
+                Kml::Element(e) if e.name == "NetworkLink" => {
+                    e.elements().nth(1)?.elements().next()?.content.clone()
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_placemarks() {
+        let mut input = vec![Placemark::default()];
+        // Two points kept in the root, three in the north west quadrant, and two in the south east
+        input.extend([point(-9., 9.), point(-8., 8.)]);
+        input.extend([point(-9., 8.), point(-8., 9.), point(-2., 2.)]);
+        input.extend([point(9., -9.), point(8., -8.)]);
+        let config = SuperOverlayConfig::default()
+            .max_features(2)
+            .bounds(Some(Bounds::new(10., -10., 10., -10.)));
+        let tiles = from_placemarks(input, &config);
+
+        let paths: Vec<String> = tiles.iter().map(Tile::path).collect();
+        assert_eq!(
+            paths,
+            vec!["doc.kml", "1/0/0.kml", "2/1/1.kml", "1/1/1.kml"]
+        );
+        // The placemark without a geometry stays in the root along with the first two
+        assert_eq!(placemarks(&tiles[0]), 3);
+        assert_eq!(hrefs(&tiles[0]), vec!["1/0/0.kml", "1/1/1.kml"]);
+        assert_eq!(placemarks(&tiles[1]), 2);
+        assert_eq!(hrefs(&tiles[1]), vec!["../../2/1/1.kml"]);
+        assert_eq!(tiles[2].bounds, Bounds::new(5., 0., 0., -5.));
+        assert_eq!(placemarks(&tiles[2]), 1);
+        assert_eq!(placemarks(&tiles[3]), 2);
+        assert!(hrefs(&tiles[3]).is_empty());
+
+        let kml = Kml::KmlDocument(tiles[0].document.clone()).to_string();
+        assert!(kml.contains(
+            "<Region><LatLonAltBox><north>10</north><south>-10</south><east>10</east>\
+             <west>-10</west></LatLonAltBox><Lod><minLodPixels>128</minLodPixels>\
+             <maxLodPixels>-1</maxLodPixels></Lod></Region>"
+        ));
+        assert!(kml.contains(
+            "<Link><href>1/0/0.kml</href><viewRefreshMode>onRegion</viewRefreshMode></Link>"
+        ));
+    }
+
+    #[test]
+    fn test_max_level() {
+        let input = (0..10).map(|_| point(1., 1.));
+        let config = SuperOverlayConfig::default().max_features(1).max_level(2);
+        let tiles = from_placemarks(input, &config);
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(placemarks(&tiles[2]), 8);
+    }
+
+    #[test]
+    fn test_raster() {
+        let mut hrefs_seen = Vec::new();
+        let tiles = raster(
+            Bounds::default(),
+            3,
+            |id| {
+                hrefs_seen.push(id.path());
+                format!("{}.png", id.level)
+            },
+            &SuperOverlayConfig::default(),
+        );
+        assert_eq!(tiles.len(), 1 + 4 + 16);
+        assert_eq!(hrefs_seen.len(), tiles.len());
+        assert_eq!(hrefs(&tiles[0]).len(), 4);
+        assert!(hrefs(&tiles[20]).is_empty());
+        assert_eq!(
+            tiles[20].id,
+            TileId {
+                level: 2,
+                x: 3,
+                y: 3
+            }
+        );
+
+        let kml = Kml::KmlDocument(tiles[1].document.clone()).to_string();
+        assert!(
+            kml.contains("<GroundOverlay><drawOrder>1</drawOrder><Icon><href>1.png</href></Icon>")
+        );
+        assert!(kml.contains(
+            "<LatLonBox><north>90</north><south>0</south><east>0</east><west>-180</west></LatLonBox>"
+        ));
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_write_kmz() {
+        let tiles = from_placemarks(
+            vec![point(1., 1.), point(-1., -1.)],
+            &SuperOverlayConfig::default().max_features(1),
+        );
+        let kmz = write_kmz(&tiles, std::io::Cursor::new(Vec::new())).unwrap();
+        let mut archive = zip::ZipArchive::new(kmz).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"doc.kml"));
+        assert!(archive.by_name("1/0/1.kml").is_ok());
+    }
+}
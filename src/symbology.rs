@@ -0,0 +1,374 @@
+//! Module for resolving the styles of placemarks into renderer-agnostic symbology
+//!
+//! A placemark's style is its shared style from `styleUrl`, following `StyleMap`s to their
+//! `normal` style, with the substyles of any inline `Style` taking precedence. Colors are converted
+//! from KML's `aabbggrr` to [`Rgba`](struct.Rgba.html), with `random` color modes applied.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::symbology::{Rgba, Symbolizer};
+//! use kml::Kml;
+//!
+//! let kml: Kml = r##"
+//! <Document>
+//!   <Style id="red">
+//!     <LineStyle><color>ff0000ff</color><width>2</width></LineStyle>
+//!   </Style>
+//!   <Placemark>
+//!     <styleUrl>#red</styleUrl>
+//!     <LineString><coordinates>1,1 2,2</coordinates></LineString>
+//!   </Placemark>
+//! </Document>"##
+//!     .parse()
+//!     .unwrap();
+//! let mut symbolizer = Symbolizer::new(&kml);
+//! let feature = kml.features().next().unwrap();
+//! let symbology = symbolizer.symbology(feature.placemark);
+//! assert_eq!(symbology.stroke, Rgba::new(255, 0, 0, 255));
+//! assert_eq!(symbology.stroke_width, 2.);
+//! ```
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::types::{
+    ColorMode, CoordType, Element, Icon, IconStyle, LabelStyle, LineStyle, Node, Placemark,
+    PolyStyle, Style, Vec2,
+};
+use crate::writer::shared_styles;
+use crate::Kml;
+
+/// Color with 8-bit red, green, blue, and alpha channels
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const WHITE: Rgba = Rgba::new(255, 255, 255, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Rgba { r, g, b, a }
+    }
+
+    /// Parses a KML color, which is hex in `aabbggrr` order
+    pub fn from_kml(color: &str) -> Option<Self> {
+        let color = color.trim().trim_start_matches('#');
+        if color.len() != 8 || !color.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&color[i..i + 2], 16).ok();
+        Some(Rgba {
+            a: channel(0)?,
+            b: channel(2)?,
+            g: channel(4)?,
+            r: channel(6)?,
+        })
+    }
+}
+
+/// Symbology of a placemark resolved from its styles, with KML defaults for anything unset
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbology {
+    /// Polygon fill, `None` if the `PolyStyle` disables filling
+    pub fill: Option<Rgba>,
+    /// Line and polygon outline color
+    pub stroke: Rgba,
+    /// Line and polygon outline width in pixels
+    pub stroke_width: f64,
+    /// Whether polygons have an outline
+    pub outline: bool,
+    pub icon_href: Option<String>,
+    pub icon_color: Rgba,
+    pub icon_scale: f64,
+    /// Icon rotation in degrees clockwise from north
+    pub icon_heading: f64,
+    /// Point of the icon anchored to the placemark, the center if `None`
+    pub icon_anchor: Option<Vec2>,
+    pub label_color: Rgba,
+    pub label_scale: f64,
+}
+
+/// Resolves the symbology of placemarks against the shared styles of a document
+#[derive(Clone, Debug)]
+pub struct Symbolizer {
+    styles: HashMap<String, Style>,
+    /// State for the random color mode, so output is the same for the same input
+    seed: u64,
+}
+
+impl Symbolizer {
+    /// Creates a symbolizer with the `Style` and `StyleMap` elements in `kml` and its containers
+    pub fn new<T: CoordType>(kml: &Kml<T>) -> Self {
+        Symbolizer {
+            styles: shared_styles(std::slice::from_ref(kml)),
+            seed: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    /// Returns the symbology of a placemark, which doesn't need to be in the document the
+    /// symbolizer was created with
+    pub fn symbology<T: CoordType>(&mut self, placemark: &Placemark<T>) -> Symbology {
+        let mut style = placemark
+            .style_url
+            .as_deref()
+            .and_then(|url| url.strip_prefix('#'))
+            .and_then(|id| self.styles.get(id))
+            .cloned()
+            .unwrap_or_default();
+        if let Some(inline) = inline_style(placemark) {
+            style.icon = inline.icon.or(style.icon);
+            style.label = inline.label.or(style.label);
+            style.line = inline.line.or(style.line);
+            style.poly = inline.poly.or(style.poly);
+        }
+
+        let poly = style.poly.unwrap_or_default();
+        let line = style.line.unwrap_or_default();
+        let icon = style.icon.unwrap_or_default();
+        let label = style.label.unwrap_or_default();
+        let fill = self.color(&poly.color, poly.color_mode);
+        Symbology {
+            fill: poly.fill.then_some(fill),
+            stroke: self.color(&line.color, line.color_mode),
+            stroke_width: line.width,
+            outline: poly.outline,
+            icon_color: self.color(&icon.color, icon.color_mode),
+            icon_href: icon.icon.map(|i| i.href),
+            icon_scale: icon.scale,
+            icon_heading: icon.heading,
+            icon_anchor: icon.hot_spot,
+            label_color: self.color(&label.color, label.color_mode),
+            label_scale: label.scale,
+        }
+    }
+
+    /// Parses a color, scaling each color channel by a random amount for the random color mode
+    fn color(&mut self, color: &str, mode: ColorMode) -> Rgba {
+        let color = Rgba::from_kml(color).unwrap_or(Rgba::WHITE);
+        match mode {
+            ColorMode::Normal => color,
+            ColorMode::Random => {
+                let mut scale = |c: u8| (c as u64 * (self.next_random() % 256) / 255) as u8;
+                Rgba::new(scale(color.r), scale(color.g), scale(color.b), color.a)
+            }
+        }
+    }
+
+    /// xorshift64*
+    fn next_random(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32
+    }
+}
+
+/// Returns the `Style` in the placemark, which is read into an `Element`
+fn inline_style<T: CoordType>(placemark: &Placemark<T>) -> Option<Style> {
+    let element = placemark
+        .children
+        .iter()
+        .filter_map(Node::as_element)
+        .find(|e| e.name == "Style")?;
+    let mut style = Style::default();
+    for substyle in element.elements() {
+        match substyle.name.as_str() {
+            "IconStyle" => style.icon = Some(icon_style(substyle)),
+            "LabelStyle" => style.label = Some(label_style(substyle)),
+            "LineStyle" => style.line = Some(line_style(substyle)),
+            "PolyStyle" => style.poly = Some(poly_style(substyle)),
+            _ => {}
+        }
+    }
+    Some(style)
+}
+
+fn child<'a>(element: &'a Element, name: &str) -> Option<&'a Element> {
+    element.elements().find(|e| e.name == name)
+}
+
+fn child_text<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    child(element, name)?.content.as_deref().map(str::trim)
+}
+
+fn child_value<V: FromStr>(element: &Element, name: &str) -> Option<V> {
+    child_text(element, name)?.parse().ok()
+}
+
+fn child_bool(element: &Element, name: &str) -> Option<bool> {
+    match child_text(element, name)? {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn icon_style(element: &Element) -> IconStyle {
+    let default = IconStyle::default();
+    let hot_spot = child(element, "hotSpot").map(|e| Vec2 {
+        x: e.attrs.get("x").and_then(|x| x.parse().ok()).unwrap_or(0.5),
+        y: e.attrs.get("y").and_then(|y| y.parse().ok()).unwrap_or(0.5),
+        xunits: e
+            .attrs
+            .get("xunits")
+            .and_then(|u| u.parse().ok())
+            .unwrap_or_default(),
+        yunits: e
+            .attrs
+            .get("yunits")
+            .and_then(|u| u.parse().ok())
+            .unwrap_or_default(),
+    });
+    IconStyle {
+        scale: child_value(element, "scale").unwrap_or(default.scale),
+        heading: child_value(element, "heading").unwrap_or(default.heading),
+        hot_spot,
+        icon: child(element, "Icon")
+            .and_then(|icon| child_text(icon, "href"))
+            .map(|href| Icon {
+                href: href.to_string(),
+                ..Default::default()
+            }),
+        color: child_value(element, "color").unwrap_or(default.color),
+        color_mode: child_value(element, "colorMode").unwrap_or(default.color_mode),
+        ..default
+    }
+}
+
+fn label_style(element: &Element) -> LabelStyle {
+    let default = LabelStyle::default();
+    LabelStyle {
+        color: child_value(element, "color").unwrap_or(default.color),
+        color_mode: child_value(element, "colorMode").unwrap_or(default.color_mode),
+        scale: child_value(element, "scale").unwrap_or(default.scale),
+        ..default
+    }
+}
+
+fn line_style(element: &Element) -> LineStyle {
+    let default = LineStyle::default();
+    LineStyle {
+        color: child_value(element, "color").unwrap_or(default.color),
+        color_mode: child_value(element, "colorMode").unwrap_or(default.color_mode),
+        width: child_value(element, "width").unwrap_or(default.width),
+        ..default
+    }
+}
+
+fn poly_style(element: &Element) -> PolyStyle {
+    let default = PolyStyle::default();
+    PolyStyle {
+        color: child_value(element, "color").unwrap_or(default.color),
+        color_mode: child_value(element, "colorMode").unwrap_or(default.color_mode),
+        fill: child_bool(element, "fill").unwrap_or(default.fill),
+        outline: child_bool(element, "outline").unwrap_or(default.outline),
+        ..default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgba_from_kml() {
+        assert_eq!(Rgba::from_kml("7f00ff00"), Some(Rgba::new(0, 255, 0, 127)));
+        assert_eq!(Rgba::from_kml("ff0000"), None);
+        assert_eq!(Rgba::from_kml("zz0000ff"), None);
+    }
+
+    #[test]
+    fn test_symbology() {
+        let kml: Kml = r##"<Document>
+            <Style id="base">
+              <LineStyle><color>ff0000ff</color><width>3</width></LineStyle>
+              <PolyStyle><color>7f00ff00</color><outline>0</outline></PolyStyle>
+              <IconStyle>
+                <scale>2</scale>
+                <Icon><href>pin.png</href></Icon>
+                <hotSpot x="0.5" y="0" xunits="fraction" yunits="fraction"/>
+              </IconStyle>
+            </Style>
+            <StyleMap id="map">
+              <Pair><key>normal</key><styleUrl>#base</styleUrl></Pair>
+              <Pair><key>highlight</key><styleUrl>#missing</styleUrl></Pair>
+            </StyleMap>
+            <Placemark>
+              <styleUrl>#map</styleUrl>
+              <Style><PolyStyle><fill>0</fill></PolyStyle></Style>
+            </Placemark>
+            <Placemark><styleUrl>#missing</styleUrl></Placemark>
+          </Document>"##
+            .parse()
+            .unwrap();
+        let mut symbolizer = Symbolizer::new(&kml);
+        let features: Vec<_> = kml.features().collect();
+
+        let symbology = symbolizer.symbology(features[0].placemark);
+        assert_eq!(symbology.stroke, Rgba::new(255, 0, 0, 255));
+        assert_eq!(symbology.stroke_width, 3.);
+        // The inline PolyStyle replaces the shared one
+        assert_eq!(symbology.fill, None);
+        assert!(symbology.outline);
+        assert_eq!(symbology.icon_href.as_deref(), Some("pin.png"));
+        assert_eq!(symbology.icon_scale, 2.);
+        assert_eq!(symbology.icon_anchor.map(|a| (a.x, a.y)), Some((0.5, 0.)));
+
+        assert_eq!(
+            symbolizer.symbology(features[1].placemark),
+            Symbology {
+                fill: Some(Rgba::WHITE),
+                stroke: Rgba::WHITE,
+                stroke_width: 1.,
+                outline: true,
+                icon_href: None,
+                icon_color: Rgba::WHITE,
+                icon_scale: 1.,
+                icon_heading: 0.,
+                icon_anchor: None,
+                label_color: Rgba::WHITE,
+                label_scale: 1.,
+            }
+        );
+    }
+
+    #[test]
+    fn test_random_color_mode() {
+        let placemark: Placemark = Placemark {
+            children: vec![Node::Element(Element {
+                name: "Style".to_string(),
+                children: vec![Node::Element(Element {
+                    name: "LineStyle".to_string(),
+                    children: vec![
+                        Node::Element(Element {
+                            name: "color".to_string(),
+                            content: Some("80ffffff".to_string()),
+                            ..Default::default()
+                        }),
+                        Node::Element(Element {
+                            name: "colorMode".to_string(),
+                            content: Some("random".to_string()),
+                            ..Default::default()
+                        }),
+                    ],
+                    ..Default::default()
+                })],
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+        let mut symbolizer = Symbolizer::new(&Kml::<f64>::Placemark(placemark.clone()));
+        let first = symbolizer.symbology(&placemark).stroke;
+        let second = symbolizer.symbology(&placemark).stroke;
+        assert_eq!(first.a, 0x80);
+        assert_ne!(first, second);
+
+        // The same sequence of colors is produced for the same input
+        let mut symbolizer = Symbolizer::new(&Kml::<f64>::Placemark(placemark.clone()));
+        assert_eq!(symbolizer.symbology(&placemark).stroke, first);
+    }
+}
@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests
+use crate::types::{Element, Geometry, Placemark, Point};
+
+/// Element with text content
+pub(crate) fn text(name: &str, content: &str) -> Element {
+    Element {
+        name: name.to_string(),
+        content: Some(content.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Placemark without a geometry
+pub(crate) fn placemark(name: &str) -> Placemark {
+    Placemark {
+        name: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Unnamed placemark with a `Point`
+pub(crate) fn point(x: f64, y: f64) -> Placemark {
+    Placemark {
+        geometry: Some(Geometry::Point(Point::new(x, y, None))),
+        ..Default::default()
+    }
+}
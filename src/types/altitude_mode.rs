@@ -6,6 +6,7 @@ use crate::errors::Error;
 /// `kml:altitudeMode`, [9.20](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#322) in the
 /// KML specification
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AltitudeMode {
     #[default]
     ClampToGround,
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use crate::types::{
+    CoordType, Document, Folder, Kml, KmlDocument, KmlVersion, Placemark, Style, StyleMap,
+};
+
+/// Builder assembling a [`KmlDocument`](struct.KmlDocument.html) with shared styles, nested
+/// folders, and placemarks in the order they're added
+///
+/// # Example
+///
+/// ```
+/// use kml::types::{DocumentBuilder, Geometry, Placemark, Point, Style};
+/// use kml::Kml;
+///
+/// let placemark = |name: &str, x: f64| Placemark {
+///     name: Some(name.to_string()),
+///     geometry: Some(Geometry::Point(Point::new(x, 0., None))),
+///     style_url: Some("#site".to_string()),
+///     ..Default::default()
+/// };
+/// let document = DocumentBuilder::new()
+///     .name("Report")
+///     .style(Style {
+///         id: Some("site".to_string()),
+///         ..Default::default()
+///     })
+///     .folder("Sites", |f| {
+///         f.placemark(placemark("a", 1.))
+///             .folder("Closed", |f| f.placemark(placemark("b", 2.)))
+///     })
+///     .build();
+///
+/// let kml = Kml::KmlDocument(document).to_string();
+/// assert!(kml.contains("<Folder><name>Sites</name><Placemark><name>a</name>"));
+/// assert!(kml.contains("<Folder><name>Closed</name><Placemark><name>b</name>"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentBuilder<T: CoordType = f64> {
+    version: KmlVersion,
+    document: Document<T>,
+}
+
+/// Builder for a `Folder` in a [`DocumentBuilder`](struct.DocumentBuilder.html), see
+/// [`DocumentBuilder::folder`](struct.DocumentBuilder.html#method.folder)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FolderBuilder<T: CoordType = f64> {
+    folder: Folder<T>,
+}
+
+/// Adds the methods shared by the document and folder builders
+macro_rules! impl_contents_methods {
+    ($($name:ident.$field:ident),*) => {
+        $(
+            impl<T: CoordType> $name<T> {
+                /// Sets the `id` attribute
+                pub fn id(mut self, id: impl Into<String>) -> Self {
+                    self.$field.attrs.insert("id".to_string(), id.into());
+                    self
+                }
+
+                /// Sets the `name` element
+                pub fn name(mut self, name: impl Into<String>) -> Self {
+                    self.$field.name = Some(name.into());
+                    self
+                }
+
+                /// Sets the `description` element
+                pub fn description(mut self, description: impl Into<String>) -> Self {
+                    self.$field.description = Some(description.into());
+                    self
+                }
+
+                /// Adds a style, which placemarks anywhere in the document can use by its id
+                pub fn style(self, style: Style) -> Self {
+                    self.element(Kml::Style(style))
+                }
+
+                /// Adds a style map, which placemarks anywhere in the document can use by its id
+                pub fn style_map(self, style_map: StyleMap) -> Self {
+                    self.element(Kml::StyleMap(style_map))
+                }
+
+                /// Adds a placemark
+                pub fn placemark(self, placemark: Placemark<T>) -> Self {
+                    self.element(Kml::Placemark(placemark))
+                }
+
+                /// Adds placemarks
+                pub fn placemarks(mut self, placemarks: impl IntoIterator<Item = Placemark<T>>) -> Self {
+                    self.$field.elements.extend(placemarks.into_iter().map(Kml::Placemark));
+                    self
+                }
+
+                /// Adds a folder with the name, filled in by `build`
+                pub fn folder(
+                    self,
+                    name: impl Into<String>,
+                    build: impl FnOnce(FolderBuilder<T>) -> FolderBuilder<T>,
+                ) -> Self {
+                    self.element(build(FolderBuilder::new().name(name)).build())
+                }
+
+                /// Adds any other element, such as a `NetworkLink` or `GroundOverlay`
+                pub fn element(mut self, element: Kml<T>) -> Self {
+                    self.$field.elements.push(element);
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_contents_methods!(DocumentBuilder.document, FolderBuilder.folder);
+
+impl<T: CoordType> Default for DocumentBuilder<T> {
+    fn default() -> Self {
+        DocumentBuilder {
+            version: KmlVersion::V22,
+            document: Document::default(),
+        }
+    }
+}
+
+impl<T: CoordType> DocumentBuilder<T> {
+    /// Creates a builder for a KML 2.2 document
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the version of the document, which is KML 2.2 by default
+    pub fn version(mut self, version: KmlVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the document with a single `Document` element holding the contents
+    pub fn build(self) -> KmlDocument<T> {
+        KmlDocument {
+            version: self.version,
+            attrs: HashMap::new(),
+            elements: vec![Kml::Document(self.document)],
+        }
+    }
+}
+
+impl<T: CoordType> Default for FolderBuilder<T> {
+    fn default() -> Self {
+        FolderBuilder {
+            folder: Folder::default(),
+        }
+    }
+}
+
+impl<T: CoordType> FolderBuilder<T> {
+    /// Creates a builder for a folder without a name
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the `Folder` element
+    pub fn build(self) -> Kml<T> {
+        Kml::Folder(self.folder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::placemark;
+
+    #[test]
+    fn test_document_builder() {
+        let style = Style {
+            id: Some("s".to_string()),
+            ..Default::default()
+        };
+        let document = DocumentBuilder::new()
+            .style(style.clone())
+            .description("Generated")
+            .name("Report")
+            .id("report")
+            .folder("Roads", |f| {
+                f.placemarks([placemark("a"), placemark("b")])
+                    .folder("Closed", |f| f.description("None yet"))
+            })
+            .placemark(placemark("c"))
+            .build();
+
+        assert_eq!(
+            document,
+            KmlDocument {
+                version: KmlVersion::V22,
+                attrs: HashMap::new(),
+                elements: vec![Kml::Document(Document {
+                    name: Some("Report".to_string()),
+                    description: Some("Generated".to_string()),
+                    attrs: HashMap::from([("id".to_string(), "report".to_string())]),
+                    elements: vec![
+                        Kml::Style(style),
+                        Kml::Folder(Folder {
+                            name: Some("Roads".to_string()),
+                            elements: vec![
+                                Kml::Placemark(placemark("a")),
+                                Kml::Placemark(placemark("b")),
+                                Kml::Folder(Folder {
+                                    name: Some("Closed".to_string()),
+                                    description: Some("None yet".to_string()),
+                                    ..Default::default()
+                                }),
+                            ],
+                            ..Default::default()
+                        }),
+                        Kml::Placemark(placemark("c")),
+                    ],
+                    ..Default::default()
+                })],
+            }
+        );
+    }
+
+    #[test]
+    fn test_folder_builder() {
+        let folder: Kml = FolderBuilder::new().placemark(placemark("a")).build();
+        assert_eq!(
+            folder,
+            Kml::Folder(Folder {
+                elements: vec![Kml::Placemark(placemark("a"))],
+                ..Default::default()
+            })
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use crate::types::{
+    Coord, CoordType, Document, Folder, Geometry, Kml, KmlDocument, LineString, LinearRing,
+    Location, MultiGeometry, MultiTrack, Orientation, Placemark, Point, Polygon, Scale, Track,
+};
+
+/// Converts a value between coordinate types, which can't fail between floats, but gives `NaN`
+/// for any other `Float` type that can't represent it
+fn cast<T: CoordType, U: CoordType>(value: T) -> U {
+    U::from(value).unwrap_or_else(U::nan)
+}
+
+impl<T: CoordType> Coord<T> {
+    /// Converts the coordinate to another coordinate type, such as `f32` for rendering
+    pub fn cast<U: CoordType>(&self) -> Coord<U> {
+        Coord {
+            x: cast(self.x),
+            y: cast(self.y),
+            z: self.z.map(cast),
+        }
+    }
+}
+
+impl<T: CoordType> Point<T> {
+    /// Converts the point to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Point<U> {
+        Point {
+            coord: self.coord.cast(),
+            extrude: self.extrude,
+            altitude_mode: self.altitude_mode,
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> LineString<T> {
+    /// Converts the line string to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> LineString<U> {
+        LineString {
+            coords: self.coords.iter().map(Coord::cast).collect(),
+            extrude: self.extrude,
+            tessellate: self.tessellate,
+            altitude_mode: self.altitude_mode,
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> LinearRing<T> {
+    /// Converts the linear ring to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> LinearRing<U> {
+        LinearRing {
+            coords: self.coords.iter().map(Coord::cast).collect(),
+            extrude: self.extrude,
+            tessellate: self.tessellate,
+            altitude_mode: self.altitude_mode,
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Polygon<T> {
+    /// Converts the polygon to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Polygon<U> {
+        Polygon {
+            outer: self.outer.cast(),
+            inner: self.inner.iter().map(LinearRing::cast).collect(),
+            extrude: self.extrude,
+            tessellate: self.tessellate,
+            altitude_mode: self.altitude_mode,
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Orientation<T> {
+    /// Converts the orientation to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Orientation<U> {
+        Orientation {
+            roll: cast(self.roll),
+            tilt: cast(self.tilt),
+            heading: cast(self.heading),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Scale<T> {
+    /// Converts the scale to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Scale<U> {
+        Scale {
+            x: cast(self.x),
+            y: cast(self.y),
+            z: cast(self.z),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Location<T> {
+    /// Converts the location to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Location<U> {
+        Location {
+            latitude: cast(self.latitude),
+            longitude: cast(self.longitude),
+            altitude: cast(self.altitude),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Track<T> {
+    /// Converts the track, including its angles, to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Track<U> {
+        Track {
+            altitude_mode: self.altitude_mode,
+            whens: self.whens.clone(),
+            coords: self.coords.iter().map(Coord::cast).collect(),
+            angles: self.angles.iter().map(Orientation::cast).collect(),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> MultiTrack<T> {
+    /// Converts the tracks to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> MultiTrack<U> {
+        MultiTrack {
+            altitude_mode: self.altitude_mode,
+            interpolate: self.interpolate,
+            tracks: self.tracks.iter().map(Track::cast).collect(),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> MultiGeometry<T> {
+    /// Converts the geometries to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> MultiGeometry<U> {
+        MultiGeometry {
+            geometries: self.geometries.iter().map(Geometry::cast).collect(),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Geometry<T> {
+    /// Converts the geometry to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Geometry<U> {
+        match self {
+            Geometry::Point(p) => Geometry::Point(p.cast()),
+            Geometry::LineString(l) => Geometry::LineString(l.cast()),
+            Geometry::LinearRing(l) => Geometry::LinearRing(l.cast()),
+            Geometry::Polygon(p) => Geometry::Polygon(p.cast()),
+            Geometry::MultiGeometry(g) => Geometry::MultiGeometry(g.cast()),
+            Geometry::Track(t) => Geometry::Track(t.cast()),
+            Geometry::MultiTrack(t) => Geometry::MultiTrack(t.cast()),
+            Geometry::Element(e) => Geometry::Element(e.clone()),
+        }
+    }
+}
+
+impl<T: CoordType> Placemark<T> {
+    /// Converts the placemark's geometry to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Placemark<U> {
+        Placemark {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            geometry: self.geometry.as_ref().map(Geometry::cast),
+            style_url: self.style_url.clone(),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<T: CoordType> Document<T> {
+    /// Converts the geometries in the document to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Document<U> {
+        Document {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            style_url: self.style_url.clone(),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+            children: self.children.clone(),
+            elements: self.elements.iter().map(Kml::cast).collect(),
+        }
+    }
+}
+
+impl<T: CoordType> Folder<T> {
+    /// Converts the geometries in the folder to another coordinate type
+    pub fn cast<U: CoordType>(&self) -> Folder<U> {
+        Folder {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            style_url: self.style_url.clone(),
+            attrs: self.attrs.clone(),
+            namespace: self.namespace.clone(),
+            children: self.children.clone(),
+            elements: self.elements.iter().map(Kml::cast).collect(),
+        }
+    }
+}
+
+impl<T: CoordType> KmlDocument<T> {
+    /// Converts the document to another coordinate type, see [`Kml::cast`](enum.Kml.html#method.cast)
+    pub fn cast<U: CoordType>(&self) -> KmlDocument<U> {
+        KmlDocument {
+            version: self.version,
+            attrs: self.attrs.clone(),
+            elements: self.elements.iter().map(Kml::cast).collect(),
+        }
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Converts the whole tree to another coordinate type, such as data read as `f64` to the `f32`
+    /// of a rendering pipeline, rounding to the nearest value of the new type
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let kml: Kml = r#"
+    /// <Folder>
+    ///   <Placemark><Point><coordinates>1.5,2.25,100</coordinates></Point></Placemark>
+    /// </Folder>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// let kml: Kml<f32> = kml.cast();
+    /// assert!(kml.to_string().contains("<coordinates>1.5,2.25,100</coordinates>"));
+    /// ```
+    pub fn cast<U: CoordType>(&self) -> Kml<U> {
+        match self {
+            Kml::KmlDocument(d) => Kml::KmlDocument(d.cast()),
+            Kml::Scale(s) => Kml::Scale(s.cast()),
+            Kml::Orientation(o) => Kml::Orientation(o.cast()),
+            Kml::Point(p) => Kml::Point(p.cast()),
+            Kml::Location(l) => Kml::Location(l.cast()),
+            Kml::LineString(l) => Kml::LineString(l.cast()),
+            Kml::LinearRing(l) => Kml::LinearRing(l.cast()),
+            Kml::Polygon(p) => Kml::Polygon(p.cast()),
+            Kml::MultiGeometry(g) => Kml::MultiGeometry(g.cast()),
+            Kml::Track(t) => Kml::Track(t.cast()),
+            Kml::MultiTrack(t) => Kml::MultiTrack(t.cast()),
+            Kml::Placemark(p) => Kml::Placemark(p.cast()),
+            Kml::Tour(t) => Kml::Tour(t.clone()),
+            Kml::Document(d) => Kml::Document(d.cast()),
+            Kml::Folder(f) => Kml::Folder(f.cast()),
+            Kml::Style(s) => Kml::Style(s.clone()),
+            Kml::StyleMap(s) => Kml::StyleMap(s.clone()),
+            Kml::Pair(p) => Kml::Pair(p.clone()),
+            Kml::BalloonStyle(s) => Kml::BalloonStyle(s.clone()),
+            Kml::IconStyle(s) => Kml::IconStyle(s.clone()),
+            Kml::Icon(i) => Kml::Icon(i.clone()),
+            Kml::LabelStyle(s) => Kml::LabelStyle(s.clone()),
+            Kml::LineStyle(s) => Kml::LineStyle(s.clone()),
+            Kml::PolyStyle(s) => Kml::PolyStyle(s.clone()),
+            Kml::ListStyle(s) => Kml::ListStyle(s.clone()),
+            Kml::LinkTypeIcon(i) => Kml::LinkTypeIcon(i.clone()),
+            Kml::Link(l) => Kml::Link(l.clone()),
+            Kml::ResourceMap(r) => Kml::ResourceMap(r.clone()),
+            Kml::Alias(a) => Kml::Alias(a.clone()),
+            Kml::SchemaData(d) => Kml::SchemaData(d.clone()),
+            Kml::SimpleArrayData(d) => Kml::SimpleArrayData(d.clone()),
+            Kml::SimpleData(d) => Kml::SimpleData(d.clone()),
+            Kml::Element(e) => Kml::Element(e.clone()),
+            Kml::Comment(c) => Kml::Comment(c.clone()),
+            Kml::Extension(e) => Kml::Extension(e.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_cast() {
+        let track = Track {
+            whens: vec!["2020-01-01T00:00:00Z".to_string()],
+            coords: vec![Coord::new(1.1, 2.2, Some(3.3))],
+            angles: vec![Orientation::new(1., 2., 3.)],
+            ..Default::default()
+        };
+        let kml: Kml = Kml::Document(Document {
+            attrs: HashMap::from([("id".to_string(), "doc".to_string())]),
+            elements: vec![
+                Kml::Placemark(Placemark {
+                    name: Some("a".to_string()),
+                    geometry: Some(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                        Geometry::Track(track),
+                        Geometry::Point(Point::new(0.1, 0.2, None)),
+                    ]))),
+                    ..Default::default()
+                }),
+                Kml::Location(Location::new(1e300, 0., 0.)),
+            ],
+            ..Default::default()
+        });
+
+        let expected: Kml<f32> = Kml::Document(Document {
+            attrs: HashMap::from([("id".to_string(), "doc".to_string())]),
+            elements: vec![
+                Kml::Placemark(Placemark {
+                    name: Some("a".to_string()),
+                    geometry: Some(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                        Geometry::Track(Track {
+                            whens: vec!["2020-01-01T00:00:00Z".to_string()],
+                            coords: vec![Coord::new(1.1, 2.2, Some(3.3))],
+                            angles: vec![Orientation::new(1., 2., 3.)],
+                            ..Default::default()
+                        }),
+                        Geometry::Point(Point::new(0.1, 0.2, None)),
+                    ]))),
+                    ..Default::default()
+                }),
+                Kml::Location(Location::new(f32::INFINITY, 0., 0.)),
+            ],
+            ..Default::default()
+        });
+        let cast: Kml<f32> = kml.cast();
+        assert_eq!(cast, expected);
+        assert_eq!(cast.cast::<f64>().cast::<f32>(), expected);
+    }
+}
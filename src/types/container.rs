@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use crate::types::coord::CoordType;
+use crate::types::element::Node;
+use crate::types::kml::Kml;
+
+/// `kml:Document`, a container for features along with the styles and schemas they share
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Document<T: CoordType = f64> {
+    pub name: Option<String>,
+    /// Unescaped description text, or the verbatim inner XML if it contains HTML elements
+    pub description: Option<String>,
+    pub style_url: Option<String>,
+    pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+    /// Other fields of the document without a typed representation, such as `visibility`, `open`,
+    /// `TimeStamp`, `Region`, and `ExtendedData`
+    pub children: Vec<Node>,
+    /// Styles, schemas, and features in the document, in document order
+    pub elements: Vec<Kml<T>>,
+}
+
+/// `kml:Folder`, a container for grouping features
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Folder<T: CoordType = f64> {
+    pub name: Option<String>,
+    /// Unescaped description text, or the verbatim inner XML if it contains HTML elements
+    pub description: Option<String>,
+    pub style_url: Option<String>,
+    pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+    /// Other fields of the folder without a typed representation, such as `visibility`, `open`,
+    /// `TimeStamp`, `Region`, and `ExtendedData`
+    pub children: Vec<Node>,
+    /// Styles and features in the folder, in document order
+    pub elements: Vec<Kml<T>>,
+}
+
+impl<T: CoordType> Default for Document<T> {
+    fn default() -> Self {
+        Document {
+            name: None,
+            description: None,
+            style_url: None,
+            attrs: HashMap::new(),
+            namespace: None,
+            children: Vec::new(),
+            elements: Vec::new(),
+        }
+    }
+}
+
+impl<T: CoordType> Default for Folder<T> {
+    fn default() -> Self {
+        Folder {
+            name: None,
+            description: None,
+            style_url: None,
+            attrs: HashMap::new(),
+            namespace: None,
+            children: Vec::new(),
+            elements: Vec::new(),
+        }
+    }
+}
@@ -15,6 +15,7 @@ impl<T: Float + Debug> CoordType for T {}
 /// Coordinates are tuples with the third Z value for altitude being optional. Coordinate tuples are
 /// separated by any whitespace character
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coord<T: CoordType = f64> {
     pub x: T,
     pub y: T,
@@ -82,7 +83,7 @@ where
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.trim().split(',');
+        let mut parts = s.trim().split(',').map(str::trim);
         let x_str = parts.next().ok_or(Error::CoordEmpty)?;
         let x: T = x_str
             .parse()
@@ -127,9 +128,61 @@ pub fn coords_from_str<T: CoordType + FromStr>(s: &str) -> Result<Vec<Coord<T>>,
     s.split_whitespace().map(Coord::from_str).collect()
 }
 
+/// Parses multiple coordinates like [`coords_from_str`], also accepting whitespace around the commas
+/// within a tuple, trailing commas, and semicolons between tuples as written by some producers
+///
+/// A comma followed by whitespace continues the tuple unless it already has three values, so
+/// `1, 2, 3 4 ,5` is read as `1,2,3` and `4,5`
+///
+/// # Example
+///
+/// ```
+/// use kml::types::{Coord, coords_from_str_tolerant};
+///
+/// let coords: Vec<Coord> = coords_from_str_tolerant("1, 2, 0,\n3,4,0; 5,6,").unwrap();
+/// assert_eq!(
+///     coords,
+///     vec![
+///         Coord::new(1., 2., Some(0.)),
+///         Coord::new(3., 4., Some(0.)),
+///         Coord::new(5., 6., None),
+///     ]
+/// );
+/// ```
+pub fn coords_from_str_tolerant<T: CoordType + FromStr>(s: &str) -> Result<Vec<Coord<T>>, Error> {
+    tolerant_tuples(s)
+        .iter()
+        .map(|t| Coord::from_str(t))
+        .collect()
+}
+
+/// Splits coordinates into tuples, joining values separated by a comma and whitespace
+pub(crate) fn tolerant_tuples(s: &str) -> Vec<String> {
+    let mut tuples: Vec<String> = Vec::new();
+    let mut continues = false;
+    for token in s
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .filter(|t| !t.is_empty())
+    {
+        match tuples.last_mut() {
+            Some(tuple) if continues || token.starts_with(',') => tuple.push_str(token),
+            _ => tuples.push(token.to_string()),
+        }
+        let tuple = tuples.last_mut().unwrap();
+        continues = tuple.ends_with(',') && tuple.matches(',').count() < 3;
+    }
+    for tuple in tuples.iter_mut() {
+        while tuple.ends_with(',') {
+            tuple.pop();
+        }
+    }
+    tuples.retain(|t| !t.is_empty());
+    tuples
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{coords_from_str, Coord};
+    use super::{coords_from_str, coords_from_str_tolerant, Coord};
     use std::str::FromStr;
 
     #[test]
@@ -170,4 +223,22 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_coords_from_str_tolerant() {
+        assert_eq!(
+            coords_from_str_tolerant("1, 2, 3 4 ,5").unwrap(),
+            vec![Coord::new(1., 2., Some(3.)), Coord::new(4., 5., None)]
+        );
+        assert_eq!(
+            coords_from_str_tolerant("1,2,3, 4,5,6,\n;7,8;").unwrap(),
+            vec![
+                Coord::new(1., 2., Some(3.)),
+                Coord::new(4., 5., Some(6.)),
+                Coord::new(7., 8., None)
+            ]
+        );
+        assert_eq!(coords_from_str_tolerant::<f64>(" ; , ").unwrap(), vec![]);
+        assert!(coords_from_str_tolerant::<f64>("1,a").is_err());
+    }
 }
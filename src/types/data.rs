@@ -2,24 +2,31 @@ use std::collections::HashMap;
 
 /// `kml:SchemaData`, [9.5](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#155) in the KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaData {
+    pub schema_url: Option<String>,
     pub data: Vec<SimpleData>,
     pub arrays: Vec<SimpleArrayData>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 /// `kml:SimpleData`, [9.6](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#167) in the KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleData {
     pub name: String,
     pub value: String,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 /// `kml:SimpleArrayData`, [9.7](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#177) in the KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleArrayData {
     pub name: String,
     pub values: Vec<String>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
@@ -2,9 +2,55 @@ use std::collections::HashMap;
 
 /// Generic type used for supporting elements that are extensions or not currently implemented
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Element {
     pub name: String,
+    /// Prefix the element's name was written with, like `atom` in `atom:link`
+    pub prefix: Option<String>,
+    /// Namespace URI the element's name resolved to, if any
+    pub namespace: Option<String>,
     pub attrs: HashMap<String, String>,
     pub content: Option<String>,
-    pub children: Vec<Element>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    /// Child elements, skipping comments
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(Node::as_element)
+    }
+}
+
+/// Child of an element without a typed field for it, which is either an element or an XML
+/// comment kept with [`KmlReaderConfig::comments`](../reader/struct.KmlReaderConfig.html#structfield.comments)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Node {
+    Element(Element),
+    /// Text of a comment, written as `<!--text-->`, with any `--` in the text written as `- -`
+    Comment(String),
+}
+
+impl Node {
+    /// Returns the element, or `None` for a comment
+    pub fn as_element(&self) -> Option<&Element> {
+        match self {
+            Node::Element(e) => Some(e),
+            Node::Comment(_) => None,
+        }
+    }
+
+    /// Returns the element mutably, or `None` for a comment
+    pub fn as_element_mut(&mut self) -> Option<&mut Element> {
+        match self {
+            Node::Element(e) => Some(e),
+            Node::Comment(_) => None,
+        }
+    }
+}
+
+impl From<Element> for Node {
+    fn from(element: Element) -> Self {
+        Node::Element(element)
+    }
 }
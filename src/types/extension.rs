@@ -0,0 +1,85 @@
+use std::any::{Any, TypeId};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::types::element::Element;
+
+/// Type for a vendor extension element, read by a parser registered with
+/// [`KmlReaderConfig::register_parser`](../reader/struct.KmlReaderConfig.html#method.register_parser)
+pub trait KmlExtension: Any + fmt::Debug + Send + Sync {
+    /// Converts the extension back into an element for writing
+    fn to_element(&self) -> Element;
+}
+
+/// Extension element parsed into a downstream type, stored in
+/// [`Kml::Extension`](enum.Kml.html#variant.Extension)
+///
+/// # Example
+///
+/// ```
+/// use kml::types::{Element, Extension, KmlExtension};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Rating(u8);
+///
+/// impl KmlExtension for Rating {
+///     fn to_element(&self) -> Element {
+///         Element {
+///             name: "Rating".to_string(),
+///             content: Some(self.0.to_string()),
+///             ..Default::default()
+///         }
+///     }
+/// }
+///
+/// let extension = Extension::new(Rating(5));
+/// assert_eq!(extension.downcast_ref::<Rating>(), Some(&Rating(5)));
+/// ```
+#[derive(Clone)]
+pub struct Extension(Arc<dyn KmlExtension>);
+
+impl Extension {
+    pub fn new(extension: impl KmlExtension) -> Self {
+        Extension(Arc::new(extension))
+    }
+
+    /// Returns the parsed value if it's an `E`
+    pub fn downcast_ref<E: KmlExtension>(&self) -> Option<&E> {
+        let extension: &dyn Any = self.0.as_ref();
+        extension.downcast_ref()
+    }
+
+    /// Type of the parsed value, used to find writers registered for it
+    pub(crate) fn value_type_id(&self) -> TypeId {
+        let extension: &dyn Any = self.0.as_ref();
+        extension.type_id()
+    }
+
+    /// Converts the extension back into an element, which is how it's written by default
+    pub fn to_element(&self) -> Element {
+        self.0.to_element()
+    }
+}
+
+impl fmt::Debug for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Extension").field(&self.0).finish()
+    }
+}
+
+/// Extensions are serialized as the element they're written as, and can't be deserialized since
+/// the type they were parsed into isn't known
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl serde::Serialize for Extension {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_element().serialize(serializer)
+    }
+}
+
+/// Extensions are equal when they're written as the same element
+impl PartialEq for Extension {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_element() == other.to_element()
+    }
+}
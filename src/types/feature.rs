@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use crate::types::container::{Document, Folder};
+use crate::types::coord::CoordType;
+use crate::types::geometry::Geometry;
+use crate::types::kml::{Kml, KmlDocument};
+use crate::types::placemark::Placemark;
+
+/// Placemark with its `ExtendedData` flattened into `properties`, yielded by
+/// [`Kml::features`](enum.Kml.html#method.features)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Feature<'a, T: CoordType = f64> {
+    pub name: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub style_url: Option<&'a str>,
+    pub geometry: Option<&'a Geometry<T>>,
+    /// Values of `Data` and `SchemaData/SimpleData` elements by name, see
+    /// [`Placemark::properties`](struct.Placemark.html#method.properties)
+    pub properties: HashMap<String, String>,
+    pub placemark: &'a Placemark<T>,
+}
+
+impl<'a, T: CoordType> From<&'a Placemark<T>> for Feature<'a, T> {
+    fn from(placemark: &'a Placemark<T>) -> Self {
+        Feature {
+            name: placemark.name.as_deref(),
+            description: placemark.description.as_deref(),
+            style_url: placemark.style_url.as_deref(),
+            geometry: placemark.geometry.as_ref(),
+            properties: placemark.properties(),
+            placemark,
+        }
+    }
+}
+
+/// Iterator over the placemarks in a tree, created by
+/// [`Kml::features`](enum.Kml.html#method.features)
+pub struct FeatureIter<'a, T: CoordType = f64> {
+    stack: Vec<std::slice::Iter<'a, Kml<T>>>,
+}
+
+impl<'a, T: CoordType> Iterator for FeatureIter<'a, T> {
+    type Item = Feature<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(elements) = self.stack.last_mut() {
+            let Some(element) = elements.next() else {
+                self.stack.pop();
+                continue;
+            };
+            match element {
+                Kml::Placemark(p) => return Some(Feature::from(p)),
+                Kml::KmlDocument(d) => self.stack.push(d.elements.iter()),
+                Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                    self.stack.push(elements.iter())
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Returns an iterator over the placemarks in the tree in document order, descending into
+    /// documents and folders, with their attributes flattened from `ExtendedData`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let kml: Kml = r#"
+    /// <Folder>
+    ///   <Placemark>
+    ///     <name>Trailhead</name>
+    ///     <ExtendedData><Data name="length"><value>5</value></Data></ExtendedData>
+    ///     <Point><coordinates>1,1</coordinates></Point>
+    ///   </Placemark>
+    /// </Folder>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// let feature = kml.features().next().unwrap();
+    /// assert_eq!(feature.name, Some("Trailhead"));
+    /// assert_eq!(feature.properties["length"], "5");
+    /// ```
+    pub fn features(&self) -> FeatureIter<'_, T> {
+        FeatureIter {
+            stack: vec![std::slice::from_ref(self).iter()],
+        }
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Removes the placemarks in the tree for which `f` returns `false`, descending into
+    /// documents and folders, which are kept even if they're left empty
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let mut kml: Kml = r#"
+    /// <Folder>
+    ///   <Placemark><name>a</name><Point><coordinates>1,1</coordinates></Point></Placemark>
+    ///   <Folder><Placemark><name>empty</name></Placemark></Folder>
+    /// </Folder>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// kml.retain_features(|p| p.geometry.is_some());
+    /// let names: Vec<_> = kml.features().filter_map(|f| f.name).collect();
+    /// assert_eq!(names, vec!["a"]);
+    /// ```
+    pub fn retain_features(&mut self, mut f: impl FnMut(&Placemark<T>) -> bool) {
+        retain_placemarks(self, &mut f);
+    }
+}
+
+impl<T: CoordType> KmlDocument<T> {
+    /// Removes the placemarks in the document for which `f` returns `false`, see
+    /// [`Kml::retain_features`](enum.Kml.html#method.retain_features)
+    pub fn retain_features(&mut self, mut f: impl FnMut(&Placemark<T>) -> bool) {
+        retain_in(&mut self.elements, &mut f);
+    }
+}
+
+/// Implements `retain_features`, taking a trait object so the recursion doesn't instantiate it
+/// for ever deeper references to the closure
+fn retain_placemarks<T: CoordType>(kml: &mut Kml<T>, f: &mut dyn FnMut(&Placemark<T>) -> bool) {
+    match kml {
+        Kml::KmlDocument(d) => retain_in(&mut d.elements, f),
+        Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+            retain_in(elements, f)
+        }
+        _ => {}
+    }
+}
+
+fn retain_in<T: CoordType>(elements: &mut Vec<Kml<T>>, f: &mut dyn FnMut(&Placemark<T>) -> bool) {
+    elements.retain_mut(|element| match element {
+        Kml::Placemark(p) => f(p),
+        element => {
+            retain_placemarks(element, f);
+            true
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Coord;
+
+    #[test]
+    fn test_features() {
+        let kml: Kml = r##"<kml xmlns="http://www.opengis.net/kml/2.2"><Document>
+            <Placemark>
+              <name>a</name>
+              <styleUrl>#s</styleUrl>
+              <ExtendedData>
+                <Data name="kind"><displayName>Kind</displayName><value>trail</value></Data>
+                <SchemaData schemaUrl="#schema">
+                  <SimpleData name="length">5</SimpleData>
+                </SchemaData>
+              </ExtendedData>
+              <Point><coordinates>1,1</coordinates></Point>
+            </Placemark>
+            <Style id="s"/>
+            <Folder><Placemark><description>b</description></Placemark></Folder>
+          </Document></kml>"##
+            .parse()
+            .unwrap();
+
+        let features: Vec<_> = kml.features().collect();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].name, Some("a"));
+        assert_eq!(features[0].style_url, Some("#s"));
+        assert!(matches!(
+            features[0].geometry,
+            Some(Geometry::Point(p)) if p.coord == Coord::new(1., 1., None)
+        ));
+        assert_eq!(
+            features[0].properties,
+            HashMap::from([
+                ("kind".to_string(), "trail".to_string()),
+                ("length".to_string(), "5".to_string()),
+            ])
+        );
+        assert_eq!(features[1].name, None);
+        assert_eq!(features[1].description, Some("b"));
+        assert!(features[1].properties.is_empty());
+    }
+
+    #[test]
+    fn test_retain_features() {
+        let placemark = |name: &str| {
+            Kml::Placemark(Placemark {
+                name: Some(name.to_string()),
+                ..Default::default()
+            })
+        };
+        let mut document: KmlDocument = KmlDocument {
+            elements: vec![Kml::Document(Document {
+                elements: vec![
+                    placemark("keep"),
+                    Kml::Folder(Folder {
+                        elements: vec![placemark("drop"), placemark("keep")],
+                        ..Default::default()
+                    }),
+                    Kml::Comment("drop".to_string()),
+                    placemark("drop"),
+                ],
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+        let mut calls = 0;
+        document.retain_features(|p| {
+            calls += 1;
+            p.name.as_deref() == Some("keep")
+        });
+
+        assert_eq!(calls, 4);
+        assert_eq!(
+            document.elements,
+            vec![Kml::Document(Document {
+                elements: vec![
+                    placemark("keep"),
+                    Kml::Folder(Folder {
+                        elements: vec![placemark("keep")],
+                        ..Default::default()
+                    }),
+                    Kml::Comment("drop".to_string()),
+                ],
+                ..Default::default()
+            })]
+        );
+    }
+}
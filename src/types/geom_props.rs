@@ -10,7 +10,7 @@ use crate::types::coord::{Coord, CoordType};
 // tessellate is true, altitudeMode must be clampToGround
 pub(crate) struct GeomProps<T: CoordType + FromStr + Default = f64> {
     pub coords: Vec<Coord<T>>,
-    pub altitude_mode: AltitudeMode,
-    pub extrude: bool,
-    pub tessellate: bool,
+    pub altitude_mode: Option<AltitudeMode>,
+    pub extrude: Option<bool>,
+    pub tessellate: Option<bool>,
 }
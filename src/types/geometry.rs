@@ -1,10 +1,11 @@
-use crate::types::coord::CoordType;
+use crate::types::coord::{Coord, CoordType};
 use crate::types::element::Element;
 use crate::types::line_string::LineString;
 use crate::types::linear_ring::LinearRing;
 use crate::types::multi_geometry::MultiGeometry;
 use crate::types::point::Point;
 use crate::types::polygon::Polygon;
+use crate::types::track::{MultiTrack, Track};
 
 /// Enum for elements in `kml:AbstractGeometryGroup`, [10.1](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#432)
 /// in the KML specification
@@ -12,11 +13,51 @@ use crate::types::polygon::Polygon;
 /// `kml:Model` is currently represented by a placeholder element
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Geometry<T: CoordType = f64> {
     Point(Point<T>),
     LineString(LineString<T>),
     LinearRing(LinearRing<T>),
     Polygon(Polygon<T>),
     MultiGeometry(MultiGeometry<T>),
+    Track(Track<T>),
+    MultiTrack(MultiTrack<T>),
     Element(Element), // Currently just a stand-in for Model
 }
+
+impl<T: CoordType> Geometry<T> {
+    /// Iterates over all of the geometry's coordinates, including those of nested geometries
+    pub(crate) fn coords(&self) -> Box<dyn Iterator<Item = &Coord<T>> + '_> {
+        match self {
+            Geometry::Point(p) => Box::new(std::iter::once(&p.coord)),
+            Geometry::LineString(l) => Box::new(l.coords.iter()),
+            Geometry::LinearRing(l) => Box::new(l.coords.iter()),
+            Geometry::Track(t) => Box::new(t.coords.iter()),
+            Geometry::Polygon(p) => Box::new(
+                p.outer
+                    .coords
+                    .iter()
+                    .chain(p.inner.iter().flat_map(|r| &r.coords)),
+            ),
+            Geometry::MultiTrack(t) => Box::new(t.tracks.iter().flat_map(|t| &t.coords)),
+            Geometry::MultiGeometry(g) => Box::new(g.geometries.iter().flat_map(Geometry::coords)),
+            Geometry::Element(_) => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// Whether the geometry has coordinates and they all have a `z` value
+    pub(crate) fn has_z(&self) -> bool {
+        let mut coords = match self {
+            Geometry::MultiGeometry(g) => {
+                return !g.geometries.is_empty() && g.geometries.iter().all(Geometry::has_z)
+            }
+            Geometry::Element(_) => return false,
+            _ => self.coords(),
+        };
+        let mut any = false;
+        coords.all(|c| {
+            any = true;
+            c.z.is_some()
+        }) && any
+    }
+}
@@ -0,0 +1,154 @@
+use crate::types::{CoordType, Document, Folder, Kml, Placemark, Style, StyleMap};
+
+/// Depth-first iterator over the elements of a tree, created by
+/// [`Kml::iter`](enum.Kml.html#method.iter)
+#[derive(Clone, Debug)]
+pub struct KmlIter<'a, T: CoordType = f64> {
+    stack: Vec<std::slice::Iter<'a, Kml<T>>>,
+}
+
+impl<'a, T: CoordType> Iterator for KmlIter<'a, T> {
+    type Item = &'a Kml<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(elements) = self.stack.last_mut() {
+            let Some(element) = elements.next() else {
+                self.stack.pop();
+                continue;
+            };
+            match element {
+                Kml::KmlDocument(d) => self.stack.push(d.elements.iter()),
+                Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                    self.stack.push(elements.iter())
+                }
+                _ => {}
+            }
+            return Some(element);
+        }
+        None
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Returns an iterator over the element and everything in it in document order, descending
+    /// into documents and folders
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let kml: Kml = r#"
+    /// <Document>
+    ///   <Style id="s"/>
+    ///   <Folder>
+    ///     <Placemark><name>a</name></Placemark>
+    ///     <Placemark><name>b</name></Placemark>
+    ///   </Folder>
+    /// </Document>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(kml.iter().filter(|e| matches!(e, Kml::Folder(_))).count(), 1);
+    /// let names: Vec<_> = kml.iter_placemarks().filter_map(|p| p.name.as_deref()).collect();
+    /// assert_eq!(names, vec!["a", "b"]);
+    /// assert_eq!(kml.iter_styles().next().unwrap().id.as_deref(), Some("s"));
+    /// ```
+    pub fn iter(&self) -> KmlIter<'_, T> {
+        KmlIter {
+            stack: vec![std::slice::from_ref(self).iter()],
+        }
+    }
+
+    /// Returns an iterator over the placemarks in the tree, see [`iter`](#method.iter) and
+    /// [`features`](#method.features) for their `ExtendedData`
+    pub fn iter_placemarks(&self) -> impl Iterator<Item = &Placemark<T>> + '_ {
+        self.iter().filter_map(|e| match e {
+            Kml::Placemark(p) => Some(p),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over the shared styles in the tree, see [`iter`](#method.iter)
+    pub fn iter_styles(&self) -> impl Iterator<Item = &Style> + '_ {
+        self.iter().filter_map(|e| match e {
+            Kml::Style(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over the style maps in the tree, see [`iter`](#method.iter)
+    pub fn iter_style_maps(&self) -> impl Iterator<Item = &StyleMap> + '_ {
+        self.iter().filter_map(|e| match e {
+            Kml::StyleMap(s) => Some(s),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::KmlDocument;
+
+    #[test]
+    fn test_iter() {
+        let placemark = |name: &str| {
+            Kml::Placemark(Placemark {
+                name: Some(name.to_string()),
+                ..Default::default()
+            })
+        };
+        let folder = |elements| {
+            Kml::Folder(Folder {
+                elements,
+                ..Default::default()
+            })
+        };
+        let kml: Kml = Kml::KmlDocument(KmlDocument {
+            elements: vec![Kml::Document(Document {
+                elements: vec![
+                    Kml::StyleMap(StyleMap::default()),
+                    folder(vec![placemark("a"), folder(vec![placemark("b")])]),
+                    folder(vec![]),
+                    placemark("c"),
+                ],
+                ..Default::default()
+            })],
+            ..Default::default()
+        });
+
+        let kinds: Vec<_> = kml
+            .iter()
+            .map(|e| match e {
+                Kml::KmlDocument(_) => "kml",
+                Kml::Document(_) => "Document",
+                Kml::Folder(_) => "Folder",
+                Kml::Placemark(_) => "Placemark",
+                Kml::StyleMap(_) => "StyleMap",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "kml",
+                "Document",
+                "StyleMap",
+                "Folder",
+                "Placemark",
+                "Folder",
+                "Placemark",
+                "Folder",
+                "Placemark"
+            ]
+        );
+        let names: Vec<_> = kml
+            .iter_placemarks()
+            .filter_map(|p| p.name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(kml.iter_styles().count(), 0);
+        assert_eq!(kml.iter_style_maps().count(), 1);
+        assert_eq!(placemark("d").iter().count(), 1);
+    }
+}
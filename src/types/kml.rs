@@ -3,17 +3,21 @@ use std::str::FromStr;
 
 use crate::errors::Error;
 use crate::types::{
-    Alias, BalloonStyle, CoordType, Element, Icon, IconStyle, LabelStyle, LineString, LineStyle,
-    LinearRing, Link, LinkTypeIcon, ListStyle, Location, MultiGeometry, Orientation, Pair,
-    Placemark, Point, PolyStyle, Polygon, ResourceMap, Scale, SchemaData, SimpleArrayData,
-    SimpleData, Style, StyleMap,
+    Alias, BalloonStyle, CoordType, Document, Element, Extension, Folder, Icon, IconStyle,
+    LabelStyle, LineString, LineStyle, LinearRing, Link, LinkTypeIcon, ListStyle, Location,
+    MultiGeometry, MultiTrack, Orientation, Pair, Placemark, Point, PolyStyle, Polygon,
+    ResourceMap, Scale, SchemaData, SimpleArrayData, SimpleData, Style, StyleMap, Tour, Track,
 };
 
 /// Enum for representing the KML version being parsed
 ///
 /// According to <http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#7> namespace for 2.3
 /// is unchanged since it should be backwards-compatible
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+///
+/// The legacy Google Earth namespaces `http://earth.google.com/kml/2.0`, `2.1`, and `2.2` are read
+/// as `V22`, the closest supported version
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum KmlVersion {
     #[default]
@@ -27,18 +31,32 @@ pub enum KmlVersion {
 impl FromStr for KmlVersion {
     type Err = Error;
 
-    // TODO: Support different Google Earth implementations? Only check end?
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "http://www.opengis.net/kml/2.2" => Ok(Self::V22),
+            "http://www.opengis.net/kml/2.2"
+            | "http://earth.google.com/kml/2.0"
+            | "http://earth.google.com/kml/2.1"
+            | "http://earth.google.com/kml/2.2" => Ok(Self::V22),
             "http://www.opengis.net/kml/2.3" => Ok(Self::V23),
             v => Err(Error::InvalidKmlVersion(v.to_string())),
         }
     }
 }
 
+impl KmlVersion {
+    /// Returns the namespace URI declared on the `kml` root element for this version
+    pub fn namespace(&self) -> Option<&'static str> {
+        match self {
+            Self::V22 => Some("http://www.opengis.net/kml/2.2"),
+            Self::V23 => Some("http://www.opengis.net/kml/2.3"),
+            Self::Unknown => None,
+        }
+    }
+}
+
 /// Container for KML root element
 #[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KmlDocument<T: CoordType = f64> {
     pub version: KmlVersion,
     pub attrs: HashMap<String, String>,
@@ -48,6 +66,7 @@ pub struct KmlDocument<T: CoordType = f64> {
 /// Enum for representing any KML element
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Kml<T: CoordType = f64> {
     KmlDocument(KmlDocument<T>),
@@ -59,15 +78,12 @@ pub enum Kml<T: CoordType = f64> {
     LinearRing(LinearRing<T>),
     Polygon(Polygon<T>),
     MultiGeometry(MultiGeometry<T>),
+    Track(Track<T>),
+    MultiTrack(MultiTrack<T>),
     Placemark(Placemark<T>),
-    Document {
-        attrs: HashMap<String, String>,
-        elements: Vec<Kml<T>>,
-    },
-    Folder {
-        attrs: HashMap<String, String>,
-        elements: Vec<Kml<T>>,
-    },
+    Tour(Tour),
+    Document(Document<T>),
+    Folder(Folder<T>),
     Style(Style),
     StyleMap(StyleMap),
     Pair(Pair),
@@ -86,4 +102,189 @@ pub enum Kml<T: CoordType = f64> {
     SimpleArrayData(SimpleArrayData),
     SimpleData(SimpleData),
     Element(Element),
+    /// XML comment, read when
+    /// [`KmlReaderConfig::comments`](../reader/struct.KmlReaderConfig.html#structfield.comments) is
+    /// set and written as `<!--text-->`, with any `--` in the text written as `- -`
+    Comment(String),
+    /// Extension element read by a parser registered with
+    /// [`KmlReaderConfig::register_parser`](../reader/struct.KmlReaderConfig.html#method.register_parser)
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
+    Extension(Extension),
+}
+
+impl<T: CoordType> KmlDocument<T> {
+    /// Returns the first element in the document with the `id`, see
+    /// [`Kml::find_by_id`](enum.Kml.html#method.find_by_id)
+    pub fn get_element_by_id(&self, id: &str) -> Option<&Kml<T>> {
+        self.elements.iter().find_map(|e| e.find_by_id(id))
+    }
+
+    /// Returns the first element in the document with the `id` for editing, see
+    /// [`Kml::find_by_id`](enum.Kml.html#method.find_by_id)
+    pub fn get_element_by_id_mut(&mut self, id: &str) -> Option<&mut Kml<T>> {
+        self.elements.iter_mut().find_map(|e| e.find_by_id_mut(id))
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Returns the element's `id`, from the `id` field of styles and the `id` attribute of
+    /// everything else
+    pub fn id(&self) -> Option<&str> {
+        let (id, attrs) = match self {
+            Kml::Style(s) => (&s.id, &s.attrs),
+            Kml::StyleMap(s) => (&s.id, &s.attrs),
+            Kml::BalloonStyle(s) => (&s.id, &s.attrs),
+            Kml::IconStyle(s) => (&s.id, &s.attrs),
+            Kml::LabelStyle(s) => (&s.id, &s.attrs),
+            Kml::LineStyle(s) => (&s.id, &s.attrs),
+            Kml::PolyStyle(s) => (&s.id, &s.attrs),
+            Kml::ListStyle(s) => (&s.id, &s.attrs),
+            _ => (&None, self.attrs()?),
+        };
+        id.as_deref()
+            .or_else(|| attrs.get("id").map(String::as_str))
+    }
+
+    /// Returns the first element in the tree with the `id`, in document order, descending into
+    /// documents and folders
+    ///
+    /// Only `Kml` values are searched, so the geometry of a placemark, or the children of a
+    /// generic element, aren't found by their ids.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let kml: Kml = r#"
+    /// <Document>
+    ///   <Style id="red"><LineStyle><color>ff0000ff</color></LineStyle></Style>
+    ///   <Folder><Placemark id="a"><name>A</name></Placemark></Folder>
+    /// </Document>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// assert!(matches!(kml.find_by_id("red"), Some(Kml::Style(_))));
+    /// match kml.find_by_id("a") {
+    ///     Some(Kml::Placemark(p)) => assert_eq!(p.name.as_deref(), Some("A")),
+    ///     _ => panic!("expected a Placemark"),
+    /// }
+    /// assert!(kml.find_by_id("b").is_none());
+    /// ```
+    pub fn find_by_id(&self, id: &str) -> Option<&Kml<T>> {
+        if self.id() == Some(id) {
+            return Some(self);
+        }
+        match self {
+            Kml::KmlDocument(d) => d.get_element_by_id(id),
+            Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                elements.iter().find_map(|e| e.find_by_id(id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the first element in the tree with the `id` for editing, see
+    /// [`find_by_id`](#method.find_by_id)
+    pub fn find_by_id_mut(&mut self, id: &str) -> Option<&mut Kml<T>> {
+        if self.id() == Some(id) {
+            return Some(self);
+        }
+        match self {
+            Kml::KmlDocument(d) => d.get_element_by_id_mut(id),
+            Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                elements.iter_mut().find_map(|e| e.find_by_id_mut(id))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn attrs(&self) -> Option<&HashMap<String, String>> {
+        Some(match self {
+            Kml::KmlDocument(d) => &d.attrs,
+            Kml::Scale(s) => &s.attrs,
+            Kml::Orientation(o) => &o.attrs,
+            Kml::Point(p) => &p.attrs,
+            Kml::Location(l) => &l.attrs,
+            Kml::LineString(l) => &l.attrs,
+            Kml::LinearRing(l) => &l.attrs,
+            Kml::Polygon(p) => &p.attrs,
+            Kml::MultiGeometry(g) => &g.attrs,
+            Kml::Track(t) => &t.attrs,
+            Kml::MultiTrack(t) => &t.attrs,
+            Kml::Placemark(p) => &p.attrs,
+            Kml::Tour(t) => &t.attrs,
+            Kml::Document(Document { attrs, .. }) | Kml::Folder(Folder { attrs, .. }) => attrs,
+            Kml::Style(s) => &s.attrs,
+            Kml::StyleMap(s) => &s.attrs,
+            Kml::Pair(p) => &p.attrs,
+            Kml::BalloonStyle(s) => &s.attrs,
+            Kml::IconStyle(s) => &s.attrs,
+            Kml::Icon(i) => &i.attrs,
+            Kml::LabelStyle(s) => &s.attrs,
+            Kml::LineStyle(s) => &s.attrs,
+            Kml::PolyStyle(s) => &s.attrs,
+            Kml::ListStyle(s) => &s.attrs,
+            Kml::LinkTypeIcon(i) => &i.attrs,
+            Kml::Link(l) => &l.attrs,
+            Kml::ResourceMap(r) => &r.attrs,
+            Kml::Alias(a) => &a.attrs,
+            Kml::SchemaData(d) => &d.attrs,
+            Kml::SimpleArrayData(d) => &d.attrs,
+            Kml::SimpleData(d) => &d.attrs,
+            Kml::Element(e) => &e.attrs,
+            Kml::Comment(_) | Kml::Extension(_) => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_by_id() {
+        let placemark = |id: &str, name: &str| {
+            Kml::Placemark(Placemark {
+                name: Some(name.to_string()),
+                attrs: HashMap::from([("id".to_string(), id.to_string())]),
+                ..Default::default()
+            })
+        };
+        let mut document: KmlDocument = KmlDocument {
+            elements: vec![Kml::Document(Document {
+                attrs: HashMap::from([("id".to_string(), "doc".to_string())]),
+                elements: vec![
+                    Kml::Style(Style {
+                        id: Some("s".to_string()),
+                        ..Default::default()
+                    }),
+                    Kml::Folder(Folder {
+                        elements: vec![placemark("p", "first"), placemark("p", "second")],
+                        ..Default::default()
+                    }),
+                ],
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            document.get_element_by_id("doc"),
+            Some(Kml::Document(_))
+        ));
+        assert_eq!(document.get_element_by_id("s").and_then(Kml::id), Some("s"));
+        assert_eq!(
+            document.get_element_by_id("p"),
+            Some(&placemark("p", "first"))
+        );
+        assert_eq!(document.get_element_by_id("missing"), None);
+
+        if let Some(Kml::Placemark(p)) = document.get_element_by_id_mut("p") {
+            p.name = Some("edited".to_string());
+        }
+        assert_eq!(
+            document.get_element_by_id("p"),
+            Some(&placemark("p", "edited"))
+        );
+    }
 }
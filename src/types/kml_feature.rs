@@ -0,0 +1,220 @@
+use crate::types::{CoordType, Document, Element, Folder, Kml, Node, Placemark};
+
+/// Common fields of `kml:AbstractFeatureGroup` elements, implemented by placemarks, documents,
+/// folders, tours through [`Kml`](enum.Kml.html), and generic elements such as `NetworkLink` and
+/// overlays, so generic code can read them from any feature
+///
+/// Fields without a typed representation are read from the feature's generic child elements.
+///
+/// # Example
+///
+/// ```
+/// use kml::types::{Document, KmlFeature};
+/// use kml::Kml;
+///
+/// let kml: Kml = r#"
+/// <Document>
+///   <Folder><name>Roads</name><visibility>0</visibility></Folder>
+///   <NetworkLink><name>Feed</name><Link><href>feed.kml</href></Link></NetworkLink>
+///   <Placemark><name>Road</name><TimeStamp><when>2020</when></TimeStamp></Placemark>
+/// </Document>"#
+///     .parse()
+///     .unwrap();
+/// let Kml::Document(Document { elements, .. }) = &kml else {
+///     unreachable!()
+/// };
+/// let names: Vec<_> = elements.iter().filter_map(|e| e.name()).collect();
+/// assert_eq!(names, vec!["Roads", "Feed", "Road"]);
+/// assert_eq!(elements[0].visibility(), Some(false));
+/// assert_eq!(elements[2].time().map(|t| t.name.as_str()), Some("TimeStamp"));
+/// ```
+pub trait KmlFeature {
+    fn name(&self) -> Option<&str>;
+
+    fn description(&self) -> Option<&str>;
+
+    fn style_url(&self) -> Option<&str>;
+
+    /// Returns the first generic child element of the feature with the name
+    fn child(&self, name: &str) -> Option<&Element>;
+
+    /// Value of the `visibility` element, if the feature has a valid one
+    fn visibility(&self) -> Option<bool> {
+        match self.child("visibility")?.content.as_deref()?.trim() {
+            "1" | "true" => Some(true),
+            "0" | "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// `TimeStamp` or `TimeSpan` element, whichever comes first
+    fn time(&self) -> Option<&Element> {
+        self.child("TimeStamp").or_else(|| self.child("TimeSpan"))
+    }
+
+    fn region(&self) -> Option<&Element> {
+        self.child("Region")
+    }
+
+    /// `ExtendedData` element, see [`Placemark::properties`](struct.Placemark.html#method.properties)
+    /// for its values
+    fn extended_data(&self) -> Option<&Element> {
+        self.child("ExtendedData")
+    }
+}
+
+/// Implements `KmlFeature` for structs with typed `name`, `description`, `style_url`, and
+/// `children` fields
+macro_rules! impl_kml_feature {
+    ($($name:ident),*) => {
+        $(
+            impl<T: CoordType> KmlFeature for $name<T> {
+                fn name(&self) -> Option<&str> {
+                    self.name.as_deref()
+                }
+
+                fn description(&self) -> Option<&str> {
+                    self.description.as_deref()
+                }
+
+                fn style_url(&self) -> Option<&str> {
+                    self.style_url.as_deref()
+                }
+
+                fn child(&self, name: &str) -> Option<&Element> {
+                    self.children
+                        .iter()
+                        .filter_map(Node::as_element)
+                        .find(|c| c.name == name)
+                }
+            }
+        )*
+    };
+}
+
+impl_kml_feature!(Placemark, Document, Folder);
+
+/// Fields of elements without a type of their own, such as `NetworkLink` and `GroundOverlay`
+impl KmlFeature for Element {
+    fn name(&self) -> Option<&str> {
+        self.child("name")?.content.as_deref()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.child("description")?.content.as_deref()
+    }
+
+    fn style_url(&self) -> Option<&str> {
+        self.child("styleUrl")?.content.as_deref()
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|c| c.name == name)
+    }
+}
+
+/// Fields of placemarks, documents, folders, tours, and generic elements, and `None` for other
+/// variants
+impl<T: CoordType> KmlFeature for Kml<T> {
+    fn name(&self) -> Option<&str> {
+        match self {
+            Kml::Placemark(p) => p.name.as_deref(),
+            Kml::Document(d) => d.name.as_deref(),
+            Kml::Folder(f) => f.name.as_deref(),
+            Kml::Tour(t) => t.name.as_deref(),
+            _ => self.child("name")?.content.as_deref(),
+        }
+    }
+
+    fn description(&self) -> Option<&str> {
+        match self {
+            Kml::Placemark(p) => p.description.as_deref(),
+            Kml::Document(d) => d.description.as_deref(),
+            Kml::Folder(f) => f.description.as_deref(),
+            Kml::Tour(t) => t.description.as_deref(),
+            _ => self.child("description")?.content.as_deref(),
+        }
+    }
+
+    fn style_url(&self) -> Option<&str> {
+        match self {
+            Kml::Placemark(p) => p.style_url.as_deref(),
+            Kml::Document(d) => d.style_url.as_deref(),
+            Kml::Folder(f) => f.style_url.as_deref(),
+            _ => self.child("styleUrl")?.content.as_deref(),
+        }
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        match self {
+            Kml::Placemark(p) => p.child(name),
+            Kml::Tour(t) => t
+                .children
+                .iter()
+                .filter_map(Node::as_element)
+                .find(|c| c.name == name),
+            Kml::Document(d) => d.child(name),
+            Kml::Folder(f) => f.child(name),
+            Kml::Element(e) => e.child(name),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::text;
+
+    #[test]
+    fn test_placemark() {
+        let placemark: Placemark = Placemark {
+            name: Some("a".to_string()),
+            style_url: Some("#s".to_string()),
+            children: vec![
+                text("visibility", "true").into(),
+                text("TimeSpan", "").into(),
+                text("Region", "").into(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(KmlFeature::name(&placemark), Some("a"));
+        assert_eq!(placemark.description(), None);
+        assert_eq!(KmlFeature::style_url(&placemark), Some("#s"));
+        assert_eq!(placemark.visibility(), Some(true));
+        assert_eq!(placemark.time().map(|t| t.name.as_str()), Some("TimeSpan"));
+        assert!(placemark.region().is_some());
+        assert!(placemark.extended_data().is_none());
+        assert_eq!(
+            Kml::Placemark(placemark.clone()).visibility(),
+            placemark.visibility()
+        );
+    }
+
+    #[test]
+    fn test_kml() {
+        let folder: Kml = Kml::Folder(Folder {
+            name: Some("Roads".to_string()),
+            description: Some("All roads".to_string()),
+            children: vec![text("visibility", "maybe").into()],
+            elements: vec![Kml::Placemark(Placemark::default())],
+            ..Default::default()
+        });
+        assert_eq!(folder.name(), Some("Roads"));
+        assert_eq!(folder.description(), Some("All roads"));
+        assert_eq!(folder.style_url(), None);
+        assert_eq!(folder.visibility(), None);
+
+        let overlay: Kml = Kml::Element(Element {
+            name: "GroundOverlay".to_string(),
+            children: vec![
+                text("styleUrl", "#o").into(),
+                text("ExtendedData", "").into(),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(overlay.style_url(), Some("#o"));
+        assert!(overlay.extended_data().is_some());
+        assert_eq!(Kml::<f64>::Comment("a".to_string()).name(), None);
+    }
+}
@@ -6,12 +6,14 @@ use crate::types::coord::{Coord, CoordType};
 /// `kml:LineString`, [10.7](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#488) in the
 /// KML specification
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineString<T: CoordType = f64> {
     pub coords: Vec<Coord<T>>,
-    pub extrude: bool,
-    pub tessellate: bool,
-    pub altitude_mode: AltitudeMode,
+    pub extrude: Option<bool>,
+    pub tessellate: Option<bool>,
+    pub altitude_mode: Option<AltitudeMode>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl<T> From<Vec<Coord<T>>> for LineString<T>
@@ -6,6 +6,7 @@ use crate::Error;
 
 /// `kml:Link`, [13.1](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#974) in the KML specification.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Link {
     pub href: Option<String>,
     pub refresh_mode: Option<RefreshMode>,
@@ -16,6 +17,7 @@ pub struct Link {
     pub view_format: Option<String>,
     pub http_query: Option<String>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for Link {
@@ -30,12 +32,14 @@ impl Default for Link {
             view_format: None,
             http_query: None,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
 
 /// `kml:Icon`, [13.1](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#974) in the KML specification.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Icon {
     pub href: Option<String>,
     pub refresh_mode: Option<RefreshMode>,
@@ -46,6 +50,7 @@ pub struct Icon {
     pub view_format: Option<String>,
     pub http_query: Option<String>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for Icon {
@@ -60,12 +65,81 @@ impl Default for Icon {
             view_format: None,
             http_query: None,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
 
+/// Adds a constructor and builder methods for the fields shared by `Link` and `Icon`
+macro_rules! impl_link_builders {
+    ($($name:ident),*) => {
+        $(
+            impl $name {
+                /// Creates a link to `href` with the default refresh settings
+                pub fn new(href: impl Into<String>) -> Self {
+                    Self {
+                        href: Some(href.into()),
+                        ..Default::default()
+                    }
+                }
+
+                /// Sets [`href`](#structfield.href)
+                pub fn href(mut self, href: Option<String>) -> Self {
+                    self.href = href;
+                    self
+                }
+
+                /// Sets [`refresh_mode`](#structfield.refresh_mode)
+                pub fn refresh_mode(mut self, refresh_mode: Option<RefreshMode>) -> Self {
+                    self.refresh_mode = refresh_mode;
+                    self
+                }
+
+                /// Sets [`refresh_interval`](#structfield.refresh_interval) in seconds
+                pub fn refresh_interval(mut self, refresh_interval: f64) -> Self {
+                    self.refresh_interval = refresh_interval;
+                    self
+                }
+
+                /// Sets [`view_refresh_mode`](#structfield.view_refresh_mode)
+                pub fn view_refresh_mode(mut self, view_refresh_mode: Option<ViewRefreshMode>) -> Self {
+                    self.view_refresh_mode = view_refresh_mode;
+                    self
+                }
+
+                /// Sets [`view_refresh_time`](#structfield.view_refresh_time) in seconds
+                pub fn view_refresh_time(mut self, view_refresh_time: f64) -> Self {
+                    self.view_refresh_time = view_refresh_time;
+                    self
+                }
+
+                /// Sets [`view_bound_scale`](#structfield.view_bound_scale)
+                pub fn view_bound_scale(mut self, view_bound_scale: f64) -> Self {
+                    self.view_bound_scale = view_bound_scale;
+                    self
+                }
+
+                /// Sets [`view_format`](#structfield.view_format)
+                pub fn view_format(mut self, view_format: Option<String>) -> Self {
+                    self.view_format = view_format;
+                    self
+                }
+
+                /// Sets [`http_query`](#structfield.http_query)
+                pub fn http_query(mut self, http_query: Option<String>) -> Self {
+                    self.http_query = http_query;
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_link_builders!(Link, Icon);
+
 /// `kml:refreshModeEnumType`, [16.21](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#1239) in the KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RefreshMode {
     #[default]
     OnChange,
@@ -98,6 +172,7 @@ impl fmt::Display for RefreshMode {
 
 /// `kml:viewRefreshModeEnumType`, [16.27](https://docs.opengeospatial.org/is/12-007r2/12-007r2.html#1270) in the KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ViewRefreshMode {
     #[default]
     Never,
@@ -135,6 +210,30 @@ impl fmt::Display for ViewRefreshMode {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_link_builders() {
+        let link = Link::new("feed.kml")
+            .refresh_mode(Some(RefreshMode::OnInterval))
+            .refresh_interval(60.)
+            .view_refresh_mode(Some(ViewRefreshMode::OnStop))
+            .http_query(Some("client=[clientName]".to_string()));
+        assert_eq!(
+            link,
+            Link {
+                href: Some("feed.kml".to_string()),
+                refresh_mode: Some(RefreshMode::OnInterval),
+                refresh_interval: 60.,
+                view_refresh_mode: Some(ViewRefreshMode::OnStop),
+                http_query: Some("client=[clientName]".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Icon::new("a.png").href(Some("b.png".to_string())).href,
+            Some("b.png".to_string())
+        );
+    }
+
     #[test]
     fn test_refresh_mode_from_str() {
         assert_eq!(
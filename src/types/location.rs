@@ -4,11 +4,13 @@ use crate::types::coord::CoordType;
 
 /// `kml:Location`, [10.10](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#542) in the KML
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location<T: CoordType = f64> {
     pub latitude: T,
     pub longitude: T,
     pub altitude: T,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl<T> Location<T>
@@ -0,0 +1,295 @@
+use std::convert::Infallible;
+
+use crate::types::{
+    Coord, CoordType, Document, Folder, Geometry, Kml, KmlDocument, LineString, LinearRing,
+    Location, MultiGeometry, MultiTrack, Placemark, Point, Polygon, Track,
+};
+
+/// Coordinate mapping shared by the types, taking a trait object so recursive types don't
+/// instantiate it for ever deeper references to the closure
+trait MapCoords<T: CoordType> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E>;
+}
+
+/// Adds `map_coords` and `try_map_coords` methods, with any extra documentation for
+/// `try_map_coords`
+macro_rules! impl_map_coords {
+    ($($(#[$doc:meta])* $name:ident;)*) => {
+        $(
+            impl<T: CoordType> $name<T> {
+                /// Applies `f` to each of the coordinates in place, see
+                /// [`try_map_coords`](#method.try_map_coords) for transforms that can fail
+                pub fn map_coords(&mut self, mut f: impl FnMut(Coord<T>) -> Coord<T>) {
+                    let result: Result<(), Infallible> = self.try_map_coords(|c| Ok(f(c)));
+                    match result {
+                        Ok(()) => {}
+                        Err(e) => match e {},
+                    }
+                }
+
+                /// Applies `f` to each of the coordinates in place, stopping at the first error
+                $(#[$doc])*
+                pub fn try_map_coords<E>(
+                    &mut self,
+                    mut f: impl FnMut(Coord<T>) -> Result<Coord<T>, E>,
+                ) -> Result<(), E> {
+                    MapCoords::map(self, &mut f)
+                }
+            }
+        )*
+    };
+}
+
+impl_map_coords! {
+    Point;
+    LineString;
+    LinearRing;
+    Polygon;
+    ///
+    /// Angles are left as they are.
+    Track;
+    MultiTrack;
+    MultiGeometry;
+    ///
+    /// Models are left as they are.
+    Geometry;
+    ///
+    /// The location is passed as a coordinate with the longitude as `x`, the latitude as `y`, and
+    /// the altitude as `z`, keeping the altitude if `f` returns no `z`.
+    Location;
+    Placemark;
+    KmlDocument;
+    ///
+    /// Maps the geometries and locations in the tree, including those in placemarks, documents,
+    /// and folders.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let mut kml: Kml = r#"
+    /// <Folder>
+    ///   <Placemark><Point><coordinates>1.23456,2.34567</coordinates></Point></Placemark>
+    /// </Folder>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// kml.map_coords(|mut c| {
+    ///     c.x = (c.x * 100.).round() / 100.;
+    ///     c.y = (c.y * 100.).round() / 100.;
+    ///     c
+    /// });
+    /// assert!(kml.to_string().contains("<coordinates>1.23,2.35</coordinates>"));
+    ///
+    /// let result = kml.try_map_coords(|c| {
+    ///     if c.x.abs() <= 180. {
+    ///         Ok(c)
+    ///     } else {
+    ///         Err("longitude out of range")
+    ///     }
+    /// });
+    /// assert!(result.is_ok());
+    /// ```
+    Kml;
+}
+
+fn map_slice<T: CoordType, E>(
+    coords: &mut [Coord<T>],
+    f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>,
+) -> Result<(), E> {
+    for coord in coords {
+        *coord = f(*coord)?;
+    }
+    Ok(())
+}
+
+impl<T: CoordType> MapCoords<T> for Point<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        self.coord = f(self.coord)?;
+        Ok(())
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for LineString<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        map_slice(&mut self.coords, f)
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for LinearRing<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        map_slice(&mut self.coords, f)
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for Polygon<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        self.outer.map(f)?;
+        self.inner.iter_mut().try_for_each(|r| r.map(f))
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for Track<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        map_slice(&mut self.coords, f)
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for MultiTrack<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        self.tracks.iter_mut().try_for_each(|t| t.map(f))
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for MultiGeometry<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        self.geometries.iter_mut().try_for_each(|g| g.map(f))
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for Geometry<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        match self {
+            Geometry::Point(p) => p.map(f),
+            Geometry::LineString(l) => l.map(f),
+            Geometry::LinearRing(l) => l.map(f),
+            Geometry::Polygon(p) => p.map(f),
+            Geometry::MultiGeometry(g) => g.map(f),
+            Geometry::Track(t) => t.map(f),
+            Geometry::MultiTrack(t) => t.map(f),
+            Geometry::Element(_) => Ok(()),
+        }
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for Location<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        let coord = f(Coord::new(
+            self.longitude,
+            self.latitude,
+            Some(self.altitude),
+        ))?;
+        self.longitude = coord.x;
+        self.latitude = coord.y;
+        self.altitude = coord.z.unwrap_or(self.altitude);
+        Ok(())
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for Placemark<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        match &mut self.geometry {
+            Some(geometry) => geometry.map(f),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for KmlDocument<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        self.elements.iter_mut().try_for_each(|e| e.map(f))
+    }
+}
+
+impl<T: CoordType> MapCoords<T> for Kml<T> {
+    fn map<E>(&mut self, f: &mut dyn FnMut(Coord<T>) -> Result<Coord<T>, E>) -> Result<(), E> {
+        match self {
+            Kml::KmlDocument(d) => d.map(f),
+            Kml::Point(p) => p.map(f),
+            Kml::Location(l) => l.map(f),
+            Kml::LineString(l) => l.map(f),
+            Kml::LinearRing(l) => l.map(f),
+            Kml::Polygon(p) => p.map(f),
+            Kml::MultiGeometry(g) => g.map(f),
+            Kml::Track(t) => t.map(f),
+            Kml::MultiTrack(t) => t.map(f),
+            Kml::Placemark(p) => p.map(f),
+            Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                elements.iter_mut().try_for_each(|e| e.map(f))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_coords() {
+        let ring = |offset: f64| {
+            LinearRing::from(vec![
+                Coord::new(offset, offset, None),
+                Coord::new(offset + 1., offset, None),
+                Coord::new(offset, offset, None),
+            ])
+        };
+        let mut kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Placemark(Placemark {
+                    geometry: Some(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                        Geometry::Point(Point::new(1., 2., Some(3.))),
+                        Geometry::Polygon(Polygon::new(ring(0.), vec![ring(0.5)])),
+                    ]))),
+                    ..Default::default()
+                }),
+                Kml::Folder(Folder {
+                    elements: vec![Kml::Location(Location::new(10., 20., 30.))],
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        });
+        kml.map_coords(|c| Coord::new(c.x + 1., c.y + 1., c.z));
+
+        let Kml::Document(Document { elements, .. }) = &kml else {
+            unreachable!()
+        };
+        let Kml::Placemark(Placemark {
+            geometry: Some(Geometry::MultiGeometry(g)),
+            ..
+        }) = &elements[0]
+        else {
+            panic!("expected a Placemark with a MultiGeometry");
+        };
+        assert_eq!(
+            g.geometries[0],
+            Geometry::Point(Point::new(2., 3., Some(3.)))
+        );
+        let Geometry::Polygon(polygon) = &g.geometries[1] else {
+            panic!("expected a Polygon");
+        };
+        assert_eq!(polygon.outer.coords[1], Coord::new(2., 1., None));
+        assert_eq!(polygon.inner[0].coords[0], Coord::new(1.5, 1.5, None));
+        assert_eq!(
+            elements[1],
+            Kml::Folder(Folder {
+                elements: vec![Kml::Location(Location::new(11., 21., 30.))],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_map_coords() {
+        let mut line_string: LineString = LineString::from(vec![
+            Coord::new(1., 1., None),
+            Coord::new(200., 1., None),
+            Coord::new(2., 2., None),
+        ]);
+        let mut calls = 0;
+        let result = line_string.try_map_coords(|c| {
+            calls += 1;
+            if c.x > 180. {
+                Err(c.x)
+            } else {
+                Ok(Coord::new(c.x * 2., c.y, c.z))
+            }
+        });
+        assert_eq!(result, Err(200.));
+        assert_eq!(calls, 2);
+        assert_eq!(line_string.coords[0], Coord::new(2., 1., None));
+        assert_eq!(line_string.coords[2], Coord::new(2., 2., None));
+    }
+}
@@ -1,9 +1,9 @@
 //! Module containing types for KML elements
 mod altitude_mode;
-mod coord;
+pub(crate) mod coord;
 
 pub use altitude_mode::AltitudeMode;
-pub use coord::{coords_from_str, Coord, CoordType};
+pub use coord::{coords_from_str, coords_from_str_tolerant, Coord, CoordType};
 
 mod line_string;
 mod linear_ring;
@@ -13,6 +13,7 @@ mod orientation;
 mod point;
 mod polygon;
 mod scale;
+mod track;
 mod vec2;
 
 pub use line_string::LineString;
@@ -23,14 +24,25 @@ pub use orientation::Orientation;
 pub use point::Point;
 pub use polygon::Polygon;
 pub use scale::Scale;
+pub use track::{MultiTrack, Track};
 pub use vec2::{Units, Vec2};
 
+mod container;
 mod element;
+mod extension;
+mod feature;
 pub(crate) mod geom_props;
+mod kml_feature;
 mod placemark;
+mod tour;
 
-pub use element::Element;
+pub use container::{Document, Folder};
+pub use element::{Element, Node};
+pub use extension::{Extension, KmlExtension};
+pub use feature::{Feature, FeatureIter};
+pub use kml_feature::KmlFeature;
 pub use placemark::Placemark;
+pub use tour::Tour;
 
 mod geometry;
 
@@ -44,7 +56,7 @@ mod style;
 
 pub use style::{
     BalloonStyle, ColorMode, Icon, IconStyle, LabelStyle, LineStyle, ListStyle, Pair, PolyStyle,
-    Style, StyleMap,
+    Style, StyleMap, StyleState,
 };
 
 mod resource_map;
@@ -62,3 +74,17 @@ pub use data::{SchemaData, SimpleArrayData, SimpleData};
 mod kml;
 
 pub use self::kml::{Kml, KmlDocument, KmlVersion};
+
+mod builder;
+
+pub use builder::{DocumentBuilder, FolderBuilder};
+
+mod iter;
+
+pub use iter::KmlIter;
+
+mod query;
+
+mod map_coords;
+
+mod cast;
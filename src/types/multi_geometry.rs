@@ -1,14 +1,19 @@
 use std::collections::HashMap;
 
 use crate::types::coord::CoordType;
+use crate::types::element::Node;
 use crate::types::geometry::Geometry;
 
 /// `kml:MultiGeometry`, [10.2](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#438) in the
 /// KML specification
 #[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiGeometry<T: CoordType = f64> {
     pub geometries: Vec<Geometry<T>>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+    /// Children that aren't geometries, like elements in other namespaces
+    pub children: Vec<Node>,
 }
 
 impl<T> MultiGeometry<T>
@@ -4,11 +4,13 @@ use crate::types::coord::CoordType;
 
 /// `kml:Orientation`, [10.11](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#558) in the KML
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Orientation<T: CoordType = f64> {
     pub roll: T,
     pub tilt: T,
     pub heading: T,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl<T> Orientation<T>
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::types::coord::CoordType;
-use crate::types::element::Element;
+use crate::types::element::{Element, Node};
 use crate::types::geometry::Geometry;
 
 /// `kml:Placemark`, [9.14](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#249) in the KML
@@ -12,11 +12,144 @@ use crate::types::geometry::Geometry;
 ///
 /// Currently leaving optional.
 #[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Placemark<T: CoordType = f64> {
     pub name: Option<String>,
+    /// Unescaped description text, or the verbatim inner XML if it contains HTML elements
     pub description: Option<String>,
     pub geometry: Option<Geometry<T>>,
     pub style_url: Option<String>,
     pub attrs: HashMap<String, String>,
-    pub children: Vec<Element>,
+    pub namespace: Option<String>,
+    pub children: Vec<Node>,
+}
+
+impl<T: CoordType> Placemark<T> {
+    /// Returns the values of the `Data` and `SchemaData/SimpleData` elements in the placemark's
+    /// `ExtendedData` by name, with later values replacing earlier ones
+    ///
+    /// Values are returned as text since `Schema` declarations, which give `SimpleData` its type,
+    /// aren't read yet. `Data` without a `value` is skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let kml: Kml = r##"
+    /// <Placemark>
+    ///   <ExtendedData>
+    ///     <Data name="kind"><value>trail</value></Data>
+    ///     <SchemaData schemaUrl="#trail"><SimpleData name="length">5</SimpleData></SchemaData>
+    ///   </ExtendedData>
+    /// </Placemark>"##
+    ///     .parse()
+    ///     .unwrap();
+    /// let Kml::Placemark(placemark) = kml else {
+    ///     unreachable!()
+    /// };
+    /// let properties = placemark.properties();
+    /// assert_eq!(properties["kind"], "trail");
+    /// assert_eq!(properties["length"], "5");
+    /// ```
+    pub fn properties(&self) -> HashMap<String, String> {
+        let mut properties = HashMap::new();
+        let extended_data = self
+            .children
+            .iter()
+            .filter_map(Node::as_element)
+            .filter(|e| e.name == "ExtendedData");
+        for element in extended_data.flat_map(Element::elements) {
+            match element.name.as_str() {
+                "Data" => {
+                    let value = element.elements().find(|e| e.name == "value");
+                    if let (Some(name), Some(value)) = (element.attrs.get("name"), value) {
+                        properties.insert(name.clone(), value.content.clone().unwrap_or_default());
+                    }
+                }
+                "SchemaData" => {
+                    for data in element.elements().filter(|e| e.name == "SimpleData") {
+                        if let Some(name) = data.attrs.get("name") {
+                            properties
+                                .insert(name.clone(), data.content.clone().unwrap_or_default());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(
+        name: &str,
+        attrs: &[(&str, &str)],
+        content: Option<&str>,
+        children: Vec<Element>,
+    ) -> Element {
+        Element {
+            name: name.to_string(),
+            attrs: attrs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            content: content.map(str::to_string),
+            children: children.into_iter().map(Node::from).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_properties() {
+        let placemark: Placemark = Placemark {
+            children: vec![Node::Element(element(
+                "ExtendedData",
+                &[],
+                None,
+                vec![
+                    element(
+                        "Data",
+                        &[("name", "kind")],
+                        None,
+                        vec![
+                            element("displayName", &[], Some("Kind"), vec![]),
+                            element("value", &[], Some("trail"), vec![]),
+                        ],
+                    ),
+                    element(
+                        "Data",
+                        &[("name", "empty")],
+                        None,
+                        vec![element("value", &[], None, vec![])],
+                    ),
+                    element("Data", &[("name", "missing")], None, vec![]),
+                    element(
+                        "SchemaData",
+                        &[("schemaUrl", "#s")],
+                        None,
+                        vec![
+                            element("SimpleData", &[("name", "kind")], Some("road"), vec![]),
+                            element("SimpleData", &[("name", "length")], Some("5"), vec![]),
+                        ],
+                    ),
+                ],
+            ))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            placemark.properties(),
+            HashMap::from([
+                ("kind".to_string(), "road".to_string()),
+                ("empty".to_string(), String::new()),
+                ("length".to_string(), "5".to_string()),
+            ])
+        );
+        assert!(Placemark::<f64>::default().properties().is_empty());
+    }
 }
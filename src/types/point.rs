@@ -8,11 +8,13 @@ use crate::types::coord::{Coord, CoordType};
 ///
 /// Coord is required as of <https://docs.opengeospatial.org/ts/14-068r2/14-068r2.html#atc-114>
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point<T: CoordType = f64> {
     pub coord: Coord<T>,
-    pub extrude: bool,
-    pub altitude_mode: AltitudeMode,
+    pub extrude: Option<bool>,
+    pub altitude_mode: Option<AltitudeMode>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl<T> From<Coord<T>> for Point<T>
@@ -7,13 +7,15 @@ use crate::types::linear_ring::LinearRing;
 /// `kml:Polygon`, [10.8](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#505) in the KML
 /// specification
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polygon<T: CoordType = f64> {
     pub outer: LinearRing<T>,
     pub inner: Vec<LinearRing<T>>,
-    pub extrude: bool,
-    pub tessellate: bool,
-    pub altitude_mode: AltitudeMode,
+    pub extrude: Option<bool>,
+    pub tessellate: Option<bool>,
+    pub altitude_mode: Option<AltitudeMode>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl<T> Polygon<T>
@@ -0,0 +1,326 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::errors::Error;
+use crate::types::{CoordType, Kml, KmlDocument, KmlFeature};
+
+/// Step of a path, matching elements by tag name, or any element for `*`, and predicates
+#[derive(Debug, PartialEq)]
+struct Step {
+    /// Whether the step matches at any depth, after `//`, instead of only children
+    descendant: bool,
+    tag: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Predicate {
+    /// `[name='value']`
+    Name(String),
+    /// `[@attr='value']`
+    Attr(String, String),
+}
+
+fn parse_path(path: &str) -> Result<Vec<Step>, Error> {
+    let invalid = |reason: &str| Error::InvalidPath(format!("{} in {:?}", reason, path));
+    let mut steps = Vec::new();
+    let mut rest = path.trim();
+    let mut descendant = false;
+    if let Some(r) = rest.strip_prefix("//") {
+        descendant = true;
+        rest = r;
+    }
+    loop {
+        let end = rest.find(['/', '[']).unwrap_or(rest.len());
+        let tag = rest[..end].trim();
+        if tag.is_empty() {
+            return Err(invalid("missing element name"));
+        }
+        rest = &rest[end..];
+
+        let mut predicates = Vec::new();
+        while let Some(r) = rest.strip_prefix('[') {
+            let close = r.find(']').ok_or_else(|| invalid("unclosed ["))?;
+            let (key, value) = r[..close]
+                .split_once('=')
+                .ok_or_else(|| invalid("predicate without ="))?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('\'')
+                .and_then(|v| v.strip_suffix('\''))
+                .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+                .ok_or_else(|| invalid("unquoted predicate value"))?
+                .to_string();
+            predicates.push(match key.trim() {
+                "name" => Predicate::Name(value),
+                key => match key.strip_prefix('@') {
+                    Some(attr) if !attr.is_empty() => Predicate::Attr(attr.to_string(), value),
+                    _ => return Err(invalid("predicate not on name or an @attribute")),
+                },
+            });
+            rest = &r[close + 1..];
+        }
+
+        steps.push(Step {
+            descendant,
+            tag: (tag != "*").then(|| tag.to_string()),
+            predicates,
+        });
+        if rest.is_empty() {
+            return Ok(steps);
+        }
+        rest = rest
+            .strip_prefix('/')
+            .ok_or_else(|| invalid("expected /"))?;
+        descendant = false;
+        if let Some(r) = rest.strip_prefix('/') {
+            descendant = true;
+            rest = r;
+        }
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Returns the elements matching a path, such as `Document/Folder[name='Tracks']/Placemark`,
+    /// in document order
+    ///
+    /// Each step of the path names the tag of the elements it matches, or `*` for any element,
+    /// with any number of `[name='value']` predicates on their name and `[@attr='value']`
+    /// predicates on their attributes, including typed `id` fields. The first step matches this
+    /// element, or the elements of a `KmlDocument`, and the following steps match the elements of
+    /// documents and folders matched by the step before. Steps after `//` match at any depth
+    /// instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let kml: Kml = r#"
+    /// <kml xmlns="http://www.opengis.net/kml/2.2">
+    ///   <Document>
+    ///     <Folder><name>Tracks</name><Placemark id="a"/><Placemark id="b"/></Folder>
+    ///     <Folder><name>Stops</name><Placemark id="c"/></Folder>
+    ///   </Document>
+    /// </kml>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// let tracks = kml.select("Document/Folder[name='Tracks']/Placemark").unwrap();
+    /// assert_eq!(tracks.len(), 2);
+    /// let c = kml.select("//Placemark[@id='c']").unwrap();
+    /// assert_eq!(c[0].id(), Some("c"));
+    /// assert!(kml.select("Document/[").is_err());
+    /// ```
+    pub fn select(&self, path: &str) -> Result<Vec<&Kml<T>>, Error> {
+        let context = match self {
+            Kml::KmlDocument(d) => d.elements.iter().collect(),
+            kml => vec![kml],
+        };
+        Ok(select(context, &parse_path(path)?))
+    }
+
+    fn children(&self) -> &[Kml<T>] {
+        match self {
+            Kml::KmlDocument(d) => &d.elements,
+            Kml::Document(d) => &d.elements,
+            Kml::Folder(f) => &f.elements,
+            _ => &[],
+        }
+    }
+
+    /// Tag name of the element, without any prefix
+    fn tag(&self) -> Option<Cow<'_, str>> {
+        let tag = match self {
+            Kml::KmlDocument(_) => "kml",
+            Kml::Scale(_) => "Scale",
+            Kml::Orientation(_) => "Orientation",
+            Kml::Point(_) => "Point",
+            Kml::Location(_) => "Location",
+            Kml::LineString(_) => "LineString",
+            Kml::LinearRing(_) => "LinearRing",
+            Kml::Polygon(_) => "Polygon",
+            Kml::MultiGeometry(_) => "MultiGeometry",
+            Kml::Track(_) => "Track",
+            Kml::MultiTrack(_) => "MultiTrack",
+            Kml::Placemark(_) => "Placemark",
+            Kml::Tour(_) => "Tour",
+            Kml::Document(_) => "Document",
+            Kml::Folder(_) => "Folder",
+            Kml::Style(_) => "Style",
+            Kml::StyleMap(_) => "StyleMap",
+            Kml::Pair(_) => "Pair",
+            Kml::BalloonStyle(_) => "BalloonStyle",
+            Kml::IconStyle(_) => "IconStyle",
+            Kml::Icon(_) | Kml::LinkTypeIcon(_) => "Icon",
+            Kml::LabelStyle(_) => "LabelStyle",
+            Kml::LineStyle(_) => "LineStyle",
+            Kml::PolyStyle(_) => "PolyStyle",
+            Kml::ListStyle(_) => "ListStyle",
+            Kml::Link(_) => "Link",
+            Kml::ResourceMap(_) => "ResourceMap",
+            Kml::Alias(_) => "Alias",
+            Kml::SchemaData(_) => "SchemaData",
+            Kml::SimpleArrayData(_) => "SimpleArrayData",
+            Kml::SimpleData(_) => "SimpleData",
+            Kml::Element(e) => return Some(Cow::Borrowed(local_name(&e.name))),
+            Kml::Extension(e) => {
+                return Some(Cow::Owned(local_name(&e.to_element().name).to_string()))
+            }
+            Kml::Comment(_) => return None,
+        };
+        Some(Cow::Borrowed(tag))
+    }
+}
+
+impl<T: CoordType> KmlDocument<T> {
+    /// Returns the elements matching a path, see [`Kml::select`](enum.Kml.html#method.select)
+    pub fn select(&self, path: &str) -> Result<Vec<&Kml<T>>, Error> {
+        Ok(select(self.elements.iter().collect(), &parse_path(path)?))
+    }
+}
+
+/// Elements matching the steps, with the first step matching elements in `context`
+fn select<'a, T: CoordType>(mut context: Vec<&'a Kml<T>>, steps: &[Step]) -> Vec<&'a Kml<T>> {
+    for (i, step) in steps.iter().enumerate() {
+        if i > 0 {
+            context = context.into_iter().flat_map(|k| k.children()).collect();
+        }
+        if step.descendant {
+            context = context.into_iter().flat_map(Kml::iter).collect();
+            // Descendants of nested elements are reached more than once
+            let mut seen = HashSet::new();
+            context.retain(|k| seen.insert(*k as *const Kml<T>));
+        }
+        context.retain(|k| step.matches(k));
+    }
+    context
+}
+
+impl Step {
+    fn matches<T: CoordType>(&self, kml: &Kml<T>) -> bool {
+        let Some(tag) = kml.tag() else {
+            return false;
+        };
+        if self.tag.as_ref().is_some_and(|t| *t != tag) {
+            return false;
+        }
+        self.predicates.iter().all(|p| match p {
+            Predicate::Name(name) => kml.name() == Some(name),
+            Predicate::Attr(attr, value) if attr == "id" => kml.id() == Some(value),
+            Predicate::Attr(attr, value) => kml.attrs().and_then(|a| a.get(attr)) == Some(value),
+        })
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Document, Element, Folder, Placemark};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("//Folder[name='a b'][@id=\"x\"]/*").unwrap(),
+            vec![
+                Step {
+                    descendant: true,
+                    tag: Some("Folder".to_string()),
+                    predicates: vec![
+                        Predicate::Name("a b".to_string()),
+                        Predicate::Attr("id".to_string(), "x".to_string()),
+                    ],
+                },
+                Step {
+                    descendant: false,
+                    tag: None,
+                    predicates: vec![],
+                },
+            ]
+        );
+        for path in [
+            "",
+            "Folder/",
+            "Folder[name=a]",
+            "Folder[name='a'",
+            "Folder[x='a']",
+        ] {
+            assert!(
+                matches!(parse_path(path), Err(Error::InvalidPath(_))),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let placemark = |name: &str| {
+            Kml::Placemark(Placemark {
+                name: Some(name.to_string()),
+                attrs: HashMap::from([("class".to_string(), name.to_string())]),
+                ..Default::default()
+            })
+        };
+        let folder = |name: &str, elements: Vec<Kml>| {
+            Kml::Folder(Folder {
+                name: Some(name.to_string()),
+                elements,
+                ..Default::default()
+            })
+        };
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                folder(
+                    "outer",
+                    vec![placemark("a"), folder("inner", vec![placemark("b")])],
+                ),
+                placemark("c"),
+                Kml::Element(Element {
+                    name: "gx:Tour".to_string(),
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        });
+
+        let names = |path: &str| -> Vec<Option<&str>> {
+            kml.select(path)
+                .unwrap()
+                .into_iter()
+                .map(Kml::name)
+                .collect()
+        };
+        assert_eq!(names("Document/Placemark"), vec![Some("c")]);
+        assert_eq!(
+            names("Document/Folder[name='outer']/Placemark"),
+            vec![Some("a")]
+        );
+        assert_eq!(names("Document/*/Folder"), vec![Some("inner")]);
+        assert_eq!(names("//Placemark"), vec![Some("a"), Some("b"), Some("c")]);
+        assert_eq!(names("//Folder//Placemark"), vec![Some("a"), Some("b")]);
+        assert_eq!(names("//Placemark[@class='b']"), vec![Some("b")]);
+        assert_eq!(kml.select("Document/Tour").unwrap().len(), 1);
+        assert!(names("Folder").is_empty());
+
+        let document = KmlDocument {
+            elements: vec![kml.clone()],
+            ..Default::default()
+        };
+        assert_eq!(
+            document.select("Document/Placemark").unwrap(),
+            vec![&placemark("c")]
+        );
+        assert_eq!(
+            Kml::KmlDocument(document)
+                .select("//Placemark")
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+}
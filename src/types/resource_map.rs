@@ -3,7 +3,9 @@ use std::collections::HashMap;
 
 /// `kml:ResourceMap`, [10.13](https://docs.ogc.org/is/12-007r2/12-007r2.html#591) in the KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResourceMap {
     pub aliases: Vec<Alias>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
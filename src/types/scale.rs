@@ -5,11 +5,13 @@ use num_traits::One;
 
 /// `kml:Scale`, [10.12](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#575) in the KML
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scale<T: CoordType = f64> {
     pub x: T,
     pub y: T,
     pub z: T,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl<T> Scale<T>
@@ -22,6 +24,7 @@ where
             y,
             z,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -33,6 +36,7 @@ impl Default for Scale {
             y: One::one(),
             z: One::one(),
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -9,6 +9,7 @@ use crate::types::Vec2;
 /// `kml:Style`, [12.2](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#798) in the KML
 /// specification
 #[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Style {
     pub id: Option<String>,
     pub balloon: Option<BalloonStyle>,
@@ -18,36 +19,83 @@ pub struct Style {
     pub poly: Option<PolyStyle>,
     pub list: Option<ListStyle>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 /// `kml:StyleMap`, [12.3](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#811) in the KML
 /// specification
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StyleMap {
     pub id: Option<String>,
     pub pairs: Vec<Pair>,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 /// `kml:Pair`, [12.4](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#819) in the KML
 /// specification
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pair {
-    pub key: String,
+    pub key: StyleState,
     pub style_url: String,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+}
+
+/// `kml:styleStateEnumType` used as the key of a `kml:Pair`, see the [Google StyleMap reference](https://developers.google.com/kml/documentation/kmlreference#stylemap)
+///
+/// Values outside of the specification are kept in `Other` so they can be written back unchanged
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StyleState {
+    #[default]
+    Normal,
+    Highlight,
+    Other(String),
+}
+
+impl FromStr for StyleState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "highlight" => Ok(Self::Highlight),
+            v => Ok(Self::Other(v.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for StyleState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Normal => "normal",
+                Self::Highlight => "highlight",
+                Self::Other(v) => v,
+            }
+        )
+    }
 }
 
 /// `kml:BalloonStyle`, [12.7](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#841) in the
 /// KML specification
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BalloonStyle {
     pub id: Option<String>,
     pub bg_color: Option<String>,
     pub text_color: String,
+    /// Unescaped text of `kml:text`, or the verbatim inner XML if it contains HTML elements, which
+    /// is written back as is
     pub text: Option<String>,
     pub display: bool,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for BalloonStyle {
@@ -55,10 +103,11 @@ impl Default for BalloonStyle {
         BalloonStyle {
             id: None,
             bg_color: None,
-            text_color: "ffffffff".to_string(),
+            text_color: "ff000000".to_string(),
             text: None,
             display: true,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -66,6 +115,7 @@ impl Default for BalloonStyle {
 /// `kml:colorMode`, [12.11](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#879) in the
 /// KML specification
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorMode {
     #[default]
     Normal,
@@ -100,15 +150,17 @@ impl fmt::Display for ColorMode {
 /// `kml:IconStyle`, [12.12](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#883) in the
 /// KML specification
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IconStyle {
     pub id: Option<String>,
     pub scale: f64,
     pub heading: f64,
     pub hot_spot: Option<Vec2>,
-    pub icon: Icon,
+    pub icon: Option<Icon>,
     pub color: String,
     pub color_mode: ColorMode,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for IconStyle {
@@ -118,10 +170,11 @@ impl Default for IconStyle {
             scale: 1.0,
             heading: 0.0,
             hot_spot: None,
-            icon: Icon::default(),
+            icon: None,
             color: "ffffffff".to_string(),
             color_mode: ColorMode::default(),
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -131,20 +184,24 @@ impl Default for IconStyle {
 ///
 /// Implements on `kml:BasicLinkType`
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Icon {
     pub href: String,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 /// `kml:LabelStyle`, [12.14](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#909) in the
 /// KML specification.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LabelStyle {
     pub id: Option<String>,
     pub color: String,
     pub color_mode: ColorMode,
     pub scale: f64,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for LabelStyle {
@@ -155,6 +212,7 @@ impl Default for LabelStyle {
             color_mode: ColorMode::default(),
             scale: 1.0,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -162,12 +220,14 @@ impl Default for LabelStyle {
 /// `kml:LineStyle`, [12.15](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#917) in the
 /// KML specification.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineStyle {
     pub id: Option<String>,
     pub color: String,
     pub color_mode: ColorMode,
     pub width: f64,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for LineStyle {
@@ -178,6 +238,7 @@ impl Default for LineStyle {
             color_mode: ColorMode::default(),
             width: 1.0,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -185,6 +246,7 @@ impl Default for LineStyle {
 /// `kml:PolyStyle`, [12.16](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#927) in the
 /// KML specification.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PolyStyle {
     pub id: Option<String>,
     pub color: String,
@@ -192,6 +254,7 @@ pub struct PolyStyle {
     pub fill: bool,
     pub outline: bool,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for PolyStyle {
@@ -203,6 +266,7 @@ impl Default for PolyStyle {
             fill: true,
             outline: true,
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
@@ -210,6 +274,7 @@ impl Default for PolyStyle {
 /// `kml:listItemType`, [12.18](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#955) in the
 /// KML specification.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ListItemType {
     #[default]
     Check,
@@ -250,12 +315,14 @@ impl fmt::Display for ListItemType {
 /// `kml:ListStyle`, [12.17](http://docs.opengeospatial.org/is/12-007r2/12-007r2.html#940) in the
 /// KML specification.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ListStyle {
     pub id: Option<String>,
     pub bg_color: String,
     pub max_snippet_lines: u32,
     pub list_item_type: ListItemType,
     pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
 }
 
 impl Default for ListStyle {
@@ -266,6 +333,29 @@ impl Default for ListStyle {
             max_snippet_lines: 2,
             list_item_type: ListItemType::default(),
             attrs: HashMap::new(),
+            namespace: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_state_from_str() {
+        assert_eq!(StyleState::Normal, StyleState::from_str("normal").unwrap());
+        assert_eq!(
+            StyleState::Highlight,
+            StyleState::from_str("highlight").unwrap()
+        );
+        assert_eq!(
+            StyleState::Other("selected".to_string()),
+            StyleState::from_str("selected").unwrap()
+        );
+        assert_eq!(
+            "selected",
+            StyleState::from_str("selected").unwrap().to_string()
+        );
+    }
+}
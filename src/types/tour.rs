@@ -0,0 +1,18 @@
+use std::collections::HashMap;
+
+use crate::types::element::{Element, Node};
+
+/// `gx:Tour` from the [Google extensions](https://developers.google.com/kml/documentation/kmlreference#gxtour),
+/// promoted to `kml:Tour` in KML 2.3
+///
+/// Tour primitives inside of the playlist are currently represented by generic elements
+#[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tour {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub playlist: Vec<Element>,
+    pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+    pub children: Vec<Node>,
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::types::altitude_mode::AltitudeMode;
+use crate::types::coord::{Coord, CoordType};
+use crate::types::element::Node;
+use crate::types::orientation::Orientation;
+
+/// `gx:Track` from the [Google extensions](https://developers.google.com/kml/documentation/kmlreference#gxtrack),
+/// promoted to `kml:Track` in KML 2.3
+///
+/// Each coordinate corresponds to the timestamp and angles at the same index when present
+#[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track<T: CoordType = f64> {
+    pub altitude_mode: Option<AltitudeMode>,
+    pub whens: Vec<String>,
+    pub coords: Vec<Coord<T>>,
+    pub angles: Vec<Orientation<T>>,
+    pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+    pub children: Vec<Node>,
+}
+
+impl<T> From<Vec<Coord<T>>> for Track<T>
+where
+    T: CoordType + Default,
+{
+    fn from(coords: Vec<Coord<T>>) -> Self {
+        Track {
+            coords,
+            ..Default::default()
+        }
+    }
+}
+
+impl<T: CoordType> Track<T> {
+    /// Returns the time of each coordinate in seconds since the Unix epoch, parsed from `whens`
+    ///
+    /// Times can be a date and time with an optional time zone, which is UTC if missing, or a
+    /// date, year and month, or year, which are taken as the start of that period. Times that
+    /// can't be parsed or are missing are `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::types::{Coord, Track};
+    ///
+    /// let track: Track = Track {
+    ///     whens: vec![
+    ///         "2010-05-28T02:02:09Z".to_string(),
+    ///         "2010-05-28T04:02:39+02:00".to_string(),
+    ///     ],
+    ///     coords: vec![Coord::new(1., 1., None), Coord::new(2., 2., None)],
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(track.timestamps(), vec![Some(1275012129.), Some(1275012159.)]);
+    /// ```
+    pub fn timestamps(&self) -> Vec<Option<f64>> {
+        (0..self.coords.len())
+            .map(|i| self.whens.get(i).and_then(|w| parse_when(w)))
+            .collect()
+    }
+}
+
+/// Parses a `kml:dateTimeType` into seconds since the Unix epoch
+fn parse_when(when: &str) -> Option<f64> {
+    let when = when.trim();
+    let (date, time) = match when.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (when, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next().map_or(Some(1), |m| m.parse().ok())?;
+    let day: i64 = parts.next().map_or(Some(1), |d| d.parse().ok())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut seconds = (days_from_civil(year, month, day) * 86400) as f64;
+    if let Some(time) = time {
+        let zone_start = time.find(['Z', '+', '-']).unwrap_or(time.len());
+        let (time, zone) = time.split_at(zone_start);
+        let mut parts = time.splitn(3, ':');
+        let hours: f64 = parts.next()?.parse().ok()?;
+        let minutes: f64 = parts.next()?.parse().ok()?;
+        let secs: f64 = parts.next().map_or(Some(0.), |s| s.parse().ok())?;
+        seconds += hours * 3600. + minutes * 60. + secs;
+
+        if let Some(offset) = zone.strip_prefix(['+', '-']) {
+            let (h, m) = offset.split_once(':')?;
+            let offset = h.parse::<f64>().ok()? * 3600. + m.parse::<f64>().ok()? * 60.;
+            seconds -= if zone.starts_with('-') {
+                -offset
+            } else {
+                offset
+            };
+        } else if !zone.is_empty() && zone != "Z" {
+            return None;
+        }
+    }
+    Some(seconds)
+}
+
+/// Days since the Unix epoch for a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// `gx:MultiTrack` from the [Google extensions](https://developers.google.com/kml/documentation/kmlreference#gxmultitrack),
+/// promoted to `kml:MultiTrack` in KML 2.3
+#[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiTrack<T: CoordType = f64> {
+    pub altitude_mode: Option<AltitudeMode>,
+    pub interpolate: bool,
+    pub tracks: Vec<Track<T>>,
+    pub attrs: HashMap<String, String>,
+    pub namespace: Option<String>,
+}
+
+impl<T> MultiTrack<T>
+where
+    T: CoordType + Default,
+{
+    pub fn new(tracks: Vec<Track<T>>) -> Self {
+        MultiTrack {
+            tracks,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_when() {
+        assert_eq!(parse_when("1970-01-01T00:00:00Z"), Some(0.));
+        assert_eq!(parse_when("2010-05-28T02:02:09Z"), Some(1275012129.));
+        assert_eq!(parse_when("2010-05-28T02:02:09.5"), Some(1275012129.5));
+        assert_eq!(parse_when("2010-05-27T21:02:09-05:00"), Some(1275012129.));
+        assert_eq!(parse_when("2000-03-01"), Some(951868800.));
+        assert_eq!(parse_when("1997-07"), Some(867715200.));
+        assert_eq!(parse_when("1969"), Some(-31536000.));
+        assert_eq!(parse_when("2010-13-01"), None);
+        assert_eq!(parse_when("yesterday"), None);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let track: Track = Track {
+            whens: vec!["1970-01-01T00:01:00Z".to_string(), "bad".to_string()],
+            coords: vec![Coord::new(0., 0., None); 3],
+            ..Default::default()
+        };
+        assert_eq!(track.timestamps(), vec![Some(60.), None, None]);
+    }
+}
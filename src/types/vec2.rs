@@ -4,6 +4,7 @@ use std::str::FromStr;
 use crate::Error;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
@@ -22,19 +23,15 @@ impl Default for Vec2 {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Units {
+    #[default]
     Fraction,
     Pixels,
     InsetPixels,
 }
 
-impl Default for Units {
-    fn default() -> Self {
-        Self::Fraction
-    }
-}
-
 impl FromStr for Units {
     type Err = Error;
 
@@ -0,0 +1,612 @@
+//! Module for traversing a tree with a [`KmlVisitor`](trait.KmlVisitor.html), or editing it in
+//! place with a [`KmlVisitorMut`](trait.KmlVisitorMut.html)
+//!
+//! Each `visit_` method of the trait is called for the matching element, and by default calls the
+//! `walk_` function of the same name to visit what's inside of it, so a visitor only overrides
+//! the methods for the elements it's interested in. An overridden method calls the `walk_`
+//! function itself to keep descending, or leaves it out to skip what's inside.
+//!
+//! # Example
+//!
+//! ```
+//! use kml::types::{Placemark, Point};
+//! use kml::visitor::{walk_placemark, KmlVisitor};
+//! use kml::Kml;
+//!
+//! #[derive(Default)]
+//! struct Stats {
+//!     placemarks: usize,
+//!     points: usize,
+//! }
+//!
+//! impl KmlVisitor for Stats {
+//!     fn visit_placemark(&mut self, placemark: &Placemark) {
+//!         self.placemarks += 1;
+//!         walk_placemark(self, placemark);
+//!     }
+//!
+//!     fn visit_point(&mut self, _: &Point) {
+//!         self.points += 1;
+//!     }
+//! }
+//!
+//! let kml: Kml = r#"
+//! <Folder>
+//!   <Placemark><Point><coordinates>1,1</coordinates></Point></Placemark>
+//!   <Placemark>
+//!     <MultiGeometry>
+//!       <Point><coordinates>2,2</coordinates></Point>
+//!       <Point><coordinates>3,3</coordinates></Point>
+//!     </MultiGeometry>
+//!   </Placemark>
+//! </Folder>"#
+//!     .parse()
+//!     .unwrap();
+//! let mut stats = Stats::default();
+//! kml.accept(&mut stats);
+//! assert_eq!((stats.placemarks, stats.points), (2, 3));
+//! ```
+use crate::types::{
+    CoordType, Document, Element, Folder, Geometry, Kml, KmlDocument, LineString, LinearRing,
+    Location, MultiGeometry, MultiTrack, Placemark, Point, Polygon, Style, StyleMap, Track,
+};
+
+/// Callbacks for each kind of element in a tree, see the [module documentation](index.html)
+#[allow(unused_variables)]
+pub trait KmlVisitor<T: CoordType = f64> {
+    /// Called for every `Kml` value, dispatching to the other methods by variant
+    fn visit_kml(&mut self, kml: &Kml<T>) {
+        walk_kml(self, kml);
+    }
+
+    fn visit_kml_document(&mut self, document: &KmlDocument<T>) {
+        walk_elements(self, &document.elements);
+    }
+
+    fn visit_document(&mut self, document: &Document<T>) {
+        walk_elements(self, &document.elements);
+    }
+
+    fn visit_folder(&mut self, folder: &Folder<T>) {
+        walk_elements(self, &folder.elements);
+    }
+
+    fn visit_placemark(&mut self, placemark: &Placemark<T>) {
+        walk_placemark(self, placemark);
+    }
+
+    /// Called for geometries in placemarks and multi geometries, dispatching to the other
+    /// methods by variant, but not for geometries directly in a `Kml` value
+    fn visit_geometry(&mut self, geometry: &Geometry<T>) {
+        walk_geometry(self, geometry);
+    }
+
+    fn visit_point(&mut self, point: &Point<T>) {}
+
+    fn visit_line_string(&mut self, line_string: &LineString<T>) {}
+
+    /// Called for linear rings on their own and in polygons
+    fn visit_linear_ring(&mut self, linear_ring: &LinearRing<T>) {}
+
+    fn visit_polygon(&mut self, polygon: &Polygon<T>) {
+        walk_polygon(self, polygon);
+    }
+
+    fn visit_multi_geometry(&mut self, multi_geometry: &MultiGeometry<T>) {
+        walk_multi_geometry(self, multi_geometry);
+    }
+
+    /// Called for tracks on their own and in multi tracks
+    fn visit_track(&mut self, track: &Track<T>) {}
+
+    fn visit_multi_track(&mut self, multi_track: &MultiTrack<T>) {
+        walk_multi_track(self, multi_track);
+    }
+
+    fn visit_location(&mut self, location: &Location<T>) {}
+
+    fn visit_style(&mut self, style: &Style) {}
+
+    fn visit_style_map(&mut self, style_map: &StyleMap) {}
+
+    /// Called for generic elements, including models in placemarks
+    fn visit_element(&mut self, element: &Element) {}
+
+    /// Called for the variants without a method of their own
+    fn visit_other(&mut self, kml: &Kml<T>) {}
+}
+
+/// Calls the visitor's method for the variant of `kml`
+pub fn walk_kml<T, V>(visitor: &mut V, kml: &Kml<T>)
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    match kml {
+        Kml::KmlDocument(d) => visitor.visit_kml_document(d),
+        Kml::Document(d) => visitor.visit_document(d),
+        Kml::Folder(f) => visitor.visit_folder(f),
+        Kml::Placemark(p) => visitor.visit_placemark(p),
+        Kml::Point(p) => visitor.visit_point(p),
+        Kml::LineString(l) => visitor.visit_line_string(l),
+        Kml::LinearRing(l) => visitor.visit_linear_ring(l),
+        Kml::Polygon(p) => visitor.visit_polygon(p),
+        Kml::MultiGeometry(g) => visitor.visit_multi_geometry(g),
+        Kml::Track(t) => visitor.visit_track(t),
+        Kml::MultiTrack(t) => visitor.visit_multi_track(t),
+        Kml::Location(l) => visitor.visit_location(l),
+        Kml::Style(s) => visitor.visit_style(s),
+        Kml::StyleMap(s) => visitor.visit_style_map(s),
+        Kml::Element(e) => visitor.visit_element(e),
+        kml => visitor.visit_other(kml),
+    }
+}
+
+/// Visits each of the elements of a document or folder in order
+pub fn walk_elements<T, V>(visitor: &mut V, elements: &[Kml<T>])
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    for element in elements {
+        visitor.visit_kml(element);
+    }
+}
+
+/// Visits the placemark's geometry, if it has one
+pub fn walk_placemark<T, V>(visitor: &mut V, placemark: &Placemark<T>)
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    if let Some(geometry) = &placemark.geometry {
+        visitor.visit_geometry(geometry);
+    }
+}
+
+/// Calls the visitor's method for the variant of `geometry`
+pub fn walk_geometry<T, V>(visitor: &mut V, geometry: &Geometry<T>)
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    match geometry {
+        Geometry::Point(p) => visitor.visit_point(p),
+        Geometry::LineString(l) => visitor.visit_line_string(l),
+        Geometry::LinearRing(l) => visitor.visit_linear_ring(l),
+        Geometry::Polygon(p) => visitor.visit_polygon(p),
+        Geometry::MultiGeometry(g) => visitor.visit_multi_geometry(g),
+        Geometry::Track(t) => visitor.visit_track(t),
+        Geometry::MultiTrack(t) => visitor.visit_multi_track(t),
+        Geometry::Element(e) => visitor.visit_element(e),
+    }
+}
+
+/// Visits the outer ring of the polygon and then its inner rings
+pub fn walk_polygon<T, V>(visitor: &mut V, polygon: &Polygon<T>)
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    visitor.visit_linear_ring(&polygon.outer);
+    for ring in &polygon.inner {
+        visitor.visit_linear_ring(ring);
+    }
+}
+
+/// Visits each of the geometries
+pub fn walk_multi_geometry<T, V>(visitor: &mut V, multi_geometry: &MultiGeometry<T>)
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    for geometry in &multi_geometry.geometries {
+        visitor.visit_geometry(geometry);
+    }
+}
+
+/// Visits each of the tracks
+pub fn walk_multi_track<T, V>(visitor: &mut V, multi_track: &MultiTrack<T>)
+where
+    T: CoordType,
+    V: KmlVisitor<T> + ?Sized,
+{
+    for track in &multi_track.tracks {
+        visitor.visit_track(track);
+    }
+}
+
+/// Callbacks for editing each kind of element in a tree in place, the mutable counterpart of
+/// [`KmlVisitor`](trait.KmlVisitor.html) with `_mut` walk functions
+#[allow(unused_variables)]
+pub trait KmlVisitorMut<T: CoordType = f64> {
+    /// Called for every `Kml` value, dispatching to the other methods by variant
+    fn visit_kml_mut(&mut self, kml: &mut Kml<T>) {
+        walk_kml_mut(self, kml);
+    }
+
+    fn visit_kml_document_mut(&mut self, document: &mut KmlDocument<T>) {
+        walk_elements_mut(self, &mut document.elements);
+    }
+
+    /// Called with the document, whose elements can be added to or removed
+    fn visit_document_mut(&mut self, document: &mut Document<T>) {
+        walk_elements_mut(self, &mut document.elements);
+    }
+
+    /// Called with the folder, whose elements can be added to or removed
+    fn visit_folder_mut(&mut self, folder: &mut Folder<T>) {
+        walk_elements_mut(self, &mut folder.elements);
+    }
+
+    fn visit_placemark_mut(&mut self, placemark: &mut Placemark<T>) {
+        walk_placemark_mut(self, placemark);
+    }
+
+    /// Called for geometries in placemarks and multi geometries, dispatching to the other
+    /// methods by variant, but not for geometries directly in a `Kml` value
+    fn visit_geometry_mut(&mut self, geometry: &mut Geometry<T>) {
+        walk_geometry_mut(self, geometry);
+    }
+
+    fn visit_point_mut(&mut self, point: &mut Point<T>) {}
+
+    fn visit_line_string_mut(&mut self, line_string: &mut LineString<T>) {}
+
+    /// Called for linear rings on their own and in polygons
+    fn visit_linear_ring_mut(&mut self, linear_ring: &mut LinearRing<T>) {}
+
+    fn visit_polygon_mut(&mut self, polygon: &mut Polygon<T>) {
+        walk_polygon_mut(self, polygon);
+    }
+
+    fn visit_multi_geometry_mut(&mut self, multi_geometry: &mut MultiGeometry<T>) {
+        walk_multi_geometry_mut(self, multi_geometry);
+    }
+
+    /// Called for tracks on their own and in multi tracks
+    fn visit_track_mut(&mut self, track: &mut Track<T>) {}
+
+    fn visit_multi_track_mut(&mut self, multi_track: &mut MultiTrack<T>) {
+        walk_multi_track_mut(self, multi_track);
+    }
+
+    fn visit_location_mut(&mut self, location: &mut Location<T>) {}
+
+    fn visit_style_mut(&mut self, style: &mut Style) {}
+
+    fn visit_style_map_mut(&mut self, style_map: &mut StyleMap) {}
+
+    /// Called for generic elements, including models in placemarks
+    fn visit_element_mut(&mut self, element: &mut Element) {}
+
+    /// Called for the variants without a method of their own
+    fn visit_other_mut(&mut self, kml: &mut Kml<T>) {}
+}
+
+/// Calls the visitor's method for the variant of `kml`
+pub fn walk_kml_mut<T, V>(visitor: &mut V, kml: &mut Kml<T>)
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    match kml {
+        Kml::KmlDocument(d) => visitor.visit_kml_document_mut(d),
+        Kml::Document(d) => visitor.visit_document_mut(d),
+        Kml::Folder(f) => visitor.visit_folder_mut(f),
+        Kml::Placemark(p) => visitor.visit_placemark_mut(p),
+        Kml::Point(p) => visitor.visit_point_mut(p),
+        Kml::LineString(l) => visitor.visit_line_string_mut(l),
+        Kml::LinearRing(l) => visitor.visit_linear_ring_mut(l),
+        Kml::Polygon(p) => visitor.visit_polygon_mut(p),
+        Kml::MultiGeometry(g) => visitor.visit_multi_geometry_mut(g),
+        Kml::Track(t) => visitor.visit_track_mut(t),
+        Kml::MultiTrack(t) => visitor.visit_multi_track_mut(t),
+        Kml::Location(l) => visitor.visit_location_mut(l),
+        Kml::Style(s) => visitor.visit_style_mut(s),
+        Kml::StyleMap(s) => visitor.visit_style_map_mut(s),
+        Kml::Element(e) => visitor.visit_element_mut(e),
+        kml => visitor.visit_other_mut(kml),
+    }
+}
+
+/// Visits each of the elements of a document or folder in order
+pub fn walk_elements_mut<T, V>(visitor: &mut V, elements: &mut [Kml<T>])
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    for element in elements {
+        visitor.visit_kml_mut(element);
+    }
+}
+
+/// Visits the placemark's geometry, if it has one
+pub fn walk_placemark_mut<T, V>(visitor: &mut V, placemark: &mut Placemark<T>)
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    if let Some(geometry) = &mut placemark.geometry {
+        visitor.visit_geometry_mut(geometry);
+    }
+}
+
+/// Calls the visitor's method for the variant of `geometry`
+pub fn walk_geometry_mut<T, V>(visitor: &mut V, geometry: &mut Geometry<T>)
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    match geometry {
+        Geometry::Point(p) => visitor.visit_point_mut(p),
+        Geometry::LineString(l) => visitor.visit_line_string_mut(l),
+        Geometry::LinearRing(l) => visitor.visit_linear_ring_mut(l),
+        Geometry::Polygon(p) => visitor.visit_polygon_mut(p),
+        Geometry::MultiGeometry(g) => visitor.visit_multi_geometry_mut(g),
+        Geometry::Track(t) => visitor.visit_track_mut(t),
+        Geometry::MultiTrack(t) => visitor.visit_multi_track_mut(t),
+        Geometry::Element(e) => visitor.visit_element_mut(e),
+    }
+}
+
+/// Visits the outer ring of the polygon and then its inner rings
+pub fn walk_polygon_mut<T, V>(visitor: &mut V, polygon: &mut Polygon<T>)
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    visitor.visit_linear_ring_mut(&mut polygon.outer);
+    for ring in &mut polygon.inner {
+        visitor.visit_linear_ring_mut(ring);
+    }
+}
+
+/// Visits each of the geometries
+pub fn walk_multi_geometry_mut<T, V>(visitor: &mut V, multi_geometry: &mut MultiGeometry<T>)
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    for geometry in &mut multi_geometry.geometries {
+        visitor.visit_geometry_mut(geometry);
+    }
+}
+
+/// Visits each of the tracks
+pub fn walk_multi_track_mut<T, V>(visitor: &mut V, multi_track: &mut MultiTrack<T>)
+where
+    T: CoordType,
+    V: KmlVisitorMut<T> + ?Sized,
+{
+    for track in &mut multi_track.tracks {
+        visitor.visit_track_mut(track);
+    }
+}
+
+impl<T: CoordType> Kml<T> {
+    /// Traverses the tree with the visitor, see the [`visitor`](../visitor/index.html) module
+    pub fn accept<V: KmlVisitor<T> + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_kml(self);
+    }
+
+    /// Traverses the tree with the visitor, editing it in place, see the
+    /// [`visitor`](../visitor/index.html) module
+    pub fn accept_mut<V: KmlVisitorMut<T> + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_kml_mut(self);
+    }
+
+    /// Calls `f` on the element and everything in it in document order, descending into
+    /// documents and folders after `f` has edited them, like a mutable
+    /// [`iter`](../enum.Kml.html#method.iter)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::Kml;
+    ///
+    /// let mut kml: Kml = r#"
+    /// <Document>
+    ///   <Placemark><name>a</name><description>Draft</description></Placemark>
+    ///   <Folder>
+    ///     <Placemark><name>b</name></Placemark>
+    ///     <Link><href>http://example.com/feed.kml</href></Link>
+    ///   </Folder>
+    /// </Document>"#
+    ///     .parse()
+    ///     .unwrap();
+    /// kml.walk_mut(|element| match element {
+    ///     Kml::Placemark(p) => {
+    ///         p.name = p.name.as_ref().map(|n| n.to_uppercase());
+    ///         p.description = None;
+    ///     }
+    ///     Kml::Link(l) => {
+    ///         l.href = l.href.as_ref().map(|h| h.replace("http:", "https:"));
+    ///     }
+    ///     _ => {}
+    /// });
+    ///
+    /// let kml = kml.to_string();
+    /// assert!(kml.contains("<name>A</name>") && kml.contains("<name>B</name>"));
+    /// assert!(!kml.contains("Draft"));
+    /// assert!(kml.contains("https://example.com/feed.kml"));
+    /// ```
+    pub fn walk_mut(&mut self, mut f: impl FnMut(&mut Kml<T>)) {
+        walk_with(self, &mut f);
+    }
+}
+
+/// Implements `Kml::walk_mut`, taking a trait object so the recursion doesn't instantiate it for
+/// ever deeper references to the closure
+fn walk_with<T: CoordType>(kml: &mut Kml<T>, f: &mut dyn FnMut(&mut Kml<T>)) {
+    f(kml);
+    match kml {
+        Kml::KmlDocument(KmlDocument { elements, .. })
+        | Kml::Document(Document { elements, .. })
+        | Kml::Folder(Folder { elements, .. }) => {
+            for element in elements {
+                walk_with(element, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Coord;
+
+    /// Records the methods called, skipping the contents of folders
+    #[derive(Default)]
+    struct Recorder(Vec<&'static str>);
+
+    impl KmlVisitor for Recorder {
+        fn visit_folder(&mut self, _: &Folder) {
+            self.0.push("folder");
+        }
+
+        fn visit_placemark(&mut self, placemark: &Placemark) {
+            self.0.push("placemark");
+            walk_placemark(self, placemark);
+        }
+
+        fn visit_point(&mut self, _: &Point) {
+            self.0.push("point");
+        }
+
+        fn visit_linear_ring(&mut self, _: &LinearRing) {
+            self.0.push("ring");
+        }
+
+        fn visit_track(&mut self, _: &Track) {
+            self.0.push("track");
+        }
+
+        fn visit_style(&mut self, _: &Style) {
+            self.0.push("style");
+        }
+
+        fn visit_other(&mut self, _: &Kml) {
+            self.0.push("other");
+        }
+    }
+
+    #[test]
+    fn test_accept() {
+        let ring = LinearRing::from(vec![Coord::new(0., 0., None)]);
+        let placemark = |geometry| {
+            Kml::Placemark(Placemark {
+                geometry: Some(geometry),
+                ..Default::default()
+            })
+        };
+        let kml: Kml = Kml::KmlDocument(KmlDocument {
+            elements: vec![Kml::Document(Document {
+                elements: vec![
+                    Kml::Style(Style::default()),
+                    placemark(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                        Geometry::Point(Point::new(1., 1., None)),
+                        Geometry::Polygon(Polygon::new(ring.clone(), vec![ring])),
+                    ]))),
+                    Kml::Folder(Folder {
+                        elements: vec![Kml::Comment("skipped".to_string())],
+                        ..Default::default()
+                    }),
+                    placemark(Geometry::MultiTrack(MultiTrack::new(vec![
+                        Track::default(),
+                        Track::default(),
+                    ]))),
+                    Kml::Comment("visited".to_string()),
+                ],
+                ..Default::default()
+            })],
+            ..Default::default()
+        });
+
+        let mut recorder = Recorder::default();
+        kml.accept(&mut recorder);
+        assert_eq!(
+            recorder.0,
+            vec![
+                "style",
+                "placemark",
+                "point",
+                "ring",
+                "ring",
+                "folder",
+                "placemark",
+                "track",
+                "track",
+                "other"
+            ]
+        );
+    }
+
+    /// Renames placemarks and drops comments from folders
+    struct Editor;
+
+    impl KmlVisitorMut for Editor {
+        fn visit_folder_mut(&mut self, folder: &mut Folder) {
+            folder.elements.retain(|e| !matches!(e, Kml::Comment(_)));
+            walk_elements_mut(self, &mut folder.elements);
+        }
+
+        fn visit_placemark_mut(&mut self, placemark: &mut Placemark) {
+            placemark.name = Some("renamed".to_string());
+            walk_placemark_mut(self, placemark);
+        }
+
+        fn visit_point_mut(&mut self, point: &mut Point) {
+            point.coord.x += 1.;
+        }
+    }
+
+    #[test]
+    fn test_accept_mut() {
+        let placemark = |name: &str, x: f64| {
+            Kml::Placemark(Placemark {
+                name: Some(name.to_string()),
+                geometry: Some(Geometry::Point(Point::new(x, 0., None))),
+                ..Default::default()
+            })
+        };
+        let folder = |elements| {
+            Kml::Folder(Folder {
+                elements,
+                ..Default::default()
+            })
+        };
+        let mut kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Comment("kept".to_string()),
+                folder(vec![
+                    Kml::Comment("dropped".to_string()),
+                    placemark("a", 1.),
+                ]),
+            ],
+            ..Default::default()
+        });
+        kml.accept_mut(&mut Editor);
+        assert_eq!(
+            kml,
+            Kml::Document(Document {
+                elements: vec![
+                    Kml::Comment("kept".to_string()),
+                    folder(vec![placemark("renamed", 2.)]),
+                ],
+                ..Default::default()
+            })
+        );
+
+        let mut visited = 0;
+        kml.walk_mut(|e| {
+            visited += 1;
+            if let Kml::Folder(Folder { elements, .. }) = e {
+                elements.push(Kml::Comment("added".to_string()));
+            }
+        });
+        // The comment added to the folder is visited too
+        assert_eq!(visited, 5);
+    }
+}
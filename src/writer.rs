@@ -1,25 +1,810 @@
 //! Module for writing KML types
-use std::collections::HashMap;
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
 use std::io::Write;
 use std::marker::PhantomData;
+#[cfg(feature = "fs")]
+use std::path::Path;
 use std::str;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::events::{BytesCData, BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 
 use crate::errors::Error;
 use crate::types::geom_props::GeomProps;
 use crate::types::{
-    Alias, BalloonStyle, Coord, CoordType, Element, Geometry, Icon, IconStyle, Kml, LabelStyle,
-    LineString, LineStyle, LinearRing, Link, LinkTypeIcon, ListStyle, Location, MultiGeometry,
+    Alias, AltitudeMode, BalloonStyle, Coord, CoordType, Document, Element, Extension, Folder,
+    Geometry, Icon, IconStyle, Kml, KmlExtension, KmlVersion, LabelStyle, LineString, LineStyle,
+    LinearRing, Link, LinkTypeIcon, ListStyle, Location, MultiGeometry, MultiTrack, Node,
     Orientation, Pair, Placemark, Point, PolyStyle, Polygon, ResourceMap, Scale, SchemaData,
-    SimpleArrayData, SimpleData, Style, StyleMap,
+    SimpleArrayData, SimpleData, Style, StyleMap, StyleState, Tour, Track,
 };
 
+/// Transform applied to coordinates as they're written, set with
+/// [`KmlWriter::with_coord_transform`](struct.KmlWriter.html#method.with_coord_transform)
+type CoordTransform<T> = Box<dyn Fn(Coord<T>) -> Coord<T> + Send + Sync>;
+
+/// Configuration for [`KmlWriter`](struct.KmlWriter.html)
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct KmlWriterConfig {
+    /// Version determining the namespace declared on `kml` roots and how elements that KML 2.3
+    /// promoted from the `gx` extension namespace (like `Track` and `Tour`) are named.
+    /// [`KmlVersion::V23`] writes them without the `gx:` prefix, while writing KML 2.3 elements
+    /// read into an [`Element`](../types/struct.Element.html) as KML 2.2 is an error. Documents
+    /// declaring the namespace of another version are written with the namespace of this one. If
+    /// `Unknown`, the version of the `KmlDocument` being written is used
+    pub version: KmlVersion,
+    /// Writers for elements keyed by local name, set with
+    /// [`register_writer`](#method.register_writer)
+    pub element_writers: HashMap<String, ElementWriter>,
+    /// Writers for extensions keyed by the type they were parsed into, set with
+    /// [`register_extension_writer`](#method.register_extension_writer)
+    pub extension_writers: HashMap<TypeId, ExtensionWriter>,
+    /// Maximum number of decimal places for coordinates and other numbers, rounding values with
+    /// more and leaving off trailing zeros. Numbers are written in full when `None`, the default
+    pub precision: Option<usize>,
+    /// Leave out elements holding the spec default, like `extrude` of `0`, `altitudeMode` of
+    /// `clampToGround`, a `scale` of `1`, or a white `color`, for smaller output. `false` by default
+    pub omit_defaults: bool,
+    /// Always wrap HTML-bearing text, like `description` and the `text` of a `BalloonStyle`, in a
+    /// CDATA section rather than escaping it. By default only descriptions containing markup and
+    /// `BalloonStyle` text containing markup that isn't well-formed XML are wrapped, while
+    /// well-formed markup in `BalloonStyle` text is written verbatim. Content that itself contains
+    /// `]]>` is never wrapped
+    pub cdata: bool,
+    /// Write HTML-bearing text, like `description` and the `text` of a `BalloonStyle`, verbatim
+    /// without escaping it or wrapping it in CDATA, for trusted content that's already valid markup
+    /// and needs to be written byte for byte. Takes precedence over [`cdata`](#structfield.cdata).
+    /// The content isn't checked, so anything that isn't valid XML makes the output invalid
+    pub raw_html: bool,
+    /// How `Style` and `StyleMap` elements sharing an id are handled when writing with
+    /// [`write`](struct.KmlWriter.html#method.write),
+    /// [`write_document`](struct.KmlWriter.html#method.write_document), or
+    /// [`write_root`](struct.KmlWriter.html#method.write_root)
+    pub duplicate_style_ids: DuplicateStyleIds,
+    /// Write elements without any content, like an `Icon` without an `href`, as self-closing
+    /// tags like `<Icon/>`, as Google Earth does. Elements with content are written as is
+    pub self_closing: bool,
+    /// How tuples in `coordinates` are separated, with one tuple per line by default
+    pub coord_layout: CoordLayout,
+    /// Whether altitudes are added to or left off coordinates, for consumers that require or
+    /// reject 3-component tuples. Coordinates are written as they are by default
+    pub altitudes: Altitudes,
+    /// Tolerance for simplifying the coordinates of `LineString` and `LinearRing` elements with the
+    /// `geo` Douglas-Peucker algorithm as they're written, in the units of the coordinates.
+    /// Altitudes aren't taken into account, and rings are written as is if simplifying would
+    /// leave fewer than 4 coordinates
+    #[cfg(feature = "geo")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+    pub simplify: Option<f64>,
+    /// Character and number of them to indent nested elements with, putting each element on its
+    /// own line. Output isn't indented when `None`, the default. Changing it through
+    /// [`KmlWriter::config_mut`](struct.KmlWriter.html#method.config_mut) applies to what's
+    /// written next
+    pub indent: Option<(u8, usize)>,
+    /// Write the top-level elements of `kml` roots at the same indentation as the root, as Google
+    /// Earth does. Only applies when [`indent`](#structfield.indent) is set
+    pub flat_root: bool,
+    /// Prefixes and URIs of namespaces declared on `kml` roots after the KML namespace, in order,
+    /// whether or not they're used. Prefixes the document declares itself keep its URI, and KML
+    /// namespaces are declared with the URI of the version being written
+    pub namespaces: Vec<(String, String)>,
+    /// Write the `Style` and `StyleMap` elements of each `Document` ahead of its other elements,
+    /// where Google Earth places them
+    pub styles_first: bool,
+    /// Move inline `Style` elements that are identical across more than one `Placemark` in a
+    /// `Document` into a single `Style` at the top of the `Document` with a generated id like
+    /// `style-1`, pointing the placemarks at it with a `styleUrl`. Placemarks that already have a
+    /// `styleUrl` or whose inline style has an id are left alone. `false` by default
+    pub share_styles: bool,
+    /// Replace the `styleUrl` of each `Placemark` that points to a `Style` or `StyleMap` in the
+    /// same file with a copy of the style, for viewers that don't handle shared styles. `StyleMap`s
+    /// are resolved to their `normal` style. References that can't be resolved, and those of
+    /// placemarks that also have an inline style, are kept. The shared styles are still written.
+    /// Only applies when writing with [`write`](struct.KmlWriter.html#method.write),
+    /// [`write_document`](struct.KmlWriter.html#method.write_document), or
+    /// [`write_root`](struct.KmlWriter.html#method.write_root)
+    pub inline_styles: bool,
+}
+
+impl KmlWriterConfig {
+    /// Config for compact, readable output: indenting with tabs, leaving out default values, and
+    /// writing empty elements as self-closing tags
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Geometry, Placemark, Point}, writer::KmlWriterConfig, Kml};
+    ///
+    /// let kml: Kml = Kml::Placemark(Placemark {
+    ///     name: Some("Pin".to_string()),
+    ///     geometry: Some(Geometry::Point(Point::new(1., 1., None))),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(KmlWriterConfig::pretty()).unwrap(),
+    ///     "<Placemark>\n\t<name>Pin</name>\n\t<Point>\n\t\t<coordinates>1,1</coordinates>\n\t</Point>\n</Placemark>"
+    /// );
+    /// ```
+    pub fn pretty() -> Self {
+        KmlWriterConfig::default()
+            .indent(Some((b'\t', 1)))
+            .omit_defaults(true)
+            .self_closing(true)
+    }
+
+    /// Config matching the formatting of files saved by Google Earth Pro with "Save Place As", for
+    /// minimal diffs against them when writing with
+    /// [`write_document`](struct.KmlWriter.html#method.write_document). Output is indented with
+    /// tabs except for the top-level elements, the root declares the `gx`, `kml`, and `atom`
+    /// namespaces, styles come first in each `Document`, defaults are left out, and coordinates
+    /// always have an altitude, with those of lines and rings on their own line
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Document, Geometry, Placemark, Point}, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let kml: Kml = Kml::Document(Document {
+    ///     elements: vec![Kml::Placemark(Placemark {
+    ///         geometry: Some(Geometry::Point(Point::new(1., 1., None))),
+    ///         ..Default::default()
+    ///     })],
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut buf = Vec::new();
+    /// KmlWriter::from_writer_with_config(&mut buf, KmlWriterConfig::google_earth())
+    ///     .write_document(&kml)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     concat!(
+    ///         "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    ///         r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2" "#,
+    ///         r#"xmlns:kml="http://www.opengis.net/kml/2.2" xmlns:atom="http://www.w3.org/2005/Atom">"#,
+    ///         "\n<Document>\n\t<Placemark>\n\t\t<Point>\n\t\t\t<coordinates>1,1,0</coordinates>",
+    ///         "\n\t\t</Point>\n\t</Placemark>\n</Document>\n</kml>"
+    ///     )
+    /// );
+    /// ```
+    pub fn google_earth() -> Self {
+        KmlWriterConfig::pretty()
+            .flat_root(true)
+            .namespaces(vec![
+                ("gx".to_string(), GX_NAMESPACE.to_string()),
+                ("kml".to_string(), KML_22_NAMESPACE.to_string()),
+                ("atom".to_string(), ATOM_NAMESPACE.to_string()),
+            ])
+            .styles_first(true)
+            .coord_layout(CoordLayout::Indented)
+            .altitudes(Altitudes::Fill)
+    }
+
+    /// Sets [`inline_styles`](#structfield.inline_styles)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Document, Placemark, Style}, writer::KmlWriterConfig, Kml};
+    ///
+    /// let kml: Kml = Kml::Document(Document {
+    ///     elements: vec![
+    ///         Kml::Style(Style {
+    ///             id: Some("pin".to_string()),
+    ///             ..Default::default()
+    ///         }),
+    ///         Kml::Placemark(Placemark {
+    ///             style_url: Some("#pin".to_string()),
+    ///             ..Default::default()
+    ///         }),
+    ///     ],
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let config = KmlWriterConfig::default().inline_styles(true);
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     r#"<Document><Style id="pin"></Style><Placemark><Style></Style></Placemark></Document>"#
+    /// );
+    /// ```
+    pub fn inline_styles(mut self, inline_styles: bool) -> Self {
+        self.inline_styles = inline_styles;
+        self
+    }
+
+    /// Sets [`share_styles`](#structfield.share_styles)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Document, Element, Node, Placemark}, writer::KmlWriterConfig, Kml};
+    ///
+    /// let placemark = Kml::Placemark(Placemark {
+    ///     children: vec![Node::Element(Element {
+    ///         name: "Style".to_string(),
+    ///         ..Default::default()
+    ///     })],
+    ///     ..Default::default()
+    /// });
+    /// let kml: Kml = Kml::Document(Document {
+    ///     elements: vec![placemark.clone(), placemark],
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let config = KmlWriterConfig::default().share_styles(true);
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     concat!(
+    ///         r#"<Document><Style id="style-1"></Style>"#,
+    ///         "<Placemark><styleUrl>#style-1</styleUrl></Placemark>",
+    ///         "<Placemark><styleUrl>#style-1</styleUrl></Placemark></Document>"
+    ///     )
+    /// );
+    /// ```
+    pub fn share_styles(mut self, share_styles: bool) -> Self {
+        self.share_styles = share_styles;
+        self
+    }
+
+    /// Sets [`indent`](#structfield.indent)
+    pub fn indent(mut self, indent: Option<(u8, usize)>) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sets [`flat_root`](#structfield.flat_root)
+    pub fn flat_root(mut self, flat_root: bool) -> Self {
+        self.flat_root = flat_root;
+        self
+    }
+
+    /// Sets [`namespaces`](#structfield.namespaces)
+    pub fn namespaces(mut self, namespaces: Vec<(String, String)>) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+
+    /// Sets [`styles_first`](#structfield.styles_first)
+    pub fn styles_first(mut self, styles_first: bool) -> Self {
+        self.styles_first = styles_first;
+        self
+    }
+
+    /// Sets [`simplify`](#structfield.simplify)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Coord, LineString}, writer::KmlWriterConfig, Kml};
+    ///
+    /// let kml: Kml = Kml::LineString(LineString::from(vec![
+    ///     Coord::new(0., 0., None),
+    ///     Coord::new(1., 0.01, None),
+    ///     Coord::new(2., 0., None),
+    /// ]));
+    ///
+    /// let config = KmlWriterConfig::default().simplify(Some(0.1));
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     "<LineString><coordinates>0,0\n2,0</coordinates></LineString>"
+    /// );
+    /// ```
+    #[cfg(feature = "geo")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "geo")))]
+    pub fn simplify(mut self, tolerance: Option<f64>) -> Self {
+        self.simplify = tolerance;
+        self
+    }
+
+    /// Sets [`coord_layout`](#structfield.coord_layout)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Coord, LineString}, writer::{CoordLayout, KmlWriterConfig}, Kml};
+    ///
+    /// let kml: Kml = Kml::LineString(LineString::from(vec![
+    ///     Coord::new(1., 1., None),
+    ///     Coord::new(2., 2., None),
+    ///     Coord::new(3., 3., None),
+    /// ]));
+    ///
+    /// let config = KmlWriterConfig::default().coord_layout(CoordLayout::Wrap(2));
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     "<LineString><coordinates>1,1 2,2\n3,3</coordinates></LineString>"
+    /// );
+    /// ```
+    pub fn coord_layout(mut self, coord_layout: CoordLayout) -> Self {
+        self.coord_layout = coord_layout;
+        self
+    }
+
+    /// Sets [`altitudes`](#structfield.altitudes)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Coord, LineString}, writer::{Altitudes, KmlWriterConfig}, Kml};
+    ///
+    /// let kml: Kml = Kml::LineString(LineString::from(vec![
+    ///     Coord::new(1., 1., None),
+    ///     Coord::new(2., 2., Some(10.)),
+    /// ]));
+    ///
+    /// let config = KmlWriterConfig::default().altitudes(Altitudes::Fill);
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     "<LineString><coordinates>1,1,0\n2,2,10</coordinates></LineString>"
+    /// );
+    /// ```
+    pub fn altitudes(mut self, altitudes: Altitudes) -> Self {
+        self.altitudes = altitudes;
+        self
+    }
+
+    /// Sets [`self_closing`](#structfield.self_closing)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::Style, writer::KmlWriterConfig, Kml};
+    ///
+    /// let kml: Kml = Kml::Style(Style {
+    ///     id: Some("empty".to_string()),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let config = KmlWriterConfig::default().self_closing(true);
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     r#"<Style id="empty"/>"#
+    /// );
+    /// ```
+    pub fn self_closing(mut self, self_closing: bool) -> Self {
+        self.self_closing = self_closing;
+        self
+    }
+
+    /// Sets [`duplicate_style_ids`](#structfield.duplicate_style_ids)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Folder, Style}, writer::{DuplicateStyleIds, KmlWriterConfig}, Kml, KmlWriter};
+    ///
+    /// let style = Kml::Style(Style {
+    ///     id: Some("red".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// let kml: Kml = Kml::Folder(Folder {
+    ///     elements: vec![style.clone(), style],
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut buf = Vec::new();
+    /// let config = KmlWriterConfig::default().duplicate_style_ids(DuplicateStyleIds::Error);
+    /// assert!(KmlWriter::from_writer_with_config(&mut buf, config)
+    ///     .write(&kml)
+    ///     .is_err());
+    /// ```
+    pub fn duplicate_style_ids(mut self, duplicate_style_ids: DuplicateStyleIds) -> Self {
+        self.duplicate_style_ids = duplicate_style_ids;
+        self
+    }
+
+    /// Sets [`precision`](#structfield.precision)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::Point, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let kml = Kml::Point(Point::new(-122.0822035425683, 37.42228990140251, None));
+    ///
+    /// let mut buf = Vec::new();
+    /// let config = KmlWriterConfig::default().precision(Some(6));
+    /// KmlWriter::from_writer_with_config(&mut buf, config)
+    ///     .write(&kml)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "<Point><coordinates>-122.082204,37.42229</coordinates></Point>"
+    /// );
+    /// ```
+    pub fn precision(mut self, precision: Option<usize>) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Sets [`omit_defaults`](#structfield.omit_defaults)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{AltitudeMode, Point}, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let mut point = Point::new(1., 1., None);
+    /// point.extrude = Some(false);
+    /// point.altitude_mode = Some(AltitudeMode::ClampToGround);
+    ///
+    /// let mut buf = Vec::new();
+    /// let config = KmlWriterConfig::default().omit_defaults(true);
+    /// KmlWriter::from_writer_with_config(&mut buf, config)
+    ///     .write(&Kml::Point(point))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "<Point><coordinates>1,1</coordinates></Point>"
+    /// );
+    /// ```
+    pub fn omit_defaults(mut self, omit_defaults: bool) -> Self {
+        self.omit_defaults = omit_defaults;
+        self
+    }
+
+    /// Sets [`cdata`](#structfield.cdata)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::Placemark, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let kml: Kml = Kml::Placemark(Placemark {
+    ///     description: Some("Fish & Chips".to_string()),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut buf = Vec::new();
+    /// let config = KmlWriterConfig::default().cdata(true);
+    /// KmlWriter::from_writer_with_config(&mut buf, config)
+    ///     .write(&kml)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "<Placemark><description><![CDATA[Fish & Chips]]></description></Placemark>"
+    /// );
+    /// ```
+    pub fn cdata(mut self, cdata: bool) -> Self {
+        self.cdata = cdata;
+        self
+    }
+
+    /// Sets [`raw_html`](#structfield.raw_html)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::Placemark, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let kml: Kml = Kml::Placemark(Placemark {
+    ///     description: Some("<p>Fish &amp; Chips</p>".to_string()),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut buf = Vec::new();
+    /// let config = KmlWriterConfig::default().raw_html(true);
+    /// KmlWriter::from_writer_with_config(&mut buf, config)
+    ///     .write(&kml)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "<Placemark><description><p>Fish &amp; Chips</p></description></Placemark>"
+    /// );
+    /// ```
+    pub fn raw_html(mut self, raw_html: bool) -> Self {
+        self.raw_html = raw_html;
+        self
+    }
+
+    /// Registers a writer for elements with the local name `name`, used instead of writing the
+    /// element's name, attributes, content, and children as is. This applies to
+    /// [`Kml::Element`](../enum.Kml.html#variant.Element), elements nested in other types, and
+    /// [`Kml::Extension`](../enum.Kml.html#variant.Extension) without a writer for its type.
+    ///
+    /// The writer is responsible for declaring any namespaces it uses, and writes without the
+    /// indentation of the `KmlWriter`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+    /// use kml::{types::Element, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// // Write the content of `Rating` elements as an attribute
+    /// let config = KmlWriterConfig::default().register_writer("Rating", |e, w| {
+    ///     let value = e.content.as_deref().unwrap_or_default();
+    ///     let start = BytesStart::new("v:Rating")
+    ///         .with_attributes([("xmlns:v", "http://example.com/v"), ("value", value)]);
+    ///     w.write_event(Event::Empty(start))?;
+    ///     Ok(())
+    /// });
+    /// let kml = Kml::<f64>::Element(Element {
+    ///     name: "Rating".to_string(),
+    ///     content: Some("5".to_string()),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut buf = Vec::new();
+    /// KmlWriter::from_writer_with_config(&mut buf, config)
+    ///     .write(&kml)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     r#"<v:Rating xmlns:v="http://example.com/v" value="5"/>"#
+    /// );
+    /// ```
+    pub fn register_writer(
+        mut self,
+        name: impl Into<String>,
+        writer: impl Fn(&Element, &mut quick_xml::Writer<&mut dyn Write>) -> Result<(), Error>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.element_writers
+            .insert(name.into(), ElementWriter(Arc::new(writer)));
+        self
+    }
+
+    /// Registers a writer for [`Kml::Extension`](../enum.Kml.html#variant.Extension)s holding an
+    /// `E`, used instead of writing the element from
+    /// [`KmlExtension::to_element`](../types/trait.KmlExtension.html#tymethod.to_element).
+    ///
+    /// The writer is responsible for declaring any namespaces it uses, and writes without the
+    /// indentation of the `KmlWriter`
+    pub fn register_extension_writer<E: KmlExtension>(
+        mut self,
+        writer: impl Fn(&E, &mut quick_xml::Writer<&mut dyn Write>) -> Result<(), Error>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        let writer = move |e: &Extension, w: &mut quick_xml::Writer<&mut dyn Write>| match e
+            .downcast_ref::<E>()
+        {
+            Some(e) => writer(e, w),
+            None => Err(Error::InvalidInput(
+                "Extension doesn't match its writer's type".to_string(),
+            )),
+        };
+        self.extension_writers
+            .insert(TypeId::of::<E>(), ExtensionWriter(Arc::new(writer)));
+        self
+    }
+}
+
+type WriteFn<V> =
+    dyn Fn(&V, &mut quick_xml::Writer<&mut dyn Write>) -> Result<(), Error> + Send + Sync;
+
+/// Writer for elements, set with
+/// [`KmlWriterConfig::register_writer`](struct.KmlWriterConfig.html#method.register_writer)
+#[derive(Clone)]
+pub struct ElementWriter(Arc<WriteFn<Element>>);
+
+impl fmt::Debug for ElementWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ElementWriter").finish_non_exhaustive()
+    }
+}
+
+/// Writer for extensions, set with
+/// [`KmlWriterConfig::register_extension_writer`](struct.KmlWriterConfig.html#method.register_extension_writer)
+#[derive(Clone)]
+pub struct ExtensionWriter(Arc<WriteFn<Extension>>);
+
+impl fmt::Debug for ExtensionWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ExtensionWriter").finish_non_exhaustive()
+    }
+}
+
+/// Type that [`KmlWriter`](struct.KmlWriter.html) can write, implemented for the types in this
+/// crate. Implement it for application types to write them directly with
+/// [`write_feature`](struct.KmlWriter.html#method.write_feature) or
+/// [`write_to`](#tymethod.write_to), without converting them into the types in this crate first.
+/// Implementations can combine the writer's
+/// [`start_element`](struct.KmlWriter.html#method.start_element),
+/// [`write_text_element`](struct.KmlWriter.html#method.write_text_element), and
+/// [`end_element`](struct.KmlWriter.html#method.end_element) with the implementations for this
+/// crate's types
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+/// use kml::{types::Point, writer::ToKml, Error, KmlWriter};
+///
+/// struct Station {
+///     name: String,
+///     lon: f64,
+///     lat: f64,
+/// }
+///
+/// impl ToKml for Station {
+///     fn write_to<W: Write>(&self, writer: &mut KmlWriter<W>) -> Result<(), Error> {
+///         writer.start_element("Placemark", [])?;
+///         writer.write_text_element("name", &self.name)?;
+///         Point::new(self.lon, self.lat, None).write_to(writer)?;
+///         writer.end_element("Placemark")
+///     }
+/// }
+///
+/// let station = Station {
+///     name: "Pier".to_string(),
+///     lon: 1.,
+///     lat: 2.,
+/// };
+///
+/// let mut buf = Vec::new();
+/// station.write_to(&mut KmlWriter::from_writer(&mut buf)).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "<Placemark><name>Pier</name><Point><coordinates>1,2</coordinates></Point></Placemark>"
+/// );
+/// ```
+pub trait ToKml<T: CoordType + FromStr + Default + fmt::Display = f64> {
+    /// Writes the value as KML with `writer`
+    fn write_to<W: Write>(&self, writer: &mut KmlWriter<W, T>) -> Result<(), Error>;
+}
+
+macro_rules! impl_to_kml {
+    ($($ty:ty => $write:ident),* $(,)?) => {
+        $(
+            impl<T> ToKml<T> for $ty
+            where
+                T: CoordType + FromStr + Default + fmt::Display,
+            {
+                fn write_to<W: Write>(&self, writer: &mut KmlWriter<W, T>) -> Result<(), Error> {
+                    writer.$write(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_kml!(
+    Kml<T> => write_kml,
+    Scale<T> => write_scale,
+    Orientation<T> => write_orientation,
+    Point<T> => write_point,
+    Location<T> => write_location,
+    LineString<T> => write_line_string,
+    LinearRing<T> => write_linear_ring,
+    Polygon<T> => write_polygon,
+    MultiGeometry<T> => write_multi_geometry,
+    Track<T> => write_track,
+    MultiTrack<T> => write_multi_track,
+    Geometry<T> => write_geometry,
+    Placemark<T> => write_placemark,
+    Tour => write_tour,
+    Style => write_style,
+    StyleMap => write_style_map,
+    Pair => write_pair,
+    BalloonStyle => write_balloon_style,
+    IconStyle => write_icon_style,
+    Icon => write_icon,
+    LabelStyle => write_label_style,
+    LineStyle => write_line_style,
+    PolyStyle => write_poly_style,
+    ListStyle => write_list_style,
+    LinkTypeIcon => write_link_type_icon,
+    Link => write_link,
+    ResourceMap => write_resource_map,
+    Alias => write_alias,
+    SchemaData => write_schema_data,
+    SimpleArrayData => write_simple_array_data,
+    SimpleData => write_simple_data,
+    Element => write_element,
+    Extension => write_extension,
+);
+
+/// How [`KmlWriter`](struct.KmlWriter.html) handles `Style` and `StyleMap` elements sharing an id,
+/// which happens when documents from multiple sources are combined
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateStyleIds {
+    /// Write styles as they are
+    #[default]
+    Keep,
+    /// Return an error naming the duplicate id
+    Error,
+    /// Give later styles a unique id like `id-2`, and point `styleUrl` references to the id in the
+    /// same `Document` or `Folder` as the renamed style to the new id
+    Rename,
+}
+
+/// Layout of the tuples in `coordinates` written by [`KmlWriter`](struct.KmlWriter.html)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoordLayout {
+    /// Each tuple on its own line
+    #[default]
+    Newline,
+    /// All tuples on one line separated by spaces, for one-line documents
+    Space,
+    /// Tuples separated by spaces with a line break after every `n` tuples
+    Wrap(usize),
+    /// Tuples each followed by a space on an indented line of their own when there's more than
+    /// one, as Google Earth writes them
+    Indented,
+}
+
+/// How altitudes of coordinates are written by [`KmlWriter`](struct.KmlWriter.html)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Altitudes {
+    /// Write altitudes when coordinates have them, so `x,y` and `x,y,z` tuples can be mixed
+    #[default]
+    Keep,
+    /// Write coordinates without an altitude as `x,y,0`
+    Fill,
+    /// Write coordinates with an altitude of exactly 0 as `x,y`
+    DropZero,
+}
+
+/// Elements that KML 2.3 promoted from the `gx` extension namespace, which aren't part of the
+/// KML 2.2 namespace
+const KML_23_ELEMENTS: [&str; 22] = [
+    "AnimatedUpdate",
+    "FlyTo",
+    "LatLonQuad",
+    "MultiTrack",
+    "Playlist",
+    "SimpleArrayData",
+    "SimpleArrayField",
+    "SoundCue",
+    "Tour",
+    "TourControl",
+    "Track",
+    "ViewerOptions",
+    "Wait",
+    "angles",
+    "balloonVisibility",
+    "coord",
+    "delayedStart",
+    "duration",
+    "flyToMode",
+    "horizFov",
+    "interpolate",
+    "playMode",
+];
+
+/// Namespace of Google's `gx` extension elements
+const GX_NAMESPACE: &str = "http://www.google.com/kml/ext/2.2";
+
+/// Namespace of KML 2.2, which Google Earth also declares for the `kml` prefix
+const KML_22_NAMESPACE: &str = "http://www.opengis.net/kml/2.2";
+
+/// Namespace of Atom elements like `atom:author`
+const ATOM_NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+
 /// Struct for managing writing KML
 pub struct KmlWriter<W: Write, T: CoordType + FromStr + Default = f64> {
     writer: quick_xml::Writer<W>,
+    /// Number of elements open around what's written next, for indenting it
+    depth: usize,
+    /// Whether the next tag goes on a new line when indenting, which isn't the case right after text
+    line_break: bool,
+    /// Whether the `kml` root being written was started without indenting its children
+    flat_root: bool,
+    config: KmlWriterConfig,
+    document_version: KmlVersion,
+    /// Namespace prefixes declared by the elements being written, along with their namespaces
+    declared_prefixes: Vec<(String, String)>,
+    /// Start of the last element written, held back until it's known whether it's empty
+    pending_start: Option<BytesStart<'static>>,
+    /// Styles written in place of `styleUrl` references by id when inlining styles
+    shared_styles: HashMap<String, Style>,
+    open_containers: Vec<&'static str>,
+    coord_transform: Option<CoordTransform<T>>,
     _phantom: PhantomData<T>,
 }
 
@@ -44,13 +829,88 @@ where
         KmlWriter::new(quick_xml::Writer::new(w))
     }
 
+    /// Creates `KmlWriter` from an input that implements `Write` with the provided configuration
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{KmlVersion, KmlWriter};
+    /// use kml::writer::KmlWriterConfig;
+    ///
+    /// let mut config = KmlWriterConfig::default();
+    /// config.version = KmlVersion::V23;
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut writer = KmlWriter::<_, f64>::from_writer_with_config(&mut buf, config);
+    /// ```
+    pub fn from_writer_with_config(w: W, config: KmlWriterConfig) -> KmlWriter<W, T> {
+        let mut writer = KmlWriter::new(quick_xml::Writer::new(w));
+        writer.config = config;
+        writer
+    }
+
     pub fn new(writer: quick_xml::Writer<W>) -> KmlWriter<W, T> {
         KmlWriter {
             writer,
+            depth: 0,
+            line_break: false,
+            flat_root: false,
+            config: KmlWriterConfig::default(),
+            document_version: KmlVersion::Unknown,
+            declared_prefixes: Vec::new(),
+            pending_start: None,
+            shared_styles: HashMap::new(),
+            open_containers: Vec::new(),
+            coord_transform: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Sets a transform applied to every coordinate as it's written, like for snapping values to
+    /// a grid or swapping axes, leaving the elements being written unchanged
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Coord, Point}, Kml, KmlWriter};
+    ///
+    /// let kml = Kml::Point(Point::new(13.4, 52.5, None));
+    ///
+    /// // Write coordinates as latitude, longitude
+    /// let mut buf = Vec::new();
+    /// KmlWriter::<_, f64>::from_writer(&mut buf)
+    ///     .with_coord_transform(|c| Coord::new(c.y, c.x, c.z))
+    ///     .write(&kml)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "<Point><coordinates>52.5,13.4</coordinates></Point>"
+    /// );
+    /// ```
+    pub fn with_coord_transform(
+        mut self,
+        transform: impl Fn(Coord<T>) -> Coord<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.coord_transform = Some(Box::new(transform));
+        self
+    }
+
+    /// Returns the underlying writer
+    #[cfg(any(feature = "flate2", feature = "fs"))]
+    pub(crate) fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    /// Returns the configuration of the writer
+    pub fn config(&self) -> &KmlWriterConfig {
+        &self.config
+    }
+
+    /// Returns a mutable reference to the configuration of the writer
+    pub fn config_mut(&mut self) -> &mut KmlWriterConfig {
+        &mut self.config
+    }
+
     /// Writes KML to a `Writer`
     ///
     /// # Example
@@ -65,133 +925,400 @@ where
     /// writer.write(&kml).unwrap();
     /// ```
     pub fn write(&mut self, kml: &Kml<T>) -> Result<(), Error> {
-        self.write_kml(kml)
-    }
-
-    fn write_kml(&mut self, k: &Kml<T>) -> Result<(), Error> {
-        match k {
-            Kml::KmlDocument(d) => self.write_container("kml", &d.attrs, &d.elements)?,
-            Kml::Scale(s) => self.write_scale(s)?,
-            Kml::Orientation(o) => self.write_orientation(o)?,
-            Kml::Point(p) => self.write_point(p)?,
-            Kml::Location(l) => self.write_location(l)?,
-            Kml::LineString(l) => self.write_line_string(l)?,
-            Kml::LinearRing(l) => self.write_linear_ring(l)?,
-            Kml::Polygon(p) => self.write_polygon(p)?,
-            Kml::MultiGeometry(g) => self.write_multi_geometry(g)?,
-            Kml::Placemark(p) => self.write_placemark(p)?,
-            Kml::Style(s) => self.write_style(s)?,
-            Kml::StyleMap(s) => self.write_style_map(s)?,
-            Kml::Pair(p) => self.write_pair(p)?,
-            Kml::BalloonStyle(b) => self.write_balloon_style(b)?,
-            Kml::IconStyle(i) => self.write_icon_style(i)?,
-            Kml::Icon(i) => self.write_icon(i)?,
-            Kml::LabelStyle(l) => self.write_label_style(l)?,
-            Kml::LineStyle(l) => self.write_line_style(l)?,
-            Kml::PolyStyle(p) => self.write_poly_style(p)?,
-            Kml::ListStyle(l) => self.write_list_style(l)?,
-            Kml::LinkTypeIcon(i) => self.write_link_type_icon(i)?,
-            Kml::Link(l) => self.write_link(l)?,
-            Kml::ResourceMap(r) => self.write_resource_map(r)?,
-            Kml::Alias(a) => self.write_alias(a)?,
-            Kml::SchemaData(s) => self.write_schema_data(s)?,
-            Kml::SimpleArrayData(s) => self.write_simple_array_data(s)?,
-            Kml::SimpleData(s) => self.write_simple_data(s)?,
-            Kml::Document { attrs, elements } => {
-                self.write_container("Document", attrs, elements)?
-            }
-            Kml::Folder { attrs, elements } => self.write_container("Folder", attrs, elements)?,
-            Kml::Element(e) => self.write_element(e)?,
-        }
-
+        let elements = self.prepare_elements(std::slice::from_ref(kml))?;
+        self.write_kml(&elements[0])?;
+        self.shared_styles.clear();
         Ok(())
     }
 
-    fn write_scale(&mut self, scale: &Scale<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
-            BytesStart::new("Scale").with_attributes(self.hash_map_as_attrs(&scale.attrs)),
-        ))?;
-        self.write_text_element("x", &scale.x.to_string())?;
-        self.write_text_element("y", &scale.y.to_string())?;
-        self.write_text_element("z", &scale.z.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Scale")))?)
+    /// Writes a complete document with an XML declaration and a single `kml` root element
+    /// declaring the KML namespace, wrapping `kml` in a root element if it isn't a
+    /// [`KmlDocument`](../struct.KmlDocument.html). Documents with an unknown version are written
+    /// as the configured version, or KML 2.2 if that's unknown too
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{Kml, KmlWriter, types::Point};
+    ///
+    /// let kml = Kml::Point(Point::new(1., 1., None));
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut writer = KmlWriter::from_writer(&mut buf);
+    /// writer.write_document(&kml).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.2"><Point><coordinates>1,1</coordinates></Point></kml>"#
+    /// );
+    /// ```
+    pub fn write_document(&mut self, kml: &Kml<T>) -> Result<(), Error> {
+        match kml {
+            Kml::KmlDocument(d) => self.write_root(d.version, &d.attrs, &d.elements),
+            kml => self.write_root(
+                KmlVersion::Unknown,
+                &HashMap::new(),
+                std::slice::from_ref(kml),
+            ),
+        }
     }
 
-    fn write_orientation(&mut self, orientation: &Orientation<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
-            BytesStart::new("Orientation")
-                .with_attributes(self.hash_map_as_attrs(&orientation.attrs)),
+    /// Writes a complete document like [`write_document`](#method.write_document) with `elements`
+    /// as the top-level elements of the `kml` root, without building a
+    /// [`KmlDocument`](../struct.KmlDocument.html) first. The root declares the namespace of
+    /// `version` along with any `attrs`, and an unknown version is written as the configured
+    /// version, or KML 2.2 if that's unknown too
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use kml::{Kml, KmlVersion, KmlWriter, types::Point};
+    ///
+    /// let elements = [
+    ///     Kml::Point(Point::new(1., 1., None)),
+    ///     Kml::Point(Point::new(2., 2., None)),
+    /// ];
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut writer = KmlWriter::from_writer(&mut buf);
+    /// writer
+    ///     .write_root(KmlVersion::V23, &HashMap::new(), &elements)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     concat!(
+    ///         r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.3">"#,
+    ///         "<Point><coordinates>1,1</coordinates></Point>",
+    ///         "<Point><coordinates>2,2</coordinates></Point></kml>"
+    ///     )
+    /// );
+    /// ```
+    pub fn write_root(
+        &mut self,
+        version: KmlVersion,
+        attrs: &HashMap<String, String>,
+        elements: &[Kml<T>],
+    ) -> Result<(), Error> {
+        let elements = self.prepare_elements(elements)?;
+        self.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        let version = match (self.config.version, version) {
+            (KmlVersion::Unknown, KmlVersion::Unknown) => KmlVersion::V22,
+            (KmlVersion::Unknown, version) | (version, _) => version,
+        };
+        self.write_kml_document(version, attrs, &elements)?;
+        self.shared_styles.clear();
+        Ok(())
+    }
+
+    /// Starts writing a document incrementally, writing an XML declaration and opening the `kml`
+    /// root element and a `Document` so features can be written one at a time with
+    /// [`write_feature`](#method.write_feature) without building the whole tree in memory
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::{Placemark, Point, Geometry}, KmlWriter};
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut writer = KmlWriter::<_, f64>::from_writer(&mut buf);
+    /// writer.start_document().unwrap();
+    /// writer.start_folder("Points").unwrap();
+    /// for i in 0..3 {
+    ///     writer
+    ///         .write_feature(&Placemark {
+    ///             geometry: Some(Geometry::Point(Point::new(i as f64, 0., None))),
+    ///             ..Default::default()
+    ///         })
+    ///         .unwrap();
+    /// }
+    /// writer.end_folder().unwrap();
+    /// writer.end_document().unwrap();
+    /// ```
+    pub fn start_document(&mut self) -> Result<(), Error> {
+        if !self.open_containers.is_empty() {
+            return Err(Error::InvalidInput(
+                "Document has already been started".to_string(),
+            ));
+        }
+        self.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        self.document_version = match self.config.version {
+            KmlVersion::Unknown => KmlVersion::V22,
+            version => version,
+        };
+        let namespace = self.document_version.namespace().unwrap_or_default();
+        let prefixes = self.configured_namespaces(self.document_version);
+        let namespaces: Vec<(String, String)> = prefixes
+            .iter()
+            .map(|(prefix, ns)| (format!("xmlns:{prefix}"), ns.clone()))
+            .collect();
+        self.write_event(Event::Start(
+            BytesStart::new("kml").with_attributes(
+                [("xmlns", namespace)]
+                    .into_iter()
+                    .chain(namespaces.iter().map(|(k, ns)| (k.as_str(), ns.as_str()))),
+            ),
+        ))?;
+        self.declared_prefixes.extend(prefixes);
+        self.write_event(Event::Start(BytesStart::new("Document")))?;
+        self.open_containers.extend(["kml", "Document"]);
+        Ok(())
+    }
+
+    /// Opens a `Folder` with a `name` inside of a document started with
+    /// [`start_document`](#method.start_document)
+    pub fn start_folder(&mut self, name: &str) -> Result<(), Error> {
+        if self.open_containers.is_empty() {
+            return Err(Error::InvalidInput(
+                "Folder must be started inside of a document".to_string(),
+            ));
+        }
+        self.write_event(Event::Start(BytesStart::new("Folder")))?;
+        self.write_text_element("name", name)?;
+        self.open_containers.push("Folder");
+        Ok(())
+    }
+
+    /// Writes a feature, like a `Placemark` or an application type implementing
+    /// [`ToKml`](trait.ToKml.html), inside of the open document or folder. Other elements like
+    /// styles can be written with [`write`](#method.write)
+    pub fn write_feature<F: ToKml<T> + ?Sized>(&mut self, feature: &F) -> Result<(), Error> {
+        if self.open_containers.is_empty() {
+            return Err(Error::InvalidInput(
+                "Feature must be written inside of a document".to_string(),
+            ));
+        }
+        feature.write_to(self)
+    }
+
+    /// Writes the start of an element named `tag` with `attrs`, for implementing
+    /// [`ToKml`](trait.ToKml.html). Each call must be matched by a call to
+    /// [`end_element`](#method.end_element)
+    pub fn start_element<'b>(
+        &mut self,
+        tag: &str,
+        attrs: impl IntoIterator<Item = (&'b str, &'b str)>,
+    ) -> Result<(), Error> {
+        self.write_event(Event::Start(BytesStart::new(tag).with_attributes(attrs)))
+    }
+
+    /// Writes the end of an element started with [`start_element`](#method.start_element)
+    pub fn end_element(&mut self, tag: &str) -> Result<(), Error> {
+        self.write_event(Event::End(BytesEnd::new(tag)))
+    }
+
+    /// Closes the innermost `Folder` opened with [`start_folder`](#method.start_folder)
+    pub fn end_folder(&mut self) -> Result<(), Error> {
+        if self.open_containers.last() != Some(&"Folder") {
+            return Err(Error::InvalidInput("No Folder is open".to_string()));
+        }
+        self.open_containers.pop();
+        self.write_event(Event::End(BytesEnd::new("Folder")))
+    }
+
+    /// Closes the document started with [`start_document`](#method.start_document) along with any
+    /// folders that are still open
+    pub fn end_document(&mut self) -> Result<(), Error> {
+        if self.open_containers.is_empty() {
+            return Err(Error::InvalidInput("No document is open".to_string()));
+        }
+        while let Some(tag) = self.open_containers.pop() {
+            self.write_event(Event::End(BytesEnd::new(tag)))?;
+        }
+        self.declared_prefixes.clear();
+        self.document_version = KmlVersion::Unknown;
+        Ok(())
+    }
+
+    /// Applies the passes over whole trees set in the config to `elements` before they're written,
+    /// copying them only if anything changes: checking for duplicate style ids according to
+    /// [`duplicate_style_ids`](struct.KmlWriterConfig.html#structfield.duplicate_style_ids) and
+    /// [`share_styles`](struct.KmlWriterConfig.html#structfield.share_styles), and collecting the
+    /// styles to write for [`inline_styles`](struct.KmlWriterConfig.html#structfield.inline_styles)
+    fn prepare_elements<'e>(&mut self, elements: &'e [Kml<T>]) -> Result<Cow<'e, [Kml<T>]>, Error> {
+        let mut elements = Cow::Borrowed(elements);
+        if self.config.duplicate_style_ids != DuplicateStyleIds::Keep {
+            if let Some(id) = duplicate_style_id(&elements, &mut HashSet::new()) {
+                if self.config.duplicate_style_ids == DuplicateStyleIds::Error {
+                    return Err(Error::InvalidInput(format!(
+                        "Style id {id} is used more than once"
+                    )));
+                }
+                rename_duplicate_styles(elements.to_mut(), &mut HashSet::new(), &HashMap::new());
+            }
+        }
+        if self.config.share_styles {
+            let mut ids = HashSet::new();
+            collect_style_ids(&elements, &mut ids);
+            share_inline_styles(elements.to_mut(), &mut ids);
+        }
+        if self.config.inline_styles {
+            self.shared_styles = shared_styles(&elements);
+        }
+        Ok(elements)
+    }
+
+    fn write_kml_document(
+        &mut self,
+        version: KmlVersion,
+        attrs: &HashMap<String, String>,
+        elements: &[Kml<T>],
+    ) -> Result<(), Error> {
+        let version = match self.config.version {
+            KmlVersion::Unknown => version,
+            v => v,
+        };
+        let parent_version = std::mem::replace(&mut self.document_version, version);
+        let mut attrs = Cow::Borrowed(attrs);
+        if let Some(ns) = version.namespace() {
+            // Namespaces of other KML versions, like those of a KML 2.2 document written as KML
+            // 2.3, are replaced with the one being written
+            let outdated: Vec<String> = attrs
+                .iter()
+                .filter(|(k, v)| {
+                    (*k == "xmlns" || k.starts_with("xmlns:"))
+                        && v.parse::<KmlVersion>().is_ok_and(|v| v != version)
+                })
+                .map(|(k, _)| k.clone())
+                .collect();
+            for k in outdated {
+                attrs.to_mut().insert(k, ns.to_string());
+            }
+            // Typed elements are written without a prefix, so the default namespace needs to be
+            // KML even if the document only declared KML for a prefix like `kml:`
+            if !matches!(attrs.get("xmlns"), Some(v) if v.parse::<KmlVersion>().is_ok()) {
+                attrs.to_mut().insert("xmlns".to_string(), ns.to_string());
+            }
+        }
+        // Declare the gx namespace once on the root rather than on each gx element
+        if !attrs.contains_key("xmlns:gx") && elements.iter().any(|e| self.uses_gx(e)) {
+            attrs
+                .to_mut()
+                .insert("xmlns:gx".to_string(), GX_NAMESPACE.to_string());
+        }
+        for (prefix, namespace) in self.configured_namespaces(version) {
+            let key = format!("xmlns:{prefix}");
+            if !attrs.contains_key(&key) {
+                attrs.to_mut().insert(key, namespace);
+            }
+        }
+        let result = self.write_container("kml", &attrs, elements);
+        self.document_version = parent_version;
+        result
+    }
+
+    fn write_kml(&mut self, k: &Kml<T>) -> Result<(), Error> {
+        match k {
+            Kml::KmlDocument(d) => self.write_kml_document(d.version, &d.attrs, &d.elements)?,
+            Kml::Scale(s) => self.write_scale(s)?,
+            Kml::Orientation(o) => self.write_orientation(o)?,
+            Kml::Point(p) => self.write_point(p)?,
+            Kml::Location(l) => self.write_location(l)?,
+            Kml::LineString(l) => self.write_line_string(l)?,
+            Kml::LinearRing(l) => self.write_linear_ring(l)?,
+            Kml::Polygon(p) => self.write_polygon(p)?,
+            Kml::MultiGeometry(g) => self.write_multi_geometry(g)?,
+            Kml::Track(t) => self.write_track(t)?,
+            Kml::MultiTrack(t) => self.write_multi_track(t)?,
+            Kml::Placemark(p) => self.write_placemark(p)?,
+            Kml::Tour(t) => self.write_tour(t)?,
+            Kml::Style(s) => self.write_style(s)?,
+            Kml::StyleMap(s) => self.write_style_map(s)?,
+            Kml::Pair(p) => self.write_pair(p)?,
+            Kml::BalloonStyle(b) => self.write_balloon_style(b)?,
+            Kml::IconStyle(i) => self.write_icon_style(i)?,
+            Kml::Icon(i) => self.write_icon(i)?,
+            Kml::LabelStyle(l) => self.write_label_style(l)?,
+            Kml::LineStyle(l) => self.write_line_style(l)?,
+            Kml::PolyStyle(p) => self.write_poly_style(p)?,
+            Kml::ListStyle(l) => self.write_list_style(l)?,
+            Kml::LinkTypeIcon(i) => self.write_link_type_icon(i)?,
+            Kml::Link(l) => self.write_link(l)?,
+            Kml::ResourceMap(r) => self.write_resource_map(r)?,
+            Kml::Alias(a) => self.write_alias(a)?,
+            Kml::SchemaData(s) => self.write_schema_data(s)?,
+            Kml::SimpleArrayData(s) => self.write_simple_array_data(s)?,
+            Kml::SimpleData(s) => self.write_simple_data(s)?,
+            Kml::Document(d) => self.write_document_container(d)?,
+            Kml::Folder(f) => self.write_folder_container(f)?,
+            Kml::Element(e) => self.write_element(e)?,
+            Kml::Comment(c) => self.write_comment(c)?,
+            Kml::Extension(e) => self.write_extension(e)?,
+        }
+
+        Ok(())
+    }
+
+    fn write_scale(&mut self, scale: &Scale<T>) -> Result<(), Error> {
+        self.write_event(Event::Start(
+            BytesStart::new("Scale").with_attributes(self.hash_map_as_attrs(&scale.attrs)),
         ))?;
-        self.write_text_element("roll", &orientation.roll.to_string())?;
-        self.write_text_element("tilt", &orientation.tilt.to_string())?;
-        self.write_text_element("heading", &orientation.heading.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Orientation")))?)
+        self.write_text_element("x", &self.number(scale.x))?;
+        self.write_text_element("y", &self.number(scale.y))?;
+        self.write_text_element("z", &self.number(scale.z))?;
+        self.write_event(Event::End(BytesEnd::new("Scale")))
+    }
+
+    fn write_orientation(&mut self, orientation: &Orientation<T>) -> Result<(), Error> {
+        self.write_event(Event::Start(
+            BytesStart::new("Orientation")
+                .with_attributes(self.hash_map_as_attrs(&orientation.attrs)),
+        ))?;
+        self.write_text_element("roll", &self.number(orientation.roll))?;
+        self.write_text_element("tilt", &self.number(orientation.tilt))?;
+        self.write_text_element("heading", &self.number(orientation.heading))?;
+        self.write_event(Event::End(BytesEnd::new("Orientation")))
     }
 
     fn write_point(&mut self, point: &Point<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Point").with_attributes(self.hash_map_as_attrs(&point.attrs)),
         ))?;
-        self.write_text_element("extrude", if point.extrude { "1" } else { "0" })?;
-        self.write_text_element("altitudeMode", &point.altitude_mode.to_string())?;
-        self.write_text_element("coordinates", &point.coord.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Point")))?)
+        self.write_bool_element("extrude", point.extrude)?;
+        self.write_altitude_mode(point.altitude_mode)?;
+        self.write_text_element("coordinates", &self.coord(&point.coord, ","))?;
+        self.write_event(Event::End(BytesEnd::new("Point")))
     }
 
     fn write_location(&mut self, location: &Location<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Location").with_attributes(self.hash_map_as_attrs(&location.attrs)),
         ))?;
-        self.write_text_element("longitude", &location.longitude.to_string())?;
-        self.write_text_element("latitude", &location.latitude.to_string())?;
-        self.write_text_element("altitude", &location.altitude.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Location")))?)
+        self.write_text_element("longitude", &self.number(location.longitude))?;
+        self.write_text_element("latitude", &self.number(location.latitude))?;
+        self.write_text_element("altitude", &self.number(location.altitude))?;
+        self.write_event(Event::End(BytesEnd::new("Location")))
     }
 
     fn write_line_string(&mut self, line_string: &LineString<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("LineString")
                 .with_attributes(self.hash_map_as_attrs(&line_string.attrs)),
         ))?;
-        // TODO: Avoid clone here?
         self.write_geom_props(GeomProps {
-            coords: line_string.coords.clone(),
+            coords: self.simplify(&line_string.coords, 2),
             altitude_mode: line_string.altitude_mode,
             extrude: line_string.extrude,
             tessellate: line_string.tessellate,
         })?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("LineString")))?)
+        self.write_event(Event::End(BytesEnd::new("LineString")))
     }
 
     fn write_linear_ring(&mut self, linear_ring: &LinearRing<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("LinearRing")
                 .with_attributes(self.hash_map_as_attrs(&linear_ring.attrs)),
         ))?;
         self.write_geom_props(GeomProps {
-            // TODO: Avoid clone if possible
-            coords: linear_ring.coords.clone(),
+            coords: self.simplify(&linear_ring.coords, 4),
             altitude_mode: linear_ring.altitude_mode,
             extrude: linear_ring.extrude,
             tessellate: linear_ring.tessellate,
         })?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("LinearRing")))?)
+        self.write_event(Event::End(BytesEnd::new("LinearRing")))
     }
 
     fn write_polygon(&mut self, polygon: &Polygon<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Polygon").with_attributes(self.hash_map_as_attrs(&polygon.attrs)),
         ))?;
         self.write_geom_props(GeomProps {
@@ -200,28 +1327,22 @@ where
             extrude: polygon.extrude,
             tessellate: polygon.tessellate,
         })?;
-        self.writer
-            .write_event(Event::Start(BytesStart::new("outerBoundaryIs")))?;
+        self.write_event(Event::Start(BytesStart::new("outerBoundaryIs")))?;
         self.write_linear_ring(&polygon.outer)?;
-        self.writer
-            .write_event(Event::End(BytesEnd::new("outerBoundaryIs")))?;
+        self.write_event(Event::End(BytesEnd::new("outerBoundaryIs")))?;
 
         if !polygon.inner.is_empty() {
-            self.writer
-                .write_event(Event::Start(BytesStart::new("innerBoundaryIs")))?;
+            self.write_event(Event::Start(BytesStart::new("innerBoundaryIs")))?;
             for b in &polygon.inner {
                 self.write_linear_ring(b)?;
             }
-            self.writer
-                .write_event(Event::End(BytesEnd::new("innerBoundaryIs")))?;
+            self.write_event(Event::End(BytesEnd::new("innerBoundaryIs")))?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Polygon")))?)
+        self.write_event(Event::End(BytesEnd::new("Polygon")))
     }
 
     fn write_multi_geometry(&mut self, multi_geometry: &MultiGeometry<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("MultiGeometry")
                 .with_attributes(self.hash_map_as_attrs(&multi_geometry.attrs)),
         ))?;
@@ -229,48 +1350,287 @@ where
         for g in multi_geometry.geometries.iter() {
             self.write_geometry(g)?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("MultiGeometry")))?)
+        for c in multi_geometry.children.iter() {
+            self.write_node(c)?;
+        }
+        self.write_event(Event::End(BytesEnd::new("MultiGeometry")))
     }
 
     fn write_placemark(&mut self, placemark: &Placemark<T>) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        let scope = self.declare_prefixes(&placemark.attrs);
+        self.write_event(Event::Start(
             BytesStart::new("Placemark").with_attributes(self.hash_map_as_attrs(&placemark.attrs)),
         ))?;
         if let Some(name) = &placemark.name {
             self.write_text_element("name", name)?;
         }
+        // Children are written around the other fields in the sequence required by the schema
+        let mut children = sorted_feature_children(&placemark.children).peekable();
+        while let Some((_, c)) = children.next_if(|(rank, _)| *rank == 0) {
+            self.write_node(c)?;
+        }
         if let Some(description) = &placemark.description {
-            self.write_text_element("description", description)?;
+            self.write_html_element("description", description)?;
         }
-        for c in placemark.children.iter() {
-            self.write_element(c)?;
+        while let Some((_, c)) = children.next_if(|(rank, _)| *rank == 1) {
+            self.write_node(c)?;
+        }
+        if let Some(style_url) = &placemark.style_url {
+            let shared_style = style_url
+                .strip_prefix('#')
+                .and_then(|id| self.shared_styles.get(id))
+                .filter(|_| {
+                    !placemark
+                        .children
+                        .iter()
+                        .filter_map(Node::as_element)
+                        .any(|c| c.name == "Style" || c.name == "StyleMap")
+                })
+                .cloned();
+            match shared_style {
+                Some(style) => self.write_style(&Style { id: None, ..style })?,
+                None => self.write_text_element("styleUrl", style_url)?,
+            }
+        }
+        for (_, c) in children {
+            self.write_node(c)?;
         }
         if let Some(geometry) = &placemark.geometry {
             self.write_geometry(geometry)?;
         }
-        if let Some(style_url) = &placemark.style_url {
-            self.write_text_element("styleUrl", style_url)?;
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new("Placemark")))
+    }
+
+    fn write_track(&mut self, track: &Track<T>) -> Result<(), Error> {
+        let tag = self.gx_tag("Track");
+        let scope = self.declare_prefixes(&track.attrs);
+        let gx_declaration = self.declare_gx(&tag, &track.attrs)?;
+        self.write_event(Event::Start(
+            BytesStart::new(&tag)
+                .with_attributes(self.hash_map_as_attrs(&track.attrs))
+                .with_attributes(gx_declaration),
+        ))?;
+        self.write_altitude_mode(track.altitude_mode)?;
+        for when in track.whens.iter() {
+            self.write_text_element("when", when)?;
+        }
+        let coord_tag = self.gx_tag("coord");
+        for coord in track.coords.iter() {
+            let coord_str = self.coord(coord, " ");
+            self.write_text_element(&coord_tag, &coord_str)?;
+        }
+        let angles_tag = self.gx_tag("angles");
+        for angles in track.angles.iter() {
+            self.write_text_element(
+                &angles_tag,
+                &format!(
+                    "{} {} {}",
+                    self.number(angles.heading),
+                    self.number(angles.tilt),
+                    self.number(angles.roll)
+                ),
+            )?;
+        }
+        for c in track.children.iter() {
+            self.write_node(c)?;
+        }
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new(&tag)))
+    }
+
+    fn write_multi_track(&mut self, multi_track: &MultiTrack<T>) -> Result<(), Error> {
+        let tag = self.gx_tag("MultiTrack");
+        let scope = self.declare_prefixes(&multi_track.attrs);
+        let gx_declaration = self.declare_gx(&tag, &multi_track.attrs)?;
+        self.write_event(Event::Start(
+            BytesStart::new(&tag)
+                .with_attributes(self.hash_map_as_attrs(&multi_track.attrs))
+                .with_attributes(gx_declaration),
+        ))?;
+        self.write_altitude_mode(multi_track.altitude_mode)?;
+        self.write_text_element(
+            &self.gx_tag("interpolate"),
+            if multi_track.interpolate { "1" } else { "0" },
+        )?;
+        for track in multi_track.tracks.iter() {
+            self.write_track(track)?;
+        }
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new(&tag)))
+    }
+
+    fn write_tour(&mut self, tour: &Tour) -> Result<(), Error> {
+        let tag = self.gx_tag("Tour");
+        let scope = self.declare_prefixes(&tour.attrs);
+        let gx_declaration = self.declare_gx(&tag, &tour.attrs)?;
+        self.write_event(Event::Start(
+            BytesStart::new(&tag)
+                .with_attributes(self.hash_map_as_attrs(&tour.attrs))
+                .with_attributes(gx_declaration),
+        ))?;
+        if let Some(name) = &tour.name {
+            self.write_text_element("name", name)?;
+        }
+        if let Some(description) = &tour.description {
+            self.write_html_element("description", description)?;
+        }
+        for c in tour.children.iter() {
+            self.write_node(c)?;
+        }
+        let playlist_tag = self.gx_tag("Playlist");
+        self.write_event(Event::Start(BytesStart::new(&playlist_tag)))?;
+        for e in tour.playlist.iter() {
+            self.write_element(e)?;
+        }
+        self.write_event(Event::End(BytesEnd::new(&playlist_tag)))?;
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new(&tag)))
+    }
+
+    fn write_extension(&mut self, e: &Extension) -> Result<(), Error> {
+        match self
+            .config
+            .extension_writers
+            .get(&e.value_type_id())
+            .cloned()
+        {
+            Some(ExtensionWriter(write)) => write(e, &mut self.custom_writer()?),
+            None => self.write_element(&e.to_element()),
+        }
+    }
+
+    /// Writes an event to the XML writer, first starting a new line indented with the current
+    /// [`indent`](struct.KmlWriterConfig.html#structfield.indent) if one is set
+    fn emit(&mut self, event: Event<'_>) -> Result<(), Error> {
+        let text = matches!(event, Event::Text(_) | Event::CData(_));
+        let root = match &event {
+            Event::Start(e) => self.depth == 0 && e.name().as_ref() == b"kml",
+            Event::End(e) => self.flat_root && self.depth == 0 && e.name().as_ref() == b"kml",
+            _ => false,
+        };
+        if let (Event::End(_), false) = (&event, root) {
+            self.depth = self.depth.saturating_sub(1);
+        }
+        if self.line_break && !text {
+            if let Some(indent) = self.indentation(self.depth) {
+                self.writer.get_mut().write_all(&indent)?;
+            }
+        }
+        match &event {
+            Event::Start(_) if root && self.config.flat_root => self.flat_root = true,
+            Event::Start(_) => self.depth += 1,
+            Event::End(_) if root => self.flat_root = false,
+            _ => {}
+        }
+        self.line_break = !text;
+        self.writer.write_event(event)?;
+        Ok(())
+    }
+
+    /// Line break followed by the configured indentation for `depth` open elements, or `None`
+    /// when output isn't indented
+    fn indentation(&self, depth: usize) -> Option<Vec<u8>> {
+        let (indent_char, indent_size) = self.config.indent?;
+        let mut indentation = vec![b'\n'];
+        indentation.resize(1 + depth * indent_size, indent_char);
+        Some(indentation)
+    }
+
+    /// Writer passed to registered element and extension writers
+    fn custom_writer(&mut self) -> Result<quick_xml::Writer<&mut dyn Write>, Error> {
+        if let Some(start) = self.pending_start.take() {
+            self.emit(Event::Start(start))?;
+        }
+        Ok(quick_xml::Writer::new(self.writer.get_mut()))
+    }
+
+    /// Writes an event, holding back the start of elements when writing
+    /// [`self_closing`](struct.KmlWriterConfig.html#structfield.self_closing) elements until it's
+    /// known whether they're empty
+    fn write_event(&mut self, event: Event<'_>) -> Result<(), Error> {
+        match (self.pending_start.take(), event) {
+            // Events are balanced, so this ends the pending element
+            (Some(start), Event::End(_)) => self.emit(Event::Empty(start))?,
+            (pending, Event::Text(text)) if text.is_empty() => self.pending_start = pending,
+            (pending, event) => {
+                if let Some(start) = pending {
+                    self.emit(Event::Start(start))?;
+                }
+                match event {
+                    Event::Start(start) if self.config.self_closing => {
+                        self.pending_start = Some(start.into_owned())
+                    }
+                    event => self.emit(event)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_comment(&mut self, comment: &str) -> Result<(), Error> {
+        // Comments can't be escaped, so `--` is broken up and a trailing `-` is followed by a space
+        // to keep the text from ending the comment early
+        let mut comment = Cow::Borrowed(comment);
+        while comment.contains("--") {
+            comment = Cow::Owned(comment.replace("--", "- -"));
+        }
+        if comment.ends_with('-') {
+            comment.to_mut().push(' ');
+        }
+        self.write_event(Event::Comment(BytesText::from_escaped(comment)))
+    }
+
+    fn write_node(&mut self, node: &Node) -> Result<(), Error> {
+        match node {
+            Node::Element(e) => self.write_element(e),
+            Node::Comment(c) => self.write_comment(c),
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Placemark")))?)
     }
 
     fn write_element(&mut self, e: &Element) -> Result<(), Error> {
-        let start = BytesStart::new(&e.name).with_attributes(self.hash_map_as_attrs(&e.attrs));
-        self.writer.write_event(Event::Start(start))?;
+        if self.version() == KmlVersion::V22
+            && e.namespace.as_deref() == KmlVersion::V23.namespace()
+            && KML_23_ELEMENTS.contains(&e.name.as_str())
+        {
+            return Err(Error::InvalidInput(format!(
+                "{} is only part of KML 2.3 and can't be written as KML 2.2, which has it in the gx namespace",
+                e.name
+            )));
+        }
+        if let Some(ElementWriter(write)) = self.config.element_writers.get(&e.name).cloned() {
+            return write(e, &mut self.custom_writer()?);
+        }
+        let name = match &e.prefix {
+            Some(prefix) => format!("{}:{}", prefix, e.name),
+            None => e.name.clone(),
+        };
+        let scope = self.declare_prefixes(&e.attrs);
+        let mut attrs = self.hash_map_as_attrs(&e.attrs);
+        // Declare the namespace of prefixed elements if an ancestor hasn't already
+        let declaration;
+        let namespace = match (&e.prefix, &e.namespace) {
+            (Some(prefix), None) if prefix == "gx" => Some(GX_NAMESPACE),
+            (_, namespace) => namespace.as_deref(),
+        };
+        if let (Some(prefix), Some(namespace)) = (&e.prefix, namespace) {
+            if !self.declared_prefixes.iter().any(|(p, _)| p == prefix) {
+                declaration = format!("xmlns:{}", prefix);
+                attrs.push((&declaration, namespace));
+                self.declared_prefixes
+                    .push((prefix.clone(), namespace.to_string()));
+            }
+        }
+        let start = BytesStart::new(&name).with_attributes(attrs);
+        self.write_event(Event::Start(start))?;
         if let Some(content) = &e.content {
-            self.writer
-                .write_event(Event::Text(BytesText::new(content)))?;
+            self.write_event(Event::Text(BytesText::new(content)))?;
         }
         for c in e.children.iter() {
-            self.write_element(c)?;
+            self.write_node(c)?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new(&e.name)))?)
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new(&name)))
     }
 
     fn write_style(&mut self, style: &Style) -> Result<(), Error> {
@@ -283,12 +1643,10 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Style").with_attributes(attrs),
         ))?;
-        if let Some(balloon) = &style.balloon {
-            self.write_balloon_style(balloon)?;
-        }
+        // Written in the order required by the schema
         if let Some(icon) = &style.icon {
             self.write_icon_style(icon)?;
         }
@@ -301,12 +1659,13 @@ where
         if let Some(poly) = &style.poly {
             self.write_poly_style(poly)?;
         }
+        if let Some(balloon) = &style.balloon {
+            self.write_balloon_style(balloon)?;
+        }
         if let Some(list) = &style.list {
             self.write_list_style(list)?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Style")))?)
+        self.write_event(Event::End(BytesEnd::new("Style")))
     }
 
     fn write_style_map(&mut self, style_map: &StyleMap) -> Result<(), Error> {
@@ -319,24 +1678,22 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&style_map.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("StyleMap").with_attributes(attrs),
         ))?;
         for p in style_map.pairs.iter() {
             self.write_pair(p)?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("StyleMap")))?)
+        self.write_event(Event::End(BytesEnd::new("StyleMap")))
     }
 
     fn write_pair(&mut self, pair: &Pair) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Pair").with_attributes(self.hash_map_as_attrs(&pair.attrs)),
         ))?;
-        self.write_text_element("key", &pair.key)?;
+        self.write_text_element("key", &pair.key.to_string())?;
         self.write_text_element("styleUrl", &pair.style_url)?;
-        Ok(self.writer.write_event(Event::End(BytesEnd::new("Pair")))?)
+        self.write_event(Event::End(BytesEnd::new("Pair")))
     }
 
     fn write_balloon_style(&mut self, balloon_style: &BalloonStyle) -> Result<(), Error> {
@@ -349,22 +1706,25 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&balloon_style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("BalloonStyle").with_attributes(attrs),
         ))?;
         if let Some(bg_color) = &balloon_style.bg_color {
             self.write_text_element("bgColor", bg_color)?;
         }
-        self.write_text_element("textColor", &balloon_style.text_color)?;
+        self.write_default_element("textColor", &balloon_style.text_color, "ff000000")?;
         if let Some(text) = &balloon_style.text {
-            self.write_text_element("text", text)?;
+            // Templates read from markup hold its inner XML, which is written back as it was
+            if !self.config.cdata && is_inner_xml(text) {
+                self.write_raw_text_element("text", text)?;
+            } else {
+                self.write_html_element("text", text)?;
+            }
         }
         if !balloon_style.display {
             self.write_text_element("displayMode", "hide")?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("BalloonStyle")))?)
+        self.write_event(Event::End(BytesEnd::new("BalloonStyle")))
     }
 
     fn write_icon_style(&mut self, icon_style: &IconStyle) -> Result<(), Error> {
@@ -377,37 +1737,34 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&icon_style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("IconStyle").with_attributes(attrs),
         ))?;
-        self.write_text_element("scale", &icon_style.scale.to_string())?;
-        self.write_text_element("heading", &icon_style.heading.to_string())?;
+        self.write_default_element("color", &icon_style.color, "ffffffff")?;
+        self.write_default_element("colorMode", &icon_style.color_mode.to_string(), "normal")?;
+        self.write_default_element("scale", &self.number(icon_style.scale), "1")?;
+        self.write_default_element("heading", &self.number(icon_style.heading), "0")?;
+        if let Some(icon) = &icon_style.icon {
+            self.write_icon(icon)?;
+        }
         if let Some(hot_spot) = &icon_style.hot_spot {
-            self.writer
-                .write_event(Event::Start(BytesStart::new("hotSpot").with_attributes(
-                    vec![
-                        ("x", &*hot_spot.x.to_string()),
-                        ("y", &*hot_spot.y.to_string()),
-                        ("xunits", &*hot_spot.xunits.to_string()),
-                        ("yunits", &*hot_spot.yunits.to_string()),
-                    ],
-                )))?;
-            self.writer
-                .write_event(Event::End(BytesEnd::new("hotSpot")))?;
-        }
-        self.write_text_element("color", &icon_style.color)?;
-        self.write_text_element("colorMode", &icon_style.color_mode.to_string())?;
-        self.write_icon(&icon_style.icon)?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("IconStyle")))?)
+            self.write_event(Event::Start(BytesStart::new("hotSpot").with_attributes(
+                vec![
+                    ("x", &*self.number(hot_spot.x)),
+                    ("y", &*self.number(hot_spot.y)),
+                    ("xunits", &*hot_spot.xunits.to_string()),
+                    ("yunits", &*hot_spot.yunits.to_string()),
+                ],
+            )))?;
+            self.write_event(Event::End(BytesEnd::new("hotSpot")))?;
+        }
+        self.write_event(Event::End(BytesEnd::new("IconStyle")))
     }
 
     fn write_icon(&mut self, icon: &Icon) -> Result<(), Error> {
-        self.writer
-            .write_event(Event::Start(BytesStart::new("Icon")))?;
+        self.write_event(Event::Start(BytesStart::new("Icon")))?;
         self.write_text_element("href", &icon.href)?;
-        Ok(self.writer.write_event(Event::End(BytesEnd::new("Icon")))?)
+        self.write_event(Event::End(BytesEnd::new("Icon")))
     }
 
     fn write_label_style(&mut self, label_style: &LabelStyle) -> Result<(), Error> {
@@ -420,15 +1777,13 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&label_style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("LabelStyle").with_attributes(attrs),
         ))?;
-        self.write_text_element("color", &label_style.color)?;
-        self.write_text_element("colorMode", &label_style.color_mode.to_string())?;
-        self.write_text_element("scale", &label_style.scale.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("LabelStyle")))?)
+        self.write_default_element("color", &label_style.color, "ffffffff")?;
+        self.write_default_element("colorMode", &label_style.color_mode.to_string(), "normal")?;
+        self.write_default_element("scale", &self.number(label_style.scale), "1")?;
+        self.write_event(Event::End(BytesEnd::new("LabelStyle")))
     }
 
     fn write_line_style(&mut self, line_style: &LineStyle) -> Result<(), Error> {
@@ -441,15 +1796,13 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&line_style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("LineStyle").with_attributes(attrs),
         ))?;
-        self.write_text_element("color", &line_style.color)?;
-        self.write_text_element("colorMode", &line_style.color_mode.to_string())?;
-        self.write_text_element("width", &line_style.width.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("LineStyle")))?)
+        self.write_default_element("color", &line_style.color, "ffffffff")?;
+        self.write_default_element("colorMode", &line_style.color_mode.to_string(), "normal")?;
+        self.write_default_element("width", &self.number(line_style.width), "1")?;
+        self.write_event(Event::End(BytesEnd::new("LineStyle")))
     }
 
     fn write_poly_style(&mut self, poly_style: &PolyStyle) -> Result<(), Error> {
@@ -462,16 +1815,14 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&poly_style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("PolyStyle").with_attributes(attrs),
         ))?;
-        self.write_text_element("color", &poly_style.color)?;
-        self.write_text_element("colorMode", &poly_style.color_mode.to_string())?;
-        self.write_text_element("fill", &poly_style.fill.to_string())?;
-        self.write_text_element("outline", &poly_style.outline.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("PolyStyle")))?)
+        self.write_default_element("color", &poly_style.color, "ffffffff")?;
+        self.write_default_element("colorMode", &poly_style.color_mode.to_string(), "normal")?;
+        self.write_default_element("fill", &poly_style.fill.to_string(), "true")?;
+        self.write_default_element("outline", &poly_style.outline.to_string(), "true")?;
+        self.write_event(Event::End(BytesEnd::new("PolyStyle")))
     }
 
     fn write_list_style(&mut self, list_style: &ListStyle) -> Result<(), Error> {
@@ -484,18 +1835,20 @@ where
             .into_iter()
             .chain(self.hash_map_as_attrs(&list_style.attrs))
             .collect();
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("ListStyle").with_attributes(attrs),
         ))?;
-        self.write_text_element("bgColor", &list_style.bg_color)?;
-        self.write_text_element("maxSnippetLines", &list_style.max_snippet_lines.to_string())?;
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("ListStyle")))?)
+        self.write_default_element("bgColor", &list_style.bg_color, "ffffffff")?;
+        self.write_default_element(
+            "maxSnippetLines",
+            &list_style.max_snippet_lines.to_string(),
+            "2",
+        )?;
+        self.write_event(Event::End(BytesEnd::new("ListStyle")))
     }
 
     fn write_link_type_icon(&mut self, icon: &LinkTypeIcon) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Icon").with_attributes(self.hash_map_as_attrs(&icon.attrs)),
         ))?;
         if let Some(href) = &icon.href {
@@ -504,23 +1857,23 @@ where
         if let Some(refresh_mode) = &icon.refresh_mode {
             self.write_text_element("refreshMode", &refresh_mode.to_string())?;
         }
-        self.write_text_element("refreshInterval", &icon.refresh_interval.to_string())?;
+        self.write_default_element("refreshInterval", &self.number(icon.refresh_interval), "4")?;
         if let Some(view_refresh_mode) = &icon.view_refresh_mode {
             self.write_text_element("viewRefreshMode", &view_refresh_mode.to_string())?;
         }
-        self.write_text_element("viewRefreshTime", &icon.view_refresh_time.to_string())?;
-        self.write_text_element("viewBoundScale", &icon.view_bound_scale.to_string())?;
+        self.write_default_element("viewRefreshTime", &self.number(icon.view_refresh_time), "4")?;
+        self.write_default_element("viewBoundScale", &self.number(icon.view_bound_scale), "1")?;
         if let Some(view_format) = &icon.view_format {
             self.write_text_element("viewFormat", view_format)?;
         }
         if let Some(http_query) = &icon.http_query {
             self.write_text_element("httpQuery", http_query)?;
         }
-        Ok(self.writer.write_event(Event::End(BytesEnd::new("Icon")))?)
+        self.write_event(Event::End(BytesEnd::new("Icon")))
     }
 
     fn write_link(&mut self, link: &Link) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Link").with_attributes(self.hash_map_as_attrs(&link.attrs)),
         ))?;
         if let Some(href) = &link.href {
@@ -529,36 +1882,34 @@ where
         if let Some(refresh_mode) = &link.refresh_mode {
             self.write_text_element("refreshMode", &refresh_mode.to_string())?;
         }
-        self.write_text_element("refreshInterval", &link.refresh_interval.to_string())?;
+        self.write_default_element("refreshInterval", &self.number(link.refresh_interval), "4")?;
         if let Some(view_refresh_mode) = &link.view_refresh_mode {
             self.write_text_element("viewRefreshMode", &view_refresh_mode.to_string())?;
         }
-        self.write_text_element("viewRefreshTime", &link.view_refresh_time.to_string())?;
-        self.write_text_element("viewBoundScale", &link.view_bound_scale.to_string())?;
+        self.write_default_element("viewRefreshTime", &self.number(link.view_refresh_time), "4")?;
+        self.write_default_element("viewBoundScale", &self.number(link.view_bound_scale), "1")?;
         if let Some(view_format) = &link.view_format {
             self.write_text_element("viewFormat", view_format)?;
         }
         if let Some(http_query) = &link.http_query {
             self.write_text_element("httpQuery", http_query)?;
         }
-        Ok(self.writer.write_event(Event::End(BytesEnd::new("Link")))?)
+        self.write_event(Event::End(BytesEnd::new("Link")))
     }
 
     fn write_resource_map(&mut self, resource_map: &ResourceMap) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("ResourceMap")
                 .with_attributes(self.hash_map_as_attrs(&resource_map.attrs)),
         ))?;
         for alias in resource_map.aliases.iter() {
             self.write_alias(alias)?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("ResourceMap")))?)
+        self.write_event(Event::End(BytesEnd::new("ResourceMap")))
     }
 
     fn write_alias(&mut self, alias: &Alias) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("Alias").with_attributes(self.hash_map_as_attrs(&alias.attrs)),
         ))?;
         if let Some(href) = &alias.target_href {
@@ -567,16 +1918,17 @@ where
         if let Some(href) = &alias.source_href {
             self.write_text_element("sourceHref", href)?;
         }
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("Alias")))?)
+        self.write_event(Event::End(BytesEnd::new("Alias")))
     }
 
     fn write_schema_data(&mut self, schema_data: &SchemaData) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
-            BytesStart::new("SchemaData")
-                .with_attributes(self.hash_map_as_attrs(&schema_data.attrs)),
-        ))?;
+        let filter_attrs = match &schema_data.schema_url {
+            Some(schema_url) => HashMap::from([("schemaUrl".to_string(), schema_url.clone())]),
+            None => HashMap::new(),
+        };
+        self.write_event(Event::Start(BytesStart::new("SchemaData").with_attributes(
+            self.hash_map_as_attrs_filtered(&schema_data.attrs, &filter_attrs),
+        )))?;
 
         for value in schema_data.data.iter() {
             self.write_simple_data(value)?;
@@ -586,9 +1938,7 @@ where
             self.write_simple_array_data(value)?;
         }
 
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("SchemaData")))?)
+        self.write_event(Event::End(BytesEnd::new("SchemaData")))
     }
 
     fn write_simple_array_data(
@@ -596,7 +1946,7 @@ where
         simple_array_data: &SimpleArrayData,
     ) -> Result<(), Error> {
         let filter_attrs = HashMap::from([("name".to_string(), simple_array_data.name.clone())]);
-        self.writer.write_event(Event::Start(
+        self.write_event(Event::Start(
             BytesStart::new("SimpleArrayData").with_attributes(
                 self.hash_map_as_attrs_filtered(&simple_array_data.attrs, &filter_attrs),
             ),
@@ -606,24 +1956,18 @@ where
             self.write_text_element("value", value)?;
         }
 
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("SimpleArrayData")))?)
+        self.write_event(Event::End(BytesEnd::new("SimpleArrayData")))
     }
 
     fn write_simple_data(&mut self, simple_data: &SimpleData) -> Result<(), Error> {
         let filter_attrs = HashMap::from([("name".to_string(), simple_data.name.clone())]);
-        self.writer
-            .write_event(Event::Start(BytesStart::new("SimpleData").with_attributes(
-                self.hash_map_as_attrs_filtered(&simple_data.attrs, &filter_attrs),
-            )))?;
+        self.write_event(Event::Start(BytesStart::new("SimpleData").with_attributes(
+            self.hash_map_as_attrs_filtered(&simple_data.attrs, &filter_attrs),
+        )))?;
 
-        self.writer
-            .write_event(Event::Text(BytesText::new(&simple_data.value)))?;
+        self.write_event(Event::Text(BytesText::new(&simple_data.value)))?;
 
-        Ok(self
-            .writer
-            .write_event(Event::End(BytesEnd::new("SimpleData")))?)
+        self.write_event(Event::End(BytesEnd::new("SimpleData")))
     }
 
     fn write_geometry(&mut self, geometry: &Geometry<T>) -> Result<(), Error> {
@@ -633,24 +1977,70 @@ where
             Geometry::LinearRing(l) => self.write_linear_ring(l),
             Geometry::Polygon(p) => self.write_polygon(p),
             Geometry::MultiGeometry(g) => self.write_multi_geometry(g),
+            Geometry::Track(t) => self.write_track(t),
+            Geometry::MultiTrack(t) => self.write_multi_track(t),
             _ => Ok(()),
         }
     }
 
+    /// Simplifies `coords` with the configured tolerance, keeping them as is if that would leave
+    /// fewer than `min_len`
+    #[cfg(feature = "geo")]
+    fn simplify(&self, coords: &[Coord<T>], min_len: usize) -> Vec<Coord<T>> {
+        use geo::SimplifyIdx;
+
+        let Some(tolerance) = self.config.simplify else {
+            return coords.to_vec();
+        };
+        let to_f64 = |v: T| v.to_f64().unwrap_or(f64::NAN);
+        let line: geo_types::LineString<f64> =
+            coords.iter().map(|c| (to_f64(c.x), to_f64(c.y))).collect();
+        // Kept by index so altitudes, which geo-types doesn't have, are written unchanged
+        let kept = line.simplify_idx(tolerance);
+        if kept.len() < min_len {
+            return coords.to_vec();
+        }
+        kept.into_iter().map(|i| coords[i]).collect()
+    }
+
+    #[cfg(not(feature = "geo"))]
+    fn simplify(&self, coords: &[Coord<T>], _min_len: usize) -> Vec<Coord<T>> {
+        coords.to_vec()
+    }
+
     fn write_geom_props(&mut self, props: GeomProps<T>) -> Result<(), Error> {
-        self.write_text_element("extrude", if props.extrude { "1" } else { "0" })?;
-        self.write_text_element("tessellate", if props.tessellate { "1" } else { "0" })?;
-        self.write_text_element("altitudeMode", &props.altitude_mode.to_string())?;
-        if !props.coords.is_empty() {
-            self.write_text_element(
-                "coordinates",
-                &props
-                    .coords
-                    .iter()
-                    .map(Coord::to_string)
-                    .collect::<Vec<String>>()
-                    .join("\n"),
-            )?
+        self.write_bool_element("extrude", props.extrude)?;
+        self.write_bool_element("tessellate", props.tessellate)?;
+        self.write_altitude_mode(props.altitude_mode)?;
+        if let (CoordLayout::Indented, [_, _, ..]) = (self.config.coord_layout, &props.coords[..]) {
+            // The element being written may still be held back, so isn't counted in `depth` yet
+            let depth = self.depth + usize::from(self.pending_start.is_some());
+            let line_break = |depth| {
+                self.indentation(depth)
+                    .map(|indent| String::from_utf8_lossy(&indent).into_owned())
+                    .unwrap_or_else(|| "\n".to_string())
+            };
+            let mut coords = line_break(depth + 1);
+            for c in props.coords.iter() {
+                coords.push_str(&self.coord(c, ","));
+                coords.push(' ');
+            }
+            coords.push_str(&line_break(depth));
+            self.write_text_element("coordinates", &coords)?
+        } else if !props.coords.is_empty() {
+            let tuples_per_line = match self.config.coord_layout {
+                CoordLayout::Newline => 1,
+                CoordLayout::Space | CoordLayout::Indented => usize::MAX,
+                CoordLayout::Wrap(n) => n.max(1),
+            };
+            let mut coords = String::new();
+            for (i, c) in props.coords.iter().enumerate() {
+                if i > 0 {
+                    coords.push(if i % tuples_per_line == 0 { '\n' } else { ' ' });
+                }
+                coords.push_str(&self.coord(c, ","));
+            }
+            self.write_text_element("coordinates", &coords)?
         }
         Ok(())
     }
@@ -661,66 +2051,756 @@ where
         attrs: &HashMap<String, String>,
         elements: &[Kml<T>],
     ) -> Result<(), Error> {
-        self.writer.write_event(Event::Start(
-            BytesStart::new(tag).with_attributes(self.hash_map_as_attrs(attrs)),
+        let scope = self.declare_prefixes(attrs);
+        // The default namespace comes first, followed by the configured ones in order
+        let mut root_attrs = self.hash_map_as_attrs(attrs);
+        root_attrs.sort_by_key(|&(k, _)| {
+            let configured = k
+                .strip_prefix("xmlns:")
+                .and_then(|prefix| self.config.namespaces.iter().position(|(p, _)| p == prefix));
+            match (k, configured) {
+                ("xmlns", _) => (0, k),
+                (_, Some(i)) => (i + 1, k),
+                _ => (usize::MAX, k),
+            }
+        });
+        self.write_event(Event::Start(
+            BytesStart::new(tag).with_attributes(root_attrs),
         ))?;
         for e in elements.iter() {
             self.write_kml(e)?;
         }
-        // Wrapping in Ok to coerce the quick_xml::Error type with ?
-        Ok(self.writer.write_event(Event::End(BytesEnd::new(tag)))?)
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new(tag)))
     }
 
-    fn write_text_element(&mut self, tag: &str, content: &str) -> Result<(), Error> {
-        self.writer
-            .write_event(Event::Start(BytesStart::new(tag)))?;
-        self.writer
-            .write_event(Event::Text(BytesText::new(content)))?;
-        Ok(self.writer.write_event(Event::End(BytesEnd::new(tag)))?)
+    fn write_document_container(&mut self, document: &Document<T>) -> Result<(), Error> {
+        let scope = self.declare_prefixes(&document.attrs);
+        self.write_event(Event::Start(
+            BytesStart::new("Document").with_attributes(self.hash_map_as_attrs(&document.attrs)),
+        ))?;
+        self.write_feature_fields(
+            document.name.as_deref(),
+            document.description.as_deref(),
+            document.style_url.as_deref(),
+            &document.children,
+        )?;
+        let is_style = |e: &&Kml<T>| matches!(e, Kml::Style(_) | Kml::StyleMap(_));
+        if self.config.styles_first {
+            let (styles, others): (Vec<_>, Vec<_>) = document.elements.iter().partition(is_style);
+            for e in styles.into_iter().chain(others) {
+                self.write_kml(e)?;
+            }
+        } else {
+            for e in document.elements.iter() {
+                self.write_kml(e)?;
+            }
+        }
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new("Document")))
     }
 
-    fn hash_map_as_attrs(&self, hash_map: &'a HashMap<String, String>) -> Vec<(&'a str, &'a str)> {
-        hash_map
-            .iter()
-            .map(|(k, v)| (&k[..], &v[..]))
-            .collect::<Vec<(&str, &str)>>()
+    fn write_folder_container(&mut self, folder: &Folder<T>) -> Result<(), Error> {
+        let scope = self.declare_prefixes(&folder.attrs);
+        self.write_event(Event::Start(
+            BytesStart::new("Folder").with_attributes(self.hash_map_as_attrs(&folder.attrs)),
+        ))?;
+        self.write_feature_fields(
+            folder.name.as_deref(),
+            folder.description.as_deref(),
+            folder.style_url.as_deref(),
+            &folder.children,
+        )?;
+        for e in folder.elements.iter() {
+            self.write_kml(e)?;
+        }
+        self.declared_prefixes.truncate(scope);
+        self.write_event(Event::End(BytesEnd::new("Folder")))
     }
 
-    fn hash_map_as_attrs_filtered(
-        &self,
-        hash_map: &'a HashMap<String, String>,
-        filter_hash_map: &'a HashMap<String, String>,
-    ) -> Vec<(&'a str, &'a str)> {
-        // Filter out select props like id/name so that we include them first in order
-        filter_hash_map
-            .iter()
-            .chain(
-                hash_map
-                    .iter()
-                    .filter(|(k, _)| !filter_hash_map.contains_key(&k.to_string())),
-            )
-            .map(|(k, v)| (&k[..], &v[..]))
-            .collect::<Vec<(&str, &str)>>()
+    /// Writes the fields of a container, with its children around the typed fields in the
+    /// sequence required by the schema
+    fn write_feature_fields(
+        &mut self,
+        name: Option<&str>,
+        description: Option<&str>,
+        style_url: Option<&str>,
+        children: &[Node],
+    ) -> Result<(), Error> {
+        if let Some(name) = name {
+            self.write_text_element("name", name)?;
+        }
+        let mut children = sorted_feature_children(children).peekable();
+        while let Some((_, c)) = children.next_if(|(rank, _)| *rank == 0) {
+            self.write_node(c)?;
+        }
+        if let Some(description) = description {
+            self.write_html_element("description", description)?;
+        }
+        while let Some((_, c)) = children.next_if(|(rank, _)| *rank == 1) {
+            self.write_node(c)?;
+        }
+        if let Some(style_url) = style_url {
+            self.write_text_element("styleUrl", style_url)?;
+        }
+        for (_, c) in children {
+            self.write_node(c)?;
+        }
+        Ok(())
     }
-}
 
-impl<T> fmt::Display for Kml<T>
-where
-    T: CoordType + Default + FromStr + fmt::Display,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut buf = Vec::new();
-        KmlWriter::from_writer(&mut buf)
-            .write(self)
-            .map_err(|_| fmt::Error)
+    /// Returns the tag name for an element that KML 2.3 promoted from the `gx` namespace
+    fn gx_tag(&self, name: &str) -> String {
+        if self.writes_gx_prefix() {
+            format!("gx:{name}")
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Whether elements that KML 2.3 promoted from the `gx` namespace are written with the prefix
+    fn writes_gx_prefix(&self) -> bool {
+        self.version() != KmlVersion::V23
+    }
+
+    /// Version being written, which is the configured version if it's known
+    fn version(&self) -> KmlVersion {
+        match self.config.version {
+            KmlVersion::Unknown => self.document_version,
+            v => v,
+        }
+    }
+
+    /// Whether writing `k` produces any elements in the `gx` namespace
+    fn uses_gx(&self, k: &Kml<T>) -> bool {
+        match k {
+            Kml::KmlDocument(d) => d.elements.iter().any(|e| self.uses_gx(e)),
+            Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                elements.iter().any(|e| self.uses_gx(e))
+            }
+            Kml::Track(_) | Kml::MultiTrack(_) | Kml::Tour(_) => self.writes_gx_prefix(),
+            Kml::MultiGeometry(g) => self.multi_geometry_uses_gx(g),
+            Kml::Placemark(p) => {
+                p.geometry
+                    .as_ref()
+                    .is_some_and(|g| self.geometry_uses_gx(g))
+                    || p.children
+                        .iter()
+                        .filter_map(Node::as_element)
+                        .any(element_uses_gx)
+            }
+            Kml::Element(e) => element_uses_gx(e),
+            Kml::Extension(e) => element_uses_gx(&e.to_element()),
+            _ => false,
+        }
+    }
+
+    fn multi_geometry_uses_gx(&self, g: &MultiGeometry<T>) -> bool {
+        g.geometries.iter().any(|g| self.geometry_uses_gx(g))
+            || g.children
+                .iter()
+                .filter_map(Node::as_element)
+                .any(element_uses_gx)
+    }
+
+    fn geometry_uses_gx(&self, g: &Geometry<T>) -> bool {
+        match g {
+            Geometry::Track(_) | Geometry::MultiTrack(_) => self.writes_gx_prefix(),
+            Geometry::MultiGeometry(g) => self.multi_geometry_uses_gx(g),
+            Geometry::Element(e) => element_uses_gx(e),
+            _ => false,
+        }
+    }
+
+    /// Writes a boolean flag as `1` or `0` if it's set
+    fn write_bool_element(&mut self, tag: &str, value: Option<bool>) -> Result<(), Error> {
+        match value {
+            Some(value) => self.write_default_element(tag, if value { "1" } else { "0" }, "0"),
+            None => Ok(()),
+        }
+    }
+
+    fn write_altitude_mode(&mut self, altitude_mode: Option<AltitudeMode>) -> Result<(), Error> {
+        match altitude_mode {
+            Some(mode) => {
+                self.write_default_element("altitudeMode", &mode.to_string(), "clampToGround")
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a text element unless [`omit_defaults`](struct.KmlWriterConfig.html#structfield.omit_defaults)
+    /// is set and its content is the spec default
+    fn write_default_element(
+        &mut self,
+        tag: &str,
+        content: &str,
+        default: &str,
+    ) -> Result<(), Error> {
+        if self.config.omit_defaults && content.eq_ignore_ascii_case(default) {
+            return Ok(());
+        }
+        self.write_text_element(tag, content)
+    }
+
+    /// Formats a number, rounded to the configured precision
+    fn number<N: fmt::Display>(&self, n: N) -> String {
+        let Some(precision) = self.config.precision else {
+            return n.to_string();
+        };
+        let mut number = format!("{n:.precision$}");
+        if number.contains('.') {
+            number.truncate(number.trim_end_matches('0').trim_end_matches('.').len());
+        }
+        if number == "-0" {
+            number.remove(0);
+        }
+        number
+    }
+
+    /// Formats a coordinate tuple with values separated by `separator`
+    fn coord(&self, coord: &Coord<T>, separator: &str) -> String {
+        let coord = match &self.coord_transform {
+            Some(transform) => transform(*coord),
+            None => *coord,
+        };
+        let mut coord_str = format!(
+            "{}{separator}{}",
+            self.number(coord.x),
+            self.number(coord.y)
+        );
+        let z = match (self.config.altitudes, coord.z) {
+            (Altitudes::Fill, None) => Some(T::zero()),
+            (Altitudes::DropZero, Some(z)) if z.is_zero() => None,
+            (_, z) => z,
+        };
+        if let Some(z) = z {
+            coord_str.push_str(separator);
+            coord_str.push_str(&self.number(z));
+        }
+        coord_str
+    }
+
+    /// Writes an element named `tag` containing the escaped text `content`, for implementing
+    /// [`ToKml`](trait.ToKml.html)
+    pub fn write_text_element(&mut self, tag: &str, content: &str) -> Result<(), Error> {
+        self.write_event(Event::Start(BytesStart::new(tag)))?;
+        self.write_event(Event::Text(BytesText::new(content)))?;
+        self.write_event(Event::End(BytesEnd::new(tag)))
+    }
+
+    /// Writes content that may contain HTML, wrapping it in CDATA so markup is kept readable
+    fn write_html_element(&mut self, tag: &str, content: &str) -> Result<(), Error> {
+        if self.config.raw_html {
+            return self.write_raw_text_element(tag, content);
+        }
+        if (!self.config.cdata && !content.contains('<')) || content.contains("]]>") {
+            return self.write_text_element(tag, content);
+        }
+        self.write_cdata_element(tag, content)
+    }
+
+    fn write_cdata_element(&mut self, tag: &str, content: &str) -> Result<(), Error> {
+        self.write_event(Event::Start(BytesStart::new(tag)))?;
+        self.write_event(Event::CData(BytesCData::new(content)))?;
+        self.write_event(Event::End(BytesEnd::new(tag)))
+    }
+
+    /// Writes content that is already valid XML, for
+    /// [`raw_html`](struct.KmlWriterConfig.html#structfield.raw_html)
+    fn write_raw_text_element(&mut self, tag: &str, content: &str) -> Result<(), Error> {
+        self.write_event(Event::Start(BytesStart::new(tag)))?;
+        self.write_event(Event::Text(BytesText::from_escaped(content)))?;
+        self.write_event(Event::End(BytesEnd::new(tag)))
+    }
+
+    /// Returns the configured [`namespaces`](struct.KmlWriterConfig.html#structfield.namespaces),
+    /// with KML namespaces of other versions, like the `kml` prefix of the Google Earth preset,
+    /// replaced with the one of the version being written
+    fn configured_namespaces(&self, version: KmlVersion) -> Vec<(String, String)> {
+        self.config
+            .namespaces
+            .iter()
+            .map(|(prefix, ns)| match version.namespace() {
+                Some(kml) if ns.parse::<KmlVersion>().is_ok() => (prefix.clone(), kml.to_string()),
+                _ => (prefix.clone(), ns.clone()),
+            })
+            .collect()
+    }
+
+    /// Adds namespace prefixes declared with `xmlns:` attributes to the current scope, returning
+    /// the length to truncate back to once the element is closed
+    fn declare_prefixes(&mut self, attrs: &HashMap<String, String>) -> usize {
+        let scope = self.declared_prefixes.len();
+        self.declared_prefixes
+            .extend(attrs.iter().filter_map(|(k, v)| {
+                k.strip_prefix("xmlns:")
+                    .map(|prefix| (prefix.to_string(), v.clone()))
+            }));
+        scope
+    }
+
+    /// Declares the `gx` namespace for an element named `tag` if it has the `gx` prefix and no
+    /// ancestor, like the root of a document, has declared it, rebinding the prefix if an
+    /// ancestor bound it to another namespace. Returns the declaration to add to the element's
+    /// attributes, or an error if the element's own `attrs` bind `gx` to another namespace
+    fn declare_gx(
+        &mut self,
+        tag: &str,
+        attrs: &HashMap<String, String>,
+    ) -> Result<Option<(&'static str, &'static str)>, Error> {
+        if !tag.starts_with("gx:") {
+            return Ok(None);
+        }
+        match self.declared_prefixes.iter().rev().find(|(p, _)| p == "gx") {
+            Some((_, namespace)) if namespace == GX_NAMESPACE => Ok(None),
+            Some((_, namespace)) if attrs.contains_key("xmlns:gx") => Err(Error::InvalidInput(
+                format!("Can't write {tag} because it binds the gx prefix to {namespace} instead of {GX_NAMESPACE}"),
+            )),
+            _ => {
+                self.declared_prefixes
+                    .push(("gx".to_string(), GX_NAMESPACE.to_string()));
+                Ok(Some(("xmlns:gx", GX_NAMESPACE)))
+            }
+        }
+    }
+
+    fn hash_map_as_attrs(&self, hash_map: &'a HashMap<String, String>) -> Vec<(&'a str, &'a str)> {
+        hash_map
+            .iter()
+            .map(|(k, v)| (&k[..], &v[..]))
+            .collect::<Vec<(&str, &str)>>()
+    }
+
+    fn hash_map_as_attrs_filtered(
+        &self,
+        hash_map: &'a HashMap<String, String>,
+        filter_hash_map: &'a HashMap<String, String>,
+    ) -> Vec<(&'a str, &'a str)> {
+        // Filter out select props like id/name so that we include them first in order
+        filter_hash_map
+            .iter()
+            .chain(
+                hash_map
+                    .iter()
+                    .filter(|(k, _)| !filter_hash_map.contains_key(&k.to_string())),
+            )
+            .map(|(k, v)| (&k[..], &v[..]))
+            .collect::<Vec<(&str, &str)>>()
+    }
+}
+
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+impl<T> KmlWriter<BufWriter<File>, T>
+where
+    T: CoordType + FromStr + Default + fmt::Display,
+{
+    /// Writes KML to a file at `path` with the provided configuration as a complete document like
+    /// [`write_document`](#method.write_document), replacing the file if it exists
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use kml::{types::Point, writer::KmlWriterConfig, Kml, KmlWriter};
+    ///
+    /// let kml = Kml::Point(Point::new(1., 1., None));
+    /// KmlWriter::to_file("point.kml", &kml, KmlWriterConfig::default()).unwrap();
+    /// ```
+    pub fn to_file<P: AsRef<Path>>(
+        path: P,
+        kml: &Kml<T>,
+        config: KmlWriterConfig,
+    ) -> Result<(), Error> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut writer = KmlWriter::from_writer_with_config(file, config);
+        writer.write_document(kml)?;
+        writer.into_inner().flush()?;
+        Ok(())
+    }
+}
+
+impl<T> Kml<T>
+where
+    T: CoordType + Default + FromStr + fmt::Display,
+{
+    /// Writes KML to a string with the provided configuration, like
+    /// [`to_string`](#method.to_string) with the default configuration
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use kml::{types::Point, writer::KmlWriterConfig, Kml};
+    ///
+    /// let kml = Kml::Point(Point::new(1.23456, 1., None));
+    /// let config = KmlWriterConfig::default().precision(Some(2));
+    /// assert_eq!(
+    ///     kml.to_kml_string_with(config).unwrap(),
+    ///     "<Point><coordinates>1.23,1</coordinates></Point>"
+    /// );
+    /// ```
+    pub fn to_kml_string_with(&self, config: KmlWriterConfig) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        KmlWriter::from_writer_with_config(&mut buf, config).write(self)?;
+        String::from_utf8(buf).map_err(|e| Error::InvalidInput(e.to_string()))
+    }
+}
+
+/// Writes KML with the default configuration, or indented by two spaces with the alternate flag
+///
+/// # Example
+///
+/// ```
+/// use kml::{types::Point, Kml};
+///
+/// let kml: Kml = Kml::Point(Point::new(1., 1., None));
+/// assert_eq!(format!("{}", kml), "<Point><coordinates>1,1</coordinates></Point>");
+/// assert_eq!(
+///     format!("{:#}", kml),
+///     "<Point>\n  <coordinates>1,1</coordinates>\n</Point>"
+/// );
+/// ```
+impl<T> fmt::Display for Kml<T>
+where
+    T: CoordType + Default + FromStr + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = Vec::new();
+        let config = KmlWriterConfig::default().indent(f.alternate().then_some((b' ', 2)));
+        KmlWriter::from_writer_with_config(&mut buf, config)
+            .write(self)
+            .map_err(|_| fmt::Error)
             .and_then(|_| f.write_str(str::from_utf8(&buf).unwrap()))
     }
 }
 
+/// Returns the id of `e` if it's a `Style` or `StyleMap` with one
+fn style_id_mut<T: CoordType>(e: &mut Kml<T>) -> Option<&mut String> {
+    match e {
+        Kml::Style(s) => s.id.as_mut(),
+        Kml::StyleMap(s) => s.id.as_mut(),
+        _ => None,
+    }
+}
+
+/// Returns the first `Style` or `StyleMap` id used more than once in `elements` and the
+/// containers in it, recording ids in `seen`
+fn duplicate_style_id<T: CoordType>(
+    elements: &[Kml<T>],
+    seen: &mut HashSet<String>,
+) -> Option<String> {
+    elements.iter().find_map(|e| match e {
+        Kml::Style(Style { id: Some(id), .. }) | Kml::StyleMap(StyleMap { id: Some(id), .. }) => {
+            (!seen.insert(id.clone())).then(|| id.clone())
+        }
+        Kml::KmlDocument(d) => duplicate_style_id(&d.elements, seen),
+        Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+            duplicate_style_id(elements, seen)
+        }
+        _ => None,
+    })
+}
+
+/// Gives `Style` and `StyleMap` elements in `elements` with an id in `seen` a new unique id, then
+/// updates `styleUrl` references in the same container and those nested in it. `renamed` holds
+/// ids already renamed in enclosing containers
+fn rename_duplicate_styles<T: CoordType>(
+    elements: &mut [Kml<T>],
+    seen: &mut HashSet<String>,
+    renamed: &HashMap<String, String>,
+) {
+    let mut renamed = renamed.clone();
+    for id in elements.iter_mut().filter_map(style_id_mut) {
+        if seen.insert(id.clone()) {
+            continue;
+        }
+        let new_id = (2..)
+            .map(|n| format!("{id}-{n}"))
+            .find(|new_id| !seen.contains(new_id))
+            .unwrap();
+        seen.insert(new_id.clone());
+        renamed.insert(std::mem::replace(id, new_id.clone()), new_id);
+    }
+    let rename_url = |url: &mut String| {
+        if let Some(new_id) = url.strip_prefix('#').and_then(|id| renamed.get(id)) {
+            *url = format!("#{new_id}");
+        }
+    };
+    for e in elements.iter_mut() {
+        match e {
+            Kml::Placemark(p) => p.style_url.iter_mut().for_each(rename_url),
+            Kml::StyleMap(m) => m
+                .pairs
+                .iter_mut()
+                .for_each(|p| rename_url(&mut p.style_url)),
+            Kml::KmlDocument(d) => rename_duplicate_styles(&mut d.elements, seen, &renamed),
+            Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                rename_duplicate_styles(elements, seen, &renamed)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the styles in `elements` and the containers in it by id, with `StyleMap`s resolved to the
+/// style of their `normal` pair
+pub(crate) fn shared_styles<T: CoordType>(elements: &[Kml<T>]) -> HashMap<String, Style> {
+    fn collect<T: CoordType>(
+        elements: &[Kml<T>],
+        styles: &mut HashMap<String, Style>,
+        style_maps: &mut HashMap<String, String>,
+    ) {
+        for e in elements {
+            match e {
+                Kml::Style(s) => {
+                    if let Some(id) = &s.id {
+                        styles.entry(id.clone()).or_insert_with(|| s.clone());
+                    }
+                }
+                Kml::StyleMap(m) => {
+                    let normal = m.pairs.iter().find(|p| p.key == StyleState::Normal);
+                    if let (Some(id), Some(normal)) = (&m.id, normal) {
+                        style_maps
+                            .entry(id.clone())
+                            .or_insert_with(|| normal.style_url.clone());
+                    }
+                }
+                Kml::KmlDocument(d) => collect(&d.elements, styles, style_maps),
+                Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                    collect(elements, styles, style_maps)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut styles = HashMap::new();
+    let mut style_maps = HashMap::new();
+    collect(elements, &mut styles, &mut style_maps);
+    for (id, url) in style_maps.iter() {
+        // Style maps can point to other style maps, so follow them until reaching a style, giving
+        // up on cycles
+        let mut url = url;
+        let mut seen = HashSet::from([id]);
+        let style = loop {
+            let Some(target) = url.strip_prefix('#') else {
+                break None;
+            };
+            if let Some(style) = styles.get(target) {
+                break Some(style.clone());
+            }
+            match style_maps.get_key_value(target) {
+                Some((target, next)) if seen.insert(target) => url = next,
+                _ => break None,
+            }
+        };
+        if let Some(style) = style {
+            styles.entry(id.clone()).or_insert(style);
+        }
+    }
+    styles
+}
+
+/// Records the ids of `Style` and `StyleMap` elements in `elements` and the containers in it,
+/// including styles read into an `Element`
+fn collect_style_ids<T: CoordType>(elements: &[Kml<T>], ids: &mut HashSet<String>) {
+    for e in elements {
+        match e {
+            Kml::Style(Style { id: Some(id), .. })
+            | Kml::StyleMap(StyleMap { id: Some(id), .. }) => {
+                ids.insert(id.clone());
+            }
+            Kml::Element(e) if e.name == "Style" || e.name == "StyleMap" => {
+                ids.extend(e.attrs.get("id").cloned());
+            }
+            Kml::Placemark(p) => ids.extend(
+                p.children
+                    .iter()
+                    .filter_map(Node::as_element)
+                    .filter(|c| c.name == "Style" || c.name == "StyleMap")
+                    .filter_map(|c| c.attrs.get("id").cloned()),
+            ),
+            Kml::KmlDocument(d) => collect_style_ids(&d.elements, ids),
+            Kml::Document(Document { elements, .. }) | Kml::Folder(Folder { elements, .. }) => {
+                collect_style_ids(elements, ids)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Shares identical inline styles of placemarks in each `Document` in `elements`, generating ids
+/// not in `ids`
+fn share_inline_styles<T: CoordType>(elements: &mut [Kml<T>], ids: &mut HashSet<String>) {
+    for e in elements.iter_mut() {
+        match e {
+            Kml::KmlDocument(d) => share_inline_styles(&mut d.elements, ids),
+            Kml::Folder(Folder { elements, .. }) => share_inline_styles(elements, ids),
+            Kml::Document(Document { elements, .. }) => {
+                share_inline_styles(elements, ids);
+                share_document_styles(elements, ids);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Moves inline styles used by more than one placemark in a `Document`, including those in its
+/// folders but not in nested documents, to the top of the `Document`
+fn share_document_styles<T: CoordType>(elements: &mut Vec<Kml<T>>, ids: &mut HashSet<String>) {
+    let mut placemarks = Vec::new();
+    document_placemarks(elements, &mut placemarks);
+    let mut styles: Vec<(&Element, usize)> = Vec::new();
+    for p in placemarks.iter() {
+        let Some(style) = inline_style_index(p).and_then(|i| p.children[i].as_element()) else {
+            continue;
+        };
+        match styles.iter_mut().find(|(s, _)| *s == style) {
+            Some((_, count)) => *count += 1,
+            None => styles.push((style, 1)),
+        }
+    }
+    let mut shared: Vec<(Element, String)> = Vec::new();
+    for (style, _) in styles.into_iter().filter(|(_, count)| *count > 1) {
+        let id = (1..)
+            .map(|n| format!("style-{n}"))
+            .find(|id| !ids.contains(id))
+            .unwrap();
+        ids.insert(id.clone());
+        shared.push((style.clone(), id));
+    }
+    if shared.is_empty() {
+        return;
+    }
+    for p in placemarks.iter_mut() {
+        let Some(i) = inline_style_index(p) else {
+            continue;
+        };
+        if let Some((_, id)) = shared
+            .iter()
+            .find(|(s, _)| p.children[i].as_element() == Some(s))
+        {
+            p.children.remove(i);
+            p.style_url = Some(format!("#{id}"));
+        }
+    }
+    // Styles go before the features of the document, after its other styles and schemas
+    let position = elements
+        .iter()
+        .position(|e| matches!(e, Kml::Placemark(_) | Kml::Document(_) | Kml::Folder(_)))
+        .unwrap_or(elements.len());
+    elements.splice(
+        position..position,
+        shared.into_iter().map(|(mut style, id)| {
+            style.attrs.insert("id".to_string(), id);
+            Kml::Element(style)
+        }),
+    );
+}
+
+/// Collects placemarks in `elements` and the folders in it
+fn document_placemarks<'a, T: CoordType>(
+    elements: &'a mut [Kml<T>],
+    placemarks: &mut Vec<&'a mut Placemark<T>>,
+) {
+    for e in elements.iter_mut() {
+        match e {
+            Kml::Placemark(p) => placemarks.push(p),
+            Kml::Folder(Folder { elements, .. }) => document_placemarks(elements, placemarks),
+            _ => {}
+        }
+    }
+}
+
+/// Index of the only `Style` among the children of `p` if it can be shared, without an id and on
+/// a placemark without a `styleUrl`
+fn inline_style_index<T: CoordType>(p: &Placemark<T>) -> Option<usize> {
+    if p.style_url.is_some() {
+        return None;
+    }
+    let mut styles = p
+        .children
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| Some((i, c.as_element()?)))
+        .filter(|(_, c)| c.prefix.is_none() && (c.name == "Style" || c.name == "StyleMap"));
+    match (styles.next(), styles.next()) {
+        (Some((i, style)), None) if style.name == "Style" && !style.attrs.contains_key("id") => {
+            Some(i)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `e` or any of its children are in the `gx` namespace
+fn element_uses_gx(e: &Element) -> bool {
+    e.prefix.as_deref() == Some("gx") || e.elements().any(element_uses_gx)
+}
+
+/// Children of a Feature in the sequence required by the schema, along with their
+/// [`feature_child_rank`]. Comments take the rank of the element following them so they stay
+/// next to it, and ones at the end stay after the other children
+fn sorted_feature_children(children: &[Node]) -> impl Iterator<Item = (u8, &Node)> {
+    let mut rank = feature_child_rank("");
+    let mut ranked: Vec<(u8, &Node)> = children
+        .iter()
+        .rev()
+        .map(|c| {
+            if let Node::Element(e) = c {
+                rank = feature_child_rank(&e.name);
+            }
+            (rank, c)
+        })
+        .collect();
+    ranked.reverse();
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter()
+}
+
+/// Position of a child element of a Feature in the sequence required by the KML schema, with
+/// `0` before `description`, `1` before `styleUrl`, and the rest following it. Unknown elements,
+/// like extensions, are placed last
+fn feature_child_rank(name: &str) -> u8 {
+    match name {
+        "visibility" | "open" | "author" | "link" | "address" | "AddressDetails"
+        | "phoneNumber" | "Snippet" | "snippet" => 0,
+        "Camera" | "LookAt" | "TimeStamp" | "TimeSpan" => 1,
+        "Style" | "StyleMap" => 2,
+        "Region" => 3,
+        "Metadata" | "ExtendedData" => 4,
+        _ => 5,
+    }
+}
+
+/// Whether `content` is well-formed XML containing elements, like the inner XML of HTML markup
+/// read from `BalloonStyle` text, so it can be written verbatim
+fn is_inner_xml(content: &str) -> bool {
+    let mut reader = quick_xml::Reader::from_str(content);
+    let mut depth: usize = 0;
+    let mut has_elements = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(_)) => {
+                depth += 1;
+                has_elements = true;
+            }
+            Ok(Event::Empty(_)) => has_elements = true,
+            Ok(Event::End(_)) => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            Ok(Event::Text(e)) if e.unescape().is_err() => return false,
+            Ok(Event::Text(_) | Event::CData(_) | Event::Comment(_)) => {}
+            Ok(Event::Eof) => return has_elements && depth == 0,
+            Ok(_) | Err(_) => return false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types;
+    use crate::types::{self, KmlDocument, Units, Vec2};
 
     #[test]
     fn test_write_point() {
@@ -730,10 +2810,10 @@ mod tests {
                 y: 1.,
                 z: Some(1.),
             },
-            altitude_mode: types::AltitudeMode::RelativeToGround,
+            altitude_mode: Some(types::AltitudeMode::RelativeToGround),
             ..Default::default()
         });
-        assert_eq!("<Point><extrude>0</extrude><altitudeMode>relativeToGround</altitudeMode><coordinates>1,1,1</coordinates></Point>", kml.to_string());
+        assert_eq!("<Point><altitudeMode>relativeToGround</altitudeMode><coordinates>1,1,1</coordinates></Point>", kml.to_string());
     }
 
     #[test]
@@ -808,6 +2888,7 @@ mod tests {
             target_href: Some("../images/foo1.jpg".to_string()),
             source_href: Some("in-geometry-file/foo1.jpg".to_string()),
             attrs: alias1_attrs,
+            namespace: None,
         };
 
         // Alias 2
@@ -818,6 +2899,7 @@ mod tests {
             target_href: Some("../images/foo2.jpg".to_string()),
             source_href: Some("in-geometry-file/foo2.jpg".to_string()),
             attrs: alias2_attrs,
+            namespace: None,
         };
 
         // ResourceMap
@@ -827,6 +2909,7 @@ mod tests {
         let kml: Kml<f64> = Kml::ResourceMap(ResourceMap {
             aliases: vec![alias1, alias2],
             attrs: resource_map_attrs,
+            namespace: None,
         });
 
         let expected_string = "<ResourceMap id=\"ResourceMap ID\">\
@@ -848,6 +2931,7 @@ mod tests {
             Kml::ResourceMap::<f64>(ResourceMap {
                 aliases: Vec::new(),
                 attrs: HashMap::new(),
+                namespace: None,
             })
             .to_string()
         );
@@ -862,6 +2946,7 @@ mod tests {
             target_href: Some("../images/foo.jpg".to_string()),
             source_href: Some("in-geometry-file/foo.jpg".to_string()),
             attrs,
+            namespace: None,
         });
 
         let expected_string = "<Alias id=\"Some ID\">\
@@ -882,6 +2967,7 @@ mod tests {
                         .iter()
                         .cloned()
                         .collect(),
+                    namespace: None,
                 },
                 SimpleData {
                     name: "TrailLength".to_string(),
@@ -890,6 +2976,7 @@ mod tests {
                         .iter()
                         .cloned()
                         .collect(),
+                    namespace: None,
                 },
             ],
             arrays: vec![
@@ -900,6 +2987,7 @@ mod tests {
                         .iter()
                         .cloned()
                         .collect(),
+                    namespace: None,
                 },
                 SimpleArrayData {
                     name: "heartrate".to_string(),
@@ -907,10 +2995,12 @@ mod tests {
                     ..Default::default()
                 },
             ],
-            attrs: [("schemaUrl".to_string(), "#TrailHeadTypeId".to_string())]
+            schema_url: Some("#TrailHeadTypeId".to_string()),
+            attrs: [("schemaUrl".to_string(), "#Duplicate".to_string())]
                 .iter()
                 .cloned()
                 .collect(),
+            namespace: None,
         });
 
         let expected_string = "<SchemaData schemaUrl=\"#TrailHeadTypeId\">\
@@ -986,7 +3076,7 @@ mod tests {
                         z: Some(0.),
                     },
                 ],
-                tessellate: true,
+                tessellate: Some(true),
                 ..Default::default()
             },
             inner: vec![],
@@ -994,7 +3084,7 @@ mod tests {
         });
 
         assert_eq!(
-            r#"<Polygon><extrude>0</extrude><tessellate>0</tessellate><altitudeMode>clampToGround</altitudeMode><outerBoundaryIs><LinearRing><extrude>0</extrude><tessellate>1</tessellate><altitudeMode>clampToGround</altitudeMode><coordinates>-1,2,0
+            r#"<Polygon><outerBoundaryIs><LinearRing><tessellate>1</tessellate><coordinates>-1,2,0
 -1.5,3,0
 -1.5,2,0
 -1,2,0</coordinates></LinearRing></outerBoundaryIs></Polygon>"#,
@@ -1002,6 +3092,1276 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_balloon_style_text_markup() {
+        let kml: Kml = Kml::BalloonStyle(BalloonStyle {
+            text: Some("<h3>$[name]</h3> <p>A &amp; B<br/></p>".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text><h3>$[name]</h3> <p>A &amp; B<br/></p></text></BalloonStyle>",
+            kml.to_string()
+        );
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text><![CDATA[<h3>$[name]</h3> <p>A &amp; B<br/></p>]]></text></BalloonStyle>",
+            kml.to_kml_string_with(KmlWriterConfig::default().cdata(true))
+                .unwrap()
+        );
+
+        // Markup is written back byte for byte
+        let kml_str = "<BalloonStyle><text><b>$[name]</b> &amp; x</text></BalloonStyle>";
+        let kml: Kml = kml_str.parse().unwrap();
+        assert_eq!(
+            kml.to_kml_string_with(KmlWriterConfig::default().omit_defaults(true))
+                .unwrap(),
+            kml_str
+        );
+
+        let kml: Kml = Kml::BalloonStyle(BalloonStyle {
+            text: Some("Fish & Chips <3".to_string()),
+            ..Default::default()
+        });
+        let kml_str = kml.to_string();
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text><![CDATA[Fish & Chips <3]]></text></BalloonStyle>",
+            kml_str
+        );
+        assert_eq!(kml, kml_str.parse::<Kml>().unwrap());
+
+        let kml: Kml = Kml::BalloonStyle(BalloonStyle {
+            text: Some("A & B".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text>A &amp; B</text></BalloonStyle>",
+            kml.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_description_markup() {
+        let kml: Kml = Kml::Placemark(Placemark {
+            description: Some("<p>A &amp; <b>B</b></p>".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<Placemark><description><![CDATA[<p>A &amp; <b>B</b></p>]]></description></Placemark>",
+            kml.to_string()
+        );
+
+        let kml: Kml = Kml::Placemark(Placemark {
+            description: Some("A & B".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<Placemark><description>A &amp; B</description></Placemark>",
+            kml.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_raw_html() {
+        let config = KmlWriterConfig::default().raw_html(true).cdata(true);
+        let kml: Kml = Kml::Placemark(Placemark {
+            description: Some("A &amp; <b>B</b>".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<Placemark><description>A &amp; <b>B</b></description></Placemark>",
+            kml.to_kml_string_with(config.clone()).unwrap()
+        );
+
+        let kml: Kml = Kml::BalloonStyle(BalloonStyle {
+            text: Some("<![CDATA[<p>$[name]</p>]]>".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text><![CDATA[<p>$[name]</p>]]></text></BalloonStyle>",
+            kml.to_kml_string_with(config).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_placemark_schema_order() {
+        let element = |name: &str| Element {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let kml: Kml = Kml::Placemark(Placemark {
+            name: Some("A".to_string()),
+            description: Some("B".to_string()),
+            geometry: Some(Geometry::Point(Point::new(1., 1., None))),
+            style_url: Some("#s".to_string()),
+            children: vec![
+                element("ExtendedData").into(),
+                element("TimeStamp").into(),
+                element("Style").into(),
+                element("visibility").into(),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            "<Placemark><name>A</name><visibility></visibility><description>B</description><TimeStamp></TimeStamp>\
+             <styleUrl>#s</styleUrl><Style></Style><ExtendedData></ExtendedData>\
+             <Point><coordinates>1,1</coordinates></Point></Placemark>",
+            kml.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_container_schema_order() {
+        let element = |name: &str| Element {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let kml: Kml = Kml::Folder(Folder {
+            name: Some("A".to_string()),
+            description: Some("B".to_string()),
+            style_url: Some("#s".to_string()),
+            children: vec![
+                element("Region").into(),
+                element("TimeSpan").into(),
+                element("open").into(),
+            ],
+            elements: vec![Kml::Placemark(Placemark::default())],
+            ..Default::default()
+        });
+        assert_eq!(
+            "<Folder><name>A</name><open></open><description>B</description><TimeSpan></TimeSpan>\
+             <styleUrl>#s</styleUrl><Region></Region><Placemark></Placemark></Folder>",
+            kml.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_cdata() {
+        let config = KmlWriterConfig::default().cdata(true);
+        let kml: Kml = Kml::Placemark(Placemark {
+            description: Some("A & B".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<Placemark><description><![CDATA[A & B]]></description></Placemark>",
+            kml.to_kml_string_with(config.clone()).unwrap()
+        );
+
+        let kml: Kml = Kml::BalloonStyle(BalloonStyle {
+            text: Some("<p>A &amp; B</p>".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text><![CDATA[<p>A &amp; B</p>]]></text></BalloonStyle>",
+            kml.to_kml_string_with(config.clone()).unwrap()
+        );
+
+        // Can't be nested in a CDATA section
+        let kml: Kml = Kml::BalloonStyle(BalloonStyle {
+            text: Some("<p>a]]>b</p>".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<BalloonStyle><textColor>ff000000</textColor><text>&lt;p&gt;a]]&gt;b&lt;/p&gt;</text></BalloonStyle>",
+            kml.to_kml_string_with(config).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_namespaced_elements() {
+        let author = Element {
+            name: "author".to_string(),
+            prefix: Some("atom".to_string()),
+            namespace: Some("http://www.w3.org/2005/Atom".to_string()),
+            children: vec![Node::Element(Element {
+                name: "name".to_string(),
+                prefix: Some("atom".to_string()),
+                namespace: Some("http://www.w3.org/2005/Atom".to_string()),
+                content: Some("Author".to_string()),
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+        assert_eq!(
+            r#"<atom:author xmlns:atom="http://www.w3.org/2005/Atom"><atom:name>Author</atom:name></atom:author>"#,
+            Kml::<f64>::Element(author.clone()).to_string()
+        );
+
+        let kml: Kml = Kml::KmlDocument(KmlDocument {
+            attrs: HashMap::from([(
+                "xmlns:atom".to_string(),
+                "http://www.w3.org/2005/Atom".to_string(),
+            )]),
+            elements: vec![Kml::Element(author)],
+            ..Default::default()
+        });
+        assert_eq!(
+            r#"<kml xmlns:atom="http://www.w3.org/2005/Atom"><atom:author><atom:name>Author</atom:name></atom:author></kml>"#,
+            kml.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_registered_writers() {
+        #[derive(Debug)]
+        struct Rating(u8);
+
+        impl KmlExtension for Rating {
+            fn to_element(&self) -> Element {
+                Element {
+                    name: "Rating".to_string(),
+                    content: Some(self.0.to_string()),
+                    ..Default::default()
+                }
+            }
+        }
+
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Extension(Extension::new(Rating(4))),
+                Kml::Element(Element {
+                    name: "Note".to_string(),
+                    content: Some("a".to_string()),
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        });
+        let write = |config: KmlWriterConfig| {
+            let mut buf = Vec::new();
+            KmlWriter::from_writer_with_config(&mut buf, config)
+                .write(&kml)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert_eq!(
+            write(KmlWriterConfig::default()),
+            "<Document><Rating>4</Rating><Note>a</Note></Document>"
+        );
+
+        let config = KmlWriterConfig::default().register_writer("Note", |e, w| {
+            w.create_element("v:Note")
+                .with_attribute(("xmlns:v", "http://example.com/v"))
+                .write_text_content(BytesText::new(e.content.as_deref().unwrap_or_default()))?;
+            Ok(())
+        });
+        assert_eq!(
+            write(config.clone()),
+            r#"<Document><Rating>4</Rating><v:Note xmlns:v="http://example.com/v">a</v:Note></Document>"#
+        );
+
+        let config = config.register_extension_writer(|r: &Rating, w| {
+            w.create_element("Rating")
+                .with_attribute(("stars", r.0.to_string().as_str()))
+                .write_empty()?;
+            Ok(())
+        });
+        assert_eq!(
+            write(config),
+            r#"<Document><Rating stars="4"/><v:Note xmlns:v="http://example.com/v">a</v:Note></Document>"#
+        );
+    }
+
+    #[test]
+    fn test_write_kml_document_version() {
+        let kml: Kml = Kml::KmlDocument(KmlDocument {
+            version: KmlVersion::V22,
+            ..Default::default()
+        });
+        assert_eq!(
+            r#"<kml xmlns="http://www.opengis.net/kml/2.2"></kml>"#,
+            kml.to_string()
+        );
+
+        // Typed elements are written without a prefix, so documents only declaring KML for a
+        // prefix also get a default namespace
+        let kml: Kml = r#"<kml:kml xmlns:kml="http://www.opengis.net/kml/2.2"><kml:Placemark><kml:name>a</kml:name></kml:Placemark></kml:kml>"#
+            .parse()
+            .unwrap();
+        let written = kml.to_string();
+        assert!(written.contains(r#" xmlns="http://www.opengis.net/kml/2.2""#));
+        assert!(written.contains(r#" xmlns:kml="http://www.opengis.net/kml/2.2""#));
+        assert!(written.ends_with("><Placemark><name>a</name></Placemark></kml>"));
+        assert_eq!(written.parse::<Kml>().unwrap(), kml);
+    }
+
+    #[test]
+    fn test_write_omitted_geometry_flags() {
+        for kml_str in [
+            "<Point><coordinates>1,1</coordinates></Point>",
+            "<Point><extrude>0</extrude><coordinates>1,1</coordinates></Point>",
+            "<LineString><tessellate>1</tessellate><coordinates>1,1\n2,2</coordinates></LineString>",
+            "<LineString><altitudeMode>absolute</altitudeMode><coordinates>1,1\n2,2</coordinates></LineString>",
+        ] {
+            let kml: Kml = kml_str.parse().unwrap();
+            assert_eq!(kml.to_string(), kml_str);
+        }
+    }
+
+    #[test]
+    fn test_write_document() {
+        let write = |kml: &Kml, config: KmlWriterConfig| {
+            let mut buf = Vec::new();
+            KmlWriter::from_writer_with_config(&mut buf, config)
+                .write_document(kml)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let decl = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+        let kml = Kml::KmlDocument(KmlDocument::default());
+        assert_eq!(
+            write(&kml, KmlWriterConfig::default()),
+            format!(r#"{decl}<kml xmlns="http://www.opengis.net/kml/2.2"></kml>"#)
+        );
+
+        let kml = Kml::Document(Document {
+            elements: vec![Kml::Track(Track::default())],
+            ..Default::default()
+        });
+        let config = KmlWriterConfig {
+            version: KmlVersion::V23,
+            ..Default::default()
+        };
+        assert_eq!(
+            write(&kml, config),
+            format!(
+                r#"{decl}<kml xmlns="http://www.opengis.net/kml/2.3"><Document><Track></Track></Document></kml>"#
+            )
+        );
+
+        let kml = Kml::KmlDocument(KmlDocument {
+            attrs: HashMap::from([(
+                "xmlns".to_string(),
+                "http://earth.google.com/kml/2.1".to_string(),
+            )]),
+            ..Default::default()
+        });
+        assert_eq!(
+            write(&kml, KmlWriterConfig::default()),
+            format!(r#"{decl}<kml xmlns="http://earth.google.com/kml/2.1"></kml>"#)
+        );
+    }
+
+    #[test]
+    fn test_write_version() {
+        let config = KmlWriterConfig {
+            version: KmlVersion::V23,
+            ..Default::default()
+        };
+        let kml: Kml = Kml::KmlDocument(KmlDocument {
+            version: KmlVersion::V22,
+            elements: vec![Kml::Track(Track::default())],
+            ..Default::default()
+        });
+        assert_eq!(
+            kml.to_kml_string_with(config.clone()).unwrap(),
+            r#"<kml xmlns="http://www.opengis.net/kml/2.3"><Track></Track></kml>"#
+        );
+
+        // A KML 2.2 document is written with the KML 2.3 namespace, promoting its gx elements
+        let kml: Kml = r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+            <gx:Track><when>2010-05-28T02:02:09Z</when></gx:Track>
+        </kml>"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            kml.to_kml_string_with(config.clone()).unwrap(),
+            concat!(
+                r#"<kml xmlns="http://www.opengis.net/kml/2.3" xmlns:gx="http://www.google.com/kml/ext/2.2">"#,
+                "<Track><when>2010-05-28T02:02:09Z</when></Track></kml>"
+            )
+        );
+        let kml: Kml = include_str!("../tests/fixtures/sample.kml")
+            .parse()
+            .unwrap();
+        let written = kml.to_kml_string_with(config).unwrap();
+        let Kml::KmlDocument(document) = written.parse::<Kml>().unwrap() else {
+            panic!("expected KmlDocument");
+        };
+        assert_eq!(document.version, KmlVersion::V23);
+        assert_eq!(
+            document.attrs["xmlns"],
+            KmlVersion::V23.namespace().unwrap()
+        );
+
+        let tour = Element {
+            name: "Tour".to_string(),
+            namespace: Some("http://www.opengis.net/kml/2.3".to_string()),
+            ..Default::default()
+        };
+        let kml: Kml = Kml::KmlDocument(KmlDocument {
+            version: KmlVersion::V23,
+            elements: vec![Kml::Element(tour)],
+            ..Default::default()
+        });
+        assert!(kml.to_string().contains("<Tour></Tour>"));
+        let config = KmlWriterConfig {
+            version: KmlVersion::V22,
+            ..Default::default()
+        };
+        assert!(matches!(
+            kml.to_kml_string_with(config),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_root() {
+        let elements: Vec<Kml> = vec![
+            Kml::Placemark(Placemark::default()),
+            Kml::Tour(Tour::default()),
+        ];
+        let mut buf = Vec::new();
+        KmlWriter::from_writer(&mut buf)
+            .write_root(KmlVersion::V23, &HashMap::new(), &elements)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.3">"#,
+                "<Placemark></Placemark><Tour><Playlist></Playlist></Tour></kml>"
+            )
+        );
+
+        let attrs = HashMap::from([(
+            "xmlns".to_string(),
+            "http://www.opengis.net/kml/2.2".to_string(),
+        )]);
+        let mut buf = Vec::new();
+        KmlWriter::from_writer(&mut buf)
+            .write_root(KmlVersion::Unknown, &attrs, &elements[..1])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.2"><Placemark></Placemark></kml>"#
+        );
+    }
+
+    #[test]
+    fn test_write_precision() {
+        let write = |kml: &Kml, precision| {
+            let mut buf = Vec::new();
+            let config = KmlWriterConfig::default().precision(precision);
+            KmlWriter::from_writer_with_config(&mut buf, config)
+                .write(kml)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let kml = Kml::LineString(LineString::from(vec![
+            Coord::new(-1.23456789, 1.0000001, Some(-0.0001)),
+            Coord::new(100., 2.6, None),
+        ]));
+        assert_eq!(
+            write(&kml, Some(3)),
+            "<LineString><coordinates>-1.235,1,0\n100,2.6</coordinates></LineString>"
+        );
+        assert_eq!(
+            write(&kml, Some(0)),
+            "<LineString><coordinates>-1,1,0\n100,3</coordinates></LineString>"
+        );
+        assert_eq!(
+            write(&kml, None),
+            "<LineString><coordinates>-1.23456789,1.0000001,-0.0001\n100,2.6</coordinates></LineString>"
+        );
+
+        let kml = Kml::Track(Track {
+            coords: vec![Coord::new(1.23456, 2., Some(3.))],
+            ..Default::default()
+        });
+        assert_eq!(
+            write(&kml, Some(2)),
+            r#"<gx:Track xmlns:gx="http://www.google.com/kml/ext/2.2"><gx:coord>1.23 2 3</gx:coord></gx:Track>"#
+        );
+    }
+
+    #[test]
+    fn test_write_coord_transform() {
+        let track = Track {
+            coords: vec![Coord::new(1.2, 3.7, Some(1.))],
+            ..Default::default()
+        };
+        let kml: Kml = Kml::Track(track.clone());
+        let mut buf = Vec::new();
+        KmlWriter::from_writer(&mut buf)
+            .with_coord_transform(|c: Coord| Coord::new(c.x.round(), c.y.round(), None))
+            .write(&kml)
+            .unwrap();
+        assert!(String::from_utf8(buf)
+            .unwrap()
+            .contains("<gx:coord>1 4</gx:coord>"));
+        assert_eq!(kml, Kml::Track(track));
+    }
+
+    #[test]
+    #[cfg(feature = "geo")]
+    fn test_write_simplify() {
+        let config = KmlWriterConfig::default().simplify(Some(0.5));
+        let kml: Kml = Kml::LineString(LineString::from(vec![
+            Coord::new(0., 0., None),
+            Coord::new(1., 0.1, None),
+            Coord::new(2., -0.1, None),
+            Coord::new(3., 5., Some(1.)),
+            Coord::new(4., 6., None),
+            Coord::new(5., 7., None),
+        ]));
+        assert_eq!(
+            kml.to_kml_string_with(config.clone()).unwrap(),
+            "<LineString><coordinates>0,0\n2,-0.1\n3,5,1\n5,7</coordinates></LineString>"
+        );
+
+        // Closed rings keep their first and last coordinates
+        let ring: Kml = Kml::LinearRing(LinearRing {
+            coords: vec![
+                Coord::new(0., 0., None),
+                Coord::new(2., 0., None),
+                Coord::new(2., 1., None),
+                Coord::new(2., 2., Some(5.)),
+                Coord::new(0., 2., None),
+                Coord::new(0., 0., None),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            ring.to_kml_string_with(config.clone()).unwrap(),
+            "<LinearRing><coordinates>0,0\n2,0\n2,2,5\n0,2\n0,0</coordinates></LinearRing>"
+        );
+
+        // Simplifying would collapse the ring
+        let ring = LinearRing {
+            coords: vec![
+                Coord::new(0., 0., None),
+                Coord::new(0.1, 0.1, None),
+                Coord::new(0.2, 0., None),
+                Coord::new(0., 0., None),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            Kml::LinearRing(ring.clone())
+                .to_kml_string_with(config)
+                .unwrap(),
+            Kml::LinearRing(ring).to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_coord_layout() {
+        let kml: Kml = Kml::LineString(LineString::from(vec![
+            Coord::new(1., 1., None),
+            Coord::new(2., 2., None),
+            Coord::new(3., 3., Some(3.)),
+        ]));
+        let write = |coord_layout| {
+            let config = KmlWriterConfig::default().coord_layout(coord_layout);
+            kml.to_kml_string_with(config).unwrap()
+        };
+        assert_eq!(
+            write(CoordLayout::Newline),
+            "<LineString><coordinates>1,1\n2,2\n3,3,3</coordinates></LineString>"
+        );
+        assert_eq!(
+            write(CoordLayout::Space),
+            "<LineString><coordinates>1,1 2,2 3,3,3</coordinates></LineString>"
+        );
+        assert_eq!(
+            write(CoordLayout::Wrap(2)),
+            "<LineString><coordinates>1,1 2,2\n3,3,3</coordinates></LineString>"
+        );
+    }
+
+    #[test]
+    fn test_write_altitudes() {
+        let kml: Kml = Kml::LineString(LineString::from(vec![
+            Coord::new(1., 1., None),
+            Coord::new(2., 2., Some(0.)),
+            Coord::new(3., 3., Some(3.)),
+        ]));
+        let write = |altitudes| {
+            let config = KmlWriterConfig::default().altitudes(altitudes);
+            kml.to_kml_string_with(config).unwrap()
+        };
+        assert_eq!(
+            write(Altitudes::Keep),
+            "<LineString><coordinates>1,1\n2,2,0\n3,3,3</coordinates></LineString>"
+        );
+        assert_eq!(
+            write(Altitudes::Fill),
+            "<LineString><coordinates>1,1,0\n2,2,0\n3,3,3</coordinates></LineString>"
+        );
+        assert_eq!(
+            write(Altitudes::DropZero),
+            "<LineString><coordinates>1,1\n2,2\n3,3,3</coordinates></LineString>"
+        );
+    }
+
+    #[test]
+    fn test_write_comments() {
+        let kml: Kml = Kml::Folder(Folder {
+            elements: vec![
+                Kml::Comment(" Generated ".to_string()),
+                Kml::Element(Element {
+                    name: "ExtendedData".to_string(),
+                    children: vec![Node::Comment("b".to_string())],
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            kml.to_string(),
+            "<Folder><!-- Generated --><ExtendedData><!--b--></ExtendedData></Folder>"
+        );
+
+        assert_eq!(
+            Kml::<f64>::Comment(" a -- b --- c-".to_string()).to_string(),
+            "<!-- a - - b - - - c- -->"
+        );
+    }
+
+    #[test]
+    fn test_write_self_closing() {
+        let kml: Kml = Kml::IconStyle(IconStyle {
+            hot_spot: Some(Vec2 {
+                x: 0.5,
+                y: 0.,
+                xunits: Units::Fraction,
+                yunits: Units::Pixels,
+            }),
+            icon: Some(Icon::default()),
+            ..Default::default()
+        });
+        let config = KmlWriterConfig::default()
+            .self_closing(true)
+            .omit_defaults(true);
+        let written = kml.to_kml_string_with(config).unwrap();
+        assert!(written.starts_with("<IconStyle><Icon><href/></Icon><hotSpot "));
+        assert!(written.ends_with("/></IconStyle>"));
+
+        let kml: Kml = Kml::Placemark(Placemark {
+            children: vec![Node::Element(Element {
+                name: "visibility".to_string(),
+                content: Some("0".to_string()),
+                ..Default::default()
+            })],
+            geometry: Some(Geometry::Point(Point {
+                extrude: Some(false),
+                ..Point::new(1., 1., None)
+            })),
+            ..Default::default()
+        });
+        let config = KmlWriterConfig::default().self_closing(true);
+        assert_eq!(
+            kml.to_kml_string_with(config).unwrap(),
+            "<Placemark><visibility>0</visibility><Point><extrude>0</extrude><coordinates>1,1</coordinates></Point></Placemark>"
+        );
+    }
+
+    #[test]
+    fn test_write_omit_defaults() {
+        let write = |kml: &Kml, omit_defaults| {
+            let mut buf = Vec::new();
+            let config = KmlWriterConfig::default().omit_defaults(omit_defaults);
+            KmlWriter::from_writer_with_config(&mut buf, config)
+                .write(kml)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let kml = Kml::Style(Style {
+            line: Some(LineStyle::default()),
+            poly: Some(PolyStyle {
+                fill: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(
+            write(&kml, true),
+            "<Style><LineStyle></LineStyle><PolyStyle><fill>false</fill></PolyStyle></Style>"
+        );
+        assert_eq!(
+            write(&kml, false),
+            "<Style><LineStyle><color>ffffffff</color><colorMode>normal</colorMode><width>1</width></LineStyle>\
+             <PolyStyle><color>ffffffff</color><colorMode>normal</colorMode><fill>false</fill><outline>true</outline></PolyStyle></Style>"
+        );
+
+        let kml: Kml = "<BalloonStyle><text>a</text></BalloonStyle>"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            write(&kml, true),
+            "<BalloonStyle><text>a</text></BalloonStyle>"
+        );
+
+        let kml = Kml::LineString(LineString {
+            coords: vec![Coord::new(1., 1., None)],
+            extrude: Some(false),
+            tessellate: Some(true),
+            altitude_mode: Some(types::AltitudeMode::ClampToGround),
+            ..Default::default()
+        });
+        assert_eq!(
+            write(&kml, true),
+            "<LineString><tessellate>1</tessellate><coordinates>1,1</coordinates></LineString>"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_to_file() {
+        let path = std::env::temp_dir().join(format!("kml-test-{}.kml", std::process::id()));
+        let kml = Kml::Point(Point::new(1.23456, 1., None));
+        let config = KmlWriterConfig::default().precision(Some(2));
+        KmlWriter::to_file(&path, &kml, config.clone()).unwrap();
+        let written = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            written.unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.2"><Point><coordinates>1.23,1</coordinates></Point></kml>"#
+        );
+        assert_eq!(
+            kml.to_kml_string_with(config).unwrap(),
+            "<Point><coordinates>1.23,1</coordinates></Point>"
+        );
+    }
+
+    #[test]
+    fn test_display_alternate() {
+        let kml: Kml = Kml::Folder(Folder {
+            elements: vec![Kml::Placemark(Placemark {
+                name: Some("Pin".to_string()),
+                geometry: Some(Geometry::Point(Point::new(1., 1., None))),
+                ..Default::default()
+            })],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            kml.to_string(),
+            "<Folder><Placemark><name>Pin</name><Point><coordinates>1,1</coordinates></Point></Placemark></Folder>"
+        );
+        assert_eq!(
+            format!("{kml:#}"),
+            concat!(
+                "<Folder>\n",
+                "  <Placemark>\n",
+                "    <name>Pin</name>\n",
+                "    <Point>\n",
+                "      <coordinates>1,1</coordinates>\n",
+                "    </Point>\n",
+                "  </Placemark>\n",
+                "</Folder>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_incrementally() {
+        let mut buf = Vec::new();
+        let mut writer = KmlWriter::<_, f64>::from_writer(&mut buf);
+        assert!(writer.start_folder("Early").is_err());
+        writer.start_document().unwrap();
+        assert!(writer.start_document().is_err());
+        assert!(writer.end_folder().is_err());
+        writer.start_folder("Outer").unwrap();
+        writer
+            .write_feature(&Placemark {
+                name: Some("1".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        writer.start_folder("Inner").unwrap();
+        writer.write(&Kml::Track(Track::default())).unwrap();
+        writer.end_folder().unwrap();
+        writer.end_document().unwrap();
+        assert!(writer.end_document().is_err());
+
+        let written = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            written,
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document>"#,
+                "<Folder><name>Outer</name><Placemark><name>1</name></Placemark>",
+                r#"<Folder><name>Inner</name><gx:Track xmlns:gx="http://www.google.com/kml/ext/2.2"></gx:Track></Folder>"#,
+                "</Folder></Document></kml>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_custom_feature() {
+        struct Stop {
+            id: u32,
+            coord: (f64, f64),
+        }
+
+        impl ToKml for Stop {
+            fn write_to<W: Write>(&self, writer: &mut KmlWriter<W>) -> Result<(), Error> {
+                let id = self.id.to_string();
+                writer.start_element("Placemark", [("id", id.as_str())])?;
+                writer.write_text_element("name", &format!("Stop {id}"))?;
+                Geometry::Point(Point::new(self.coord.0, self.coord.1, None)).write_to(writer)?;
+                writer.end_element("Placemark")
+            }
+        }
+
+        let mut buf = Vec::new();
+        let mut writer = KmlWriter::from_writer_with_config(
+            &mut buf,
+            KmlWriterConfig::default().precision(Some(1)),
+        );
+        assert!(writer
+            .write_feature(&Stop {
+                id: 0,
+                coord: (0., 0.)
+            })
+            .is_err());
+        writer.start_document().unwrap();
+        for (id, coord) in [(1, (1.23, 4.56)), (2, (7., 8.))] {
+            writer.write_feature(&Stop { id, coord }).unwrap();
+        }
+        writer.end_document().unwrap();
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document>"#,
+                r#"<Placemark id="1"><name>Stop 1</name><Point><coordinates>1.2,4.6</coordinates></Point></Placemark>"#,
+                r#"<Placemark id="2"><name>Stop 2</name><Point><coordinates>7,8</coordinates></Point></Placemark>"#,
+                "</Document></kml>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_duplicate_style_ids() {
+        let style = |id: &str| {
+            Kml::Style(Style {
+                id: Some(id.to_string()),
+                ..Default::default()
+            })
+        };
+        let placemark = |style_url: &str| {
+            Kml::Placemark(Placemark {
+                style_url: Some(style_url.to_string()),
+                ..Default::default()
+            })
+        };
+        let folder = |elements| {
+            Kml::Folder(Folder {
+                elements,
+                ..Default::default()
+            })
+        };
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                folder(vec![style("a"), placemark("#a")]),
+                folder(vec![
+                    style("a"),
+                    style("a-2"),
+                    folder(vec![placemark("#a")]),
+                ]),
+                placemark("#a"),
+            ],
+            ..Default::default()
+        });
+        let write = |duplicate_style_ids| {
+            let config = KmlWriterConfig::default().duplicate_style_ids(duplicate_style_ids);
+            kml.to_kml_string_with(config)
+        };
+
+        assert_eq!(write(DuplicateStyleIds::Keep).unwrap(), kml.to_string());
+        assert!(matches!(
+            write(DuplicateStyleIds::Error),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(
+            write(DuplicateStyleIds::Rename).unwrap(),
+            Kml::Document(Document {
+                elements: vec![
+                    folder(vec![style("a"), placemark("#a")]),
+                    folder(vec![
+                        style("a-2"),
+                        style("a-2-2"),
+                        folder(vec![placemark("#a-2")])
+                    ]),
+                    placemark("#a"),
+                ],
+                ..Default::default()
+            })
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_inline_styles() {
+        let style = |id: Option<&str>, color: &str| Style {
+            id: id.map(str::to_string),
+            line: Some(LineStyle {
+                color: color.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let style_map = |id: &str, normal: &str| {
+            Kml::StyleMap(StyleMap {
+                id: Some(id.to_string()),
+                pairs: vec![
+                    Pair {
+                        key: StyleState::Highlight,
+                        style_url: "#blue".to_string(),
+                        ..Default::default()
+                    },
+                    Pair {
+                        key: StyleState::Normal,
+                        style_url: normal.to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            })
+        };
+        let placemark = |style_url: Option<&str>, children: Vec<Element>| {
+            Kml::Placemark(Placemark {
+                style_url: style_url.map(str::to_string),
+                children: children.into_iter().map(Node::from).collect(),
+                ..Default::default()
+            })
+        };
+        let inline_style = Element {
+            name: "Style".to_string(),
+            ..Default::default()
+        };
+        let shared = [
+            Kml::Style(style(Some("red"), "ff0000ff")),
+            Kml::Style(style(Some("blue"), "ffff0000")),
+            style_map("map", "#red"),
+            style_map("nested", "#map"),
+            style_map("cycle", "#cycle-2"),
+            style_map("cycle-2", "#cycle"),
+        ];
+        let document = |placemarks: Vec<Kml>| {
+            Kml::Document(Document {
+                elements: shared.iter().cloned().chain(placemarks).collect(),
+                ..Default::default()
+            })
+        };
+        let kml = document(vec![Kml::Folder(Folder {
+            elements: vec![
+                placemark(Some("#red"), vec![]),
+                placemark(Some("#nested"), vec![]),
+                placemark(Some("#cycle"), vec![]),
+                placemark(Some("other.kml#red"), vec![]),
+                placemark(Some("#red"), vec![inline_style.clone()]),
+            ],
+            ..Default::default()
+        })]);
+        let config = KmlWriterConfig::default().inline_styles(true);
+
+        let mut buf = Vec::new();
+        KmlWriter::<_, f64>::from_writer(&mut buf)
+            .write_style(&style(None, "ff0000ff"))
+            .unwrap();
+        let inlined = format!("<Placemark>{}</Placemark>", String::from_utf8(buf).unwrap());
+        let expected = document(vec![Kml::Folder(Folder {
+            elements: vec![
+                placemark(None, vec![]),
+                placemark(None, vec![]),
+                placemark(Some("#cycle"), vec![]),
+                placemark(Some("other.kml#red"), vec![]),
+                placemark(Some("#red"), vec![inline_style]),
+            ],
+            ..Default::default()
+        })])
+        .to_string()
+        .replacen(
+            "<Placemark></Placemark><Placemark></Placemark>",
+            &inlined.repeat(2),
+            1,
+        );
+
+        assert_eq!(kml.to_kml_string_with(config).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_share_styles() {
+        let style = |color: &str| Element {
+            name: "Style".to_string(),
+            children: vec![Node::Element(Element {
+                name: "LineStyle".to_string(),
+                children: vec![Node::Element(Element {
+                    name: "color".to_string(),
+                    content: Some(color.to_string()),
+                    ..Default::default()
+                })],
+                ..Default::default()
+            })],
+            ..Default::default()
+        };
+        let placemark = |name: &str, style_url: Option<&str>, children: Vec<Element>| {
+            Kml::Placemark(Placemark {
+                name: Some(name.to_string()),
+                style_url: style_url.map(str::to_string),
+                children: children.into_iter().map(Node::from).collect(),
+                ..Default::default()
+            })
+        };
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Style(Style {
+                    id: Some("style-1".to_string()),
+                    ..Default::default()
+                }),
+                placemark("a", None, vec![style("ff0000ff")]),
+                Kml::Folder(Folder {
+                    elements: vec![placemark("b", None, vec![style("ff0000ff")])],
+                    ..Default::default()
+                }),
+                placemark("c", None, vec![style("ff00ff00")]),
+                placemark("d", Some("#style-1"), vec![style("ff0000ff")]),
+            ],
+            ..Default::default()
+        });
+        let config = KmlWriterConfig::default().share_styles(true);
+
+        assert_eq!(
+            kml.to_kml_string_with(config).unwrap(),
+            concat!(
+                r#"<Document><Style id="style-1"></Style>"#,
+                r#"<Style id="style-2"><LineStyle><color>ff0000ff</color></LineStyle></Style>"#,
+                "<Placemark><name>a</name><styleUrl>#style-2</styleUrl></Placemark>",
+                "<Folder><Placemark><name>b</name><styleUrl>#style-2</styleUrl></Placemark></Folder>",
+                "<Placemark><name>c</name><Style><LineStyle><color>ff00ff00</color></LineStyle></Style></Placemark>",
+                "<Placemark><name>d</name><styleUrl>#style-1</styleUrl>",
+                "<Style><LineStyle><color>ff0000ff</color></LineStyle></Style></Placemark></Document>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_gx_namespace() {
+        let placemark = Placemark {
+            geometry: Some(Geometry::MultiGeometry(MultiGeometry::new(vec![
+                Geometry::<f64>::Track(Track::default()),
+            ]))),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        KmlWriter::from_writer(&mut buf)
+            .write_document(&Kml::Placemark(placemark))
+            .unwrap();
+        // Attributes of the root are written in any order
+        let written = String::from_utf8(buf).unwrap();
+        assert!(written.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?><kml "#));
+        assert!(written.contains(r#" xmlns:gx="http://www.google.com/kml/ext/2.2""#));
+        assert!(written.ends_with(
+            "><Placemark><MultiGeometry><gx:Track></gx:Track></MultiGeometry></Placemark></kml>"
+        ));
+
+        // The prefix is rebound on gx elements written where an ancestor binds it to another
+        // namespace, like documents read with `ignore_namespaces`
+        let kml: Kml = Kml::Folder(Folder {
+            attrs: HashMap::from([("xmlns:gx".to_string(), "http://example.com".to_string())]),
+            elements: vec![Kml::Tour(Tour::default())],
+            ..Default::default()
+        });
+        assert_eq!(
+            kml.to_string(),
+            r#"<Folder xmlns:gx="http://example.com"><gx:Tour xmlns:gx="http://www.google.com/kml/ext/2.2"><gx:Playlist></gx:Playlist></gx:Tour></Folder>"#
+        );
+        let kml_str = r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://example.com/gx"><gx:Track><when>2010-05-28T02:02:09Z</when></gx:Track></kml>"#;
+        let config = crate::reader::KmlReaderConfig::default().ignore_namespaces(true);
+        let kml = crate::KmlReader::<_, f64>::from_string_with_config(kml_str, config)
+            .read()
+            .unwrap();
+        assert!(kml
+            .to_string()
+            .contains(r#"<gx:Track xmlns:gx="http://www.google.com/kml/ext/2.2">"#));
+
+        // gx elements can't be written where their own attributes bind the prefix to something
+        // else
+        let kml: Kml = Kml::Tour(Tour {
+            attrs: HashMap::from([("xmlns:gx".to_string(), "http://example.com".to_string())]),
+            ..Default::default()
+        });
+        let mut buf = Vec::new();
+        assert!(matches!(
+            KmlWriter::from_writer(&mut buf).write(&kml),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_track() {
+        let track = Track {
+            whens: vec!["2010-05-28T02:02:09Z".to_string()],
+            coords: vec![Coord::new(-122.207881, 37.371915, Some(156.))],
+            ..Default::default()
+        };
+        assert_eq!(
+            r#"<gx:Track xmlns:gx="http://www.google.com/kml/ext/2.2"><when>2010-05-28T02:02:09Z</when><gx:coord>-122.207881 37.371915 156</gx:coord></gx:Track>"#,
+            Kml::Track(track.clone()).to_string()
+        );
+
+        let kml = Kml::KmlDocument(KmlDocument {
+            version: KmlVersion::V23,
+            elements: vec![Kml::Track(track)],
+            ..Default::default()
+        });
+        assert_eq!(
+            "<kml xmlns=\"http://www.opengis.net/kml/2.3\"><Track><when>2010-05-28T02:02:09Z</when><coord>-122.207881 37.371915 156</coord></Track></kml>",
+            kml.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_pretty() {
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Style(Style {
+                    id: Some("pin".to_string()),
+                    icon: Some(IconStyle {
+                        scale: 1.1,
+                        icon: Some(Icon {
+                            href: "pin.png".to_string(),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    line: Some(LineStyle::default()),
+                    ..Default::default()
+                }),
+                Kml::Placemark(Placemark {
+                    style_url: Some("#pin".to_string()),
+                    geometry: Some(Geometry::Point(Point {
+                        altitude_mode: Some(types::AltitudeMode::ClampToGround),
+                        ..Point::new(1., 1., Some(0.))
+                    })),
+                    ..Default::default()
+                }),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            kml.to_kml_string_with(KmlWriterConfig::pretty()).unwrap(),
+            r#"<Document>
+	<Style id="pin">
+		<IconStyle>
+			<scale>1.1</scale>
+			<Icon>
+				<href>pin.png</href>
+			</Icon>
+		</IconStyle>
+		<LineStyle/>
+	</Style>
+	<Placemark>
+		<styleUrl>#pin</styleUrl>
+		<Point>
+			<coordinates>1,1,0</coordinates>
+		</Point>
+	</Placemark>
+</Document>"#
+        );
+    }
+
+    #[test]
+    fn test_write_google_earth() {
+        let fixture = include_str!("../tests/fixtures/google-earth.kml");
+        let kml: Kml = fixture.parse().unwrap();
+        let mut buf = Vec::new();
+        KmlWriter::from_writer_with_config(&mut buf, KmlWriterConfig::google_earth())
+            .write_document(&kml)
+            .unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), fixture.trim_end());
+
+        // The kml prefix is declared with the namespace of the version being written
+        let config = KmlWriterConfig {
+            version: KmlVersion::V23,
+            ..KmlWriterConfig::google_earth()
+        };
+        for kml in [
+            Kml::Document(Document::default()),
+            Kml::KmlDocument(KmlDocument::default()),
+        ] {
+            let mut buf = Vec::new();
+            KmlWriter::<_, f64>::from_writer_with_config(&mut buf, config.clone())
+                .write_document(&kml)
+                .unwrap();
+            assert!(String::from_utf8(buf).unwrap().contains(
+                r#"<kml xmlns="http://www.opengis.net/kml/2.3" xmlns:gx="http://www.google.com/kml/ext/2.2" xmlns:kml="http://www.opengis.net/kml/2.3" "#
+            ));
+        }
+        let mut buf = Vec::new();
+        let mut writer = KmlWriter::<_, f64>::from_writer_with_config(&mut buf, config);
+        writer.start_document().unwrap();
+        writer.end_document().unwrap();
+        assert!(String::from_utf8(buf)
+            .unwrap()
+            .contains(r#" xmlns:kml="http://www.opengis.net/kml/2.3" "#));
+
+        // Styles are moved ahead of the features of each document
+        let kml: Kml = Kml::Document(Document {
+            elements: vec![
+                Kml::Placemark(Placemark::default()),
+                Kml::Style(Style::default()),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            kml.to_kml_string_with(KmlWriterConfig::default().styles_first(true))
+                .unwrap(),
+            "<Document><Style></Style><Placemark></Placemark></Document>"
+        );
+    }
+
+    #[test]
+    fn test_write_indent_config_mut() {
+        let kml: Kml = Kml::Point(Point::new(1., 1., None));
+        let mut buf = Vec::new();
+        let mut writer = KmlWriter::from_writer(&mut buf);
+        writer.write(&kml).unwrap();
+        writer.config_mut().indent = Some((b' ', 2));
+        writer.write(&kml).unwrap();
+        writer.config_mut().indent = None;
+        writer.write(&kml).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<Point><coordinates>1,1</coordinates></Point>\
+             \n<Point>\n  <coordinates>1,1</coordinates>\n</Point>\
+             <Point><coordinates>1,1</coordinates></Point>"
+        );
+
+        // Indentation picks up at the depth of the elements that are still open
+        let mut buf = Vec::new();
+        let mut writer = KmlWriter::from_writer(&mut buf);
+        writer.start_document().unwrap();
+        writer.config_mut().indent = Some((b' ', 2));
+        writer.write_feature(&Placemark::<f64>::default()).unwrap();
+        writer.end_document().unwrap();
+        assert!(String::from_utf8(buf)
+            .unwrap()
+            .ends_with("<Document>\n    <Placemark>\n    </Placemark>\n  </Document>\n</kml>"));
+    }
+
+    #[test]
+    fn test_write_icon_style() {
+        let mut icon_style = IconStyle::default();
+        assert_eq!(
+            "<IconStyle><color>ffffffff</color><colorMode>normal</colorMode><scale>1</scale><heading>0</heading></IconStyle>",
+            Kml::<f64>::IconStyle(icon_style.clone()).to_string()
+        );
+
+        icon_style.icon = Some(Icon {
+            href: "icon.png".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            "<IconStyle><color>ffffffff</color><colorMode>normal</colorMode><scale>1</scale><heading>0</heading><Icon><href>icon.png</href></Icon></IconStyle>",
+            Kml::<f64>::IconStyle(icon_style).to_string()
+        );
+    }
+
     #[test]
     fn test_write_style_map() {
         let kml: Kml = Kml::StyleMap(StyleMap {
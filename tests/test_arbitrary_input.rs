@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::Path;
+
+use kml::reader::KmlReaderConfig;
+use kml::{Kml, KmlReader};
+
+/// Small deterministic xorshift generator so failures are reproducible without extra dependencies
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
+
+const FRAGMENTS: [&[u8]; 24] = [
+    b"<Placemark>",
+    b"</Placemark>",
+    b"<Point>",
+    b"</Point>",
+    b"<coordinates>",
+    b"</coordinates>",
+    b"<Polygon><outerBoundaryIs>",
+    b"<LinearRing/>",
+    b"<MultiGeometry>",
+    b"<gx:Track><gx:coord>1 a</gx:coord>",
+    b"<Folder>",
+    b"</Document>",
+    b"<kml xmlns=\"http://www.opengis.net/kml/2.2\">",
+    b"<![CDATA[",
+    b"]]>",
+    b"<!--",
+    b"-->",
+    b"<?pi?>",
+    b"&amp;",
+    b"&#xFFFFFF;",
+    b",,,",
+    b" 1e999,-,NaN ",
+    b"<hotSpot x=\"a\" y=\"\"/>",
+    b"\xFF\xFE\x00",
+];
+
+fn mutate(rng: &mut Rng, input: &[u8]) -> Vec<u8> {
+    let mut bytes = input.to_vec();
+    for _ in 0..=rng.below(8) {
+        let at = rng.below(bytes.len() + 1);
+        match rng.below(4) {
+            0 => bytes.truncate(at),
+            1 if at < bytes.len() => bytes[at] = rng.next() as u8,
+            2 => {
+                let fragment = FRAGMENTS[rng.below(FRAGMENTS.len())];
+                bytes.splice(at..at, fragment.iter().copied());
+            }
+            _ => {
+                let end = (at + rng.below(64)).min(bytes.len());
+                bytes.drain(at..end);
+            }
+        }
+    }
+    bytes
+}
+
+fn configs() -> Vec<KmlReaderConfig> {
+    vec![
+        KmlReaderConfig::default(),
+        KmlReaderConfig::default()
+            .lenient(true)
+            .tolerant_coordinates(true)
+            .skip_invalid_features(true),
+        KmlReaderConfig::default()
+            .case_insensitive(true)
+            .ignore_namespaces(true)
+            .trim_text(false),
+        KmlReaderConfig::default()
+            .strict(true)
+            .skip_coordinates(true)
+            .max_depth(Some(4)),
+    ]
+}
+
+fn read_all_ways(bytes: &[u8]) {
+    for config in configs() {
+        let _ = KmlReader::<_, f64>::from_reader_with_config(bytes, config.clone()).read();
+        let mut reader = KmlReader::<_, f64>::from_reader_with_config(bytes, config.clone());
+        for _ in reader.features().take(10_000) {}
+        let mut reader = KmlReader::<_, f32>::from_reader_with_config(bytes, config);
+        while let Ok(Some(_)) = reader.next_event() {}
+    }
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        let _ = s.parse::<Kml>();
+    }
+}
+
+#[test]
+fn test_mutated_fixtures() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for name in [
+        "sample.kml",
+        "polygon.kml",
+        "namespaces.kml",
+        "style-merging.kml",
+    ] {
+        let input = fs::read(fixtures.join(name)).unwrap();
+        for _ in 0..200 {
+            read_all_ways(&mutate(&mut rng, &input));
+        }
+    }
+}
+
+#[test]
+fn test_random_bytes() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..500 {
+        let len = rng.below(256);
+        let bytes = (0..len)
+            .flat_map(|_| {
+                if rng.below(2) == 0 {
+                    FRAGMENTS[rng.below(FRAGMENTS.len())].to_vec()
+                } else {
+                    vec![rng.next() as u8]
+                }
+            })
+            .collect::<Vec<_>>();
+        read_all_ways(&bytes);
+    }
+}
+
+#[test]
+fn test_deeply_nested_input() {
+    for tag in ["Folder", "MultiGeometry", "Unknown", "Placemark"] {
+        let depth = 100_000;
+        let input = format!("<{tag}>").repeat(depth) + &format!("</{tag}>").repeat(depth);
+        read_all_ways(input.as_bytes());
+    }
+}
@@ -31,6 +31,8 @@ mod roundtrip_tests {
         test_sample: "sample.kml",
         test_countries: "countries.kml",
         test_style_merging: "style-merging.kml",
+        test_namespaces: "namespaces.kml",
+        test_google_earth: "google-earth.kml",
     }
 
     // Confirms that parsing from KML and writing back doesn't drop any currently tracked data